@@ -69,10 +69,12 @@ impl Handler<MessageOne> for ActorThree {
 
 #[derive(Clone, Debug, Message)]
 #[rtype(result = "()")]
+#[allow(dead_code)]
 struct MessageOne(String);
 
 #[derive(Clone, Debug, Message)]
 #[rtype(result = "()")]
+#[allow(dead_code)]
 struct MessageTwo(u8);
 
 fn main() {
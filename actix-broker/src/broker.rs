@@ -68,6 +68,29 @@ impl<T> Broker<T> {
         Some(subs)
     }
 
+    /// Subscribes `sub`, unless the same recipient is subscribed already.
+    fn subscribe<M: BrokerMsg>(&mut self, sub: Recipient<M>, id: TypeId) {
+        let msg_id = TypeId::of::<M>();
+        if let Some(subs) = self.sub_map.get(&msg_id) {
+            if subs
+                .iter()
+                .any(|(_, s)| s.downcast_ref::<Recipient<M>>() == Some(&sub))
+            {
+                trace!("Broker: Already subscribed to {:?}.", msg_id);
+                return;
+            }
+        }
+        self.add_sub::<M>(sub, id);
+    }
+
+    fn unsubscribe<M: BrokerMsg>(&mut self, sub: &Recipient<M>) {
+        let msg_id = TypeId::of::<M>();
+        if let Some(subs) = self.sub_map.get_mut(&msg_id) {
+            trace!("Broker: Removing from {:?} subscription list.", msg_id);
+            subs.retain(|(_, s)| s.downcast_ref::<Recipient<M>>() != Some(sub));
+        }
+    }
+
     fn add_sub<M: BrokerMsg>(&mut self, sub: Recipient<M>, id: TypeId) {
         let msg_id = TypeId::of::<M>();
         let boxed = Box::new(sub);
@@ -108,7 +131,7 @@ impl<T: 'static + Unpin, M: BrokerMsg> Handler<SubscribeAsync<M>> for Broker<T>
 
     fn handle(&mut self, msg: SubscribeAsync<M>, _ctx: &mut Context<Self>) {
         trace!("Broker: Received SubscribeAsync");
-        self.add_sub::<M>(msg.0, msg.1);
+        self.subscribe::<M>(msg.0, msg.1);
     }
 }
 
@@ -117,11 +140,20 @@ impl<T: 'static + Unpin, M: BrokerMsg> Handler<SubscribeSync<M>> for Broker<T> {
 
     fn handle(&mut self, msg: SubscribeSync<M>, _ctx: &mut Context<Self>) -> Self::Result {
         trace!("Broker: Received SubscribeSync");
-        self.add_sub::<M>(msg.0, msg.1);
+        self.subscribe::<M>(msg.0, msg.1);
         self.get_previous_msg::<M>()
     }
 }
 
+impl<T: 'static + Unpin, M: BrokerMsg> Handler<Unsubscribe<M>> for Broker<T> {
+    type Result = ();
+
+    fn handle(&mut self, msg: Unsubscribe<M>, _ctx: &mut Context<Self>) {
+        trace!("Broker: Received Unsubscribe");
+        self.unsubscribe::<M>(&msg.0);
+    }
+}
+
 impl<T: 'static + Unpin, M: BrokerMsg> Handler<IssueAsync<M>> for Broker<T> {
     type Result = ();
 
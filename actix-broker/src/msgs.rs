@@ -16,6 +16,10 @@ impl<M: BrokerMsg> Message for SubscribeSync<M> {
     type Result = Option<M>;
 }
 
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Unsubscribe<M: BrokerMsg>(pub Recipient<M>);
+
 #[derive(Message)]
 #[rtype(result = "()")]
 pub struct IssueAsync<M: BrokerMsg>(pub M, pub TypeId);
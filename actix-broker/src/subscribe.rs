@@ -10,6 +10,8 @@ use crate::{
 
 /// The `BrokerSubscribe` trait has functions to register an actor's interest in different
 /// messages.
+///
+/// An actor is subscribed to a message at most once per broker, subscribing again has no effect.
 pub trait BrokerSubscribe
 where
     Self: Actor,
@@ -88,6 +90,38 @@ where
     {
         self.subscribe_sync::<ArbiterBroker, M>(ctx);
     }
+
+    /// Unsubscribe from a message.
+    /// Messages issued after the broker has handled this are no longer delivered to this actor.
+    fn unsubscribe<T: RegisteredBroker, M: BrokerMsg>(&self, ctx: &mut Self::Context)
+    where
+        Self: Handler<M>,
+        <Self as Actor>::Context: ToEnvelope<Self, M>,
+    {
+        let broker = T::get_broker();
+        let recipient = ctx.address().recipient::<M>();
+        broker.do_send(Unsubscribe(recipient));
+    }
+
+    /// Helper to unsubscribe from a system broker
+    /// This is the equivalent of `self.unsubscribe::<SystemBroker, M>(ctx);`
+    fn unsubscribe_system<M: BrokerMsg>(&self, ctx: &mut Self::Context)
+    where
+        Self: Handler<M>,
+        <Self as Actor>::Context: ToEnvelope<Self, M>,
+    {
+        self.unsubscribe::<SystemBroker, M>(ctx);
+    }
+
+    /// Helper to unsubscribe from an arbiter-specific broker
+    /// This is the equivalent of `self.unsubscribe::<ArbiterBroker, M>(ctx);`
+    fn unsubscribe_arbiter<M: BrokerMsg>(&self, ctx: &mut Self::Context)
+    where
+        Self: Handler<M>,
+        <Self as Actor>::Context: ToEnvelope<Self, M>,
+    {
+        self.unsubscribe::<ArbiterBroker, M>(ctx);
+    }
 }
 
 impl<A> BrokerSubscribe for A
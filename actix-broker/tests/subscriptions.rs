@@ -0,0 +1,67 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix::{clock::sleep, prelude::*};
+use actix_broker::{Broker, BrokerSubscribe, SystemBroker};
+
+#[derive(Clone, Message)]
+#[rtype(result = "()")]
+struct Event;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Unsubscribe;
+
+struct Subscriber(Arc<AtomicUsize>);
+
+impl Actor for Subscriber {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_system_async::<Event>(ctx);
+        self.subscribe_system_sync::<Event>(ctx);
+    }
+}
+
+impl Handler<Event> for Subscriber {
+    type Result = ();
+
+    fn handle(&mut self, _: Event, _: &mut Self::Context) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Handler<Unsubscribe> for Subscriber {
+    type Result = ();
+
+    fn handle(&mut self, _: Unsubscribe, ctx: &mut Self::Context) {
+        self.unsubscribe_system::<Event>(ctx);
+    }
+}
+
+#[actix::test]
+async fn subscribes_once_and_unsubscribes() {
+    let first = Arc::new(AtomicUsize::new(0));
+    let second = Arc::new(AtomicUsize::new(0));
+    let addr = Subscriber(Arc::clone(&first)).start();
+    Subscriber(Arc::clone(&second)).start();
+    sleep(Duration::from_millis(20)).await;
+
+    Broker::<SystemBroker>::issue_async(Event);
+    sleep(Duration::from_millis(20)).await;
+    assert_eq!(first.load(Ordering::SeqCst), 1);
+    assert_eq!(second.load(Ordering::SeqCst), 1);
+
+    addr.send(Unsubscribe).await.unwrap();
+    sleep(Duration::from_millis(20)).await;
+
+    Broker::<SystemBroker>::issue_async(Event);
+    sleep(Duration::from_millis(20)).await;
+    assert_eq!(first.load(Ordering::SeqCst), 1);
+    assert_eq!(second.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,164 @@
+//! Custom `ActorFuture` example
+//!
+//! A client runs a two-step handshake with a server: it says hello to learn the server's
+//! nonce, then authenticates with a token derived from the nonce and its own secret. Instead
+//! of chaining combinators, the handshake is a hand-rolled state machine implementing
+//! `ActorFuture`, which reads and updates the client's state between the steps.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{self, Poll},
+};
+
+use actix::{dev::Request, prelude::*};
+
+#[derive(Message)]
+#[rtype(result = "u64")]
+struct Hello;
+
+#[derive(Message)]
+#[rtype(result = "Result<u32, ()>")]
+struct Auth {
+    nonce: u64,
+    token: u64,
+}
+
+/// Hands out nonces and opens a session for each valid token.
+struct Server {
+    secret: u64,
+    next_nonce: u64,
+    sessions: u32,
+}
+
+impl Actor for Server {
+    type Context = Context<Self>;
+}
+
+impl Handler<Hello> for Server {
+    type Result = u64;
+
+    fn handle(&mut self, _: Hello, _: &mut Context<Self>) -> u64 {
+        self.next_nonce += 1;
+        self.next_nonce
+    }
+}
+
+impl Handler<Auth> for Server {
+    type Result = Result<u32, ()>;
+
+    fn handle(&mut self, msg: Auth, _: &mut Context<Self>) -> Self::Result {
+        if msg.token != msg.nonce ^ self.secret {
+            return Err(());
+        }
+        self.sessions += 1;
+        Ok(self.sessions)
+    }
+}
+
+enum HandshakeError {
+    Mailbox(MailboxError),
+    Rejected,
+}
+
+/// The steps of the handshake, each waiting on the reply to one request.
+///
+/// Requests are boxed to keep the state machine `Unpin`, so that `poll` can move between the
+/// steps without pin projections.
+enum Handshake {
+    Hello(Pin<Box<Request<Server, Hello>>>),
+    Auth(Pin<Box<Request<Server, Auth>>>),
+    Done,
+}
+
+impl Handshake {
+    fn start(server: &Addr<Server>) -> Self {
+        Handshake::Hello(Box::pin(server.send(Hello)))
+    }
+}
+
+impl ActorFuture<Client> for Handshake {
+    type Output = Result<u32, HandshakeError>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        client: &mut Client,
+        _: &mut Context<Client>,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            match this {
+                Handshake::Hello(req) => {
+                    // the request registers the task's waker, so returning `Pending` here
+                    // guarantees that the future is polled again once the reply arrives
+                    let nonce = match req.as_mut().poll(task) {
+                        Poll::Ready(Ok(nonce)) => nonce,
+                        Poll::Ready(Err(err)) => {
+                            *this = Handshake::Done;
+                            return Poll::Ready(Err(HandshakeError::Mailbox(err)));
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    };
+
+                    println!("got nonce {}", nonce);
+                    let token = nonce ^ client.secret;
+                    *this = Handshake::Auth(Box::pin(client.server.send(Auth { nonce, token })));
+                }
+                Handshake::Auth(req) => {
+                    let res = match req.as_mut().poll(task) {
+                        Poll::Ready(Ok(Ok(session))) => Ok(session),
+                        Poll::Ready(Ok(Err(()))) => Err(HandshakeError::Rejected),
+                        Poll::Ready(Err(err)) => Err(HandshakeError::Mailbox(err)),
+                        Poll::Pending => return Poll::Pending,
+                    };
+
+                    *this = Handshake::Done;
+                    return Poll::Ready(res);
+                }
+                Handshake::Done => panic!("Handshake polled after completion"),
+            }
+        }
+    }
+}
+
+struct Client {
+    server: Addr<Server>,
+    secret: u64,
+}
+
+impl Actor for Client {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.spawn(Handshake::start(&self.server).map(|res, _, ctx| {
+            match res {
+                Ok(session) => println!("handshake done, session {}", session),
+                Err(HandshakeError::Mailbox(err)) => println!("handshake failed: {}", err),
+                Err(HandshakeError::Rejected) => println!("handshake rejected"),
+            }
+            ctx.stop();
+            System::current().stop();
+        }));
+    }
+}
+
+fn main() {
+    let sys = System::new();
+
+    sys.block_on(async {
+        let server = Server {
+            secret: 0x5eed,
+            next_nonce: 0,
+            sessions: 0,
+        }
+        .start();
+        Client {
+            server,
+            secret: 0x5eed,
+        }
+        .start();
+    });
+
+    sys.run().unwrap();
+}
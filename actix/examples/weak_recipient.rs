@@ -106,7 +106,7 @@ impl Handler<TimePing> for ClientA {
     type Result = ();
 
     fn handle(&mut self, msg: TimePing, _ctx: &mut Self::Context) -> Self::Result {
-        println!("🐰 ClientA received ping: {:?}", msg);
+        println!("🐰 ClientA received ping sent at {:?}", msg.0);
     }
 }
 
@@ -139,7 +139,7 @@ impl Handler<TimePing> for ClientB {
     type Result = ();
 
     fn handle(&mut self, msg: TimePing, _ctx: &mut Self::Context) -> Self::Result {
-        println!("🐇  ClientB received ping: {:?}", msg);
+        println!("🐇  ClientB received ping sent at {:?}", msg.0);
     }
 }
 
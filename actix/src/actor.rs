@@ -1,18 +1,26 @@
-use std::time::Duration;
+use std::{cell::Cell, error, fmt, future::Future, rc::Rc, time::Duration};
 
 use actix_rt::ArbiterHandle;
 use futures_core::stream::Stream;
 use log::error;
 
 use crate::{
-    address::{channel, Addr},
+    address::{channel, Addr, Recipient},
     context::Context,
-    contextitems::{ActorDelayedMessageItem, ActorMessageItem, ActorMessageStreamItem},
-    fut::{ActorFuture, ActorStreamExt},
-    handler::{Handler, Message},
+    contextitems::{
+        ActorDelayedMessageItem, ActorMessageItem, ActorMessageStreamItem, ActorResultStreamItem,
+        Scope,
+    },
+    fut::{wrap_future, ActorFuture, ActorFutureExt, ActorStreamExt},
+    handler::{ErrorHandler, Handler, Message, MessageResponse},
+    limit::TooManyActors,
     mailbox::DEFAULT_CAPACITY,
-    stream::StreamHandler,
-    utils::{IntervalFunc, TimerFunc},
+    registry::{MissingDependency, ServiceId},
+    stream::{
+        AckStreamHandle, AckStreamHandler, ActorStream, Sequenced, SequencedStreamHandler,
+        StreamHandler, UntilErr,
+    },
+    utils::{IntervalFunc, MissedTicks, TimerFunc},
 };
 
 /// Actors are objects which encapsulate state and behavior.
@@ -71,6 +79,62 @@ use crate::{
 /// If an actor does not modify execution context while in stopping
 /// state, the actor state changes to `Stopped`. This state is
 /// considered final and at this point the actor gets dropped.
+///
+/// # Thread affinity
+///
+/// An actor never migrates between threads: it is created, run and
+/// dropped on the thread of the arbiter that started it. Actors are
+/// therefore not required to be `Send` and may hold `!Send` state such
+/// as `Rc` based caches. Only the address is shared across threads,
+/// which is why messages and their results have to be `Send`.
+///
+/// The APIs that start an actor on another thread, like
+/// [`start_in_arbiter`](Actor::start_in_arbiter) or
+/// [`SyncArbiter::start`](crate::SyncArbiter::start), take a `Send`
+/// factory and construct the actor on its target thread:
+///
+/// ```
+/// use std::rc::Rc;
+/// use actix::prelude::*;
+///
+/// struct Cache(Rc<Vec<u8>>);
+///
+/// impl Actor for Cache {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let arbiter = Arbiter::new();
+///     let addr = Cache::start_in_arbiter(&arbiter.handle(), |_| Cache(Rc::new(Vec::new())));
+///
+///     // the address itself can be sent anywhere
+///     std::thread::spawn(move || drop(addr)).join().unwrap();
+///     # arbiter.stop();
+///     # System::current().stop();
+/// }
+/// ```
+///
+/// Handing an already constructed `!Send` actor to another thread is
+/// rejected at compile time:
+///
+/// ```compile_fail,E0277
+/// use std::rc::Rc;
+/// use actix::prelude::*;
+///
+/// struct Cache(Rc<Vec<u8>>);
+///
+/// impl Actor for Cache {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let arbiter = Arbiter::new();
+///     let cache = Cache(Rc::new(Vec::new()));
+///     Cache::start_in_arbiter(&arbiter.handle(), move |_| cache);
+/// }
+/// ```
 #[allow(unused_variables)]
 pub trait Actor: Sized + Unpin + 'static {
     /// Actor execution context type
@@ -79,6 +143,88 @@ pub trait Actor: Sized + Unpin + 'static {
     /// Called when an actor gets polled the first time.
     fn started(&mut self, ctx: &mut Self::Context) {}
 
+    /// Called when the last address of the actor gets dropped.
+    ///
+    /// Without addresses, an actor stops once no more evented objects are left in its context.
+    /// Returning `Running::Continue` keeps the actor running instead, until it stops itself,
+    /// e.g. for a daemon that should outlive the actors that started it. This is called again
+    /// if the actor hands out new addresses which then all get dropped.
+    ///
+    /// This is only called for actors running in a [`Context`](crate::Context).
+    fn no_listeners(&mut self, ctx: &mut Self::Context) -> Running {
+        Running::Stop
+    }
+
+    /// Called once the actor has handled every message in its mailbox.
+    ///
+    /// This is called each time the mailbox has been drained after handling messages, once the
+    /// ready futures of the context have been polled as well. It is not called while the actor
+    /// [waits](AsyncContext::wait) on a future or still has to respond to a message, so nothing
+    /// is left to do right away. Unlike [`Context::on_idle`](crate::Context::on_idle), there is
+    /// no debounce interval, which makes this the point to flush work accumulated while
+    /// handling a burst of messages.
+    ///
+    /// This is only called for actors running in a [`Context`](crate::Context).
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Write(String);
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "usize")]
+    /// struct Flushes;
+    ///
+    /// #[derive(Default)]
+    /// struct Writer {
+    ///     pending: Vec<String>,
+    ///     flushes: usize,
+    /// }
+    ///
+    /// impl Actor for Writer {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn idle(&mut self, _: &mut Context<Self>) {
+    ///         if !self.pending.is_empty() {
+    ///             // a single write for the whole burst
+    ///             self.pending.clear();
+    ///             self.flushes += 1;
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Write> for Writer {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, Write(line): Write, _: &mut Context<Self>) {
+    ///         self.pending.push(line);
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Flushes> for Writer {
+    ///     type Result = usize;
+    ///
+    ///     fn handle(&mut self, _: Flushes, _: &mut Context<Self>) -> usize {
+    ///         self.flushes
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let writer = Writer::default().start();
+    ///     for i in 0..10 {
+    ///         writer.do_send(Write(format!("line {}", i)));
+    ///     }
+    ///     actix_rt::task::yield_now().await;
+    ///
+    ///     assert_eq!(writer.send(Flushes).await.unwrap(), 1);
+    /// }
+    /// ```
+    fn idle(&mut self, ctx: &mut Self::Context) {}
+
     /// Called after an actor is in `Actor::Stopping` state.
     ///
     /// There can be several reasons for stopping:
@@ -100,8 +246,66 @@ pub trait Actor: Sized + Unpin + 'static {
     /// method got called, the actor will be dropped.
     fn stopped(&mut self, ctx: &mut Self::Context) {}
 
+    /// Returns the system services the actor requires.
+    ///
+    /// Before the actor starts, the required services are started through the
+    /// [registry](crate::registry), along with the services they require in turn, unless they
+    /// are already running. If a service can not be provided, because no system is running or
+    /// services require each other in a cycle, starting the actor fails with
+    /// [`MissingDependency`](crate::registry::MissingDependency), as reported by
+    /// [`try_start`](Actor::try_start).
+    ///
+    /// This is only checked for actors running in a [`Context`](crate::Context).
+    ///
+    /// Default implementation requires nothing.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::{prelude::*, registry::ServiceId};
+    ///
+    /// #[derive(Default)]
+    /// struct Database;
+    ///
+    /// impl Actor for Database {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Supervised for Database {}
+    /// impl SystemService for Database {}
+    ///
+    /// struct Api;
+    ///
+    /// impl Actor for Api {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn requires() -> &'static [ServiceId] {
+    ///         const REQUIRES: &[ServiceId] = &[ServiceId::of::<Database>()];
+    ///         REQUIRES
+    ///     }
+    ///
+    ///     fn started(&mut self, _: &mut Context<Self>) {
+    ///         // already running
+    ///         let _db = Database::from_registry();
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     Api.try_start().unwrap();
+    /// }
+    /// ```
+    fn requires() -> &'static [ServiceId] {
+        &[]
+    }
+
     /// Start a new asynchronous actor, returning its address.
     ///
+    /// If the system has reached its [actor limit](crate::ActorLimit), or a service the actor
+    /// [requires](Actor::requires) can not be provided, the failure is logged and the actor is
+    /// dropped without being started. The returned address is closed then, see
+    /// [`try_start`](Actor::try_start) to handle the failure instead.
+    ///
     /// # Examples
     ///
     /// ```
@@ -126,6 +330,21 @@ pub trait Actor: Sized + Unpin + 'static {
         Context::new().run(self)
     }
 
+    /// Starts an asynchronous actor, failing if the system has reached its
+    /// [actor limit](crate::ActorLimit), or a service the actor [requires](Actor::requires) can
+    /// not be provided.
+    ///
+    /// On failure the actor is dropped without being started.
+    fn try_start(self) -> Result<Addr<Self>, StartError>
+    where
+        Self: Actor<Context = Context<Self>>,
+    {
+        let fut = Context::new().try_into_future(self)?;
+        let addr = fut.address();
+        actix_rt::spawn(fut);
+        Ok(addr)
+    }
+
     /// Construct and start a new asynchronous actor, returning its
     /// address.
     ///
@@ -150,9 +369,12 @@ pub trait Actor: Sized + Unpin + 'static {
         wrk.spawn_fn(move || {
             let mut ctx = Context::with_receiver(rx);
             let act = f(&mut ctx);
-            let fut = ctx.into_future(act);
-
-            actix_rt::spawn(fut);
+            match ctx.try_into_future(act) {
+                Ok(fut) => {
+                    actix_rt::spawn(fut);
+                }
+                Err(err) => error!("Can not start {}: {}", std::any::type_name::<Self>(), err),
+            }
         });
 
         Addr::new(tx)
@@ -161,7 +383,8 @@ pub trait Actor: Sized + Unpin + 'static {
     /// Start a new asynchronous actor given a `Context`.
     ///
     /// Use this method if you need the `Context` object during actor
-    /// initialization.
+    /// initialization. Like [`start`](Actor::start), a failure to start the actor is logged and
+    /// the returned address is closed.
     ///
     /// # Examples
     ///
@@ -192,6 +415,45 @@ pub trait Actor: Sized + Unpin + 'static {
     }
 }
 
+/// Error returned when an actor can not be started, see [`Actor::try_start`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartError {
+    /// The system has reached its [actor limit](crate::ActorLimit).
+    TooManyActors(TooManyActors),
+    /// A service the actor [requires](Actor::requires) can not be provided.
+    MissingDependency(MissingDependency),
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartError::TooManyActors(err) => err.fmt(fmt),
+            StartError::MissingDependency(err) => err.fmt(fmt),
+        }
+    }
+}
+
+impl error::Error for StartError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            StartError::TooManyActors(err) => Some(err),
+            StartError::MissingDependency(err) => Some(err),
+        }
+    }
+}
+
+impl From<TooManyActors> for StartError {
+    fn from(err: TooManyActors) -> Self {
+        StartError::TooManyActors(err)
+    }
+}
+
+impl From<MissingDependency> for StartError {
+    fn from(err: MissingDependency) -> Self {
+        StartError::MissingDependency(err)
+    }
+}
+
 #[allow(unused_variables)]
 /// Actors with the ability to restart after failure.
 ///
@@ -213,6 +475,7 @@ pub trait Supervised: Actor {
 
 /// Actor execution state
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "state", derive(serde::Serialize))]
 pub enum ActorState {
     /// Actor is started.
     Started,
@@ -283,6 +546,115 @@ where
     where
         F: ActorFuture<A, Output = ()> + 'static;
 
+    /// Spawns a group of futures into the context as a single scope.
+    ///
+    /// The closure receives a [`Scope`] which futures can be spawned into. The returned handle
+    /// refers to the whole group: cancelling it drops all child futures at once. As with
+    /// [`spawn`](AsyncContext::spawn), the scope is also cancelled when the actor stops.
+    ///
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         let handle = ctx.scope(|scope| {
+    ///             scope.spawn(async { println!("one") }.into_actor(self));
+    ///             scope.spawn(async { println!("two") }.into_actor(self));
+    ///         });
+    ///
+    ///         // cancels both futures
+    ///         ctx.cancel_future(handle);
+    /// #       System::current().stop();
+    ///     }
+    /// }
+    /// # #[actix::main] async fn main() { MyActor.start(); }
+    /// ```
+    fn scope<F>(&mut self, f: F) -> SpawnHandle
+    where
+        F: FnOnce(&mut Scope<A>),
+    {
+        let mut scope = Scope::new();
+        f(&mut scope);
+        self.spawn(scope)
+    }
+
+    /// Spawns a future into the context, delivering its result to another actor.
+    ///
+    /// An `Ok` item is sent to `recipient`, bypassing its mailbox capacity, so the result never
+    /// has to pass through a handler of this actor. An error is turned into a message by
+    /// `on_error` and delivered to self instead. As with [`spawn`](AsyncContext::spawn), the
+    /// future is cancelled when the actor stops.
+    ///
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Parsed(u32);
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Failed(String);
+    ///
+    /// struct Sink;
+    ///
+    /// impl Actor for Sink {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Parsed> for Sink {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, Parsed(n): Parsed, _: &mut Context<Self>) {
+    ///         println!("parsed {}", n);
+    /// #       System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// struct Parser(Recipient<Parsed>);
+    ///
+    /// impl Actor for Parser {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         let parse = async { "42".parse().map(Parsed).map_err(|err| format!("{}", err)) };
+    ///         ctx.spawn_to(parse.into_actor(self), self.0.clone(), Failed);
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Failed> for Parser {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, Failed(err): Failed, _: &mut Context<Self>) {
+    ///         println!("parse failed: {}", err);
+    ///     }
+    /// }
+    /// # #[actix::main] async fn main() { Parser(Sink.start().recipient()).start(); }
+    /// ```
+    fn spawn_to<F, M, E, N, H>(
+        &mut self,
+        fut: F,
+        recipient: Recipient<M>,
+        on_error: H,
+    ) -> SpawnHandle
+    where
+        F: ActorFuture<A, Output = Result<M, E>> + 'static,
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<N>,
+        N: Message + 'static,
+        H: FnOnce(E) -> N + 'static,
+    {
+        self.spawn(fut.map(move |res, _, ctx: &mut A::Context| match res {
+            Ok(item) => recipient.do_send(item),
+            Err(err) => ctx.notify(on_error(err)),
+        }))
+    }
+
     /// Spawns a future into the context, waiting for it to resolve.
     ///
     /// This stops processing any incoming events until the future
@@ -350,6 +722,126 @@ where
         <A as StreamHandler<S::Item>>::add_stream(fut, self)
     }
 
+    /// Registers a stream with the context, sending `on_done` to self once it ends.
+    ///
+    /// Items are handled by [`StreamHandler::handle`] as with [`add_stream`](Self::add_stream),
+    /// but when the stream is exhausted the message `on_done` is delivered as with
+    /// [`notify`](Self::notify) instead of calling [`StreamHandler::finished`]. This keeps
+    /// follow-up logic in a regular [`Handler`].
+    ///
+    /// ```
+    /// # use actix::prelude::*;
+    /// use futures_util::stream::iter;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Done;
+    ///
+    /// struct MyActor(usize);
+    ///
+    /// impl StreamHandler<usize> for MyActor {
+    ///     fn handle(&mut self, item: usize, _: &mut Context<Self>) {
+    ///         self.0 += item;
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Done> for MyActor {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: Done, _: &mut Context<Self>) {
+    ///         assert_eq!(self.0, 6);
+    /// #       System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.add_stream_with_completion(iter([1, 2, 3]), Done);
+    ///     }
+    /// }
+    /// # #[actix::main] async fn main() { MyActor(0).start(); }
+    /// ```
+    fn add_stream_with_completion<S, M>(&mut self, fut: S, on_done: M) -> SpawnHandle
+    where
+        S: Stream + 'static,
+        A: StreamHandler<S::Item> + Handler<M>,
+        M: Message + 'static,
+    {
+        if self.state() == ActorState::Stopped {
+            error!("Context::add_stream_with_completion called for stopped actor.");
+            SpawnHandle::default()
+        } else {
+            self.spawn(ActorStream::new(
+                fut,
+                move |_: &mut A, ctx: &mut A::Context| ctx.notify(on_done),
+            ))
+        }
+    }
+
+    /// Registers a stream of results with the context, sending a completion message to self
+    /// once it ends.
+    ///
+    /// `Ok` items are handled by [`StreamHandler::handle`]. If the stream ends cleanly,
+    /// `on_done` is delivered; if it yields an error, the stream is dropped and the message
+    /// produced by `on_error` is delivered instead. [`StreamHandler::finished`] is not called.
+    fn add_try_stream_with_completion<S, T, E, M, N, F>(
+        &mut self,
+        fut: S,
+        on_done: M,
+        on_error: F,
+    ) -> SpawnHandle
+    where
+        S: Stream<Item = Result<T, E>> + 'static,
+        E: 'static,
+        A: StreamHandler<T> + Handler<M> + Handler<N>,
+        M: Message + 'static,
+        N: Message + 'static,
+        F: FnOnce(E) -> N + 'static,
+    {
+        if self.state() == ActorState::Stopped {
+            error!("Context::add_try_stream_with_completion called for stopped actor.");
+            SpawnHandle::default()
+        } else {
+            let err = Rc::new(Cell::new(None));
+            let stream = UntilErr::new(fut, Rc::clone(&err));
+            self.spawn(ActorStream::new(
+                stream,
+                move |_: &mut A, ctx: &mut A::Context| match err.take() {
+                    Some(err) => ctx.notify(on_error(err)),
+                    None => ctx.notify(on_done),
+                },
+            ))
+        }
+    }
+
+    /// Registers a stream with per-item flow control with the context.
+    ///
+    /// Items are handled by [`AckStreamHandler::handle`], whose [`StreamAck`](crate::StreamAck)
+    /// decides whether the stream is polled further. The returned handle resumes a paused
+    /// stream.
+    fn add_ack_stream<S>(&mut self, fut: S) -> AckStreamHandle
+    where
+        S: Stream + 'static,
+        A: AckStreamHandler<S::Item>,
+    {
+        <A as AckStreamHandler<S::Item>>::add_ack_stream(fut, self)
+    }
+
+    /// Registers an ordered stream with the context, detecting gaps in its sequence.
+    ///
+    /// Items are handled by [`SequencedStreamHandler::handle`], and items out of sequence are
+    /// first passed to [`SequencedStreamHandler::on_gap`].
+    fn add_sequenced_stream<S>(&mut self, fut: S) -> SpawnHandle
+    where
+        S: Stream + 'static,
+        S::Item: Sequenced,
+        A: SequencedStreamHandler<S::Item>,
+    {
+        <A as SequencedStreamHandler<S::Item>>::add_sequenced_stream(fut, self)
+    }
+
     /// Registers a stream with the context, ignoring errors.
     ///
     /// This method is similar to `add_stream` but it skips stream
@@ -400,6 +892,92 @@ where
         }
     }
 
+    /// Registers a future resolving to a result with the context.
+    ///
+    /// An `Ok` value is handled by [`Handler::handle`] as a message, and an error by
+    /// [`ErrorHandler::error`], so that neither has to match on the result. This requires the
+    /// actor to implement `Handler<T>`, with `T` a [`Message`], and `ErrorHandler<E>`.
+    ///
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Config(String);
+    ///
+    /// #[derive(Debug)]
+    /// struct LoadError;
+    ///
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         let load = async { Ok::<_, LoadError>(Config("debug = true".to_owned())) };
+    ///         ctx.add_result_future(load);
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Config> for MyActor {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, Config(config): Config, _: &mut Context<Self>) {
+    ///         println!("loaded {}", config);
+    ///     }
+    /// }
+    ///
+    /// impl ErrorHandler<LoadError> for MyActor {
+    ///     fn error(&mut self, err: LoadError, ctx: &mut Context<Self>) -> Running {
+    ///         println!("failed to load the config: {:?}", err);
+    ///         ctx.stop();
+    ///         Running::Stop
+    ///     }
+    /// }
+    /// # #[actix::main] async fn main() { MyActor.start(); }
+    /// ```
+    fn add_result_future<F, T, E>(&mut self, fut: F) -> SpawnHandle
+    where
+        F: Future<Output = Result<T, E>> + 'static,
+        T: Message + 'static,
+        A: Handler<T> + ErrorHandler<E>,
+    {
+        if self.state() == ActorState::Stopped {
+            error!("Context::add_result_future called for stopped actor.");
+            SpawnHandle::default()
+        } else {
+            self.spawn(
+                wrap_future(fut).map(|res, act: &mut A, ctx: &mut A::Context| match res {
+                    Ok(msg) => Handler::handle(act, msg, ctx).handle(ctx, None),
+                    Err(err) => {
+                        act.error(err, ctx);
+                    }
+                }),
+            )
+        }
+    }
+
+    /// Registers a stream of results with the context.
+    ///
+    /// `Ok` items are handled by [`Handler::handle`] as messages, and errors by
+    /// [`ErrorHandler::error`], which decides whether the stream is polled further. This
+    /// requires the actor to implement `Handler<T>`, with `T` a [`Message`], and
+    /// `ErrorHandler<E>`. Like with [`add_message_stream`](Self::add_message_stream), nothing is
+    /// called when the stream ends.
+    fn add_result_stream<S, T, E>(&mut self, fut: S) -> SpawnHandle
+    where
+        S: Stream<Item = Result<T, E>> + 'static,
+        T: Message + 'static,
+        A: Handler<T> + ErrorHandler<E>,
+    {
+        if self.state() == ActorState::Stopped {
+            error!("Context::add_result_stream called for stopped actor.");
+            SpawnHandle::default()
+        } else {
+            self.spawn(ActorResultStreamItem::new(fut))
+        }
+    }
+
     /// Sends the message `msg` to self. This bypasses the mailbox capacity, and
     /// will always queue the message. If the actor is in the `stopped` state, an
     /// error will be raised.
@@ -449,18 +1027,56 @@ where
 
     /// Spawns a job to execute the given closure periodically, at a
     /// specified fixed interval.
+    ///
+    /// If the actor falls behind, missed ticks are coalesced into a single
+    /// call, see [`run_interval_with`](Self::run_interval_with).
     fn run_interval<F>(&mut self, dur: Duration, f: F) -> SpawnHandle
     where
         F: FnMut(&mut A, &mut A::Context) + 'static,
     {
         self.spawn(IntervalFunc::new(dur, f).finish())
     }
+
+    /// Spawns a job to execute the given closure periodically, choosing
+    /// what happens when the actor falls behind.
+    ///
+    /// The closure receives the number of ticks that were coalesced into
+    /// the current call, which is always zero with [`MissedTicks::CatchUp`].
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// use actix::prelude::*;
+    ///
+    /// struct Poller;
+    ///
+    /// impl Actor for Poller {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.run_interval_with(Duration::from_secs(1), MissedTicks::Coalesce, |_, _, skipped| {
+    ///             if skipped > 0 {
+    ///                 println!("fell behind by {} ticks", skipped);
+    ///             }
+    ///         });
+    ///     }
+    /// }
+    /// ```
+    fn run_interval_with<F>(&mut self, dur: Duration, missed: MissedTicks, f: F) -> SpawnHandle
+    where
+        F: FnMut(&mut A, &mut A::Context, u32) + 'static,
+    {
+        self.spawn(
+            IntervalFunc::with_skipped(dur, f)
+                .missed_ticks(missed)
+                .finish(),
+        )
+    }
 }
 
 /// A handle to a spawned future.
 ///
 /// Can be used to cancel the future.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SpawnHandle(usize);
 
 impl SpawnHandle {
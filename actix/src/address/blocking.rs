@@ -0,0 +1,123 @@
+use std::{
+    fmt,
+    sync::Arc,
+    task::{self, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use super::{channel::AddressSender, SendError, ToEnvelope};
+use crate::handler::{Handler, Message};
+
+/// Sending half of an actor's mailbox, typed over the message only.
+trait ThreadSender<M>: Send + Sync {
+    fn poll_ready(&self, cx: &mut task::Context<'_>) -> Poll<Result<(), ()>>;
+
+    fn try_send(&self, msg: M) -> Result<(), SendError<M>>;
+
+    fn connected(&self) -> bool;
+
+    fn boxed(&self) -> Box<dyn ThreadSender<M>>;
+}
+
+impl<A, M> ThreadSender<M> for AddressSender<A>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    fn poll_ready(&self, cx: &mut task::Context<'_>) -> Poll<Result<(), ()>> {
+        AddressSender::poll_ready(self, cx)
+    }
+
+    fn try_send(&self, msg: M) -> Result<(), SendError<M>> {
+        AddressSender::try_send(self, msg, true)
+    }
+
+    fn connected(&self) -> bool {
+        AddressSender::connected(self)
+    }
+
+    fn boxed(&self) -> Box<dyn ThreadSender<M>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Wakes a thread blocked in [`BlockingSender::send`].
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// A handle for sending messages of type `M` to an actor from plain threads, created with
+/// [`Addr::into_sender`](super::Addr::into_sender).
+///
+/// The handle does not depend on the actor type, and messages are sent without waiting for
+/// their results. [`send`](Self::send) blocks the calling thread while the mailbox is full, so
+/// it must not be called from an async context, like an actor or an `async` block.
+pub struct BlockingSender<M: Message> {
+    tx: Box<dyn ThreadSender<M>>,
+}
+
+impl<M> BlockingSender<M>
+where
+    M: Message + Send,
+    M::Result: Send,
+{
+    pub(super) fn new<A>(tx: AddressSender<A>) -> Self
+    where
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: 'static,
+    {
+        BlockingSender { tx: Box::new(tx) }
+    }
+
+    /// Sends a message, blocking the current thread while the actor's mailbox is full.
+    ///
+    /// Fails with [`SendError::Closed`] once the mailbox has closed, returning the message.
+    pub fn send(&self, msg: M) -> Result<(), SendError<M>> {
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = task::Context::from_waker(&waker);
+
+        loop {
+            match self.tx.poll_ready(&mut cx) {
+                Poll::Ready(Ok(())) => return self.tx.try_send(msg),
+                Poll::Ready(Err(())) => return Err(SendError::Closed(msg)),
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// Sends a message without blocking.
+    ///
+    /// Fails with [`SendError::Full`] if the actor's mailbox is full, or with
+    /// [`SendError::Closed`] once it has closed.
+    pub fn try_send(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.try_send(msg)
+    }
+
+    /// Returns whether the actor's mailbox has closed.
+    pub fn is_closed(&self) -> bool {
+        !self.tx.connected()
+    }
+}
+
+impl<M: Message> Clone for BlockingSender<M> {
+    fn clone(&self) -> Self {
+        BlockingSender {
+            tx: self.tx.boxed(),
+        }
+    }
+}
+
+impl<M: Message> fmt::Debug for BlockingSender<M> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("BlockingSender")
+            .field("message", &std::any::type_name::<M>())
+            .finish()
+    }
+}
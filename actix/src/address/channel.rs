@@ -20,13 +20,17 @@ use parking_lot::Mutex;
 use tokio::sync::oneshot::{channel as oneshot_channel, Receiver as OneshotReceiver};
 
 use super::{
-    envelope::{Envelope, ToEnvelope},
+    envelope::{Envelope, EnvelopeProxy, ToEnvelope},
     queue::Queue,
     SendError,
 };
 use crate::{
     actor::Actor,
+    audit,
+    directory::ActorId,
     handler::{Handler, Message},
+    observe::Observers,
+    replies::ReplyState,
 };
 
 pub trait Sender<M>: Send
@@ -38,12 +42,15 @@ where
 
     fn try_send(&self, msg: M) -> Result<(), SendError<M>>;
 
-    fn send(&self, msg: M) -> Result<OneshotReceiver<M::Result>, SendError<M>>;
+    fn send(&self, msg: M) -> Result<ReplyReceiver<M::Result>, SendError<M>>;
 
     fn boxed(&self) -> Box<dyn Sender<M> + Sync>;
 
     fn hash(&self) -> usize;
 
+    /// Returns the id of the receiving actor.
+    fn actor_id(&self) -> ActorId;
+
     fn connected(&self) -> bool;
 
     /// Returns a downgraded sender, where the sender is downgraded into its weak counterpart.
@@ -64,7 +71,7 @@ where
         (**self).try_send(msg)
     }
 
-    fn send(&self, msg: M) -> Result<OneshotReceiver<<M as Message>::Result>, SendError<M>> {
+    fn send(&self, msg: M) -> Result<ReplyReceiver<M::Result>, SendError<M>> {
         (**self).send(msg)
     }
 
@@ -76,6 +83,10 @@ where
         (**self).hash()
     }
 
+    fn actor_id(&self) -> ActorId {
+        (**self).actor_id()
+    }
+
     fn connected(&self) -> bool {
         (**self).connected()
     }
@@ -98,6 +109,10 @@ where
     fn boxed(&self) -> Box<dyn WeakSender<M> + Sync>;
 }
 
+/// Receiver of a reply, along with the state of the request for telling why the reply was
+/// dropped.
+pub type ReplyReceiver<R> = (OneshotReceiver<R>, Arc<ReplyState>);
+
 /// The transmission end of a channel which is used to send values.
 ///
 /// This is created by the `channel` method.
@@ -153,8 +168,12 @@ impl<A: Actor> PartialEq for WeakAddressSender<A> {
 
 impl<A: Actor> Eq for WeakAddressSender<A> {}
 
+// only checked at compile time through its impls
+#[allow(dead_code)]
 trait AssertKinds: Send + Sync + Clone {}
 
+impl<A: Actor> AssertKinds for AddressSender<A> {}
+
 /// The receiving end of a channel which implements the `Stream` trait.
 ///
 /// This is a concrete implementation of a stream which can be used to represent
@@ -186,8 +205,17 @@ struct Inner<A: Actor> {
     // Number of senders in existence.
     num_senders: AtomicUsize,
 
+    // Highest number of messages the channel has held at once.
+    high_water: AtomicUsize,
+
     // Handle to the receiver's task.
     recv_task: AtomicWaker,
+
+    // Identity of the actor receiving from the channel.
+    id: ActorId,
+
+    // Observers of the events emitted by the actor.
+    observers: Observers,
 }
 
 // Struct representation of `Inner::state`.
@@ -271,7 +299,10 @@ pub fn channel<A: Actor>(buffer: usize) -> (AddressSender<A>, AddressReceiver<A>
         message_queue: Queue::new(),
         parked_queue: Queue::new(),
         num_senders: AtomicUsize::new(1),
+        high_water: AtomicUsize::new(0),
         recv_task: AtomicWaker::new(),
+        id: ActorId::next(),
+        observers: Observers::default(),
     });
 
     let tx = AddressSender {
@@ -291,6 +322,21 @@ pub fn channel<A: Actor>(buffer: usize) -> (AddressSender<A>, AddressReceiver<A>
 //
 //
 impl<A: Actor> AddressSender<A> {
+    /// Id of the receiving actor
+    pub(crate) fn id(&self) -> ActorId {
+        self.inner.id
+    }
+
+    /// Number of messages waiting in the channel
+    pub(crate) fn queued(&self) -> usize {
+        self.inner.queued()
+    }
+
+    /// Observers of the receiving actor
+    pub(crate) fn observers(&self) -> &Observers {
+        &self.inner.observers
+    }
+
     /// Is the channel still open
     pub fn connected(&self) -> bool {
         let curr = self.inner.state.load(SeqCst);
@@ -302,7 +348,7 @@ impl<A: Actor> AddressSender<A> {
     /// Attempts to send a message on this `Sender<A>` with blocking.
     ///
     /// This function must be called from inside of a task.
-    pub fn send<M>(&self, msg: M) -> Result<OneshotReceiver<M::Result>, SendError<M>>
+    pub fn send<M>(&self, msg: M) -> Result<ReplyReceiver<M::Result>, SendError<M>>
     where
         A: Handler<M>,
         A::Context: ToEnvelope<A, M>,
@@ -336,9 +382,12 @@ impl<A: Actor> AddressSender<A> {
             self.park();
         }
         let (tx, rx) = oneshot_channel();
-        let env = <A::Context as ToEnvelope<A, M>>::pack(msg, Some(tx));
+        let mut env = <A::Context as ToEnvelope<A, M>>::pack(msg, Some(tx));
+        let reply = Arc::new(ReplyState::default());
+        env.set_reply_state(Arc::clone(&reply));
         self.queue_push_and_signal(env);
-        Ok(rx)
+        audit::record::<A, M>(self.inner.id);
+        Ok((rx, reply))
     }
 
     /// Attempts to send a message on this `Sender<A>` without blocking.
@@ -368,6 +417,7 @@ impl<A: Actor> AddressSender<A> {
         }
         let env = <A::Context as ToEnvelope<A, M>>::pack(msg, None);
         self.queue_push_and_signal(env);
+        audit::record::<A, M>(self.inner.id);
         Ok(())
     }
 
@@ -388,11 +438,51 @@ impl<A: Actor> AddressSender<A> {
             // We ignore the boolean (indicating to park and wait) in the Some, and queue the
             // message regardless.
             let env = <A::Context as ToEnvelope<A, M>>::pack(msg, None);
+            self.queue_push_and_signal(env);
+            audit::record::<A, M>(self.inner.id);
+            Ok(())
+        }
+    }
+
+    /// Queue an already packed envelope without blocking.
+    ///
+    /// This function does not park current task.
+    pub(crate) fn do_send_envelope(&self, env: Envelope<A>) -> Result<(), Envelope<A>> {
+        if self.inc_num_messages().is_none() {
+            Err(env)
+        } else {
             self.queue_push_and_signal(env);
             Ok(())
         }
     }
 
+    /// Polls whether a message can be sent without exceeding the channel capacity, registering
+    /// the current task to be woken once the receiver made room.
+    ///
+    /// Fails if the channel is closed.
+    pub(crate) fn poll_ready(&self, cx: &mut task::Context<'_>) -> Poll<Result<(), ()>> {
+        if !self.connected() {
+            return Poll::Ready(Err(()));
+        }
+
+        self.poll_unparked(true, Some(cx)).map(Ok)
+    }
+
+    /// Returns whether this sender is parked, i.e. the last message it sent filled the channel
+    /// and the receiver has not made room since.
+    pub(crate) fn is_parked(&self) -> bool {
+        self.poll_unparked(false, None).is_pending()
+    }
+
+    /// Returns a sender sharing the parked state of this one, to wait for room in the channel
+    /// on its behalf.
+    pub(crate) fn share(&self) -> AddressSender<A> {
+        let mut tx = self.clone();
+        tx.sender_task = Arc::clone(&self.sender_task);
+        tx.maybe_parked = Arc::clone(&self.maybe_parked);
+        tx
+    }
+
     /// Downgrade to `WeakAddressSender` which can later be upgraded
     pub fn downgrade(&self) -> WeakAddressSender<A> {
         WeakAddressSender {
@@ -428,6 +518,7 @@ impl<A: Actor> AddressSender<A> {
                 .compare_exchange(curr, next, SeqCst, SeqCst)
             {
                 Ok(_) => {
+                    self.inner.high_water.fetch_max(state.num_messages, Relaxed);
                     return Some(state.num_messages);
                 }
                 Err(actual) => curr = actual,
@@ -495,7 +586,7 @@ where
     fn try_send(&self, msg: M) -> Result<(), SendError<M>> {
         self.try_send(msg, true)
     }
-    fn send(&self, msg: M) -> Result<OneshotReceiver<M::Result>, SendError<M>> {
+    fn send(&self, msg: M) -> Result<ReplyReceiver<M::Result>, SendError<M>> {
         self.send(msg)
     }
     fn boxed(&self) -> Box<dyn Sender<M> + Sync> {
@@ -507,6 +598,10 @@ where
         hash as usize
     }
 
+    fn actor_id(&self) -> ActorId {
+        self.inner.id
+    }
+
     fn connected(&self) -> bool {
         self.connected()
     }
@@ -617,7 +712,37 @@ where
 // ===== impl SenderProducer =====
 //
 //
+impl<A: Actor> Clone for AddressSenderProducer<A> {
+    fn clone(&self) -> Self {
+        AddressSenderProducer {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
 impl<A: Actor> AddressSenderProducer<A> {
+    /// Id of the receiving actor
+    pub(crate) fn id(&self) -> ActorId {
+        self.inner.id
+    }
+
+    /// Get a weak sender side of the channel, without connecting a sender
+    pub(crate) fn downgrade(&self) -> WeakAddressSender<A> {
+        WeakAddressSender {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
+    /// Observers of the receiving actor
+    pub(crate) fn observers(&self) -> &Observers {
+        &self.inner.observers
+    }
+
+    /// Wakes the receiving task
+    pub(crate) fn wake(&self) {
+        self.inner.recv_task.wake();
+    }
+
     /// Are any senders connected
     pub fn connected(&self) -> bool {
         self.inner.num_senders.load(SeqCst) != 0
@@ -628,6 +753,17 @@ impl<A: Actor> AddressSenderProducer<A> {
         self.inner.buffer.load(Relaxed)
     }
 
+    /// Get highest number of messages queued at once
+    pub fn high_water(&self) -> usize {
+        self.inner.high_water.load(Relaxed)
+    }
+
+    /// Get a handle for observing the number of queued messages from any thread
+    pub(crate) fn queued_handle(&self) -> Weak<dyn QueuedMessages> {
+        let inner: Arc<dyn QueuedMessages> = self.inner.clone();
+        Arc::downgrade(&inner)
+    }
+
     /// Set channel capacity
     ///
     /// This method wakes up all waiting senders if new capacity is greater
@@ -690,6 +826,11 @@ impl<A: Actor> AddressReceiver<A> {
         self.inner.buffer.load(Relaxed)
     }
 
+    /// Returns the number of messages waiting to be received.
+    pub(crate) fn queued(&self) -> usize {
+        self.inner.queued()
+    }
+
     /// Sets the channel capacity.
     ///
     /// This method wakes up all waiting senders if the new capacity
@@ -814,6 +955,9 @@ impl<A: Actor> Drop for AddressReceiver<A> {
         // close
         self.inner.set_closed();
 
+        // End the streams of the observers
+        self.inner.observers.clear();
+
         // Wake up any threads waiting as they'll see that we've closed the
         // channel and will continue on their merry way.
         while let Some(task) = unsafe { self.inner.parked_queue.pop_spin() } {
@@ -868,6 +1012,17 @@ impl<A: Actor> Inner<A> {
     }
 }
 
+/// Type-erased view on the number of messages queued in a channel.
+pub(crate) trait QueuedMessages: Send + Sync {
+    fn queued(&self) -> usize;
+}
+
+impl<A: Actor> QueuedMessages for Inner<A> {
+    fn queued(&self) -> usize {
+        decode_state(self.state.load(SeqCst)).num_messages
+    }
+}
+
 unsafe impl<A: Actor> Send for Inner<A> {}
 unsafe impl<A: Actor> Sync for Inner<A> {}
 
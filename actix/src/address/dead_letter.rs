@@ -0,0 +1,70 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+};
+
+use log::warn;
+
+use super::Recipient;
+use crate::handler::Message;
+
+thread_local! {
+    /// Dead-letter recipients of the arbiter running on the current thread, keyed by message
+    /// type.
+    static DEAD_LETTERS: RefCell<HashMap<TypeId, Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+pub(crate) fn set<M>(recipient: Recipient<M>) -> Option<Recipient<M>>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    DEAD_LETTERS.with(|letters| {
+        letters
+            .borrow_mut()
+            .insert(TypeId::of::<M>(), Box::new(recipient))
+            .and_then(|prev| prev.downcast().ok())
+            .map(|prev| *prev)
+    })
+}
+
+pub(crate) fn remove<M>() -> Option<Recipient<M>>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    DEAD_LETTERS.with(|letters| {
+        letters
+            .borrow_mut()
+            .remove(&TypeId::of::<M>())
+            .and_then(|prev| prev.downcast().ok())
+            .map(|prev| *prev)
+    })
+}
+
+/// Hands `msg`, refused by a mailbox, to the dead-letter recipient for `M` of the current
+/// arbiter, dropping it if there is none.
+pub(crate) fn deliver<M>(msg: M)
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    let recipient = DEAD_LETTERS.with(|letters| {
+        letters
+            .borrow()
+            .get(&TypeId::of::<M>())
+            .and_then(|recipient| recipient.downcast_ref::<Recipient<M>>())
+            .cloned()
+    });
+
+    if let Some(recipient) = recipient {
+        // not routed again, a dead letter is dropped if its recipient is gone as well
+        if recipient.tx.do_send(msg).is_err() {
+            warn!(
+                "Dead letter {} dropped, its recipient is gone",
+                type_name::<M>()
+            );
+        }
+    }
+}
@@ -1,9 +1,17 @@
+use std::sync::Arc;
+
 use tokio::sync::oneshot::Sender;
 
 use crate::{
-    actor::{Actor, AsyncContext},
+    actor::{Actor, ActorState},
+    clock::Instant,
     context::Context,
-    handler::{Handler, Message, MessageResponse},
+    contextimpl::AsyncContextParts,
+    deadline,
+    handler::{Handler, Message},
+    handler_timeout, memo, middleware, panic,
+    propagation::{self, Captured},
+    replies::{self, ReplyState},
 };
 
 /// Converter trait, packs message into a suitable envelope.
@@ -19,6 +27,10 @@ where
 pub trait EnvelopeProxy<A: Actor> {
     /// handle message within new actor and context
     fn handle(&mut self, act: &mut A, ctx: &mut A::Context);
+
+    #[doc(hidden)]
+    /// Sets the state of the request to update while the message is handled, if supported.
+    fn set_reply_state(&mut self, _reply: Arc<ReplyState>) {}
 }
 
 impl<A, M> ToEnvelope<A, M> for Context<A>
@@ -38,11 +50,17 @@ impl<A: Actor> Envelope<A> {
     pub fn new<M>(msg: M, tx: Option<Sender<M::Result>>) -> Self
     where
         A: Handler<M>,
-        A::Context: AsyncContext<A>,
+        A::Context: AsyncContextParts<A>,
         M: Message + Send + 'static,
         M::Result: Send,
     {
-        Envelope(Box::new(SyncEnvelopeProxy { tx, msg: Some(msg) }))
+        Envelope(Box::new(SyncEnvelopeProxy {
+            tx,
+            msg: Some(msg),
+            cx: propagation::capture(),
+            deadline: deadline::current(),
+            reply: None,
+        }))
     }
 
     pub fn with_proxy(proxy: Box<dyn EnvelopeProxy<A> + Send>) -> Self {
@@ -54,6 +72,10 @@ impl<A: Actor> EnvelopeProxy<A> for Envelope<A> {
     fn handle(&mut self, act: &mut A, ctx: &mut <A as Actor>::Context) {
         self.0.handle(act, ctx)
     }
+
+    fn set_reply_state(&mut self, reply: Arc<ReplyState>) {
+        self.0.set_reply_state(reply)
+    }
 }
 
 pub struct SyncEnvelopeProxy<M>
@@ -63,6 +85,12 @@ where
 {
     msg: Option<M>,
     tx: Option<Sender<M::Result>>,
+    /// The context of the sender, see [`ContextPropagator`](crate::ContextPropagator).
+    cx: Option<Captured>,
+    /// The deadline of the message, see [`Addr::send_deadline`](crate::Addr::send_deadline).
+    deadline: Option<Instant>,
+    /// The state of the request, telling its sender why the reply was dropped.
+    reply: Option<Arc<ReplyState>>,
 }
 
 impl<A, M> EnvelopeProxy<A> for SyncEnvelopeProxy<M>
@@ -70,7 +98,7 @@ where
     M: Message + Send + 'static,
     M::Result: Send,
     A: Actor + Handler<M>,
-    A::Context: AsyncContext<A>,
+    A::Context: AsyncContextParts<A>,
 {
     fn handle(&mut self, act: &mut A, ctx: &mut <A as Actor>::Context) {
         let tx = self.tx.take();
@@ -79,8 +107,84 @@ where
         }
 
         if let Some(msg) = self.msg.take() {
-            let fut = <A as Handler<M>>::handle(act, msg, ctx);
-            fut.handle(ctx, tx)
+            let reply = self.reply.take();
+            let handle = || {
+                replies::scope(reply.clone(), Some(ctx.parts().replies().clone()), || {
+                    panic::guard::<A, M, _>(act, ctx, |act, ctx| {
+                        let timeout = ctx.parts().handler_timeout();
+                        handler_timeout::guard::<M, _>(timeout, reply, tx, |tx| {
+                            if let Some((msg, tx)) = middleware::handle(act, msg, ctx, tx) {
+                                memo::handle(act, msg, ctx, tx)
+                            }
+                        });
+                    })
+                })
+            };
+            deadline::scope(self.deadline, || match self.cx {
+                Some(ref cx) => cx.scope(handle),
+                None => handle(),
+            })
+        }
+    }
+
+    fn set_reply_state(&mut self, reply: Arc<ReplyState>) {
+        self.reply = Some(reply);
+    }
+}
+
+/// Envelope that reports the execution state of the actor once it is handled.
+pub(crate) struct StateProbeProxy {
+    tx: Option<Sender<ActorState>>,
+}
+
+impl StateProbeProxy {
+    pub(crate) fn new(tx: Sender<ActorState>) -> Self {
+        StateProbeProxy { tx: Some(tx) }
+    }
+}
+
+impl<A> EnvelopeProxy<A> for StateProbeProxy
+where
+    A: Actor,
+    A::Context: AsyncContextParts<A>,
+{
+    fn handle(&mut self, _: &mut A, ctx: &mut <A as Actor>::Context) {
+        if let Some(tx) = self.tx.take() {
+            let _ = tx.send(ctx.parts().state());
+        }
+    }
+}
+
+/// Envelope that registers a waiter notified once the actor has reached a state, and
+/// optionally stops the actor once all messages queued before it are handled.
+pub(crate) struct StateWaiterProxy {
+    tx: Option<Sender<()>>,
+    state: ActorState,
+    stop: bool,
+}
+
+impl StateWaiterProxy {
+    pub(crate) fn new(tx: Sender<()>, state: ActorState, stop: bool) -> Self {
+        StateWaiterProxy {
+            tx: Some(tx),
+            state,
+            stop,
+        }
+    }
+}
+
+impl<A> EnvelopeProxy<A> for StateWaiterProxy
+where
+    A: Actor,
+    A::Context: AsyncContextParts<A>,
+{
+    fn handle(&mut self, _: &mut A, ctx: &mut <A as Actor>::Context) {
+        if let Some(tx) = self.tx.take() {
+            let parts = ctx.parts();
+            parts.wait_state(self.state, tx);
+            if self.stop {
+                parts.stop();
+            }
         }
     }
 }
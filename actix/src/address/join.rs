@@ -0,0 +1,45 @@
+use futures_util::future::{join, join3, join4, join5, Join, Join3, Join4, Join5};
+
+use super::{Addr, Request, ToEnvelope};
+use crate::{
+    actor::Actor,
+    handler::{Handler, Message},
+};
+
+/// A tuple of messages which can be sent to the same actor at once.
+///
+/// This is implemented for tuples of two to five messages. See [`Addr::send_join`].
+pub trait SendJoin<A: Actor> {
+    /// The future resolving to the tuple of replies.
+    type Future;
+
+    /// Sends every message of the tuple to `addr`.
+    fn send_join(self, addr: &Addr<A>) -> Self::Future;
+}
+
+macro_rules! send_join_impl {
+    ($join:ident, $fn:ident; $($msg:ident),+) => {
+        impl<A, $($msg),+> SendJoin<A> for ($($msg,)+)
+        where
+            A: Actor $(+ Handler<$msg>)+,
+            $(
+                $msg: Message + Send + 'static,
+                $msg::Result: Send,
+                A::Context: ToEnvelope<A, $msg>,
+            )+
+        {
+            type Future = $join<$(Request<A, $msg>),+>;
+
+            #[allow(non_snake_case)]
+            fn send_join(self, addr: &Addr<A>) -> Self::Future {
+                let ($($msg,)+) = self;
+                $fn($(addr.send($msg)),+)
+            }
+        }
+    };
+}
+
+send_join_impl!(Join, join; M1, M2);
+send_join_impl!(Join3, join3; M1, M2, M3);
+send_join_impl!(Join4, join4; M1, M2, M3, M4);
+send_join_impl!(Join5, join5; M1, M2, M3, M4, M5);
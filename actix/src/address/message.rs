@@ -1,6 +1,7 @@
 use std::{
     future::Future,
     pin::Pin,
+    sync::Arc,
     task::{self, Poll},
     time::Duration,
 };
@@ -9,10 +10,15 @@ use pin_project_lite::pin_project;
 use tokio::sync::oneshot;
 
 use super::{
-    channel::{AddressSender, Sender},
+    channel::{AddressSender, ReplyReceiver, Sender},
     MailboxError, SendError,
 };
-use crate::{clock::Sleep, handler::Message};
+use crate::{
+    clock::{Instant, Sleep},
+    deadline,
+    handler::Message,
+    replies::ReplyState,
+};
 
 pub type Request<A, M> = MsgRequest<AddressSender<A>, M>;
 
@@ -29,9 +35,13 @@ pin_project! {
         M::Result: Send
     {
         rx: Option<oneshot::Receiver<M::Result>>,
+        // tells why the reply was dropped
+        reply: Option<Arc<ReplyState>>,
         info: Option<(S, M)>,
         #[pin]
         timeout: Option<Sleep>,
+        // deadline for the message, in case it is queued later on
+        deadline: Option<Instant>,
     }
 }
 
@@ -41,11 +51,14 @@ where
     M: Message + Send,
     M::Result: Send,
 {
-    pub(crate) fn new(rx: Option<oneshot::Receiver<M::Result>>, info: Option<(S, M)>) -> Self {
+    pub(crate) fn new(rx: Option<ReplyReceiver<M::Result>>, info: Option<(S, M)>) -> Self {
+        let (rx, reply) = rx.unzip();
         Self {
             rx,
+            reply,
             info,
             timeout: None,
+            deadline: deadline::current(),
         }
     }
 
@@ -59,6 +72,49 @@ where
         self.timeout = Some(actix_rt::time::sleep(dur));
         self
     }
+
+    /// Resolves to `value` instead of failing with a [`MailboxError`].
+    ///
+    /// Combined with [`timeout`](Self::timeout), this turns a best-effort request into a single
+    /// expression:
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// # #[derive(Message)]
+    /// # #[rtype(result = "Option<String>")]
+    /// # struct Lookup(&'static str);
+    /// # struct Cache;
+    /// # impl Actor for Cache { type Context = Context<Self>; }
+    /// # impl Handler<Lookup> for Cache {
+    /// #     type Result = Option<String>;
+    /// #     fn handle(&mut self, _: Lookup, _: &mut Context<Self>) -> Self::Result { None }
+    /// # }
+    /// # #[actix::main] async fn main() {
+    /// # let cache = Cache.start();
+    /// let cached = cache
+    ///     .send(Lookup("key"))
+    ///     .timeout(Duration::from_millis(10))
+    ///     .or_default(None)
+    ///     .await;
+    /// # assert_eq!(cached, None);
+    /// # }
+    /// ```
+    pub async fn or_default(self, value: M::Result) -> M::Result {
+        self.or_else_default(|_| value).await
+    }
+
+    /// Resolves to the value computed by `f` from the error instead of failing with a
+    /// [`MailboxError`].
+    pub async fn or_else_default<F>(self, f: F) -> M::Result
+    where
+        F: FnOnce(MailboxError) -> M::Result,
+    {
+        match self.await {
+            Ok(res) => res,
+            Err(err) => f(err),
+        }
+    }
 }
 
 impl<S, M> Future for MsgRequest<S, M>
@@ -73,8 +129,11 @@ where
         let this = self.project();
 
         if let Some((sender, msg)) = this.info.take() {
-            match sender.send(msg) {
-                Ok(rx) => *this.rx = Some(rx),
+            match deadline::scope(*this.deadline, || sender.send(msg)) {
+                Ok((rx, reply)) => {
+                    *this.rx = Some(rx);
+                    *this.reply = Some(reply);
+                }
                 Err(SendError::Full(msg)) => {
                     *this.info = Some((sender, msg));
                     return Poll::Pending;
@@ -85,7 +144,11 @@ where
 
         match this.rx {
             Some(rx) => match Pin::new(rx).poll(cx) {
-                Poll::Ready(res) => Poll::Ready(res.map_err(|_| MailboxError::Closed)),
+                Poll::Ready(res) => Poll::Ready(res.map_err(|_| {
+                    this.reply
+                        .as_ref()
+                        .map_or(MailboxError::Closed, |reply| reply.error())
+                })),
                 Poll::Pending => match this.timeout.as_pin_mut() {
                     Some(timeout) => timeout.poll(cx).map(|_| Err(MailboxError::Timeout)),
                     None => Poll::Pending,
@@ -1,22 +1,44 @@
 use std::{
     error, fmt,
+    future::{self, Future},
     hash::{Hash, Hasher},
+    time::Duration,
 };
 
+use actix_rt::Arbiter;
+use tokio::runtime::Handle;
+
+mod blocking;
 pub(crate) mod channel;
+pub(crate) mod dead_letter;
 mod envelope;
+mod join;
 mod message;
 mod queue;
+mod reply;
+mod sink;
 
 pub(crate) use self::channel::{AddressReceiver, AddressSenderProducer};
 use self::channel::{AddressSender, Sender, WeakAddressSender, WeakSender};
+use self::envelope::{StateProbeProxy, StateWaiterProxy};
 pub use self::{
+    blocking::BlockingSender,
     envelope::{Envelope, EnvelopeProxy, ToEnvelope},
+    join::SendJoin,
     message::{RecipientRequest, Request},
+    reply::{Reply, ReplyFailed},
+    sink::AddrSink,
 };
 use crate::{
-    actor::Actor,
+    actor::{Actor, ActorState},
+    clock::{sleep, Instant},
+    contextimpl::AsyncContextParts,
+    deadline,
+    directory::ActorId,
     handler::{Handler, Message},
+    mailbox::DEFAULT_CAPACITY,
+    observe::{Observable, Observer},
+    progress::{self, WithProgress},
 };
 
 pub enum SendError<T> {
@@ -29,6 +51,11 @@ pub enum SendError<T> {
 pub enum MailboxError {
     Closed,
     Timeout,
+    /// The handler completed without replying.
+    NoResponse,
+    /// The handler did not reply within the handler timeout of the actor, see
+    /// [`Context::set_handler_timeout`](crate::Context::set_handler_timeout).
+    HandlerTimeout,
 }
 
 impl fmt::Debug for MailboxError {
@@ -42,6 +69,8 @@ impl fmt::Display for MailboxError {
         match self {
             MailboxError::Closed => write!(fmt, "Mailbox has closed"),
             MailboxError::Timeout => write!(fmt, "Message delivery timed out"),
+            MailboxError::NoResponse => write!(fmt, "Message handled without a response"),
+            MailboxError::HandlerTimeout => write!(fmt, "Message handler timed out"),
         }
     }
 }
@@ -92,19 +121,52 @@ impl<A: Actor> Addr<A> {
         self.tx.connected()
     }
 
+    /// Returns the id of the actor.
+    #[inline]
+    pub(crate) fn actor_id(&self) -> ActorId {
+        self.tx.id()
+    }
+
+    /// Returns the number of messages waiting in the mailbox.
+    #[inline]
+    pub(crate) fn queued(&self) -> usize {
+        self.tx.queued()
+    }
+
+    /// Returns a future waiting for room in the mailbox on behalf of this address, if the last
+    /// message sent through it filled the mailbox.
+    ///
+    /// The future resolves to an address to send the next message through, also once the
+    /// mailbox has been closed.
+    pub(crate) fn ready(&self) -> Option<impl Future<Output = Addr<A>>> {
+        if !self.tx.is_parked() {
+            return None;
+        }
+
+        let tx = self.tx.share();
+        Some(async move {
+            let _ = future::poll_fn(|cx| tx.poll_ready(cx)).await;
+            Addr::new(tx)
+        })
+    }
+
     /// Sends a message unconditionally, ignoring any potential errors.
     ///
     /// The message is always queued, even if the mailbox for the receiver is full. If the mailbox
-    /// is closed, the message is silently dropped.
+    /// is closed, the message goes to the dead-letter recipient for `M` of the current arbiter,
+    /// see [`ArbiterExt::set_dead_letter_for`](crate::ArbiterExt::set_dead_letter_for), and is
+    /// dropped if there is none.
     #[inline]
     pub fn do_send<M>(&self, msg: M)
     where
-        M: Message + Send,
+        M: Message + Send + 'static,
         M::Result: Send,
         A: Handler<M>,
         A::Context: ToEnvelope<A, M>,
     {
-        let _ = self.tx.do_send(msg);
+        if let Err(SendError::Closed(msg)) = self.tx.do_send(msg) {
+            dead_letter::deliver(msg);
+        }
     }
 
     /// Tries to send a message.
@@ -121,6 +183,52 @@ impl<A: Actor> Addr<A> {
         self.tx.try_send(msg, true)
     }
 
+    /// Sends a message without waiting for a response, retrying for a while if the mailbox is
+    /// full.
+    ///
+    /// The message is sent as with [`try_send`](Self::try_send), up to `attempts` times but at
+    /// least once, waiting `delay` between attempts on a task spawned on the current arbiter.
+    /// If the mailbox is still full after the last attempt, or is closed, the message goes to the
+    /// dead-letter recipient for `M` of the current arbiter, see
+    /// [`ArbiterExt::set_dead_letter_for`](crate::ArbiterExt::set_dead_letter_for), and is
+    /// dropped if there is none.
+    ///
+    /// This sits between [`do_send`](Self::do_send), which queues past the capacity of the
+    /// mailbox, and [`send`](Self::send), which waits for room as long as it takes.
+    ///
+    /// Retrying needs a running arbiter. Called outside of one, e.g. from a plain thread or
+    /// before the system runs, a message refused by a full mailbox is dead-lettered right away.
+    pub fn do_send_retry<M>(&self, msg: M, attempts: usize, delay: Duration)
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+    {
+        let running = Arbiter::try_current().is_some() && Handle::try_current().is_ok();
+        let msg = match self.tx.try_send(msg, true) {
+            Ok(()) => return,
+            Err(SendError::Full(msg)) if attempts > 1 && running => msg,
+            Err(SendError::Full(msg) | SendError::Closed(msg)) => return dead_letter::deliver(msg),
+        };
+
+        // shares the parked state, so attempts fail until the mailbox made room
+        let tx = self.tx.share();
+        actix_rt::spawn(async move {
+            let mut msg = msg;
+            for attempt in 2..=attempts {
+                sleep(delay).await;
+                match tx.try_send(msg, true) {
+                    Ok(()) => return,
+                    Err(SendError::Full(rejected)) if attempt < attempts => msg = rejected,
+                    Err(SendError::Full(msg) | SendError::Closed(msg)) => {
+                        return dead_letter::deliver(msg)
+                    }
+                }
+            }
+        });
+    }
+
     /// Sends an asynchronous message and waits for a response.
     ///
     /// The communication channel to the actor is bounded. If the returned request future gets
@@ -140,12 +248,555 @@ impl<A: Actor> Addr<A> {
         }
     }
 
+    /// Sends a message that has to be handled by `deadline`, and waits for a response.
+    ///
+    /// The handler gets the deadline from [`message_deadline`](crate::Context::message_deadline),
+    /// e.g. to give up on work that would not finish in time. The deadline propagates to the
+    /// messages the handler sends, and to the futures it spawns and the messages they send, so
+    /// that downstream actors work with the time left of the same budget rather than with
+    /// timeouts of their own. A deadline passed to this method replaces a propagated one.
+    ///
+    /// The request fails with [`MailboxError::Timeout`] once the deadline has passed.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use actix::{clock::Instant, prelude::*};
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "Option<Duration>")]
+    /// struct TimeLeft;
+    ///
+    /// struct Worker;
+    ///
+    /// impl Actor for Worker {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<TimeLeft> for Worker {
+    ///     type Result = Option<Duration>;
+    ///
+    ///     fn handle(&mut self, _: TimeLeft, ctx: &mut Context<Self>) -> Self::Result {
+    ///         let deadline = ctx.message_deadline()?;
+    ///         Some(deadline.saturating_duration_since(Instant::now()))
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let addr = Worker.start();
+    ///     let deadline = Instant::now() + Duration::from_secs(1);
+    ///
+    ///     let left = addr.send_deadline(TimeLeft, deadline).await.unwrap().unwrap();
+    ///     assert!(left <= Duration::from_secs(1));
+    ///     assert_eq!(addr.send(TimeLeft).await.unwrap(), None);
+    /// }
+    /// ```
+    pub fn send_deadline<M>(&self, msg: M, deadline: Instant) -> Request<A, M>
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+    {
+        deadline::scope(Some(deadline), || self.send(msg))
+            .timeout(deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Sends a message and delivers the handler's result to `reply_to` as a [`Reply`], instead
+    /// of returning a request to await.
+    ///
+    /// This decouples submitting a command from consuming its result. If the message can not
+    /// be handled, e.g. because the actor has stopped, a warning is logged; use
+    /// [`send_reply_to_or`](Self::send_reply_to_or) to be notified instead.
+    ///
+    /// The reply is routed by a task spawned on the current arbiter.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "u64")]
+    /// struct CreateOrder;
+    ///
+    /// struct Orders;
+    ///
+    /// impl Actor for Orders {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<CreateOrder> for Orders {
+    ///     type Result = u64;
+    ///
+    ///     fn handle(&mut self, _: CreateOrder, _: &mut Self::Context) -> u64 {
+    ///         42
+    ///     }
+    /// }
+    ///
+    /// struct Projection;
+    ///
+    /// impl Actor for Projection {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Reply<u64>> for Projection {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, Reply(id): Reply<u64>, _: &mut Self::Context) {
+    ///         assert_eq!(id, 42);
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let projection = Projection.start();
+    ///     Orders
+    ///         .start()
+    ///         .send_reply_to(CreateOrder, projection.recipient());
+    /// }
+    /// ```
+    pub fn send_reply_to<M>(&self, msg: M, reply_to: Recipient<Reply<M::Result>>)
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+    {
+        actix_rt::spawn(reply::route_reply(self.send(msg), reply_to, None));
+    }
+
+    /// Sends a message like [`send_reply_to`](Self::send_reply_to), delivering a
+    /// [`ReplyFailed`] to `on_error` if the message can not be handled.
+    pub fn send_reply_to_or<M>(
+        &self,
+        msg: M,
+        reply_to: Recipient<Reply<M::Result>>,
+        on_error: Recipient<ReplyFailed>,
+    ) where
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+    {
+        actix_rt::spawn(reply::route_reply(self.send(msg), reply_to, Some(on_error)));
+    }
+
+    /// Sends a message and calls `callback` with the result, instead of returning a request to
+    /// await.
+    ///
+    /// This suits callback-style APIs, or results that only trigger a side effect. The callback
+    /// runs on the current arbiter, i.e. the sender's, once the response arrives or the message
+    /// fails. If that arbiter stops first, the callback is dropped without being called.
+    ///
+    /// # Panics
+    /// Panics if called outside of an arbiter.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "u32")]
+    /// struct Double(u32);
+    ///
+    /// struct Doubler;
+    ///
+    /// impl Actor for Doubler {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Double> for Doubler {
+    ///     type Result = u32;
+    ///
+    ///     fn handle(&mut self, Double(n): Double, _: &mut Self::Context) -> u32 {
+    ///         n * 2
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     Doubler.start().send_then(Double(21), |res| {
+    ///         assert_eq!(res, Ok(42));
+    ///         System::current().stop();
+    ///     });
+    /// }
+    /// ```
+    pub fn send_then<M, F>(&self, msg: M, callback: F)
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        F: FnOnce(Result<M::Result, MailboxError>) + 'static,
+    {
+        let req = self.send(msg);
+        actix_rt::spawn(async move { callback(req.await) });
+    }
+
+    /// Sends a message like [`send`](Self::send), retrying if it fails with
+    /// [`MailboxError::Closed`].
+    ///
+    /// This smooths over the restart window of a supervised actor, which drops the pending
+    /// responses of the failed instance. The message is sent at most `attempts` times, waiting
+    /// `backoff` between attempts, after which the last error is returned. Timeouts are not
+    /// retried.
+    ///
+    /// Since a delivered message is consumed by the handler, every attempt sends a clone. The
+    /// message is sent once the returned future is polled.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// # #[derive(Clone, Message)]
+    /// # #[rtype(result = "()")]
+    /// # struct Ping;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor { type Context = Context<Self>; }
+    /// # impl Handler<Ping> for MyActor {
+    /// #     type Result = ();
+    /// #     fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+    /// # }
+    /// # #[actix::main] async fn main() {
+    /// let addr = MyActor.start();
+    /// addr.send_retry_on_closed(Ping, 3, Duration::from_millis(10))
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub fn send_retry_on_closed<M>(
+        &self,
+        msg: M,
+        attempts: usize,
+        backoff: Duration,
+    ) -> impl Future<Output = Result<M::Result, MailboxError>>
+    where
+        M: Message + Clone + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+    {
+        let addr = self.clone();
+        async move {
+            let mut attempt = 1;
+            loop {
+                match addr.send(msg.clone()).await {
+                    Err(MailboxError::Closed) if attempt < attempts => {
+                        attempt += 1;
+                        sleep(backoff).await;
+                    }
+                    res => return res,
+                }
+            }
+        }
+    }
+
+    /// Sends a tuple of messages and waits for all of the responses.
+    ///
+    /// Each message is sent as with [`send`](Self::send). The returned future resolves to a
+    /// tuple holding the result of every request, in the order the messages were given, so
+    /// every reply keeps its own type and delivery error. Tuples of two to five messages are
+    /// supported.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor { type Context = Context<Self>; }
+    /// # #[derive(Message)] #[rtype(result = "usize")] struct Count;
+    /// # #[derive(Message)] #[rtype(result = "String")] struct Name;
+    /// # impl Handler<Count> for MyActor {
+    /// #     type Result = usize;
+    /// #     fn handle(&mut self, _: Count, _: &mut Context<Self>) -> usize { 42 }
+    /// # }
+    /// # impl Handler<Name> for MyActor {
+    /// #     type Result = String;
+    /// #     fn handle(&mut self, _: Name, _: &mut Context<Self>) -> String { "actor".to_owned() }
+    /// # }
+    /// # #[actix::main] async fn main() {
+    /// let addr = MyActor.start();
+    /// let (count, name) = addr.send_join((Count, Name)).await;
+    /// assert_eq!(count.unwrap(), 42);
+    /// assert_eq!(name.unwrap(), "actor");
+    /// # }
+    /// ```
+    pub fn send_join<T>(&self, msgs: T) -> T::Future
+    where
+        T: SendJoin<A>,
+    {
+        msgs.send_join(self)
+    }
+
+    /// Sends a message and waits for its final result, passing the progress reported by the
+    /// actor in the meantime to `on_progress`.
+    ///
+    /// The message is delivered wrapped in a [`WithProgress`], whose
+    /// [`ProgressResponder`](crate::ProgressResponder) reports progress and then finishes the
+    /// request with the final result. Progress is passed to `on_progress` in the order it was
+    /// reported, and all of it before the returned future resolves. If the responder is dropped
+    /// without a final result, the request fails with [`MailboxError::NoResponse`].
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "usize")]
+    /// struct Upload(Vec<u8>);
+    ///
+    /// struct Uploader;
+    ///
+    /// impl Actor for Uploader {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<WithProgress<Upload, usize>> for Uploader {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, msg: WithProgress<Upload, usize>, _: &mut Context<Self>) {
+    ///         let (Upload(data), responder) = msg.into_parts();
+    ///         for (chunk, _) in data.chunks(2).enumerate() {
+    ///             responder.progress(chunk + 1);
+    ///         }
+    ///         responder.finish(data.len());
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let uploader = Uploader.start();
+    ///
+    ///     let mut chunks = Vec::new();
+    ///     let res = uploader
+    ///         .send_with_progress(Upload(vec![0; 5]), |chunk| chunks.push(chunk))
+    ///         .await;
+    ///     assert_eq!(res.unwrap(), 5);
+    ///     assert_eq!(chunks, [1, 2, 3]);
+    /// }
+    /// ```
+    pub fn send_with_progress<M, P, F>(
+        &self,
+        msg: M,
+        on_progress: F,
+    ) -> impl Future<Output = Result<M::Result, MailboxError>>
+    where
+        A: Handler<WithProgress<M, P>>,
+        A::Context: ToEnvelope<A, WithProgress<M, P>>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+        P: Send + 'static,
+        F: FnMut(P),
+    {
+        progress::send(self, msg, on_progress)
+    }
+
+    /// Returns a [`Sink`](futures_sink::Sink) sending messages of type `M` to the actor.
+    ///
+    /// The sink honors the mailbox capacity: it is not ready while the mailbox is full, and is
+    /// woken once the actor handled a message, so a stream forwarded into it is throttled to the
+    /// pace of the actor, also across threads. Message results are discarded.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// use futures_util::{sink::SinkExt, stream};
+    ///
+    /// # #[derive(Message)] #[rtype(result = "()")] struct Item(usize);
+    /// # struct MyActor;
+    /// # impl Actor for MyActor { type Context = Context<Self>; }
+    /// # impl Handler<Item> for MyActor {
+    /// #     type Result = ();
+    /// #     fn handle(&mut self, _: Item, _: &mut Context<Self>) {}
+    /// # }
+    /// # #[actix::main] async fn main() {
+    /// let addr = MyActor.start();
+    /// let mut items = stream::iter((0..100).map(|n| Ok(Item(n))));
+    /// addr.sink().send_all(&mut items).await.unwrap();
+    /// # }
+    /// ```
+    pub fn sink<M>(&self) -> AddrSink<A, M>
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+    {
+        AddrSink::new(self.tx.clone())
+    }
+
+    /// Attaches an observer to the events the actor emits with
+    /// [`Context::emit`](crate::Context::emit).
+    ///
+    /// The observer receives the events emitted after this call, buffering up to 16 of them.
+    /// It is detached when the returned stream is dropped, and the stream ends once the actor
+    /// has stopped. See [`Observable`].
+    pub fn observe(&self) -> Observer<A::Event>
+    where
+        A: Observable,
+    {
+        self.observe_with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Attaches an observer like [`observe`](Self::observe), buffering up to `capacity` events.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn observe_with_capacity(&self, capacity: usize) -> Observer<A::Event>
+    where
+        A: Observable,
+    {
+        self.tx.observers().attach(capacity)
+    }
+
+    /// Gracefully stops the actor and waits until it has stopped.
+    ///
+    /// Messages queued before this call are still handled, while messages sent afterwards are
+    /// dropped and their requests fail with [`MailboxError::Closed`]. The returned future
+    /// resolves once the actor has reached [`ActorState::Stopped`](crate::ActorState::Stopped),
+    /// which also covers an actor that was already stopping or has stopped before the call.
+    ///
+    /// The stop is delivered through the mailbox, after the queued messages. An actor whose
+    /// mailbox is [paused](crate::Context::pause) is therefore only stopped once it resumes,
+    /// and the returned future stays pending until then.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor { type Context = Context<Self>; }
+    /// # #[actix::main] async fn main() {
+    /// let addr = MyActor.start();
+    /// addr.stop_and_wait().await;
+    /// # }
+    /// ```
+    pub fn stop_and_wait(&self) -> impl Future<Output = ()>
+    where
+        A::Context: AsyncContextParts<A>,
+    {
+        let rx = self.wait_stopped(true);
+        async move {
+            if let Some(rx) = rx {
+                let _ = rx.await;
+            }
+        }
+    }
+
+    /// Waits until the actor has first reached `state`.
+    ///
+    /// States are ordered as [`Started`](ActorState::Started), [`Running`](ActorState::Running),
+    /// [`Stopping`](ActorState::Stopping) and [`Stopped`](ActorState::Stopped), and the returned
+    /// future resolves once the actor is in `state` or any later one. A state the actor skips
+    /// therefore still resolves the future: an actor that stops because it has nothing left to
+    /// do, or that is terminated, never passes through `Stopping`, and waiting for it resolves
+    /// once the actor has stopped. The future resolves immediately if the actor is already gone.
+    ///
+    /// The waiter is delivered through the mailbox, so it is only registered once the messages
+    /// sent before this call have been handled. A stop that is prevented by
+    /// [`Actor::stopping`] still counts as having reached `Stopping`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// # struct MyActor;
+    /// # impl Actor for MyActor { type Context = Context<Self>; }
+    /// # #[actix::main] async fn main() {
+    /// let addr = MyActor.start();
+    /// addr.wait_state(ActorState::Running).await;
+    ///
+    /// let stopped = addr.wait_state(ActorState::Stopped);
+    /// drop(addr);
+    /// stopped.await;
+    /// # }
+    /// ```
+    pub fn wait_state(&self, state: ActorState) -> impl Future<Output = ()>
+    where
+        A::Context: AsyncContextParts<A>,
+    {
+        let rx = self.wait_for(state, false);
+        async move {
+            if let Some(rx) = rx {
+                let _ = rx.await;
+            }
+        }
+    }
+
+    /// Registers a waiter that is notified once the actor has stopped, and stops the actor
+    /// after the messages queued before this call if `stop` is set.
+    ///
+    /// Returns `None` if the actor is already gone. Otherwise the receiver resolves once the
+    /// actor has stopped, or errors if the waiter is dropped together with the actor's mailbox.
+    pub(crate) fn wait_stopped(&self, stop: bool) -> Option<tokio::sync::oneshot::Receiver<()>>
+    where
+        A::Context: AsyncContextParts<A>,
+    {
+        self.wait_for(ActorState::Stopped, stop)
+    }
+
+    /// Asks the actor for its execution state, which it reports once the messages queued before
+    /// have been handled.
+    ///
+    /// Returns `None` if the actor is already gone.
+    pub(crate) fn probe_state(&self) -> Option<tokio::sync::oneshot::Receiver<ActorState>>
+    where
+        A::Context: AsyncContextParts<A>,
+    {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let env = Envelope::with_proxy(Box::new(StateProbeProxy::new(tx)));
+        self.tx.do_send_envelope(env).ok().map(|_| rx)
+    }
+
+    /// Registers a waiter that is notified once the actor has reached `state`, see
+    /// [`wait_stopped`](Self::wait_stopped).
+    fn wait_for(&self, state: ActorState, stop: bool) -> Option<tokio::sync::oneshot::Receiver<()>>
+    where
+        A::Context: AsyncContextParts<A>,
+    {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let env = Envelope::with_proxy(Box::new(StateWaiterProxy::new(tx, state, stop)));
+        self.tx.do_send_envelope(env).ok().map(|_| rx)
+    }
+
+    /// Returns a [`BlockingSender`] for sending messages of type `M` to the actor from plain
+    /// threads.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// # #[derive(Message)] #[rtype(result = "()")] struct Line(String);
+    /// # struct Log;
+    /// # impl Actor for Log { type Context = Context<Self>; }
+    /// # impl Handler<Line> for Log {
+    /// #     type Result = ();
+    /// #     fn handle(&mut self, _: Line, _: &mut Context<Self>) {}
+    /// # }
+    /// # #[actix::main] async fn main() {
+    /// let sender = Log.start().into_sender();
+    /// std::thread::spawn(move || {
+    ///     for line in ["a", "b"] {
+    ///         sender.send(Line(line.to_owned())).unwrap();
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn into_sender<M>(self) -> BlockingSender<M>
+    where
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        BlockingSender::new(self.tx)
+    }
+
     /// Returns the [`Recipient`] for a specific message type.
-    pub fn recipient<M: 'static>(self) -> Recipient<M>
+    pub fn recipient<M>(self) -> Recipient<M>
     where
         A: Handler<M>,
         A::Context: ToEnvelope<A, M>,
-        M: Message + Send,
+        M: Message + Send + 'static,
         M::Result: Send,
     {
         self.into()
@@ -210,11 +861,11 @@ impl<A: Actor> WeakAddr<A> {
         }
     }
 
-    pub fn recipient<M: 'static>(self) -> WeakRecipient<M>
+    pub fn recipient<M>(self) -> WeakRecipient<M>
     where
         A: Handler<M>,
         A::Context: ToEnvelope<A, M>,
-        M: Message + Send,
+        M: Message + Send + 'static,
         M::Result: Send,
     {
         self.into()
@@ -245,6 +896,98 @@ impl<A: Actor> PartialEq for WeakAddr<A> {
 
 impl<A: Actor> std::cmp::Eq for WeakAddr<A> {}
 
+/// A non-owning handle for an actor to send messages to itself from spawned work, created with
+/// [`Context::notifier`](crate::Context::notifier).
+///
+/// Unlike an [`Addr`], holding a notifier does not keep the actor running: it stops once its
+/// last address is dropped, even if notifiers remain. Once the actor has stopped, notifying
+/// does nothing.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix::prelude::*;
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Refreshed(u32);
+///
+/// struct Cache {
+///     version: u32,
+/// }
+///
+/// impl Actor for Cache {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         let notifier = ctx.notifier();
+///         actix_rt::spawn(async move {
+///             actix_rt::time::sleep(Duration::from_millis(10)).await;
+///             notifier.notify(Refreshed(2));
+///         });
+///     }
+/// }
+///
+/// impl Handler<Refreshed> for Cache {
+///     type Result = ();
+///
+///     fn handle(&mut self, Refreshed(version): Refreshed, _: &mut Context<Self>) {
+///         self.version = version;
+///         System::current().stop();
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let _cache = Cache { version: 1 }.start();
+/// #   actix_rt::time::sleep(Duration::from_millis(50)).await;
+/// }
+/// ```
+pub struct SelfNotifier<A: Actor> {
+    wtx: WeakAddressSender<A>,
+}
+
+impl<A: Actor> SelfNotifier<A> {
+    pub(crate) fn new(wtx: WeakAddressSender<A>) -> Self {
+        SelfNotifier { wtx }
+    }
+
+    /// Sends `msg` to the actor, without waiting for a response.
+    ///
+    /// The message is queued in the mailbox like with [`Addr::do_send`], ignoring its capacity.
+    /// Does nothing if the actor has stopped.
+    pub fn notify<M>(&self, msg: M)
+    where
+        M: Message + Send,
+        M::Result: Send,
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+    {
+        if let Some(tx) = self.wtx.upgrade() {
+            let _ = tx.do_send(msg);
+        }
+    }
+
+    /// Returns whether the actor is still running.
+    pub fn connected(&self) -> bool {
+        self.wtx.upgrade().map_or(false, |tx| tx.connected())
+    }
+}
+
+impl<A: Actor> Clone for SelfNotifier<A> {
+    fn clone(&self) -> Self {
+        SelfNotifier {
+            wtx: self.wtx.clone(),
+        }
+    }
+}
+
+impl<A: Actor> fmt::Debug for SelfNotifier<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("SelfNotifier").finish()
+    }
+}
+
 /// The [`Recipient`] type allows to send one specific message to an actor.
 ///
 /// You can get a recipient using the `Addr::recipient()` method. It is possible
@@ -270,9 +1013,16 @@ where
     /// Sends a message.
     ///
     /// The message is always queued, even if the mailbox for the receiver is full. If the mailbox
-    /// is closed, the message is silently dropped.
-    pub fn do_send(&self, msg: M) {
-        let _ = self.tx.do_send(msg);
+    /// is closed, the message goes to the dead-letter recipient for `M` of the current arbiter,
+    /// see [`ArbiterExt::set_dead_letter_for`](crate::ArbiterExt::set_dead_letter_for), and is
+    /// dropped if there is none.
+    pub fn do_send(&self, msg: M)
+    where
+        M: 'static,
+    {
+        if let Err(SendError::Closed(msg)) = self.tx.do_send(msg) {
+            dead_letter::deliver(msg);
+        }
     }
 
     /// Attempts to send a message.
@@ -363,7 +1113,10 @@ where
     M::Result: Send,
 {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(fmt, "Recipient {{ /* omitted */ }}")
+        fmt.debug_struct("Recipient")
+            .field("actor", &self.tx.actor_id())
+            .field("message", &std::any::type_name::<M>())
+            .finish()
     }
 }
 
@@ -0,0 +1,61 @@
+use std::any::type_name;
+
+use log::warn;
+
+use super::{MailboxError, Recipient, Request, ToEnvelope};
+use crate::{
+    actor::Actor,
+    handler::{Handler, Message},
+};
+
+/// Message delivered by [`Addr::send_reply_to`](super::Addr::send_reply_to) to the continuation
+/// actor, holding the result of the handler.
+#[derive(Debug)]
+pub struct Reply<T>(pub T);
+
+impl<T: 'static> Message for Reply<T> {
+    type Result = ();
+}
+
+/// Message delivered by [`Addr::send_reply_to_or`](super::Addr::send_reply_to_or) if the
+/// message could not be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplyFailed {
+    /// Type name of the message that was sent.
+    pub message_type: &'static str,
+    /// Why no reply was produced.
+    pub error: MailboxError,
+}
+
+impl Message for ReplyFailed {
+    type Result = ();
+}
+
+pub(super) async fn route_reply<A, M>(
+    request: Request<A, M>,
+    reply_to: Recipient<Reply<M::Result>>,
+    on_error: Option<Recipient<ReplyFailed>>,
+) where
+    A: Actor + Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    match request.await {
+        Ok(res) if reply_to.connected() => reply_to.do_send(Reply(res)),
+        Ok(_) => warn!(
+            "Reply to {} dropped, its recipient is gone",
+            type_name::<M>()
+        ),
+        Err(error) => {
+            let failed = ReplyFailed {
+                message_type: type_name::<M>(),
+                error,
+            };
+            match on_error {
+                Some(on_error) if on_error.connected() => on_error.do_send(failed),
+                _ => warn!("{} was not handled: {}", failed.message_type, error),
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_sink::Sink;
+
+use super::{channel::AddressSender, MailboxError, SendError, ToEnvelope};
+use crate::{
+    actor::Actor,
+    handler::{Handler, Message},
+};
+
+/// A [`Sink`] of messages for an actor, created with [`Addr::sink`](super::Addr::sink).
+///
+/// The sink is ready while the actor's mailbox has room, and becomes ready again once the actor
+/// takes a message out of it, including when the actor runs on another thread than the task
+/// driving the sink. Messages are sent without waiting for their results.
+#[must_use = "sinks do nothing unless polled"]
+pub struct AddrSink<A: Actor, M> {
+    tx: AddressSender<A>,
+    _msg: PhantomData<fn(M)>,
+}
+
+impl<A: Actor, M> AddrSink<A, M> {
+    pub(super) fn new(tx: AddressSender<A>) -> Self {
+        AddrSink {
+            tx,
+            _msg: PhantomData,
+        }
+    }
+}
+
+impl<A: Actor, M> Clone for AddrSink<A, M> {
+    fn clone(&self) -> Self {
+        AddrSink::new(self.tx.clone())
+    }
+}
+
+impl<A: Actor, M> fmt::Debug for AddrSink<A, M> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("AddrSink").field("tx", &self.tx).finish()
+    }
+}
+
+impl<A, M> Sink<M> for AddrSink<A, M>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    type Error = MailboxError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.tx.poll_ready(cx).map_err(|_| MailboxError::Closed)
+    }
+
+    fn start_send(self: Pin<&mut Self>, msg: M) -> Result<(), Self::Error> {
+        match self.tx.try_send(msg, true) {
+            Ok(()) => Ok(()),
+            Err(SendError::Closed(_)) => Err(MailboxError::Closed),
+            Err(SendError::Full(_)) => panic!("AddrSink::start_send called without poll_ready"),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // messages are queued by `start_send`
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
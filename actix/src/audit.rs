@@ -0,0 +1,241 @@
+use std::{
+    any::type_name,
+    cell::Cell,
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use actix_rt::System;
+use log::error;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{actor::Actor, clock::Instant, directory::ActorId, handler::Message};
+
+/// Number of systems with auditing enabled, to skip recording entirely otherwise.
+static AUDITING: AtomicUsize = AtomicUsize::new(0);
+
+/// Audit logs of every auditing system, keyed by system id.
+static LOGS: Lazy<Mutex<HashMap<usize, Arc<Mutex<AuditLog>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+thread_local! {
+    /// The actor being polled on the current thread, while auditing.
+    static SENDER: Cell<Option<ActorId>> = const { Cell::new(None) };
+}
+
+struct AuditLog {
+    capacity: usize,
+    next_seq: u64,
+    entries: VecDeque<AuditEntry>,
+}
+
+/// A message sent within an auditing system, see [`SystemAudit`].
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    /// Position of the message among all messages sent within the system.
+    ///
+    /// Gaps show entries that were evicted from the audit log.
+    pub seq: u64,
+    /// When the message was sent.
+    pub time: Instant,
+    /// The actor that sent the message, or `None` if it was sent from outside of an actor.
+    pub sender: Option<ActorId>,
+    /// The actor the message was sent to.
+    pub receiver: ActorId,
+    /// Type name of the receiving actor.
+    pub receiver_type: &'static str,
+    /// Type name of the message.
+    pub message_type: &'static str,
+}
+
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}: ", self.seq)?;
+        match self.sender {
+            Some(sender) => write!(fmt, "{}", sender)?,
+            None => write!(fmt, "-")?,
+        }
+        write!(
+            fmt,
+            " -> {} ({}): {}",
+            self.receiver, self.receiver_type, self.message_type
+        )
+    }
+}
+
+/// Recording the messages sent within a [`System`], for post-mortem debugging.
+///
+/// While enabled, every message sent to an actor from one of the system's arbiters is recorded
+/// in a bounded audit log, keeping the latest entries. Only metadata is recorded, never the
+/// messages themselves. If a handler panics, the audit log is logged along with the panic.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Ping;
+///
+/// struct MyActor;
+///
+/// impl Actor for MyActor {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Ping> for MyActor {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     System::current().enable_audit(1024);
+///
+///     let addr = MyActor.start();
+///     addr.send(Ping).await.unwrap();
+///
+///     let audit = System::current().dump_audit();
+///     assert_eq!(audit.len(), 1);
+///     assert!(audit[0].receiver_type.ends_with("MyActor"));
+///     assert!(audit[0].message_type.ends_with("Ping"));
+/// }
+/// ```
+pub trait SystemAudit {
+    /// Starts recording sent messages, keeping the latest `capacity` entries.
+    ///
+    /// If auditing is already enabled, only the capacity is changed, evicting the oldest
+    /// entries if needed.
+    fn enable_audit(&self, capacity: usize);
+
+    /// Stops recording sent messages and discards the audit log.
+    fn disable_audit(&self);
+
+    /// Returns the recorded entries, oldest first.
+    fn dump_audit(&self) -> Vec<AuditEntry>;
+}
+
+impl SystemAudit for System {
+    fn enable_audit(&self, capacity: usize) {
+        crate::system::track(self);
+        let mut logs = LOGS.lock();
+        let log = logs.entry(self.id()).or_insert_with(|| {
+            AUDITING.fetch_add(1, Ordering::AcqRel);
+            Arc::new(Mutex::new(AuditLog {
+                capacity,
+                next_seq: 0,
+                entries: VecDeque::with_capacity(capacity),
+            }))
+        });
+
+        let mut log = log.lock();
+        log.capacity = capacity;
+        while log.entries.len() > capacity {
+            log.entries.pop_front();
+        }
+    }
+
+    fn disable_audit(&self) {
+        release(self.id());
+    }
+
+    fn dump_audit(&self) -> Vec<AuditEntry> {
+        match log(self.id()) {
+            Some(log) => log.lock().entries.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Disables auditing of the system `id`.
+pub(crate) fn release(system: usize) {
+    if LOGS.lock().remove(&system).is_some() {
+        AUDITING.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[inline]
+fn auditing() -> bool {
+    AUDITING.load(Ordering::Acquire) != 0
+}
+
+fn log(system: usize) -> Option<Arc<Mutex<AuditLog>>> {
+    LOGS.lock().get(&system).cloned()
+}
+
+/// Marks `id` as the sender of the messages sent until the returned guard is dropped.
+#[inline]
+pub(crate) fn enter(id: ActorId) -> Option<Enter> {
+    if !auditing() {
+        return None;
+    }
+    Some(Enter(SENDER.with(|sender| sender.replace(Some(id)))))
+}
+
+pub(crate) struct Enter(Option<ActorId>);
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        SENDER.with(|sender| sender.set(self.0));
+    }
+}
+
+/// Records a message of type `M` sent to the actor `receiver`.
+#[inline]
+pub(crate) fn record<A: Actor, M: Message>(receiver: ActorId) {
+    if auditing() {
+        record_slow(receiver, type_name::<A>(), type_name::<M>());
+    }
+}
+
+#[cold]
+fn record_slow(receiver: ActorId, receiver_type: &'static str, message_type: &'static str) {
+    let Some(log) = System::try_current().and_then(|sys| log(sys.id())) else {
+        return;
+    };
+
+    let sender = SENDER.with(Cell::get);
+    let mut log = log.lock();
+    let seq = log.next_seq;
+    log.next_seq += 1;
+    if log.capacity == 0 {
+        return;
+    }
+    if log.entries.len() == log.capacity {
+        log.entries.pop_front();
+    }
+    log.entries.push_back(AuditEntry {
+        seq,
+        time: Instant::now(),
+        sender,
+        receiver,
+        receiver_type,
+        message_type,
+    });
+}
+
+/// Logs the audit log of the current system, if it is auditing.
+pub(crate) fn log_trail() {
+    if !auditing() {
+        return;
+    }
+    let Some(system) = System::try_current() else {
+        return;
+    };
+
+    let entries = system.dump_audit();
+    if !entries.is_empty() {
+        let trail = entries
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        error!("Messages sent before the panic:\n{}", trail);
+    }
+}
@@ -0,0 +1,164 @@
+use std::{fmt, mem, time::Duration};
+
+use crate::{
+    actor::{Actor, AsyncContext, SpawnHandle},
+    context::Context,
+    handler::{Message, MessageResponse, OneshotSender},
+    replies::PendingReply,
+};
+
+/// Handling requests in batches, for coalescing requests on the receiving side.
+///
+/// Requests are collected by a [`Handler`](crate::Handler) that responds with [`Batched`].
+/// The first request of a batch waits up to [`LINGER`](Self::LINGER) for more requests, and a
+/// batch is handled right away once it holds [`MAX_BATCH`](Self::MAX_BATCH) requests. Each
+/// request comes with a [`Responder`], which delivers the response to its sender.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// #[derive(Message)]
+/// #[rtype(result = "Option<String>")]
+/// struct Get(u32);
+///
+/// struct Db;
+///
+/// impl Actor for Db {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Get> for Db {
+///     type Result = Batched<Get>;
+///
+///     fn handle(&mut self, msg: Get, _: &mut Context<Self>) -> Self::Result {
+///         Batched(msg)
+///     }
+/// }
+///
+/// impl BatchingHandler<Get> for Db {
+///     fn handle_batch(
+///         &mut self,
+///         batch: Vec<(Get, Responder<Option<String>>)>,
+///         _: &mut Context<Self>,
+///     ) {
+///         // a single query for all keys of the batch
+///         for (Get(key), responder) in batch {
+///             responder.send(Some(format!("row {}", key)));
+///         }
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let db = Db.start();
+///     let (a, b) = futures_util::future::join(db.send(Get(1)), db.send(Get(2))).await;
+///     assert_eq!(a.unwrap().as_deref(), Some("row 1"));
+///     assert_eq!(b.unwrap().as_deref(), Some("row 2"));
+/// }
+/// ```
+pub trait BatchingHandler<M>
+where
+    Self: Actor<Context = Context<Self>>,
+    M: Message,
+{
+    /// How long the first request of a batch waits for more requests.
+    const LINGER: Duration = Duration::from_millis(5);
+
+    /// The number of requests that completes a batch.
+    const MAX_BATCH: usize = 64;
+
+    /// Handles a batch of requests, in the order they were received.
+    ///
+    /// A request whose responder is dropped without sending a response fails with
+    /// [`MailboxError::NoResponse`](crate::MailboxError::NoResponse).
+    fn handle_batch(&mut self, batch: Vec<(M, Responder<M::Result>)>, ctx: &mut Self::Context);
+}
+
+/// Response of a [`Handler`](crate::Handler) adding the message to the pending batch of a
+/// [`BatchingHandler`].
+#[derive(Debug)]
+pub struct Batched<M>(pub M);
+
+impl<A, M> MessageResponse<A, M> for Batched<M>
+where
+    A: BatchingHandler<M>,
+    M: Message + 'static,
+{
+    fn handle(self, ctx: &mut Context<A>, tx: Option<OneshotSender<M::Result>>) {
+        let batch = ctx.batch::<Batch<M>>();
+        let responder = Responder {
+            tx,
+            _pending: PendingReply::current(),
+        };
+        batch.items.push((self.0, responder));
+
+        if batch.items.len() >= A::MAX_BATCH {
+            if let Some(timer) = batch.timer.take() {
+                ctx.cancel_future(timer);
+            }
+            ctx.defer(flush::<A, M>);
+        } else if batch.timer.is_none() {
+            let timer = ctx.run_later(A::LINGER, |act, ctx| {
+                ctx.batch::<Batch<M>>().timer = None;
+                flush::<A, M>(act, ctx);
+            });
+            ctx.batch::<Batch<M>>().timer = Some(timer);
+        }
+    }
+}
+
+/// Delivers the response to a single request of a batch, see [`BatchingHandler`].
+pub struct Responder<T> {
+    tx: Option<OneshotSender<T>>,
+    _pending: PendingReply,
+}
+
+impl<T> Responder<T> {
+    /// Sends the response to the sender of the request.
+    pub fn send(self, res: T) {
+        if let Some(tx) = self.tx {
+            let _ = tx.send(res);
+        }
+    }
+
+    /// Returns `true` if the sender does not wait for the response, because it has gone or
+    /// sent the request without waiting for a response.
+    pub fn is_closed(&self) -> bool {
+        self.tx.as_ref().map_or(true, OneshotSender::is_closed)
+    }
+}
+
+impl<T> fmt::Debug for Responder<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Responder")
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+/// Requests of type `M` waiting for their batch to be handled.
+struct Batch<M: Message> {
+    items: Vec<(M, Responder<M::Result>)>,
+    timer: Option<SpawnHandle>,
+}
+
+impl<M: Message> Default for Batch<M> {
+    fn default() -> Self {
+        Batch {
+            items: Vec::new(),
+            timer: None,
+        }
+    }
+}
+
+fn flush<A, M>(act: &mut A, ctx: &mut Context<A>)
+where
+    A: BatchingHandler<M>,
+    M: Message + 'static,
+{
+    let items = mem::take(&mut ctx.batch::<Batch<M>>().items);
+    if !items.is_empty() {
+        act.handle_batch(items, ctx);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{
+    actor::Actor,
+    handler::{Handler, Message, MessageResult},
+};
+
+/// A message that carries its own handler.
+///
+/// Actors implementing [`DynHandler`] handle `Box<dyn Command<Self, R>>`: the actor receives
+/// the boxed command and calls [`execute`](Command::execute) on it, which dispatches back into
+/// the command's own code with mutable access to the actor and its context. This lets new
+/// operations be registered at runtime, e.g. by plugins, without the actor listing a
+/// [`Handler`] for each of them.
+///
+/// The price is static typing. The actor no longer declares what it can do, so any code that
+/// can name the actor's type can run arbitrary logic on its state, and the result type `R` is
+/// fixed for all commands sent through the same [`Recipient`](crate::Recipient). Prefer
+/// regular messages wherever the set of operations is known at compile time.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// #[derive(Default)]
+/// struct Registry {
+///     entries: Vec<String>,
+/// }
+///
+/// impl Actor for Registry {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl DynHandler for Registry {}
+///
+/// // provided by a plugin
+/// struct Insert(String);
+///
+/// impl Command<Registry, usize> for Insert {
+///     fn execute(self: Box<Self>, act: &mut Registry, _: &mut Context<Registry>) -> usize {
+///         act.entries.push(self.0);
+///         act.entries.len()
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let registry = Registry::default().start();
+///     let commands: Recipient<Box<dyn Command<Registry, usize>>> = registry.recipient();
+///
+///     let len = commands.send(Box::new(Insert("plugin".to_owned()))).await.unwrap();
+///     assert_eq!(len, 1);
+/// }
+/// ```
+pub trait Command<A: Actor, R = ()>: Send {
+    /// Runs the command against the actor that received it.
+    fn execute(self: Box<Self>, act: &mut A, ctx: &mut A::Context) -> R;
+}
+
+/// Opts an actor into handling boxed [`Command`]s.
+///
+/// This is a marker trait; the actor gets a [`Handler`] implementation for
+/// `Box<dyn Command<Self, R>>` for every result type `R`.
+pub trait DynHandler: Actor {}
+
+impl<A, R> Message for Box<dyn Command<A, R>>
+where
+    A: Actor,
+    R: 'static,
+{
+    type Result = R;
+}
+
+impl<A, R> Handler<Box<dyn Command<A, R>>> for A
+where
+    A: DynHandler,
+    R: 'static,
+{
+    type Result = MessageResult<Box<dyn Command<A, R>>>;
+
+    fn handle(&mut self, cmd: Box<dyn Command<A, R>>, ctx: &mut A::Context) -> Self::Result {
+        MessageResult(cmd.execute(self, ctx))
+    }
+}
@@ -1,11 +1,26 @@
-use std::fmt;
+use std::{cell::Cell, fmt, future::Future, hash::Hash, rc::Rc, time::Duration};
+
+use futures_core::stream::Stream;
+use log::error;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    actor::{Actor, ActorContext, ActorState, AsyncContext, SpawnHandle},
-    address::{Addr, AddressReceiver},
+    actor::{Actor, ActorContext, ActorState, AsyncContext, SpawnHandle, StartError},
+    address::{Addr, AddressReceiver, SelfNotifier, WeakAddr},
+    clock::Instant,
     contextimpl::{AsyncContextParts, ContextFut, ContextParts},
-    fut::ActorFuture,
+    contextitems::{ActorDelayedMessageItem, ActorMessageItem},
+    deadline,
+    fut::{self, ActorFuture, ActorFutureExt, ActorStreamExt},
+    handler::{Handler, Message},
     mailbox::Mailbox,
+    middleware::MessageMiddleware,
+    observe::Observable,
+    rate_limit::{Rate, RateLimiter},
+    registry::{ActorPath, PathRegistry},
+    semaphore::Semaphore,
+    stream::{ActorStream, StreamHandler},
+    utils::{IntervalFunc, MissedTicks, TimerFunc},
 };
 
 /// An actor execution context.
@@ -78,6 +93,54 @@ where
     fn address(&self) -> Addr<A> {
         self.parts.address()
     }
+
+    fn notify_later<M>(&mut self, msg: M, after: Duration) -> SpawnHandle
+    where
+        A: Handler<M>,
+        M: Message + 'static,
+    {
+        if self.state() == ActorState::Stopped {
+            error!("Context::notify_later called for stopped actor.");
+            SpawnHandle::default()
+        } else {
+            let handle = self.spawn(ActorDelayedMessageItem::new(msg, after));
+            self.parts
+                .track_timer(handle, Rc::new(Cell::new(Instant::now() + after)));
+            handle
+        }
+    }
+
+    fn run_later<F>(&mut self, dur: Duration, f: F) -> SpawnHandle
+    where
+        F: FnOnce(&mut A, &mut Self) + 'static,
+    {
+        let handle = self.spawn(TimerFunc::new(dur, f));
+        self.parts
+            .track_timer(handle, Rc::new(Cell::new(Instant::now() + dur)));
+        handle
+    }
+
+    fn run_interval<F>(&mut self, dur: Duration, f: F) -> SpawnHandle
+    where
+        F: FnMut(&mut A, &mut Self) + 'static,
+    {
+        let interval = IntervalFunc::new(dur, f);
+        let deadline = interval.deadline();
+        let handle = self.spawn(interval.finish());
+        self.parts.track_timer(handle, deadline);
+        handle
+    }
+
+    fn run_interval_with<F>(&mut self, dur: Duration, missed: MissedTicks, f: F) -> SpawnHandle
+    where
+        F: FnMut(&mut A, &mut Self, u32) + 'static,
+    {
+        let interval = IntervalFunc::with_skipped(dur, f).missed_ticks(missed);
+        let deadline = interval.deadline();
+        let handle = self.spawn(interval.finish());
+        self.parts.track_timer(handle, deadline);
+        handle
+    }
 }
 
 impl<A> Context<A>
@@ -141,19 +204,38 @@ where
         }
     }
 
-    #[inline]
+    /// Starts `act` with this context, returning its address.
+    ///
+    /// If the system has reached its [actor limit](crate::ActorLimit), or a service the actor
+    /// [requires](Actor::requires) can not be provided, the failure is logged and the actor is
+    /// dropped without being started. The returned address is closed then, see
+    /// [`Actor::try_start`] to handle the failure instead.
     pub fn run(self, act: A) -> Addr<A> {
-        let fut = self.into_future(act);
-        let addr = fut.address();
-        actix_rt::spawn(fut);
+        let addr = self.parts.address();
+        match self.try_into_future(act) {
+            Ok(fut) => {
+                actix_rt::spawn(fut);
+            }
+            Err(err) => error!("Can not start {}: {}", std::any::type_name::<A>(), err),
+        }
         addr
     }
 
+    /// Turns the context and `act` into the future running the actor.
+    ///
+    /// # Panics
+    /// Panics if the system has reached its [actor limit](crate::ActorLimit), or a service the
+    /// actor [requires](Actor::requires) can not be provided.
     pub fn into_future(mut self, act: A) -> ContextFut<A, Self> {
         let mb = self.mb.take().unwrap();
         ContextFut::new(self, act, mb)
     }
 
+    pub(crate) fn try_into_future(mut self, act: A) -> Result<ContextFut<A, Self>, StartError> {
+        let mb = self.mb.take().unwrap();
+        ContextFut::try_new(self, act, mb)
+    }
+
     /// Returns a handle to the running future.
     ///
     /// This is the handle returned by the `AsyncContext::spawn()`
@@ -188,10 +270,1247 @@ where
         self.parts.set_mailbox_capacity(cap)
     }
 
+    /// Returns the highest number of messages that have been queued in the mailbox at once
+    /// over the actor's lifetime.
+    ///
+    /// This is useful for picking a mailbox capacity empirically, e.g. by logging it from
+    /// [`Actor::stopped`](crate::Actor::stopped). Messages sent with `do_send` are counted
+    /// even when they exceed the capacity.
+    pub fn mailbox_high_water(&self) -> usize {
+        self.parts.mailbox_high_water()
+    }
+
+    /// Returns how many messages are handled in a row before the spawned futures are polled.
+    pub fn mailbox_budget(&self) -> usize {
+        self.parts.mailbox_budget()
+    }
+
+    /// Sets how many messages are handled in a row before the spawned futures are polled, 64
+    /// by default.
+    ///
+    /// The actor takes turns between its mailbox and the futures and streams spawned in its
+    /// context, so that neither starves the other. Each turn handles up to this many messages,
+    /// then polls up to [`futures_budget`](Self::set_futures_budget) spawned futures. When
+    /// either has more work left, the actor yields to the arbiter and carries on afterwards.
+    ///
+    /// # Panics
+    /// Panics if `budget` is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct Relay;
+    ///
+    /// impl Actor for Relay {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         // forwarding streams get polled after every few messages
+    ///         ctx.set_mailbox_budget(8);
+    ///         ctx.set_futures_budget(256);
+    ///     }
+    /// }
+    /// ```
+    pub fn set_mailbox_budget(&mut self, budget: usize) {
+        self.parts.set_mailbox_budget(budget)
+    }
+
+    /// Returns how many spawned futures are polled in a row before the mailbox is processed.
+    pub fn futures_budget(&self) -> usize {
+        self.parts.futures_budget()
+    }
+
+    /// Sets how many spawned futures are polled in a row before the mailbox is processed, 64
+    /// by default.
+    ///
+    /// Futures left out in one turn are polled first in the next one. See
+    /// [`set_mailbox_budget`](Self::set_mailbox_budget).
+    ///
+    /// # Panics
+    /// Panics if `budget` is zero.
+    pub fn set_futures_budget(&mut self, budget: usize) {
+        self.parts.set_futures_budget(budget)
+    }
+
+    /// Returns the time a handler has to reply before its response is aborted, see
+    /// [`set_handler_timeout`](Self::set_handler_timeout).
+    pub fn handler_timeout(&self) -> Option<Duration> {
+        self.parts.handler_timeout()
+    }
+
+    /// Sets the time a handler has to reply before its asynchronous response is aborted, or
+    /// `None` to let handlers take as long as they need, which is the default.
+    ///
+    /// The time counts from the start of the handler. If it elapses before the handler replied,
+    /// its asynchronous response, e.g. a [`ResponseActFuture`](crate::ResponseActFuture) or
+    /// a [`ResponseFuture`](crate::ResponseFuture), is dropped, the sender of the message gets
+    /// [`MailboxError::HandlerTimeout`](crate::MailboxError::HandlerTimeout), and a warning
+    /// naming the message type is logged. An aborted [`AtomicResponse`](crate::AtomicResponse)
+    /// lets the actor handle messages again.
+    ///
+    /// The synchronous body of a handler can not be interrupted. A body running longer than
+    /// the timeout is only logged, once it returns. CPU bound work belongs in
+    /// [`fut::blocking`](crate::fut::blocking) or a [`SyncArbiter`](crate::SyncArbiter)
+    /// instead, so that the actor stays responsive.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Fetch;
+    ///
+    /// struct Fetcher;
+    ///
+    /// impl Actor for Fetcher {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.set_handler_timeout(Some(Duration::from_millis(10)));
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Fetch> for Fetcher {
+    ///     type Result = ResponseFuture<()>;
+    ///
+    ///     fn handle(&mut self, _: Fetch, _: &mut Context<Self>) -> Self::Result {
+    ///         Box::pin(actix_rt::time::sleep(Duration::from_secs(60)))
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let addr = Fetcher.start();
+    ///     let res = addr.send(Fetch).await;
+    ///     assert_eq!(res, Err(MailboxError::HandlerTimeout));
+    /// }
+    /// ```
+    pub fn set_handler_timeout(&mut self, timeout: Option<Duration>) {
+        self.parts.set_handler_timeout(timeout)
+    }
+
+    /// Registers a callback fired once the actor has become idle.
+    ///
+    /// The actor is idle once it has not handled any message, and has no
+    /// [pending replies](Self::pending_replies), for the
+    /// [debounce interval](Self::set_idle_debounce), 100ms by default. The callback fires once
+    /// per transition, and again only after the actor has been busy in between, see
+    /// [`on_busy`](Self::on_busy). Aggregated over a pool of workers, this can drive scaling
+    /// decisions.
+    ///
+    /// Registered callbacks are dropped when the actor is restarted by its supervisor.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// struct Worker;
+    ///
+    /// impl Actor for Worker {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.set_idle_debounce(Duration::from_millis(10));
+    ///         ctx.on_idle(|_, _| System::current().stop());
+    ///         ctx.on_busy(|_, _| println!("busy again"));
+    ///     }
+    /// }
+    /// # #[actix::main] async fn main() { let _addr = Worker.start(); }
+    /// ```
+    pub fn on_idle<F>(&mut self, f: F)
+    where
+        F: FnMut(&mut A, &mut Self) + 'static,
+    {
+        self.parts.on_idle(Box::new(f))
+    }
+
+    /// Registers a callback fired once the actor handles a message after it has become idle,
+    /// see [`on_idle`](Self::on_idle).
+    pub fn on_busy<F>(&mut self, f: F)
+    where
+        F: FnMut(&mut A, &mut Self) + 'static,
+    {
+        self.parts.on_busy(Box::new(f))
+    }
+
+    /// Sets how long the actor has to be without work before it is considered idle.
+    ///
+    /// This debounces the [`on_idle`](Self::on_idle) and [`on_busy`](Self::on_busy) callbacks,
+    /// so that an actor handling a steady stream of messages does not flap between states.
+    pub fn set_idle_debounce(&mut self, debounce: Duration) {
+        self.parts.set_idle_debounce(debounce)
+    }
+
     /// Returns whether any addresses are still connected.
     pub fn connected(&self) -> bool {
         self.parts.connected()
     }
+
+    /// Pauses message processing.
+    ///
+    /// While paused, the actor stays alive and its address keeps accepting messages (subject to
+    /// the mailbox capacity), but the mailbox is not drained. Spawned futures, streams and timers
+    /// keep running, so the actor is able to resume itself, e.g. from a
+    /// [`run_later`](AsyncContext::run_later) callback.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         // maintenance window
+    ///         ctx.pause();
+    ///         ctx.run_later(Duration::from_millis(10), |_, ctx| {
+    ///             ctx.resume();
+    /// #           System::current().stop();
+    ///         });
+    ///     }
+    /// }
+    /// # #[actix::main] async fn main() { MyActor.start(); }
+    /// ```
+    pub fn pause(&mut self) {
+        self.parts.pause()
+    }
+
+    /// Resumes message processing paused by [`pause`](Self::pause).
+    ///
+    /// Messages accumulated while paused are processed in the order they were received.
+    pub fn resume(&mut self) {
+        self.parts.resume()
+    }
+
+    /// Returns whether message processing is paused.
+    pub fn paused(&self) -> bool {
+        self.parts.paused()
+    }
+
+    /// Sets `msg` aside until [`unstash_all`](Self::unstash_all) is called.
+    ///
+    /// Called from the handler of `msg`, the reply to the sender moves along with the message:
+    /// the value returned by the handler is discarded, and the sender receives the reply once
+    /// the message is handled after being unstashed. A message of the type being handled is
+    /// always taken to be the handled message. Messages are handled again including
+    /// their middleware. Stashed messages are dropped when the actor stops, failing their
+    /// requests with [`MailboxError::Closed`](crate::MailboxError::Closed), and are unstashed
+    /// when the actor is restarted by its supervisor.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "Option<String>")]
+    /// struct Get(&'static str);
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Loaded;
+    ///
+    /// #[derive(Default)]
+    /// struct Config {
+    ///     ready: bool,
+    /// }
+    ///
+    /// impl Actor for Config {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Get> for Config {
+    ///     type Result = Option<String>;
+    ///
+    ///     fn handle(&mut self, msg: Get, ctx: &mut Context<Self>) -> Self::Result {
+    ///         if !self.ready {
+    ///             ctx.stash(msg);
+    ///             return None;
+    ///         }
+    ///         Some(msg.0.to_uppercase())
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Loaded> for Config {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: Loaded, ctx: &mut Context<Self>) {
+    ///         self.ready = true;
+    ///         ctx.unstash_all();
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let config = Config::default().start();
+    ///     let value = config.send(Get("key"));
+    ///     config.do_send(Loaded);
+    ///     assert_eq!(value.await.unwrap().as_deref(), Some("KEY"));
+    /// }
+    /// ```
+    pub fn stash<M>(&mut self, msg: M)
+    where
+        A: Handler<M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        self.parts.stash().push(msg)
+    }
+
+    /// Returns all stashed messages to the front of the mailbox, in the order they were
+    /// stashed, and returns their number.
+    ///
+    /// The unstashed messages are handled before any message waiting in the mailbox.
+    pub fn unstash_all(&mut self) -> usize {
+        self.parts.unstash_all()
+    }
+
+    /// Returns the number of stashed messages.
+    pub fn stashed(&self) -> usize {
+        self.parts.stashed()
+    }
+
+    /// Delivers `msg` to this actor again after `delay`, for a handler that cannot process it
+    /// yet.
+    ///
+    /// `msg` is taken to be the message being handled if it is of its type, as with
+    /// [`stash`](Self::stash): the sender receives the reply once the message is eventually
+    /// handled, and [`attempt`](Self::attempt) counts the retries. The redelivered message is
+    /// handled before the messages waiting in the mailbox. Cancelling the returned handle
+    /// drops the message, failing its request with
+    /// [`MailboxError::Closed`](crate::MailboxError::Closed).
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// #[derive(Message)]
+    /// #[rtype(result = "Result<(), ()>")]
+    /// struct Write(Vec<u8>);
+    ///
+    /// struct Storage {
+    ///     locked: bool,
+    /// }
+    ///
+    /// impl Actor for Storage {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Write> for Storage {
+    ///     type Result = Result<(), ()>;
+    ///
+    ///     fn handle(&mut self, msg: Write, ctx: &mut Context<Self>) -> Self::Result {
+    ///         if self.locked {
+    ///             if ctx.attempt() == 3 {
+    ///                 return Err(());
+    ///             }
+    ///             // the sender receives the reply of the next attempt instead
+    ///             ctx.retry_current(msg, Duration::from_millis(100));
+    ///             return Ok(());
+    ///         }
+    ///         // write msg.0
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    pub fn retry_current<M>(&mut self, msg: M, delay: Duration) -> SpawnHandle
+    where
+        A: Handler<M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        let (env, attempt) = self.parts.stash().detach(msg);
+        self.run_later(delay, move |_, ctx| ctx.parts.requeue(env, attempt + 1))
+    }
+
+    /// Returns the deadline of the message being handled, if it was sent with
+    /// [`Addr::send_deadline`] or by a handler of such a message.
+    ///
+    /// Within a future spawned by a handler, this is the deadline of the message the future was
+    /// spawned for.
+    pub fn message_deadline(&self) -> Option<Instant> {
+        deadline::current()
+    }
+
+    /// Returns how many times the message being handled was redelivered with
+    /// [`retry_current`](Self::retry_current), or zero on its first delivery.
+    pub fn attempt(&self) -> u32 {
+        self.parts.attempt()
+    }
+
+    pub(crate) fn batch<B: Default + 'static>(&mut self) -> &mut B {
+        self.parts.batch()
+    }
+
+    /// Runs `f` right after the current handler returns, before the next message is handled.
+    ///
+    /// Deferred closures run in the order they were registered, and closures deferred by them
+    /// run right after. Unlike [`notify`](AsyncContext::notify), the closure does not go
+    /// through the mailbox, and unlike [`run_later`](AsyncContext::run_later), it does not wait
+    /// for a timer. Closures deferred in [`Actor::started`] or in a spawned future run once
+    /// it returns or has been polled.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Insert(u32);
+    ///
+    /// #[derive(Default)]
+    /// struct Batcher {
+    ///     batch: Vec<u32>,
+    ///     committed: Vec<Vec<u32>>,
+    /// }
+    ///
+    /// impl Actor for Batcher {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Insert> for Batcher {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, Insert(val): Insert, ctx: &mut Context<Self>) {
+    ///         if self.batch.is_empty() {
+    ///             // commit whatever this message has accumulated
+    ///             ctx.defer(|act, _| {
+    ///                 let batch = std::mem::take(&mut act.batch);
+    ///                 act.committed.push(batch);
+    ///             });
+    ///         }
+    ///         self.batch.extend([val, val * 10]);
+    ///     }
+    /// }
+    /// ```
+    pub fn defer<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut A, &mut Self) + 'static,
+    {
+        self.parts.deferred().push(Box::new(f));
+    }
+
+    /// Sends the message `msg` to self, unless a message of the same type sent with this method
+    /// is still waiting to be handled.
+    ///
+    /// Like [`notify`](AsyncContext::notify), this bypasses the mailbox capacity. Notifications
+    /// are coalesced by message type, so at most one of each type is pending, and `msg` is
+    /// dropped if one already is. Once the pending notification is handled, the next call
+    /// queues a new one, as does a call after the pending notification was cancelled, e.g.
+    /// with [`cancel_all`](Self::cancel_all). This suits "mark dirty, then flush" patterns,
+    /// where bursts of changes should cause a single flush.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Set(String, u32);
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Flush;
+    ///
+    /// #[derive(Default)]
+    /// struct Cache {
+    ///     dirty: Vec<String>,
+    /// }
+    ///
+    /// impl Actor for Cache {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Set> for Cache {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, Set(key, _): Set, ctx: &mut Context<Self>) {
+    ///         self.dirty.push(key);
+    ///         ctx.notify_once(Flush);
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Flush> for Cache {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: Flush, _: &mut Context<Self>) {
+    ///         // write all dirty keys at once
+    ///         self.dirty.clear();
+    ///     }
+    /// }
+    /// ```
+    pub fn notify_once<M>(&mut self, msg: M)
+    where
+        A: Handler<M>,
+        M: Message + 'static,
+    {
+        if self.state() == ActorState::Stopped {
+            error!("Context::notify_once called for stopped actor.");
+        } else if let Some(pending) = self.parts.notify_once::<M>() {
+            let item = ActorMessageItem::new(msg);
+            self.spawn(fut::ready(()).then(move |_, _, _| {
+                drop(pending);
+                item
+            }));
+        }
+    }
+
+    /// Returns the number of requests this actor has received but not answered yet.
+    ///
+    /// A request is pending while its asynchronous response, e.g. a [`ResponseActFuture`] or
+    /// a [`ResponseFuture`], has not resolved. Messages sent with
+    /// [`do_send`](crate::Addr::do_send) expect no reply and are not counted.
+    ///
+    /// Combined with [`Actor::stopping`], this lets an actor answer in-flight requests before
+    /// stopping.
+    ///
+    /// [`ResponseActFuture`]: crate::ResponseActFuture
+    /// [`ResponseFuture`]: crate::ResponseFuture
+    pub fn pending_replies(&self) -> usize {
+        self.parts.replies().count()
+    }
+
+    /// Returns the time until the soonest pending timer fires, or `None` if no timer is
+    /// pending.
+    ///
+    /// Timers are scheduled with [`run_later`](AsyncContext::run_later),
+    /// [`notify_later`](AsyncContext::notify_later), [`run_interval`](AsyncContext::run_interval)
+    /// and [`run_interval_with`](AsyncContext::run_interval_with). A timer that is due but has
+    /// not fired yet returns a zero duration. This lets a handler decide whether to wait for
+    /// upcoming scheduled work, e.g. to flush a batch now or along with the next tick.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use actix::prelude::*;
+    ///
+    /// struct Batcher;
+    ///
+    /// impl Actor for Batcher {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.run_interval(Duration::from_secs(1), |_, _| { /* flush */ });
+    ///
+    ///         let next = ctx.next_timer().unwrap();
+    ///         assert!(next <= Duration::from_secs(1));
+    ///         System::current().stop();
+    ///     }
+    /// }
+    /// # fn main() {
+    /// # let sys = System::new();
+    /// # sys.block_on(async { Batcher.start() });
+    /// # sys.run().unwrap();
+    /// # }
+    /// ```
+    pub fn next_timer(&self) -> Option<Duration> {
+        self.parts.next_timer()
+    }
+
+    /// Delays the transition to stopped until `fut` resolves, or `timeout` elapsed.
+    ///
+    /// Once the actor decided to stop, i.e. [`Actor::stopping`] returned [`Running::Stop`], the
+    /// registered futures are polled until all of them resolved, and only then is
+    /// [`Actor::stopped`] called and the actor dropped. No messages are handled in the meantime.
+    /// This is typically called from [`Actor::stopping`], e.g. to deregister the actor
+    /// somewhere and wait for the confirmation. [`terminate`](ActorContext::terminate) does not
+    /// wait.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use actix::prelude::*;
+    ///
+    /// struct Session;
+    ///
+    /// impl Actor for Session {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.stop();
+    ///     }
+    ///
+    ///     fn stopping(&mut self, ctx: &mut Context<Self>) -> Running {
+    ///         let deregistered = async { /* remove the session from a registry */ };
+    ///         ctx.delay_stop(deregistered.into_actor(self), Duration::from_secs(5));
+    ///         Running::Stop
+    ///     }
+    /// }
+    /// # #[actix::main] async fn main() { Session.start(); }
+    /// ```
+    ///
+    /// [`Running::Stop`]: crate::Running::Stop
+    pub fn delay_stop<F>(&mut self, fut: F, timeout: Duration)
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        let fut = fut.timeout(timeout).map(|res, _, _| {
+            if res.is_err() {
+                log::warn!("Delayed stop of {} timed out", std::any::type_name::<A>());
+            }
+        });
+        self.parts.delay_stop(Box::pin(fut));
+    }
+
+    /// Returns the actor's rate limiter named `name`.
+    ///
+    /// The limiter is created with `rate` on first use; later calls with the same name return
+    /// the same limiter and ignore `rate`. Limiters are kept when the actor is restarted by its
+    /// supervisor.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Call;
+    ///
+    /// struct Client;
+    ///
+    /// impl Actor for Client {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Call> for Client {
+    ///     type Result = ResponseActFuture<Self, ()>;
+    ///
+    ///     fn handle(&mut self, _: Call, ctx: &mut Context<Self>) -> Self::Result {
+    ///         let limiter = ctx.rate_limiter("api", Rate::per_second(10).burst(2));
+    ///         Box::pin(limiter.acquire().map(|_, _, _| {
+    ///             // perform the call
+    ///         }))
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let client = Client.start();
+    ///     client.send(Call).await.unwrap();
+    /// }
+    /// ```
+    pub fn rate_limiter(&mut self, name: &str, rate: Rate) -> RateLimiter {
+        self.parts.rate_limiter(name, rate)
+    }
+
+    /// Returns the actor's semaphore named `name`, for capping how many spawned operations run
+    /// concurrently.
+    ///
+    /// The semaphore is created with `permits` permits on first use; later calls with the same
+    /// name return the same semaphore and ignore `permits`. Semaphores are kept when the actor
+    /// is restarted by its supervisor. See [`Semaphore`] for an example.
+    ///
+    /// # Panics
+    /// Panics if the semaphore is created with zero permits.
+    pub fn semaphore(&mut self, name: &str, permits: usize) -> Semaphore {
+        self.parts.semaphore(name, permits)
+    }
+
+    /// Returns a token that is cancelled once the actor starts stopping.
+    ///
+    /// The token is cancelled when [`stop`](ActorContext::stop) or
+    /// [`terminate`](ActorContext::terminate) is called, or when the actor stops on its own.
+    /// Long-running work can check it cooperatively, e.g. between chunks of a stream. Clones
+    /// share the same state. If [`Actor::stopping`] keeps the actor running, later calls return
+    /// a fresh token.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "usize")]
+    /// struct Upload(Vec<Vec<u8>>);
+    ///
+    /// struct Uploader;
+    ///
+    /// impl Actor for Uploader {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Upload> for Uploader {
+    ///     type Result = ResponseFuture<usize>;
+    ///
+    ///     fn handle(&mut self, Upload(chunks): Upload, ctx: &mut Context<Self>) -> Self::Result {
+    ///         let token = ctx.cancellation_token();
+    ///         Box::pin(async move {
+    ///             let mut sent = 0;
+    ///             for chunk in chunks {
+    ///                 if token.is_cancelled() {
+    ///                     break;
+    ///                 }
+    ///                 // send the chunk
+    ///                 sent += chunk.len();
+    ///             }
+    ///             sent
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let uploader = Uploader.start();
+    ///     let sent = uploader.send(Upload(vec![vec![0; 4], vec![0; 2]])).await.unwrap();
+    ///     assert_eq!(sent, 6);
+    /// }
+    /// ```
+    pub fn cancellation_token(&mut self) -> CancellationToken {
+        self.parts.cancellation_token()
+    }
+
+    /// Spawns a housekeeping future, which runs while the actor lives but does not keep it
+    /// alive.
+    ///
+    /// An actor stops once its addresses are dropped and it has no spawned futures or streams
+    /// left. Housekeeping futures are not counted, so e.g. a periodic cleanup does not keep an
+    /// otherwise unused actor running; it is dropped when the actor stops. The returned handle
+    /// cancels the future like the handle of [`spawn`](AsyncContext::spawn).
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// use actix::prelude::*;
+    ///
+    /// struct Cache;
+    ///
+    /// impl Actor for Cache {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         let evict = fut::loop_fn((), |_, _, _| {
+    ///             fut::wrap_future(actix_rt::time::sleep(Duration::from_secs(60)))
+    ///                 .map(|_, _, _| fut::Loop::Continue(()))
+    ///         });
+    ///         ctx.spawn_detached_housekeeping(evict);
+    ///     }
+    ///
+    ///     fn stopped(&mut self, _: &mut Context<Self>) {
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     // stops as soon as its address is dropped
+    ///     Cache.start();
+    /// }
+    /// ```
+    pub fn spawn_detached_housekeeping<F>(&mut self, fut: F) -> SpawnHandle
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        self.parts.spawn_housekeeping(fut)
+    }
+
+    /// Returns the handles of the spawned futures, streams and intervals that have neither
+    /// completed nor been cancelled, oldest first.
+    ///
+    /// Futures the actor waits on with [`wait`](AsyncContext::wait) are not included.
+    pub fn spawned_handles(&self) -> Vec<SpawnHandle> {
+        self.parts.spawned_handles()
+    }
+
+    /// Cancels every spawned future, stream and interval, returning how many were cancelled.
+    ///
+    /// Unlike [`stop`](ActorContext::stop), this leaves the actor running, and the futures it
+    /// waits on with [`wait`](AsyncContext::wait) are left alone. As with
+    /// [`cancel_future`](AsyncContext::cancel_future), the futures are dropped before the
+    /// actor is polled again.
+    ///
+    /// An actor without spawned futures and without any address stops, so an actor that only
+    /// runs its spawned futures stops once they are cancelled, unless it keeps an address of its
+    /// own.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "usize")]
+    /// struct Reset;
+    ///
+    /// struct Poller;
+    ///
+    /// impl Actor for Poller {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.run_interval(Duration::from_secs(1), |_, _| {});
+    ///         ctx.run_later(Duration::from_secs(5), |_, _| {});
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Reset> for Poller {
+    ///     type Result = usize;
+    ///
+    ///     fn handle(&mut self, _: Reset, ctx: &mut Context<Self>) -> usize {
+    ///         ctx.cancel_all()
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let addr = Poller.start();
+    ///     assert_eq!(addr.send(Reset).await.unwrap(), 2);
+    ///     assert_eq!(addr.send(Reset).await.unwrap(), 0);
+    /// }
+    /// ```
+    pub fn cancel_all(&mut self) -> usize {
+        self.parts.cancel_all()
+    }
+
+    /// Replaces the actor instance with `act` once the current handler returns, keeping the
+    /// address, the mailbox and the messages queued in it.
+    ///
+    /// This is Erlang's `become`, e.g. for actors modelled as state machines, where each state
+    /// is a variant of the actor. The swap happens before the next message is handled, like a
+    /// closure passed to [`defer`](Self::defer). `handover` decides what happens to the old
+    /// instance and the work it spawned:
+    ///
+    /// - with [`Handover::Swap`], the instances are swapped silently. Spawned futures, streams
+    ///   and timers keep running and get the new instance when they are polled next.
+    /// - with [`Handover::Restart`], [`Actor::stopped`] runs on the old instance, everything
+    ///   spawned in the context is cancelled as with [`cancel_all`](Self::cancel_all), and
+    ///   [`Actor::started`] runs on the new instance. Cleanup registered with
+    ///   [`on_cancel`](crate::fut::ActorFutureExt::on_cancel) runs on the new instance too.
+    ///
+    /// If `become_actor` is called more than once before the swap, the instances are swapped in
+    /// turn and the last one wins.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "bool")]
+    /// struct Toggle;
+    ///
+    /// enum Switch {
+    ///     Off,
+    ///     On,
+    /// }
+    ///
+    /// impl Actor for Switch {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Toggle> for Switch {
+    ///     type Result = bool;
+    ///
+    ///     /// Returns whether the switch is on after toggling it.
+    ///     fn handle(&mut self, _: Toggle, ctx: &mut Context<Self>) -> bool {
+    ///         match self {
+    ///             Switch::Off => ctx.become_actor(Switch::On, Handover::Swap),
+    ///             Switch::On => ctx.become_actor(Switch::Off, Handover::Swap),
+    ///         }
+    ///         matches!(self, Switch::Off)
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let addr = Switch::Off.start();
+    ///     assert!(addr.send(Toggle).await.unwrap());
+    ///     assert!(!addr.send(Toggle).await.unwrap());
+    /// }
+    /// ```
+    #[doc(alias = "become")]
+    pub fn become_actor(&mut self, act: A, handover: Handover) {
+        self.defer(move |old, ctx| {
+            let mut old = std::mem::replace(old, act);
+            if handover == Handover::Restart {
+                old.stopped(ctx);
+                ctx.cancel_all();
+                ctx.defer(Actor::started);
+            }
+        });
+    }
+
+    /// Replaces the stream `handle`, added with [`add_stream`](AsyncContext::add_stream), with
+    /// `stream`, returning `false` if there is no such stream.
+    ///
+    /// The old stream is dropped and `stream` takes over under the same handle, e.g. to fail
+    /// over to a backup feed. [`StreamHandler::finished`] is not called for the old stream, nor
+    /// [`StreamHandler::started`] for the new one, and no item of the old stream is handled
+    /// once this returns. Once `stream` is exhausted, `finished` is called as usual.
+    ///
+    /// A stream can replace itself from its own [`StreamHandler::handle`] or `finished`. In the
+    /// latter case, `stream` takes over instead of the old stream completing.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    /// use futures_util::stream::{iter, pending, StreamExt as _};
+    ///
+    /// #[derive(Default)]
+    /// struct Feed {
+    ///     source: Option<SpawnHandle>,
+    ///     quotes: Vec<u32>,
+    /// }
+    ///
+    /// impl Actor for Feed {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         // the primary feed stalls after its first quote
+    ///         let primary = iter([1]).chain(pending());
+    ///         self.source = Some(ctx.add_stream(primary));
+    ///     }
+    /// }
+    ///
+    /// impl StreamHandler<u32> for Feed {
+    ///     fn handle(&mut self, quote: u32, ctx: &mut Context<Self>) {
+    ///         self.quotes.push(quote);
+    ///         if quote == 1 {
+    ///             // fail over to the backup feed
+    ///             let source = self.source.unwrap();
+    ///             assert!(ctx.replace_stream(source, iter([2, 3])));
+    ///         }
+    ///     }
+    ///
+    ///     fn finished(&mut self, ctx: &mut Context<Self>) {
+    ///         assert_eq!(self.quotes, [1, 2, 3]);
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     Feed::default().start();
+    /// }
+    /// ```
+    pub fn replace_stream<S>(&mut self, handle: SpawnHandle, stream: S) -> bool
+    where
+        S: Stream + 'static,
+        A: StreamHandler<S::Item>,
+    {
+        self.parts.replace(
+            handle,
+            ActorStream::switched(stream, <A as StreamHandler<S::Item>>::finished),
+        )
+    }
+
+    /// Sends `event` to every observer attached with [`Addr::observe`], returning the number of
+    /// observers it was delivered to.
+    ///
+    /// Every observer buffers a bounded number of events. An observer that does not keep up
+    /// misses the events emitted while its buffer is full, so a slow observer never blocks the
+    /// actor. See [`Observable`].
+    pub fn emit(&mut self, event: A::Event) -> usize
+    where
+        A: Observable,
+    {
+        self.parts.observers().emit(event)
+    }
+
+    /// Returns a [`SelfNotifier`] for sending messages to this actor from spawned work, without
+    /// keeping the actor running like an [`Addr`] does.
+    pub fn notifier(&self) -> SelfNotifier<A> {
+        self.parts.notifier()
+    }
+
+    /// Adds the actor to shutdown `phase` of the current system.
+    ///
+    /// On [`PhasedShutdown::shutdown`](crate::PhasedShutdown::shutdown), phases are stopped in
+    /// ascending order, each one once the previous one has fully stopped. Calling this again
+    /// moves the actor to another phase. An actor that keeps running from
+    /// [`Actor::stopping`] holds up the shutdown.
+    pub fn shutdown_phase(&mut self, phase: u32) {
+        crate::shutdown::join(self.parts.id(), phase, self.address().downgrade());
+    }
+
+    /// Records the messages of type `M` handled by this actor into `recorder`.
+    ///
+    /// See the [`record`](crate::record) module. Like other middleware, recording stops when
+    /// the actor is restarted by its supervisor.
+    #[cfg(feature = "record")]
+    pub fn record<M>(&mut self, recorder: &crate::record::Recorder)
+    where
+        M: crate::record::RecordableMessage,
+    {
+        let actor = self.parts.id();
+        self.add_middleware::<M, _>(crate::record::Record {
+            recorder: recorder.clone(),
+            actor,
+        });
+    }
+
+    /// Registers middleware wrapping the handling of messages of type `M`.
+    ///
+    /// Middleware for the same message type runs in registration order, see
+    /// [`MessageMiddleware`]. Registered middleware is dropped when the actor is restarted by
+    /// its supervisor.
+    pub fn add_middleware<M, T>(&mut self, middleware: T)
+    where
+        M: Message + 'static,
+        T: MessageMiddleware<A, M>,
+    {
+        self.parts.middlewares().add(middleware)
+    }
+
+    /// Caches the results of messages of type `M` for `ttl`, so that a message equal to one
+    /// handled within `ttl` is answered with the cached result, without calling the handler.
+    ///
+    /// This suits expensive queries whose results change slowly. Asynchronous results are
+    /// cached once they resolve; equal messages arriving until then are handled as well.
+    /// Middleware registered for `M` runs for cached results too. Calling this again changes
+    /// the `ttl` of the cached results. Caching stops when the actor is restarted by its
+    /// supervisor.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message, Clone, PartialEq, Eq, Hash)]
+    /// #[rtype(result = "u64")]
+    /// struct Balance(u32);
+    ///
+    /// #[derive(Default)]
+    /// struct Ledger {
+    ///     queries: usize,
+    /// }
+    ///
+    /// impl Actor for Ledger {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.enable_memo::<Balance>(Duration::from_secs(1));
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Balance> for Ledger {
+    ///     type Result = u64;
+    ///
+    ///     fn handle(&mut self, Balance(account): Balance, _: &mut Context<Self>) -> u64 {
+    ///         // an expensive query
+    ///         self.queries += 1;
+    ///         account as u64 * 100
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let ledger = Ledger::default().start();
+    ///     assert_eq!(ledger.send(Balance(7)).await.unwrap(), 700);
+    ///     assert_eq!(ledger.send(Balance(7)).await.unwrap(), 700); // cached
+    /// }
+    /// ```
+    pub fn enable_memo<M>(&mut self, ttl: Duration)
+    where
+        M: Message + Eq + Hash + Clone + 'static,
+        M::Result: Clone,
+    {
+        self.parts.memos().enable::<M>(ttl)
+    }
+
+    /// Drops the cached result of `msg`, see [`enable_memo`](Self::enable_memo).
+    ///
+    /// Results of messages of type `M` that are still pending are not cached either, as they
+    /// may be outdated.
+    pub fn invalidate_memo<M>(&mut self, msg: &M)
+    where
+        M: Message + 'static,
+    {
+        self.parts.memos().invalidate(msg)
+    }
+
+    /// Drops all cached results of messages of type `M`, see
+    /// [`enable_memo`](Self::enable_memo).
+    pub fn clear_memo<M>(&mut self)
+    where
+        M: Message + 'static,
+    {
+        self.parts.memos().clear::<M>()
+    }
+
+    /// Creates a child actor asynchronously.
+    ///
+    /// The future returned by `f` is spawned into this context. Once it resolves, the child
+    /// actor is started on the current arbiter and its address is delivered to this actor as a
+    /// [`ChildStarted`] message. The creation is cancelled if this actor stops before the future
+    /// resolves, or if the returned handle is passed to
+    /// [`cancel_future`](AsyncContext::cancel_future).
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct Child;
+    ///
+    /// impl Actor for Child {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// struct Parent {
+    ///     child: Option<Addr<Child>>,
+    /// }
+    ///
+    /// impl Actor for Parent {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         ctx.create_child(|| async {
+    ///             // async setup, e.g. opening a connection
+    ///             Child
+    ///         });
+    ///     }
+    /// }
+    ///
+    /// impl Handler<ChildStarted<Child>> for Parent {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, msg: ChildStarted<Child>, _: &mut Self::Context) {
+    ///         self.child = Some(msg.0);
+    /// #       System::current().stop();
+    ///     }
+    /// }
+    /// # #[actix::main] async fn main() { Parent { child: None }.start(); }
+    /// ```
+    pub fn create_child<B, F, Fut>(&mut self, f: F) -> SpawnHandle
+    where
+        A: Handler<ChildStarted<B>>,
+        B: Actor<Context = Context<B>>,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = B> + 'static,
+    {
+        let fut = fut::wrap_future::<_, A>(f())
+            .map(|child: B, _, ctx: &mut Self| ctx.notify(ChildStarted(child.start())));
+        self.spawn(fut)
+    }
+
+    /// Links an actor to this one, so that it is stopped once this actor stops.
+    ///
+    /// When this actor has stopped for good, i.e. [`Actor::stopping`] returned
+    /// [`Running::Stop`](crate::Running::Stop), the linked actor is asked to stop gracefully:
+    /// messages already queued in its mailbox are still handled. Linking does not keep the
+    /// child alive.
+    ///
+    /// Linking is one-directional: this actor stops its children, but a child stopping,
+    /// including one that stopped before its parent, does not affect this actor. Use
+    /// [`watch`](Self::watch) to react to that as well.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// struct Child;
+    ///
+    /// impl Actor for Child {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn stopped(&mut self, _: &mut Self::Context) {
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// struct Parent;
+    ///
+    /// impl Actor for Parent {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         let child = Child.start();
+    ///         ctx.link_child(&child);
+    ///         ctx.stop();
+    ///     }
+    /// }
+    /// # #[actix::main] async fn main() { Parent.start(); }
+    /// ```
+    pub fn link_child<B>(&mut self, addr: &Addr<B>)
+    where
+        B: Actor,
+        B::Context: AsyncContextParts<B>,
+    {
+        let child = addr.downgrade();
+        self.parts.on_stop(Box::new(move || {
+            if let Some(child) = child.upgrade() {
+                child.wait_stopped(true);
+            }
+        }));
+    }
+
+    /// Registers this actor under `path` in the [`PathRegistry`] for messages of type `M`,
+    /// removing the registration once the actor stops.
+    ///
+    /// Like [`PathRegistry::register`], this replaces a recipient registered for the same path
+    /// and message type before. A registration that got replaced is left in place when the
+    /// actor stops.
+    pub fn register_path<M>(&mut self, path: ActorPath)
+    where
+        A: Handler<M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        let registration = PathRegistry::insert(path, self.address().recipient::<M>());
+        self.parts.on_stop(Box::new(move || registration.remove()));
+    }
+
+    /// Watches another actor, delivering a [`Terminated`] message to this actor once it has
+    /// stopped.
+    ///
+    /// The message is also delivered if the watched actor has already stopped. Watching does
+    /// not keep either actor alive, and nothing is delivered if this actor has stopped first.
+    pub fn watch<B>(&mut self, addr: &Addr<B>)
+    where
+        A: Handler<Terminated<B>>,
+        B: Actor,
+        B::Context: AsyncContextParts<B>,
+    {
+        let stopped = addr.wait_stopped(false);
+        let watched = addr.downgrade();
+        let watcher = self.address().downgrade();
+
+        actix_rt::spawn(async move {
+            if let Some(stopped) = stopped {
+                let _ = stopped.await;
+            }
+            if let Some(watcher) = watcher.upgrade() {
+                watcher.do_send(Terminated(watched));
+            }
+        });
+    }
+}
+
+/// Message delivered to an actor watching another one with [`Context::watch`], once the
+/// watched actor has stopped.
+pub struct Terminated<B: Actor>(pub WeakAddr<B>);
+
+impl<B: Actor> Message for Terminated<B> {
+    type Result = ();
+}
+
+impl<B: Actor> fmt::Debug for Terminated<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("Terminated").field(&self.0).finish()
+    }
+}
+
+/// What [`Context::become_actor`] does with the replaced actor instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handover {
+    /// Swaps the instances without running lifecycle methods. Spawned work keeps running.
+    Swap,
+
+    /// Runs `stopped` on the old instance, cancels spawned work and runs `started` on the new
+    /// instance.
+    Restart,
+}
+
+/// Message delivered to an actor once a child created with [`Context::create_child`] has
+/// started.
+pub struct ChildStarted<B: Actor>(pub Addr<B>);
+
+impl<B: Actor> Message for ChildStarted<B> {
+    type Result = ();
+}
+
+impl<B: Actor> fmt::Debug for ChildStarted<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("ChildStarted").field(&self.0).finish()
+    }
 }
 
 impl<A> Default for Context<A>
@@ -1,36 +1,209 @@
 use std::{
+    any::{Any, TypeId},
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
     fmt,
     future::Future,
     pin::Pin,
+    rc::Rc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use bitflags::bitflags;
 use futures_core::ready;
 use smallvec::SmallVec;
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    actor::{Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle, Supervised},
-    address::{Addr, AddressSenderProducer},
+    actor::{
+        Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle, StartError, Supervised,
+    },
+    address::{Addr, AddressSenderProducer, Envelope, SelfNotifier},
+    audit,
+    clock::{sleep, Instant, Sleep},
     contextitems::ActorWaitItem,
-    fut::ActorFuture,
+    deadline::{self, WithDeadline},
+    directory::{ActorId, Registration},
+    fut::{wrap_future, ActorFuture, LocalBoxActorFuture},
+    limit::Slot,
     mailbox::Mailbox,
+    memo::Memos,
+    middleware::Middlewares,
+    observe::Observers,
+    propagation::{self, Propagated},
+    rate_limit::{Rate, RateLimiter},
+    registry,
+    replies::PendingReplies,
+    semaphore::Semaphore,
+    stash::Stash,
 };
 
+/// Default number of messages handled in a row before the spawned futures get their turn.
+pub(crate) const DEFAULT_MAILBOX_BUDGET: usize = 64;
+
+/// Default number of spawned futures polled in a row before the mailbox gets its turn.
+pub(crate) const DEFAULT_FUTURES_BUDGET: usize = 64;
+
+/// Closures deferred with [`Context::defer`](crate::Context::defer), in registration order.
+pub(crate) type Deferred<A> = Vec<Box<dyn FnOnce(&mut A, &mut <A as Actor>::Context)>>;
+
+/// Closures creating the futures that drain attached sinks once the actor stops, see
+/// [`SinkWrite`](crate::io::SinkWrite).
+pub(crate) type Drains<A> = Vec<Box<dyn FnOnce() -> Option<LocalBoxActorFuture<A, ()>>>>;
+
+/// Deadlines of the timers scheduled in a context, by the handle of their future.
+pub(crate) type Timers = Vec<(SpawnHandle, Rc<Cell<Instant>>)>;
+
+/// Pending notification from `Context::notify_once`, cleared once dropped, whether the
+/// notification was delivered or cancelled.
+pub(crate) struct NotifiedOnce {
+    pending: Rc<RefCell<HashSet<TypeId>>>,
+    type_id: TypeId,
+}
+
+impl Drop for NotifiedOnce {
+    fn drop(&mut self) {
+        self.pending.borrow_mut().remove(&self.type_id);
+    }
+}
+
+/// Runs the closures deferred by the handler that just returned, including closures deferred
+/// by them.
+pub(crate) fn run_deferred<A>(act: &mut A, ctx: &mut A::Context)
+where
+    A: Actor,
+    A::Context: AsyncContextParts<A>,
+{
+    loop {
+        let deferred = std::mem::take(ctx.parts().deferred());
+        if deferred.is_empty() {
+            return;
+        }
+        for f in deferred {
+            f(act, ctx);
+        }
+    }
+}
+
+type Suspended = Vec<Pin<Box<dyn Future<Output = ()>>>>;
+
+thread_local! {
+    /// Futures suspending the context handling a message on this thread, see [`suspend`].
+    static SUSPENDED: RefCell<Option<Suspended>> = const { RefCell::new(None) };
+}
+
+/// Stops processing incoming messages until `fut` resolves, if a context is handling a message
+/// on the calling thread, and runs `fut` on the current arbiter otherwise.
+pub(crate) fn suspend(fut: Pin<Box<dyn Future<Output = ()>>>) {
+    let fut = SUSPENDED.with(|suspended| match *suspended.borrow_mut() {
+        Some(ref mut suspended) => {
+            suspended.push(fut);
+            None
+        }
+        None => Some(fut),
+    });
+    if let Some(fut) = fut {
+        actix_rt::spawn(fut);
+    }
+}
+
+/// Runs `f`, which handles a message, then waits for the futures passed to [`suspend`] meanwhile
+/// before the context processes incoming messages again.
+pub(crate) fn suspending<A, R>(
+    act: &mut A,
+    ctx: &mut A::Context,
+    f: impl FnOnce(&mut A, &mut A::Context) -> R,
+) -> R
+where
+    A: Actor,
+    A::Context: AsyncContextParts<A>,
+{
+    /// Restores the previous futures once dropped, also if `f` panics.
+    struct Restore(Option<Suspended>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SUSPENDED.with(|suspended| *suspended.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(SUSPENDED.with(|suspended| suspended.replace(Some(Vec::new()))));
+    let res = f(act, ctx);
+    let suspended = SUSPENDED.with(|suspended| suspended.borrow_mut().take());
+    for fut in suspended.into_iter().flatten() {
+        ctx.parts().wait(wrap_future(fut));
+    }
+    res
+}
+
 bitflags! {
     /// Internal context state.
     #[derive(Debug)]
-    struct ContextFlags: u8 {
+    struct ContextFlags: u16 {
         const STARTED =  0b0000_0001;
         const RUNNING =  0b0000_0010;
         const STOPPING = 0b0000_0100;
         const STOPPED =  0b0001_0000;
         const MB_CAP_CHANGED = 0b0010_0000;
+        const PAUSED = 0b0100_0000;
+        const RESUMED = 0b1000_0000;
     }
 }
 
 type Item<A> = (SpawnHandle, Pin<Box<dyn ActorFuture<A, Output = ()>>>);
 
+/// Boxes a future spawned into a context, propagating the context and the message deadline
+/// current on the calling thread.
+fn boxed<A, F>(fut: F) -> Pin<Box<dyn ActorFuture<A, Output = ()>>>
+where
+    A: Actor,
+    F: ActorFuture<A, Output = ()> + 'static,
+{
+    match deadline::current() {
+        Some(deadline) => propagated(WithDeadline::new(fut, deadline)),
+        None => propagated(fut),
+    }
+}
+
+fn propagated<A, F>(fut: F) -> Pin<Box<dyn ActorFuture<A, Output = ()>>>
+where
+    A: Actor,
+    F: ActorFuture<A, Output = ()> + 'static,
+{
+    match propagation::capture() {
+        Some(cx) => Box::pin(Propagated::new(fut, cx)),
+        None => Box::pin(fut),
+    }
+}
+
+type Callback<A> = Box<dyn FnMut(&mut A, &mut <A as Actor>::Context)>;
+
+/// Default time an actor has to be idle before the idle callback fires.
+const DEFAULT_IDLE_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Idle and busy callbacks of an actor.
+struct IdleWatch<A: Actor> {
+    debounce: Duration,
+    on_idle: Option<Callback<A>>,
+    on_busy: Option<Callback<A>>,
+    idle: bool,
+    timer: Pin<Box<Sleep>>,
+}
+
+impl<A: Actor> IdleWatch<A> {
+    fn new(debounce: Duration) -> Self {
+        IdleWatch {
+            debounce,
+            on_idle: None,
+            on_busy: None,
+            idle: false,
+            timer: Box::pin(sleep(debounce)),
+        }
+    }
+}
+
 pub trait AsyncContextParts<A>: ActorContext + AsyncContext<A>
 where
     A: Actor<Context = Self>,
@@ -44,10 +217,37 @@ where
     A::Context: AsyncContext<A>,
 {
     addr: AddressSenderProducer<A>,
+    id: ActorId,
     flags: ContextFlags,
     wait: SmallVec<[ActorWaitItem<A>; 2]>,
     items: SmallVec<[Item<A>; 3]>,
     handles: SmallVec<[SpawnHandle; 2]>,
+    state_waiters: Vec<(ActorState, oneshot::Sender<()>)>,
+    stop_hooks: Vec<Box<dyn FnOnce()>>,
+    middlewares: Middlewares<A>,
+    memos: Memos,
+    replies: PendingReplies,
+    idle: Option<IdleWatch<A>>,
+    rate_limiters: HashMap<String, RateLimiter>,
+    semaphores: HashMap<String, Semaphore>,
+    stop_gates: Vec<LocalBoxActorFuture<A, ()>>,
+    drains: Drains<A>,
+    cancel: Option<CancellationToken>,
+    stash: Stash<A>,
+    /// Handles of the spawned futures that have neither completed nor been cancelled, in
+    /// ascending order.
+    spawned: Vec<SpawnHandle>,
+    timers: Timers,
+    /// Futures replacing merged items, installed by `ContextFut` before polling them again.
+    replaced: Vec<Item<A>>,
+    housekeeping: Vec<SpawnHandle>,
+    deferred: Deferred<A>,
+    batches: HashMap<TypeId, Box<dyn Any>>,
+    /// Message types with a notification from `Context::notify_once` that is not handled yet.
+    notified_once: Rc<RefCell<HashSet<TypeId>>>,
+    mailbox_budget: usize,
+    futures_budget: usize,
+    handler_timeout: Option<Duration>,
 }
 
 impl<A> fmt::Debug for ContextParts<A>
@@ -71,11 +271,34 @@ where
     /// Create new [`ContextParts`] instance
     pub fn new(addr: AddressSenderProducer<A>) -> Self {
         ContextParts {
+            id: addr.id(),
             addr,
             flags: ContextFlags::RUNNING,
             wait: SmallVec::new(),
             items: SmallVec::new(),
             handles: SmallVec::from_slice(&[SpawnHandle::default(), SpawnHandle::default()]),
+            state_waiters: Vec::new(),
+            stop_hooks: Vec::new(),
+            middlewares: Middlewares::default(),
+            memos: Memos::default(),
+            replies: PendingReplies::default(),
+            idle: None,
+            rate_limiters: HashMap::new(),
+            semaphores: HashMap::new(),
+            stop_gates: Vec::new(),
+            drains: Vec::new(),
+            cancel: None,
+            stash: Stash::default(),
+            spawned: Vec::new(),
+            timers: Vec::new(),
+            replaced: Vec::new(),
+            housekeeping: Vec::new(),
+            deferred: Vec::new(),
+            batches: HashMap::new(),
+            notified_once: Rc::default(),
+            mailbox_budget: DEFAULT_MAILBOX_BUDGET,
+            futures_budget: DEFAULT_FUTURES_BUDGET,
+            handler_timeout: None,
         }
     }
 
@@ -88,13 +311,38 @@ where
         if self.flags.contains(ContextFlags::RUNNING) {
             self.flags.remove(ContextFlags::RUNNING);
             self.flags.insert(ContextFlags::STOPPING);
+            self.cancel();
+            self.notify_state_waiters();
+        }
+    }
+
+    /// Returns the token cancelled once the actor stops.
+    pub(crate) fn cancellation_token(&mut self) -> CancellationToken {
+        let stopping = !self.flags.contains(ContextFlags::RUNNING)
+            && self.flags.contains(ContextFlags::STARTED);
+        let token = self.cancel.get_or_insert_with(CancellationToken::new);
+        if stopping {
+            token.cancel();
         }
+        token.clone()
+    }
+
+    fn cancel(&mut self) {
+        if let Some(ref token) = self.cancel {
+            token.cancel();
+        }
+    }
+
+    /// Delays the transition to stopped until `fut` resolves.
+    pub(crate) fn delay_stop(&mut self, fut: LocalBoxActorFuture<A, ()>) {
+        self.stop_gates.push(fut);
     }
 
     #[inline]
     /// Terminate actor execution
     pub fn terminate(&mut self) {
         self.flags = ContextFlags::STOPPED;
+        self.cancel();
     }
 
     #[inline]
@@ -134,8 +382,8 @@ where
     {
         let handle = self.handles[0].next();
         self.handles[0] = handle;
-        let fut: Box<dyn ActorFuture<A, Output = ()>> = Box::new(fut);
-        self.items.push((handle, Pin::from(fut)));
+        self.items.push((handle, boxed(fut)));
+        self.spawned.push(handle);
         handle
     }
 
@@ -147,32 +395,343 @@ where
     where
         F: ActorFuture<A, Output = ()> + 'static,
     {
-        self.wait.push(ActorWaitItem::new(f));
+        match deadline::current() {
+            Some(deadline) => self
+                .wait
+                .push(ActorWaitItem::new(propagated(WithDeadline::new(
+                    f, deadline,
+                )))),
+            None => self.wait.push(ActorWaitItem::new(propagated(f))),
+        }
     }
 
     #[inline]
     /// Cancel previously scheduled future.
     pub fn cancel_future(&mut self, handle: SpawnHandle) -> bool {
+        self.forget_spawned(handle);
         self.handles.push(handle);
         true
     }
 
+    /// Handles of the spawned futures that have neither completed nor been cancelled.
+    pub(crate) fn spawned_handles(&self) -> Vec<SpawnHandle> {
+        self.spawned.clone()
+    }
+
+    /// Cancels every spawned future, returning how many were cancelled.
+    pub(crate) fn cancel_all(&mut self) -> usize {
+        let count = self.spawned.len();
+        self.handles.extend(self.spawned.drain(..));
+        self.timers.clear();
+        count
+    }
+
+    /// Records the deadline of the timer spawned as `handle`, see [`next_timer`](Self::next_timer).
+    pub(crate) fn track_timer(&mut self, handle: SpawnHandle, deadline: Rc<Cell<Instant>>) {
+        self.timers.push((handle, deadline));
+    }
+
+    /// Returns the time until the soonest deadline of the pending timers.
+    pub(crate) fn next_timer(&self) -> Option<Duration> {
+        let deadline = self
+            .timers
+            .iter()
+            .map(|(_, deadline)| deadline.get())
+            .min()?;
+        Some(deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Replaces the spawned future `handle` with `fut`, keeping its handle. Returns `false` if
+    /// there is no such future.
+    pub(crate) fn replace<F>(&mut self, handle: SpawnHandle, fut: F) -> bool
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        if self.spawned.binary_search(&handle).is_err() {
+            return false;
+        }
+        self.timers.retain(|(timer, _)| *timer != handle);
+
+        let fut = boxed(fut);
+        match self.items.iter_mut().find(|item| item.0 == handle) {
+            Some(item) => item.1 = fut,
+            None => self.replaced.push((handle, fut)),
+        }
+        true
+    }
+
+    #[inline]
+    fn forget_spawned(&mut self, handle: SpawnHandle) {
+        // handles are issued in ascending order
+        if let Ok(idx) = self.spawned.binary_search(&handle) {
+            self.spawned.remove(idx);
+            self.timers.retain(|(timer, _)| *timer != handle);
+        }
+    }
+
     #[inline]
     pub fn capacity(&mut self) -> usize {
         self.addr.capacity()
     }
 
+    #[inline]
+    pub fn mailbox_high_water(&self) -> usize {
+        self.addr.high_water()
+    }
+
     #[inline]
     pub fn set_mailbox_capacity(&mut self, cap: usize) {
         self.flags.insert(ContextFlags::MB_CAP_CHANGED);
         self.addr.set_capacity(cap);
     }
 
+    /// Returns the number of messages handled in a row before the spawned futures are polled.
+    #[inline]
+    pub fn mailbox_budget(&self) -> usize {
+        self.mailbox_budget
+    }
+
+    /// Sets the number of messages handled in a row before the spawned futures are polled.
+    #[inline]
+    pub fn set_mailbox_budget(&mut self, budget: usize) {
+        assert!(budget > 0, "the mailbox budget must not be zero");
+        self.mailbox_budget = budget;
+    }
+
+    /// Returns the time a handler has to reply before its response is aborted.
+    #[inline]
+    pub fn handler_timeout(&self) -> Option<Duration> {
+        self.handler_timeout
+    }
+
+    /// Sets the time a handler has to reply before its response is aborted.
+    #[inline]
+    pub fn set_handler_timeout(&mut self, timeout: Option<Duration>) {
+        self.handler_timeout = timeout;
+    }
+
+    /// Returns the number of spawned futures polled in a row before the mailbox is processed.
+    #[inline]
+    pub fn futures_budget(&self) -> usize {
+        self.futures_budget
+    }
+
+    /// Sets the number of spawned futures polled in a row before the mailbox is processed.
+    #[inline]
+    pub fn set_futures_budget(&mut self, budget: usize) {
+        assert!(budget > 0, "the futures budget must not be zero");
+        self.futures_budget = budget;
+    }
+
+    #[inline]
+    pub(crate) fn notifier(&self) -> SelfNotifier<A> {
+        SelfNotifier::new(self.addr.downgrade())
+    }
+
     #[inline]
     pub fn address(&self) -> Addr<A> {
         Addr::new(self.addr.sender())
     }
 
+    #[inline]
+    pub(crate) fn observers(&self) -> &Observers {
+        self.addr.observers()
+    }
+
+    #[inline]
+    /// Stop draining the mailbox until [`resume`](Self::resume) is called.
+    pub fn pause(&mut self) {
+        self.flags.insert(ContextFlags::PAUSED);
+    }
+
+    #[inline]
+    /// Continue draining the mailbox.
+    pub fn resume(&mut self) {
+        if self.flags.contains(ContextFlags::PAUSED) {
+            self.flags.remove(ContextFlags::PAUSED);
+            self.flags.insert(ContextFlags::RESUMED);
+        }
+    }
+
+    #[inline]
+    /// Is mailbox processing paused
+    pub fn paused(&self) -> bool {
+        self.flags.contains(ContextFlags::PAUSED)
+    }
+
+    /// Register a waiter that gets notified once the actor has reached `state` or a later one.
+    pub(crate) fn wait_state(&mut self, state: ActorState, tx: oneshot::Sender<()>) {
+        if state_order(self.state()) >= state_order(state) {
+            let _ = tx.send(());
+        } else {
+            self.state_waiters.push((state, tx));
+        }
+    }
+
+    /// Notifies the waiters for states the actor has reached.
+    fn notify_state_waiters(&mut self) {
+        let current = state_order(self.state());
+        for (state, tx) in std::mem::take(&mut self.state_waiters) {
+            if state_order(state) <= current {
+                let _ = tx.send(());
+            } else {
+                self.state_waiters.push((state, tx));
+            }
+        }
+    }
+
+    #[inline]
+    /// Register a callback run once this actor stops, e.g. to stop a linked actor.
+    pub(crate) fn on_stop(&mut self, hook: Box<dyn FnOnce()>) {
+        self.stop_hooks.push(hook);
+    }
+
+    fn idle_watch(&mut self) -> &mut IdleWatch<A> {
+        self.idle
+            .get_or_insert_with(|| IdleWatch::new(DEFAULT_IDLE_DEBOUNCE))
+    }
+
+    /// Sets the callback fired once the actor has become idle.
+    pub(crate) fn on_idle(&mut self, f: Callback<A>) {
+        self.idle_watch().on_idle = Some(f);
+    }
+
+    /// Sets the callback fired once the actor has become busy after being idle.
+    pub(crate) fn on_busy(&mut self, f: Callback<A>) {
+        self.idle_watch().on_busy = Some(f);
+    }
+
+    /// Sets how long the actor has to be idle before it is considered idle.
+    pub(crate) fn set_idle_debounce(&mut self, debounce: Duration) {
+        let watch = self.idle_watch();
+        watch.debounce = debounce;
+        watch.timer.as_mut().reset(Instant::now() + debounce);
+    }
+
+    /// Returns the pending batch of type `B`, creating it if needed.
+    pub(crate) fn batch<B: Default + 'static>(&mut self) -> &mut B {
+        self.batches
+            .entry(TypeId::of::<B>())
+            .or_insert_with(|| Box::<B>::default())
+            .downcast_mut()
+            .expect("batch keyed by its type")
+    }
+
+    /// Marks a notification of type `M` as pending, returning `None` if one already is.
+    ///
+    /// The notification stays pending until the returned guard is dropped.
+    pub(crate) fn notify_once<M: 'static>(&mut self) -> Option<NotifiedOnce> {
+        let type_id = TypeId::of::<M>();
+        self.notified_once
+            .borrow_mut()
+            .insert(type_id)
+            .then(|| NotifiedOnce {
+                pending: Rc::clone(&self.notified_once),
+                type_id,
+            })
+    }
+
+    /// Returns the rate limiter registered under `name`, creating it if needed.
+    pub(crate) fn rate_limiter(&mut self, name: &str, rate: Rate) -> RateLimiter {
+        if let Some(limiter) = self.rate_limiters.get(name) {
+            return limiter.clone();
+        }
+
+        let limiter = RateLimiter::new(rate);
+        self.rate_limiters.insert(name.to_owned(), limiter.clone());
+        limiter
+    }
+
+    pub(crate) fn semaphore(&mut self, name: &str, permits: usize) -> Semaphore {
+        if let Some(semaphore) = self.semaphores.get(name) {
+            return semaphore.clone();
+        }
+
+        let semaphore = Semaphore::new(permits);
+        self.semaphores.insert(name.to_owned(), semaphore.clone());
+        semaphore
+    }
+
+    #[inline]
+    pub(crate) fn id(&self) -> ActorId {
+        self.id
+    }
+
+    #[inline]
+    pub(crate) fn replies(&self) -> &PendingReplies {
+        &self.replies
+    }
+
+    #[inline]
+    pub(crate) fn middlewares(&mut self) -> &mut Middlewares<A> {
+        &mut self.middlewares
+    }
+
+    #[inline]
+    pub(crate) fn memos(&mut self) -> &mut Memos {
+        &mut self.memos
+    }
+
+    #[inline]
+    pub(crate) fn stash(&mut self) -> &mut Stash<A> {
+        &mut self.stash
+    }
+
+    #[inline]
+    pub(crate) fn drains(&mut self) -> &mut Drains<A> {
+        &mut self.drains
+    }
+
+    #[inline]
+    pub(crate) fn deferred(&mut self) -> &mut Deferred<A> {
+        &mut self.deferred
+    }
+
+    /// Spawns a future that does not keep the actor alive.
+    pub(crate) fn spawn_housekeeping<F>(&mut self, fut: F) -> SpawnHandle
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        let handle = self.spawn(fut);
+        self.housekeeping.push(handle);
+        handle
+    }
+
+    /// Stops tracking the future `handle`, once it has completed or been cancelled.
+    #[inline]
+    fn forget(&mut self, handle: SpawnHandle) {
+        self.forget_spawned(handle);
+        if !self.housekeeping.is_empty() {
+            self.housekeeping.retain(|h| *h != handle);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn stashed(&self) -> usize {
+        self.stash.len()
+    }
+
+    #[inline]
+    pub(crate) fn attempt(&self) -> u32 {
+        self.stash.attempt()
+    }
+
+    /// Returns the stashed messages to the front of the mailbox.
+    pub(crate) fn unstash_all(&mut self) -> usize {
+        let count = self.stash.unstash_all();
+        if count > 0 {
+            // the context may be polled from a spawned future, which does not revisit the mailbox
+            self.addr.wake();
+        }
+        count
+    }
+
+    /// Queues a message detached from the stash to be handled again, before the mailbox.
+    pub(crate) fn requeue(&mut self, env: Envelope<A>, attempt: u32) {
+        self.stash.requeue(env, attempt);
+        self.addr.wake();
+    }
+
     /// Restart context. Cleanup all futures, except address queue.
     #[inline]
     pub(crate) fn restart(&mut self) {
@@ -180,6 +739,18 @@ where
         self.wait = SmallVec::new();
         self.items = SmallVec::new();
         self.handles[0] = SpawnHandle::default();
+        self.spawned.clear();
+        self.timers.clear();
+        self.replaced.clear();
+        self.middlewares = Middlewares::default();
+        self.memos = Memos::default();
+        self.idle = None;
+        self.cancel = None;
+        self.housekeeping.clear();
+        self.batches.clear();
+        self.notified_once = Rc::default();
+        self.drains.clear();
+        self.unstash_all();
     }
 
     #[inline]
@@ -194,6 +765,16 @@ where
     }
 }
 
+/// Position of `state` in the lifecycle of an actor.
+fn state_order(state: ActorState) -> u8 {
+    match state {
+        ActorState::Started => 0,
+        ActorState::Running => 1,
+        ActorState::Stopping => 2,
+        ActorState::Stopped => 3,
+    }
+}
+
 pub struct ContextFut<A, C>
 where
     C: AsyncContextParts<A> + Unpin,
@@ -204,6 +785,17 @@ where
     mailbox: Mailbox<A>,
     wait: SmallVec<[ActorWaitItem<A>; 2]>,
     items: SmallVec<[Item<A>; 3]>,
+    directory: Option<Registration>,
+    handled: bool,
+    /// Whether a message has been handled since [`Actor::idle`] was last called.
+    busy: bool,
+    listeners: bool,
+    keep_alive: bool,
+    stop_gates: Vec<LocalBoxActorFuture<A, ()>>,
+    stop_gated: bool,
+    #[cfg(feature = "test-util")]
+    step: u64,
+    _slot: Slot,
 }
 
 impl<A, C> fmt::Debug for ContextFut<A, C>
@@ -227,6 +819,11 @@ where
             let waker = futures_task::noop_waker();
             let mut cx = std::task::Context::from_waker(&waker);
             let _ = Pin::new(self).poll(&mut cx);
+        } else if self.stop_gated {
+            // dropped while waiting for a delayed stop
+            self.stop_gates.clear();
+            self.stop_gated = false;
+            self.stopped();
         }
     }
 }
@@ -236,14 +833,35 @@ where
     C: AsyncContextParts<A> + Unpin,
     A: Actor<Context = C>,
 {
+    /// # Panics
+    /// Panics if the system has reached its [actor limit](crate::ActorLimit), or a service the
+    /// actor [requires](Actor::requires) can not be provided.
     pub fn new(ctx: C, act: A, mailbox: Mailbox<A>) -> Self {
-        ContextFut {
+        Self::try_new(ctx, act, mailbox).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub(crate) fn try_new(mut ctx: C, act: A, mailbox: Mailbox<A>) -> Result<Self, StartError> {
+        registry::provide_services::<A>()?;
+        let slot = Slot::reserve()?;
+        let parts = ctx.parts();
+        let directory = Registration::register::<A>(parts.id, parts.addr.queued_handle());
+        Ok(ContextFut {
             ctx,
             act,
             mailbox,
             wait: SmallVec::new(),
             items: SmallVec::new(),
-        }
+            directory,
+            handled: false,
+            busy: false,
+            listeners: true,
+            keep_alive: false,
+            stop_gates: Vec::new(),
+            stop_gated: false,
+            #[cfg(feature = "test-util")]
+            step: 0,
+            _slot: slot,
+        })
     }
 
     #[inline]
@@ -269,9 +887,15 @@ where
         if self.ctx.parts().flags.contains(ContextFlags::STOPPED) {
             false
         } else {
-            !self.ctx.parts().flags.contains(ContextFlags::STARTED)
+            let parts = self.ctx.parts();
+            let housekeeping = &parts.housekeeping;
+            !parts.flags.contains(ContextFlags::STARTED)
+                || self.keep_alive
                 || self.mailbox.connected()
-                || !self.items.is_empty()
+                || self
+                    .items
+                    .iter()
+                    .any(|(handle, _)| !housekeeping.contains(handle))
                 || !self.wait.is_empty()
         }
     }
@@ -283,8 +907,7 @@ where
         A: Supervised,
     {
         if self.mailbox.connected() {
-            self.wait = SmallVec::new();
-            self.items = SmallVec::new();
+            self.cancel_items();
             self.ctx.parts().restart();
             self.act.restarting(&mut self.ctx);
             true
@@ -293,6 +916,126 @@ where
         }
     }
 
+    /// Cancels the futures that are pending in the context, as they are not polled anymore.
+    fn cancel_items(&mut self) {
+        let parts = self.ctx.parts();
+        let mut wait = std::mem::take(&mut self.wait);
+        wait.extend(parts.wait.drain(..));
+        let mut items = std::mem::take(&mut self.items);
+        items.extend(parts.items.drain(..));
+
+        for item in wait.iter_mut().rev() {
+            Pin::new(item).cancel(&mut self.act, &mut self.ctx);
+        }
+        for (_, fut) in items.iter_mut() {
+            fut.as_mut().cancel(&mut self.act, &mut self.ctx);
+        }
+    }
+
+    /// Cancel pending futures, run the stop hooks, call `Actor::stopped` and notify everyone
+    /// waiting for the stop.
+    fn stopped(&mut self) {
+        self.cancel_items();
+        for hook in std::mem::take(&mut self.ctx.parts().stop_hooks) {
+            hook();
+        }
+        Actor::stopped(&mut self.act, &mut self.ctx);
+        for (_, tx) in self.ctx.parts().state_waiters.drain(..) {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Waits for the futures registered with `delay_stop`, then stops.
+    fn finish_stop(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let (act, ctx) = (&mut self.act, &mut self.ctx);
+        let parts = ctx.parts();
+        for drain in std::mem::take(&mut parts.drains) {
+            parts.stop_gates.extend(drain());
+        }
+        loop {
+            // gates may register further gates
+            self.stop_gates
+                .extend(std::mem::take(&mut ctx.parts().stop_gates));
+            self.stop_gates
+                .retain_mut(|gate| gate.as_mut().poll(act, ctx, cx).is_pending());
+            if ctx.parts().stop_gates.is_empty() {
+                break;
+            }
+        }
+
+        if self.stop_gates.is_empty() {
+            self.stop_gated = false;
+            self.stopped();
+            Poll::Ready(())
+        } else {
+            self.stop_gated = true;
+            Poll::Pending
+        }
+    }
+
+    /// Fires the idle and busy callbacks on transitions, returning whether one was called.
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> bool {
+        let handled = std::mem::take(&mut self.handled);
+        let parts = self.ctx.parts();
+        let in_flight = parts.replies.count() > 0;
+        let watch = match parts.idle {
+            Some(ref mut watch) => watch,
+            None => return false,
+        };
+
+        let callback = if handled || in_flight {
+            let deadline = Instant::now() + watch.debounce;
+            watch.timer.as_mut().reset(deadline);
+            let _ = watch.timer.as_mut().poll(cx);
+            if std::mem::take(&mut watch.idle) {
+                watch.on_busy.take().map(|f| (f, false))
+            } else {
+                None
+            }
+        } else if !watch.idle && watch.timer.as_mut().poll(cx).is_ready() {
+            watch.idle = true;
+            watch.on_idle.take().map(|f| (f, true))
+        } else {
+            None
+        };
+
+        let (mut f, idle) = match callback {
+            Some(callback) => callback,
+            None => return false,
+        };
+        f(&mut self.act, &mut self.ctx);
+
+        // put the callback back, unless it has been replaced or the context restarted
+        if let Some(watch) = self.ctx.parts().idle.as_mut() {
+            let slot = if idle {
+                &mut watch.on_idle
+            } else {
+                &mut watch.on_busy
+            };
+            slot.get_or_insert(f);
+        }
+        true
+    }
+
+    /// Calls [`Actor::idle`] once the mailbox has been drained, returning whether it was called.
+    fn poll_drained(&mut self) -> bool {
+        if !self.busy || !self.wait.is_empty() || !self.mailbox.is_empty() {
+            return false;
+        }
+        let parts = self.ctx.parts();
+        if !parts.flags.contains(ContextFlags::RUNNING)
+            || parts.paused()
+            || parts.replies.count() > 0
+        {
+            return false;
+        }
+
+        self.busy = false;
+        Actor::idle(&mut self.act, &mut self.ctx);
+        run_deferred(&mut self.act, &mut self.ctx);
+        true
+    }
+
     fn merge(&mut self) -> bool {
         let mut modified = false;
 
@@ -310,6 +1053,11 @@ where
             modified = true;
             parts.flags.remove(ContextFlags::MB_CAP_CHANGED);
         }
+        // mailbox has to be polled again after resume
+        if parts.flags.contains(ContextFlags::RESUMED) {
+            modified = true;
+            parts.flags.remove(ContextFlags::RESUMED);
+        }
         if parts.handles.len() > 2 {
             modified = true;
         }
@@ -317,31 +1065,39 @@ where
         modified
     }
 
+    /// Installs the futures replacing merged items, returning whether the item `current` was
+    /// replaced.
+    fn replace_items(&mut self, current: SpawnHandle) -> bool {
+        let mut replaced = false;
+        for (handle, fut) in std::mem::take(&mut self.ctx.parts().replaced) {
+            if let Some(item) = self.items.iter_mut().find(|item| item.0 == handle) {
+                item.1 = fut;
+                replaced |= handle == current;
+            }
+        }
+        replaced
+    }
+
     fn clean_canceled_handle(&mut self) {
         fn remove_item_by_handle<C>(
             items: &mut SmallVec<[Item<C>; 3]>,
             handle: &SpawnHandle,
-        ) -> bool {
-            let mut idx = 0;
-            let mut removed = false;
-            while idx < items.len() {
-                if &items[idx].0 == handle {
-                    items.swap_remove(idx);
-                    removed = true;
-                } else {
-                    idx += 1;
-                }
-            }
-            removed
+        ) -> Option<Item<C>> {
+            let idx = items.iter().position(|item| &item.0 == handle)?;
+            Some(items.swap_remove(idx))
         }
 
         while self.ctx.parts().handles.len() > 2 {
             let handle = self.ctx.parts().handles.pop().unwrap();
+            self.ctx.parts().forget(handle);
             // remove item from ContextFut.items in case associated item is already merged
-            if !remove_item_by_handle(&mut self.items, &handle) {
+            let item = remove_item_by_handle(&mut self.items, &handle).or_else(|| {
                 // item is not merged into ContextFut.items yet,
                 // so it should be in ContextParts.items
-                remove_item_by_handle(&mut self.ctx.parts().items, &handle);
+                remove_item_by_handle(&mut self.ctx.parts().items, &handle)
+            });
+            if let Some((_, mut fut)) = item {
+                fut.as_mut().cancel(&mut self.act, &mut self.ctx);
             }
         }
     }
@@ -358,9 +1114,38 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
 
+        #[cfg(feature = "test-util")]
+        if !crate::testing::enter_poll(&mut this.step) {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let _sender = audit::enter(this.ctx.parts().id());
+        let mut res = this.poll_actor(cx);
+        while res.is_pending() && (this.poll_idle(cx) || this.poll_drained()) {
+            res = this.poll_actor(cx);
+        }
+
+        if let Some(ref directory) = this.directory {
+            directory.set_state(this.ctx.parts().state());
+        }
+
+        res
+    }
+}
+
+impl<A, C> ContextFut<A, C>
+where
+    C: AsyncContextParts<A> + Unpin,
+    A: Actor<Context = C>,
+{
+    fn poll_actor(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self;
+
         if !this.ctx.parts().flags.contains(ContextFlags::STARTED) {
             this.ctx.parts().flags.insert(ContextFlags::STARTED);
             Actor::started(&mut this.act, &mut this.ctx);
+            run_deferred(&mut this.act, &mut this.ctx);
 
             // check cancelled handles, just in case
             if this.merge() {
@@ -368,6 +1153,13 @@ where
             }
         }
 
+        if this.stop_gated {
+            return this.finish_stop(cx);
+        }
+
+        // whether a budget ran out, leaving work for the next poll
+        let mut exhausted = false;
+
         'outer: loop {
             // check wait futures. order does matter
             // ctx.wait() always add to the back of the list
@@ -380,17 +1172,53 @@ where
                 this.merge();
             }
 
-            // process mailbox
-            this.mailbox.poll(&mut this.act, &mut this.ctx, cx);
+            // process mailbox, unless paused, up to its budget
+            let mut budget = this.ctx.parts().mailbox_budget;
+            let handled = this
+                .mailbox
+                .poll_while(&mut this.act, &mut this.ctx, cx, |ctx| {
+                    if budget == 0 || ctx.parts().paused() {
+                        return false;
+                    }
+                    budget -= 1;
+                    true
+                });
+            exhausted |= budget == 0;
+            this.handled |= handled;
+            this.busy |= handled;
             if !this.wait.is_empty() && !this.stopping() {
                 continue;
             }
 
-            // process items
+            // process items, up to their budget
+            if !this.ctx.parts().replaced.is_empty() {
+                this.replace_items(SpawnHandle::default());
+            }
+            let mut budget = this.ctx.parts().futures_budget;
             let mut idx = 0;
             while idx < this.items.len() && !this.stopping() {
-                this.ctx.parts().handles[1] = this.items[idx].0;
-                match Pin::new(&mut this.items[idx].1).poll(&mut this.act, &mut this.ctx, cx) {
+                if budget == 0 {
+                    // the items that did not get polled go first next time
+                    this.items.rotate_left(idx);
+                    exhausted = true;
+                    break;
+                }
+                budget -= 1;
+
+                let handle = this.items[idx].0;
+                this.ctx.parts().handles[1] = handle;
+                let item = &mut this.items[idx].1;
+                let poll = suspending(&mut this.act, &mut this.ctx, |act, ctx| {
+                    Pin::new(item).poll(act, ctx, cx)
+                });
+                run_deferred(&mut this.act, &mut this.ctx);
+
+                // the item got replaced while it was polled, poll its replacement right away
+                if !this.ctx.parts().replaced.is_empty() && this.replace_items(handle) {
+                    continue;
+                }
+
+                match poll {
                     Poll::Pending => {
                         // got new waiting item. merge
                         if this.ctx.waiting() {
@@ -423,7 +1251,8 @@ where
                         }
                     }
                     Poll::Ready(()) => {
-                        this.items.swap_remove(idx);
+                        let (handle, _) = this.items.swap_remove(idx);
+                        this.ctx.parts().forget(handle);
 
                         // got new waiting item. merge
                         if this.ctx.waiting() {
@@ -441,13 +1270,18 @@ where
 
             // merge returns true if context contains new items or handles to be cancelled
             if this.merge() && !this.ctx.parts().flags.contains(ContextFlags::STOPPING) {
-                // if we have no item to process, cancelled handles wouldn't be
-                // reaped in the above loop. this means this.merge() will never
-                // be false and the poll() never ends. so, discard the handles
-                // as we're sure there are no more items to be cancelled.
-                if this.items.is_empty() {
-                    this.ctx.parts().handles.truncate(2);
-                }
+                // reap the cancelled handles before polling the merged items, otherwise an
+                // item cancelled right after it was spawned would still run. with no items
+                // left this discards the handles, so this.merge() turns false eventually.
+                this.clean_canceled_handle();
+                continue;
+            }
+
+            // the last address got dropped
+            let listeners = this.mailbox.connected();
+            if std::mem::replace(&mut this.listeners, listeners) && !listeners && !this.stopping() {
+                this.keep_alive =
+                    Actor::no_listeners(&mut this.act, &mut this.ctx) == Running::Continue;
                 continue;
             }
 
@@ -456,24 +1290,30 @@ where
                 // possible stop condition
                 if !this.alive() && Actor::stopping(&mut this.act, &mut this.ctx) == Running::Stop {
                     this.ctx.parts().flags = ContextFlags::STOPPED | ContextFlags::STARTED;
-                    Actor::stopped(&mut this.act, &mut this.ctx);
-                    return Poll::Ready(());
+                    this.ctx.parts().cancel();
+                    return this.finish_stop(cx);
                 }
             } else if this.ctx.parts().flags.contains(ContextFlags::STOPPING) {
                 if Actor::stopping(&mut this.act, &mut this.ctx) == Running::Stop {
                     this.ctx.parts().flags = ContextFlags::STOPPED | ContextFlags::STARTED;
-                    Actor::stopped(&mut this.act, &mut this.ctx);
-                    return Poll::Ready(());
+                    return this.finish_stop(cx);
                 } else {
-                    this.ctx.parts().flags.remove(ContextFlags::STOPPING);
-                    this.ctx.parts().flags.insert(ContextFlags::RUNNING);
+                    let parts = this.ctx.parts();
+                    parts.flags.remove(ContextFlags::STOPPING);
+                    parts.flags.insert(ContextFlags::RUNNING);
+                    // the running actor gets a fresh token
+                    parts.cancel = None;
                     continue;
                 }
             } else if this.ctx.parts().flags.contains(ContextFlags::STOPPED) {
-                Actor::stopped(&mut this.act, &mut this.ctx);
+                this.stopped();
                 return Poll::Ready(());
             }
 
+            if exhausted {
+                // come back for the remaining work, after other tasks had their turn
+                cx.waker().wake_by_ref();
+            }
             return Poll::Pending;
         }
     }
@@ -1,4 +1,5 @@
 use std::{
+    fmt,
     future::Future,
     pin::Pin,
     task::{self, Poll},
@@ -9,10 +10,10 @@ use futures_core::{ready, stream::Stream};
 use pin_project_lite::pin_project;
 
 use crate::{
-    actor::{Actor, ActorContext, AsyncContext},
+    actor::{Actor, ActorContext, AsyncContext, Running},
     clock::Sleep,
     fut::ActorFuture,
-    handler::{Handler, Message, MessageResponse},
+    handler::{ErrorHandler, Handler, Message, MessageResponse},
 };
 
 pub(crate) struct ActorWaitItem<A: Actor>(Pin<Box<dyn ActorFuture<A, Output = ()>>>);
@@ -41,12 +42,18 @@ where
                 if ctx.state().alive() {
                     Poll::Pending
                 } else {
+                    self.cancel(act, ctx);
                     Poll::Ready(())
                 }
             }
             Poll::Ready(_) => Poll::Ready(()),
         }
     }
+
+    /// Cancels the future, which is not polled anymore.
+    pub fn cancel(mut self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        self.0.as_mut().cancel(act, ctx)
+    }
 }
 
 pin_project! {
@@ -162,3 +169,129 @@ where
         Poll::Ready(())
     }
 }
+
+pin_project! {
+    pub(crate) struct ActorResultStreamItem<S>{
+        #[pin]
+        stream: S,
+    }
+}
+
+impl<S> ActorResultStreamItem<S> {
+    pub fn new(st: S) -> Self {
+        Self { stream: st }
+    }
+}
+
+impl<A, S, T, E> ActorFuture<A> for ActorResultStreamItem<S>
+where
+    S: Stream<Item = Result<T, E>>,
+    A: Actor + Handler<T> + ErrorHandler<E>,
+    A::Context: AsyncContext<A>,
+    T: Message + 'static,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        while let Some(item) = ready!(this.stream.as_mut().poll_next(task)) {
+            match item {
+                Ok(msg) => Handler::handle(act, msg, ctx).handle(ctx, None),
+                Err(err) => {
+                    if act.error(err, ctx) == Running::Stop {
+                        return Poll::Ready(());
+                    }
+                }
+            }
+            if ctx.waiting() {
+                return Poll::Pending;
+            }
+        }
+
+        Poll::Ready(())
+    }
+}
+
+/// A group of actor futures that are spawned and cancelled together.
+///
+/// A scope is populated by the closure passed to [`AsyncContext::scope`]. All futures spawned
+/// into the scope are driven by a single context item, so cancelling the returned
+/// [`SpawnHandle`](crate::SpawnHandle) cancels every unresolved child future at once. The scope
+/// resolves once all of its children have resolved.
+pub struct Scope<A: Actor> {
+    items: Vec<Pin<Box<dyn ActorFuture<A, Output = ()>>>>,
+}
+
+impl<A: Actor> Scope<A> {
+    pub(crate) fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    /// Spawns a future into the scope.
+    pub fn spawn<F>(&mut self, fut: F)
+    where
+        F: ActorFuture<A, Output = ()> + 'static,
+    {
+        self.items.push(Box::pin(fut));
+    }
+
+    /// Returns the number of futures in the scope which have not resolved yet.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the scope has no unresolved futures.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl<A: Actor> fmt::Debug for Scope<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Scope")
+            .field("items", &self.items.len())
+            .finish()
+    }
+}
+
+impl<A: Actor> ActorFuture<A> for Scope<A> {
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let mut idx = 0;
+        while idx < this.items.len() {
+            match this.items[idx].as_mut().poll(act, ctx, task) {
+                Poll::Ready(()) => {
+                    // order of children is irrelevant
+                    this.items.swap_remove(idx);
+                }
+                Poll::Pending => idx += 1,
+            }
+        }
+
+        if this.items.is_empty() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        for mut item in self.get_mut().items.drain(..) {
+            item.as_mut().cancel(act, ctx);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    cell::Cell,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+
+use crate::{actor::Actor, clock::Instant, fut::ActorFuture};
+
+thread_local! {
+    /// Deadline of the message being handled on this thread, if any.
+    static CURRENT: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Returns the deadline of the message being handled on the calling thread.
+#[inline]
+pub(crate) fn current() -> Option<Instant> {
+    CURRENT.with(Cell::get)
+}
+
+/// Runs `f` with `deadline` as the current deadline, so that messages sent by `f` carry it.
+pub(crate) fn scope<R>(deadline: Option<Instant>, f: impl FnOnce() -> R) -> R {
+    /// Restores the previous deadline once dropped, also if `f` panics.
+    struct Restore(Option<Instant>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(CURRENT.with(|current| current.replace(deadline)));
+    f()
+}
+
+pin_project! {
+    /// A future running with the deadline of the message it was spawned for.
+    pub(crate) struct WithDeadline<F> {
+        #[pin]
+        fut: F,
+        deadline: Instant,
+    }
+}
+
+impl<F> WithDeadline<F> {
+    pub(crate) fn new(fut: F, deadline: Instant) -> Self {
+        WithDeadline { fut, deadline }
+    }
+}
+
+impl<A, F> ActorFuture<A> for WithDeadline<F>
+where
+    A: Actor,
+    F: ActorFuture<A>,
+{
+    type Output = F::Output;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        srv: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.project();
+        let fut = this.fut;
+        scope(Some(*this.deadline), || fut.poll(srv, ctx, task))
+    }
+
+    fn cancel(self: Pin<&mut Self>, srv: &mut A, ctx: &mut A::Context) {
+        let this = self.project();
+        let fut = this.fut;
+        scope(Some(*this.deadline), || fut.cancel(srv, ctx))
+    }
+}
@@ -0,0 +1,207 @@
+//! Opt-in directory of live actors, for diagnostics.
+
+use std::{
+    any::type_name,
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+        Arc, Weak,
+    },
+};
+
+use actix_rt::System;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{actor::ActorState, address::channel::QueuedMessages};
+
+type Entries = HashMap<ActorId, Arc<Entry>>;
+
+/// Live actors of every system that enabled the directory, keyed by system id.
+static DIRECTORY: Lazy<Mutex<HashMap<usize, Entries>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Set once any system enables the directory, so that actors of other systems skip the lock.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Process-wide unique identifier of an actor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "record", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActorId(u64);
+
+impl ActorId {
+    /// Allocates a new unique id.
+    pub(crate) fn next() -> Self {
+        ActorId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Returns the numeric value of this id.
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for ActorId {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "#{}", self.0)
+    }
+}
+
+/// Snapshot of a live actor, as returned by [`ActorDirectory::actors`].
+#[derive(Clone, Debug)]
+pub struct ActorInfo {
+    /// Unique id of the actor.
+    pub id: ActorId,
+    /// Type name of the actor.
+    pub type_name: &'static str,
+    /// Execution state as of the last time the actor was polled.
+    pub state: ActorState,
+    /// Number of messages currently waiting in the mailbox.
+    pub mailbox_len: usize,
+}
+
+/// Enumeration of the live actors of a [`System`].
+///
+/// The directory is opt-in: until [`enable_actor_directory`](Self::enable_actor_directory) is
+/// called, actors are not tracked and there is no overhead. Actors started before enabling are
+/// not listed. Only actors running in a [`Context`](crate::Context), including supervised ones,
+/// are tracked.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// struct MyActor;
+///
+/// impl Actor for MyActor {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     System::current().enable_actor_directory();
+///
+///     let _addr = MyActor.start();
+///     actix_rt::task::yield_now().await;
+///
+///     let actors = System::current().actors();
+///     assert_eq!(actors.len(), 1);
+///     assert!(actors[0].type_name.ends_with("MyActor"));
+/// }
+/// ```
+pub trait ActorDirectory {
+    /// Starts tracking actors started from now on.
+    fn enable_actor_directory(&self);
+
+    /// Returns all live actors, ordered by id.
+    ///
+    /// Returns an empty list if the directory is not enabled.
+    fn actors(&self) -> Vec<ActorInfo>;
+}
+
+impl ActorDirectory for System {
+    fn enable_actor_directory(&self) {
+        crate::system::track(self);
+        DIRECTORY.lock().entry(self.id()).or_default();
+        ENABLED.store(true, Ordering::Release);
+    }
+
+    fn actors(&self) -> Vec<ActorInfo> {
+        let mut actors = DIRECTORY
+            .lock()
+            .get(&self.id())
+            .map(|entries| {
+                entries
+                    .values()
+                    .map(|entry| entry.info())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        actors.sort_by_key(|info| info.id);
+        actors
+    }
+}
+
+/// Returns `true` if the system `id` has the directory enabled.
+pub(crate) fn enabled(system: usize) -> bool {
+    DIRECTORY.lock().contains_key(&system)
+}
+
+/// Disables the directory of the system `id`.
+pub(crate) fn release(system: usize) {
+    DIRECTORY.lock().remove(&system);
+}
+
+struct Entry {
+    id: ActorId,
+    type_name: &'static str,
+    state: AtomicU8,
+    mailbox: Weak<dyn QueuedMessages>,
+}
+
+impl Entry {
+    fn info(&self) -> ActorInfo {
+        let state = match self.state.load(Ordering::Relaxed) {
+            0 => ActorState::Started,
+            1 => ActorState::Running,
+            2 => ActorState::Stopping,
+            _ => ActorState::Stopped,
+        };
+
+        ActorInfo {
+            id: self.id,
+            type_name: self.type_name,
+            state,
+            mailbox_len: self.mailbox.upgrade().map_or(0, |mb| mb.queued()),
+        }
+    }
+}
+
+/// Directory entry of a running actor, removed from the directory on drop.
+pub(crate) struct Registration {
+    system: usize,
+    entry: Arc<Entry>,
+}
+
+impl Registration {
+    /// Registers an actor of type `A` if the current system has the directory enabled.
+    pub(crate) fn register<A>(id: ActorId, mailbox: Weak<dyn QueuedMessages>) -> Option<Self> {
+        if !ENABLED.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let system = System::try_current()?.id();
+        let mut directory = DIRECTORY.lock();
+        let entries = directory.get_mut(&system)?;
+
+        let entry = Arc::new(Entry {
+            id,
+            type_name: type_name::<A>(),
+            state: AtomicU8::new(0),
+            mailbox,
+        });
+        entries.insert(entry.id, Arc::clone(&entry));
+
+        Some(Registration { system, entry })
+    }
+
+    pub(crate) fn set_state(&self, state: ActorState) {
+        let state = match state {
+            ActorState::Started => 0,
+            ActorState::Running => 1,
+            ActorState::Stopping => 2,
+            ActorState::Stopped => 3,
+        };
+        self.entry.state.store(state, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Some(entries) = DIRECTORY.lock().get_mut(&self.system) {
+            entries.remove(&self.entry.id);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Definition of [`blocking`], for running blocking code off the event loop.
+
+use std::{
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+use futures_util::FutureExt as _;
+use once_cell::sync::Lazy;
+use tokio::sync::oneshot;
+
+use crate::{
+    actor::Actor,
+    fut::{wrap_future, ActorFuture},
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Threads shared by every actor for running blocking closures.
+///
+/// Threads are started on demand while all of them are busy, up to `max_threads`. Once the
+/// limit is reached, closures queue up until a thread is free.
+struct BlockingPool {
+    jobs: Sender<Job>,
+    queue: Receiver<Job>,
+    threads: AtomicUsize,
+    idle: AtomicUsize,
+    max_threads: usize,
+}
+
+static POOL: Lazy<BlockingPool> = Lazy::new(|| {
+    let (jobs, queue) = crossbeam_channel::unbounded();
+    let cpus = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+
+    BlockingPool {
+        jobs,
+        queue,
+        threads: AtomicUsize::new(0),
+        idle: AtomicUsize::new(0),
+        max_threads: cpus.max(4),
+    }
+});
+
+impl BlockingPool {
+    fn execute(&'static self, job: Job) {
+        self.jobs
+            .send(job)
+            .expect("the blocking queue is never closed");
+
+        if self.idle.load(Ordering::Acquire) > 0 {
+            return;
+        }
+        if let Some(idx) = self.reserve_thread() {
+            thread::Builder::new()
+                .name(format!("actix-blocking-{idx}"))
+                .spawn(move || self.work())
+                .expect("failed to spawn a blocking thread");
+        }
+    }
+
+    /// Reserves a slot for starting another thread, returning its index.
+    fn reserve_thread(&self) -> Option<usize> {
+        self.threads
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |threads| {
+                (threads < self.max_threads).then_some(threads + 1)
+            })
+            .ok()
+    }
+
+    fn work(&self) {
+        loop {
+            self.idle.fetch_add(1, Ordering::AcqRel);
+            let job = self.queue.recv();
+            self.idle.fetch_sub(1, Ordering::AcqRel);
+
+            match job {
+                Ok(job) => job(),
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+/// Runs the blocking closure `f` on a shared thread pool, resolving to its result in the actor.
+///
+/// The event loop of the actor keeps running while `f` blocks, and the returned future resumes
+/// in the actor's context, so that its continuation gets access to the actor. This suits short
+/// blocking calls, such as synchronous crypto or a blocking C library, which do not warrant a
+/// [`SyncArbiter`](crate::SyncArbiter) of their own.
+///
+/// The pool is bounded to as many threads as there are CPUs, but at least four. Closures submitted
+/// while every thread is busy wait in a queue for a free thread. A closure that was submitted
+/// runs to completion even if the returned future is dropped, e.g. because the actor stopped.
+///
+/// # Panics
+/// If `f` panics, the panic is resumed when the returned future is polled, i.e. in the actor.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// #[derive(Message)]
+/// #[rtype(result = "u64")]
+/// struct Hash(&'static str);
+///
+/// #[derive(Default)]
+/// struct Hasher {
+///     hashed: usize,
+/// }
+///
+/// impl Actor for Hasher {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Hash> for Hasher {
+///     type Result = ResponseActFuture<Self, u64>;
+///
+///     fn handle(&mut self, Hash(text): Hash, _: &mut Context<Self>) -> Self::Result {
+///         let hash = fut::blocking(move || {
+///             // stands in for a slow, synchronous computation
+///             text.bytes().fold(0, |hash, byte| hash * 31 + u64::from(byte))
+///         });
+///
+///         Box::pin(hash.map(|hash, act: &mut Self, _| {
+///             act.hashed += 1;
+///             hash
+///         }))
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let addr = Hasher::default().start();
+///     assert_eq!(addr.send(Hash("ab")).await.unwrap(), 97 * 31 + 98);
+/// }
+/// ```
+pub fn blocking<A, F, R>(f: F) -> impl ActorFuture<A, Output = R>
+where
+    A: Actor,
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+
+    POOL.execute(Box::new(move || {
+        // catch the panic so that the thread survives it, it is resumed in the actor
+        let _ = tx.send(panic::catch_unwind(AssertUnwindSafe(f)));
+    }));
+
+    let res = rx.map(|res| res.expect("blocking closures always send their result"));
+    wrap_future(res.map(|res| res.unwrap_or_else(|payload| panic::resume_unwind(payload))))
+}
@@ -21,21 +21,30 @@ where
         ctx: &mut Act::Context,
         task: &mut Context<'_>,
     ) -> Poll<A::Output> {
-        // SAFETY:
-        //
-        // Copied from futures_util::future::Either::project method.
-        // This is used to expose this method to public.
-        // It has the same safety as the private one.
-        let this = unsafe {
-            match self.get_unchecked_mut() {
-                Either::Left(a) => Either::Left(Pin::new_unchecked(a)),
-                Either::Right(b) => Either::Right(Pin::new_unchecked(b)),
-            }
-        };
-
-        match this {
+        match project(self) {
             Either::Left(left) => left.poll(act, ctx, task),
             Either::Right(right) => right.poll(act, ctx, task),
         }
     }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut Act, ctx: &mut Act::Context) {
+        match project(self) {
+            Either::Left(left) => left.cancel(act, ctx),
+            Either::Right(right) => right.cancel(act, ctx),
+        }
+    }
+}
+
+fn project<A, B>(either: Pin<&mut Either<A, B>>) -> Either<Pin<&mut A>, Pin<&mut B>> {
+    // SAFETY:
+    //
+    // Copied from futures_util::future::Either::project method.
+    // This is used to expose this method to public.
+    // It has the same safety as the private one.
+    unsafe {
+        match either.get_unchecked_mut() {
+            Either::Left(a) => Either::Left(Pin::new_unchecked(a)),
+            Either::Right(b) => Either::Right(Pin::new_unchecked(b)),
+        }
+    }
 }
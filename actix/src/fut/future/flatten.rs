@@ -0,0 +1,72 @@
+use std::{
+    pin::Pin,
+    task::{self, Poll},
+};
+
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{actor::Actor, fut::ActorFuture};
+
+pin_project! {
+    /// Future for the [`flatten`](super::ActorFutureExt::flatten) combinator, running the
+    /// future that another future resolves to.
+    #[project = FlattenProj]
+    #[derive(Debug)]
+    #[must_use = "futures do nothing unless polled"]
+    pub enum Flatten<Fut1, Fut2> {
+        First {
+            #[pin]
+            fut1: Fut1,
+        },
+        Second {
+            #[pin]
+            fut2: Fut2,
+        },
+        Empty,
+    }
+}
+
+impl<Fut1, Fut2> Flatten<Fut1, Fut2> {
+    pub(super) fn new(future: Fut1) -> Self {
+        Flatten::First { fut1: future }
+    }
+}
+
+impl<Fut1, A> ActorFuture<A> for Flatten<Fut1, Fut1::Output>
+where
+    Fut1: ActorFuture<A>,
+    Fut1::Output: ActorFuture<A>,
+    A: Actor,
+{
+    type Output = <Fut1::Output as ActorFuture<A>>::Output;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Self::Output> {
+        match self.as_mut().project() {
+            FlattenProj::First { fut1 } => {
+                let fut2 = ready!(fut1.poll(act, ctx, task));
+                self.set(Flatten::Second { fut2 });
+                self.poll(act, ctx, task)
+            }
+            FlattenProj::Second { fut2 } => {
+                let res = ready!(fut2.poll(act, ctx, task));
+                self.set(Flatten::Empty);
+                Poll::Ready(res)
+            }
+            FlattenProj::Empty => panic!("ActorFuture polled after finish"),
+        }
+    }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        match self.project() {
+            FlattenProj::First { fut1 } => fut1.cancel(act, ctx),
+            FlattenProj::Second { fut2 } => fut2.cancel(act, ctx),
+            FlattenProj::Empty => {}
+        }
+    }
+}
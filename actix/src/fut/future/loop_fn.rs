@@ -0,0 +1,140 @@
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::ready;
+
+use crate::{actor::Actor, fut::ActorFuture};
+
+/// Outcome of one iteration of a [`loop_fn`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Loop<T, S> {
+    /// Ends the loop with a value.
+    Break(T),
+    /// Runs another iteration with a new state.
+    Continue(S),
+}
+
+/// Creates an actor future which runs the future created by `f` in a loop, until it resolves
+/// to [`Loop::Break`].
+///
+/// `f` is called with the current state, starting with `init`, and with the actor and its
+/// context, so the actor can be inspected and updated between iterations. The loop yields to
+/// the context after each iteration, so cancelling the spawned loop with
+/// [`AsyncContext::cancel_future`](crate::AsyncContext::cancel_future) stops it before the next
+/// iteration starts.
+///
+/// # Examples
+/// ```
+/// use actix::{fut::Loop, prelude::*};
+///
+/// #[derive(Message)]
+/// #[rtype(result = "usize")]
+/// struct FetchAll;
+///
+/// struct Client {
+///     pages: Vec<Vec<u32>>,
+/// }
+///
+/// impl Actor for Client {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<FetchAll> for Client {
+///     type Result = ResponseActFuture<Self, usize>;
+///
+///     fn handle(&mut self, _: FetchAll, _: &mut Context<Self>) -> Self::Result {
+///         Box::pin(fut::loop_fn((0, 0), |(page, items), act: &mut Self, _| {
+///             // fetch one page
+///             fut::ready(match act.pages.get(page) {
+///                 Some(rows) => Loop::Continue((page + 1, items + rows.len())),
+///                 None => Loop::Break(items),
+///             })
+///         }))
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let addr = Client { pages: vec![vec![1, 2], vec![3]] }.start();
+///     assert_eq!(addr.send(FetchAll).await.unwrap(), 3);
+/// }
+/// ```
+pub fn loop_fn<A, S, T, F, Fut>(init: S, f: F) -> LoopFn<S, F, Fut>
+where
+    A: Actor,
+    F: FnMut(S, &mut A, &mut A::Context) -> Fut,
+    Fut: ActorFuture<A, Output = Loop<T, S>>,
+{
+    LoopFn {
+        state: Some(init),
+        f,
+        fut: None,
+    }
+}
+
+/// Future for the [`loop_fn`] function.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct LoopFn<S, F, Fut> {
+    state: Option<S>,
+    f: F,
+    fut: Option<Pin<Box<Fut>>>,
+}
+
+impl<S, F, Fut> fmt::Debug for LoopFn<S, F, Fut> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("LoopFn")
+            .field("running", &self.fut.is_some())
+            .finish()
+    }
+}
+
+impl<S, F, Fut> Unpin for LoopFn<S, F, Fut> {}
+
+impl<A, S, T, F, Fut> ActorFuture<A> for LoopFn<S, F, Fut>
+where
+    A: Actor,
+    F: FnMut(S, &mut A, &mut A::Context) -> Fut,
+    Fut: ActorFuture<A, Output = Loop<T, S>>,
+{
+    type Output = T;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<T> {
+        let this = self.get_mut();
+
+        let fut = match this.fut {
+            Some(ref mut fut) => fut,
+            None => {
+                let state = this.state.take().expect("LoopFn polled after completion");
+                this.fut.insert(Box::pin((this.f)(state, act, ctx)))
+            }
+        };
+
+        match ready!(fut.as_mut().poll(act, ctx, task)) {
+            Loop::Break(value) => {
+                this.fut = None;
+                Poll::Ready(value)
+            }
+            Loop::Continue(state) => {
+                this.fut = None;
+                this.state = Some(state);
+                // yield, so that the loop can be cancelled between iterations
+                task.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        if let Some(fut) = self.get_mut().fut.as_mut() {
+            fut.as_mut().cancel(act, ctx);
+        }
+    }
+}
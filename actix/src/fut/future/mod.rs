@@ -7,16 +7,24 @@ use std::{
     time::Duration,
 };
 
+pub use flatten::Flatten;
+pub use loop_fn::{loop_fn, Loop, LoopFn};
 pub use map::Map;
+pub use on_cancel::OnCancel;
 use pin_project_lite::pin_project;
+pub use shared::Shared;
 pub use then::Then;
-pub use timeout::Timeout;
+pub use timeout::{Timeout, TimeoutOr};
 
 use crate::actor::Actor;
 
 mod either;
+mod flatten;
+mod loop_fn;
 mod map;
+mod on_cancel;
 pub mod result;
+mod shared;
 mod then;
 mod timeout;
 
@@ -112,17 +120,125 @@ mod timeout;
 /// ```
 ///
 /// See also [`into_actor`](trait.WrapFuture.html#tymethod.into_actor), which provides future conversion using trait
+///
+/// # Implementing `ActorFuture`
+/// Besides wrapping futures and chaining combinators, `ActorFuture` can be implemented directly,
+/// e.g. for a state machine that reads or updates the actor between its steps. Such a future
+/// is spawned, waited on, or returned from a handler like any other, see [`poll`](Self::poll)
+/// for the contract it has to uphold. The `handshake` example in the repository implements a
+/// two-step protocol this way.
+///
+/// ```
+/// use std::{pin::Pin, task::{self, Poll}, time::Duration};
+///
+/// use actix::{clock::{interval, Interval}, prelude::*};
+///
+/// struct Counter {
+///     ticks: u32,
+/// }
+///
+/// impl Actor for Counter {
+///     type Context = Context<Self>;
+/// }
+///
+/// /// Counts the actor's ticks up once per interval, `remaining` times.
+/// struct Ticker {
+///     interval: Interval,
+///     remaining: u32,
+/// }
+///
+/// impl ActorFuture<Counter> for Ticker {
+///     type Output = u32;
+///
+///     fn poll(
+///         self: Pin<&mut Self>,
+///         act: &mut Counter,
+///         _: &mut Context<Counter>,
+///         task: &mut task::Context<'_>,
+///     ) -> Poll<u32> {
+///         let this = self.get_mut();
+///         while this.remaining > 0 {
+///             // `poll_tick` registers the waker of `task` when it is pending
+///             if this.interval.poll_tick(task).is_pending() {
+///                 return Poll::Pending;
+///             }
+///             act.ticks += 1;
+///             this.remaining -= 1;
+///         }
+///         Poll::Ready(act.ticks)
+///     }
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "u32")]
+/// struct Tick(u32);
+///
+/// impl Handler<Tick> for Counter {
+///     type Result = ResponseActFuture<Self, u32>;
+///
+///     fn handle(&mut self, Tick(n): Tick, _: &mut Context<Self>) -> Self::Result {
+///         Box::pin(Ticker {
+///             interval: interval(Duration::from_millis(1)),
+///             remaining: n,
+///         })
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let addr = Counter { ticks: 0 }.start();
+///     assert_eq!(addr.send(Tick(3)).await.unwrap(), 3);
+///     assert_eq!(addr.send(Tick(2)).await.unwrap(), 5);
+/// }
+/// ```
 pub trait ActorFuture<A: Actor> {
     /// The type of value that this future will resolved with if it is
     /// successful.
     type Output;
 
+    /// Attempts to resolve the future to a final value, with access to the actor and its
+    /// context.
+    ///
+    /// The context polls the future on the actor's task, in between handling messages, so the
+    /// actor may have changed since the previous poll.
+    ///
+    /// # Contract
+    /// - Returns `Poll::Pending` only after arranging for the waker of `task` to be woken once
+    ///   the future can make progress, usually by polling an inner future or stream that
+    ///   returned `Poll::Pending` itself. Otherwise the future may never be polled again.
+    /// - Must not block, as that blocks the actor and every other actor of its arbiter.
+    /// - Returns `Poll::Ready` at most once; a completed future is not polled again.
+    /// - Failures are part of [`Output`](Self::Output), e.g. as a [`Result`], as there is no
+    ///   separate error channel. A future returned from a handler as
+    ///   [`ResponseActFuture`](crate::ResponseActFuture) replies with its output, failures
+    ///   included; a spawned future handles them itself or in a combinator, like
+    ///   [`map`](ActorFutureExt::map).
+    ///
+    /// A future that has not completed is dropped without being polled again when it is
+    /// cancelled with [`AsyncContext::cancel_future`](crate::AsyncContext::cancel_future), and
+    /// when the actor stops or restarts. Custom futures are treated like the built-in ones, so
+    /// cleanup belongs in `Drop`, or in [`cancel`](Self::cancel) if it needs access to the
+    /// actor.
     fn poll(
         self: Pin<&mut Self>,
         srv: &mut A,
         ctx: &mut A::Context,
         task: &mut Context<'_>,
     ) -> Poll<Self::Output>;
+
+    /// Called when the future is abandoned before it has completed, right before it is dropped.
+    ///
+    /// The context calls this when the future is cancelled with
+    /// [`AsyncContext::cancel_future`](crate::AsyncContext::cancel_future), and when the actor
+    /// stops or restarts while the future is pending. It is not called for a future that has
+    /// completed.
+    ///
+    /// The default does nothing. Futures wrapping other actor futures forward the call to the
+    /// future they are currently polling, as the built-in combinators do, so that cleanup
+    /// registered with [`on_cancel`](ActorFutureExt::on_cancel) runs wherever it is in a chain.
+    fn cancel(self: Pin<&mut Self>, srv: &mut A, ctx: &mut A::Context) {
+        let _ = (srv, ctx);
+    }
 }
 
 pub trait ActorFutureExt<A: Actor>: ActorFuture<A> {
@@ -147,6 +263,18 @@ pub trait ActorFutureExt<A: Actor>: ActorFuture<A> {
         then::new(self, f)
     }
 
+    /// Flattens a future resolving to another future, running the inner future once the outer
+    /// one has resolved.
+    ///
+    /// This avoids nesting when a combinator produces a future to run next.
+    fn flatten(self) -> Flatten<Self, Self::Output>
+    where
+        Self::Output: ActorFuture<A>,
+        Self: Sized,
+    {
+        Flatten::new(self)
+    }
+
     /// Add timeout to futures chain.
     ///
     /// `Err(())` returned as a timeout error.
@@ -157,6 +285,99 @@ pub trait ActorFutureExt<A: Actor>: ActorFuture<A> {
         Timeout::new(self, timeout)
     }
 
+    /// Add timeout to futures chain, resolving to a fallback value on timeout.
+    ///
+    /// If the future does not resolve within `timeout`, it is dropped and `default` is called
+    /// with the actor and its context to produce the output instead.
+    fn timeout_or<D>(self, timeout: Duration, default: D) -> TimeoutOr<Self, D>
+    where
+        D: FnOnce(&mut A, &mut A::Context) -> Self::Output,
+        Self: Sized,
+    {
+        TimeoutOr::new(self, timeout, default)
+    }
+
+    /// Returns a future that can be cloned, each clone resolving to a clone of the output.
+    ///
+    /// The future is polled only once at a time, by whichever clone is polled, so concurrent
+    /// tasks of an actor depending on the same result share a single computation.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// struct Client;
+    ///
+    /// impl Actor for Client {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         // fetch the token once for both requests
+    ///         let token = fut::wrap_future::<_, Self>(async { "token".to_owned() }).shared();
+    ///
+    ///         ctx.spawn(token.clone().map(|token, _, _| assert_eq!(token, "token")));
+    ///         ctx.spawn(token.map(|token, _, _| {
+    ///             assert_eq!(token, "token");
+    ///             System::current().stop();
+    ///         }));
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     Client.start();
+    /// }
+    /// ```
+    fn shared(self) -> Shared<Self, A>
+    where
+        Self: Sized,
+        Self::Output: Clone,
+    {
+        Shared::new(self)
+    }
+
+    /// Runs `f` with the actor and its context if the future is abandoned before it has
+    /// completed, see [`ActorFuture::cancel`].
+    ///
+    /// This allows cleaning up after work that is abandoned, e.g. releasing a lock taken for
+    /// the future, which does not happen when the future completes. The future is abandoned
+    /// when it is cancelled with
+    /// [`AsyncContext::cancel_future`](crate::AsyncContext::cancel_future), directly or through
+    /// a cancelled [`scope`](crate::AsyncContext::scope), and when the actor stops or restarts
+    /// while it is pending. Combinators wrapping this future forward the cancellation, except
+    /// [`shared`](Self::shared), whose future may still be awaited by other clones.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use actix::prelude::*;
+    ///
+    /// struct Uploader {
+    ///     locked: bool,
+    /// }
+    ///
+    /// impl Actor for Uploader {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         self.locked = true;
+    ///         let upload = fut::wrap_future::<_, Self>(actix_rt::time::sleep(Duration::from_secs(60)))
+    ///             .map(|_, act, _| act.locked = false)
+    ///             .on_cancel(|act, _| act.locked = false);
+    ///
+    ///         let handle = ctx.spawn(upload);
+    ///         ctx.cancel_future(handle);
+    ///     }
+    /// }
+    /// ```
+    fn on_cancel<F>(self, f: F) -> OnCancel<Self, F>
+    where
+        F: FnOnce(&mut A, &mut A::Context),
+        Self: Sized,
+    {
+        OnCancel::new(self, f)
+    }
+
     /// Wrap the future in a Box, pinning it.
     ///
     /// A shortcut for wrapping in [`Box::pin`].
@@ -193,6 +414,10 @@ where
     ) -> Poll<Self::Output> {
         Pin::new(&mut **self.as_mut()).poll(srv, ctx, task)
     }
+
+    fn cancel(mut self: Pin<&mut Self>, srv: &mut A, ctx: &mut A::Context) {
+        Pin::new(&mut **self.as_mut()).cancel(srv, ctx)
+    }
 }
 
 impl<P, A> ActorFuture<A> for Pin<P>
@@ -211,6 +436,10 @@ where
     ) -> Poll<Self::Output> {
         Pin::get_mut(self).as_mut().poll(srv, ctx, task)
     }
+
+    fn cancel(self: Pin<&mut Self>, srv: &mut A, ctx: &mut A::Context) {
+        Pin::get_mut(self).as_mut().cancel(srv, ctx)
+    }
 }
 
 /// Helper trait that allows conversion of normal future into [`ActorFuture`]
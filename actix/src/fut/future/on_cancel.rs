@@ -0,0 +1,51 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+
+use crate::{actor::Actor, fut::ActorFuture};
+
+pin_project! {
+    /// Future for the [`on_cancel`](super::ActorFutureExt::on_cancel) method.
+    #[derive(Debug)]
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct OnCancel<Fut, F> {
+        #[pin]
+        future: Fut,
+        f: Option<F>,
+    }
+}
+
+impl<Fut, F> OnCancel<Fut, F> {
+    pub(super) fn new(future: Fut, f: F) -> Self {
+        OnCancel { future, f: Some(f) }
+    }
+}
+
+impl<Fut, A, F> ActorFuture<A> for OnCancel<Fut, F>
+where
+    Fut: ActorFuture<A>,
+    A: Actor,
+    F: FnOnce(&mut A, &mut A::Context),
+{
+    type Output = Fut::Output;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        self.project().future.poll(act, ctx, task)
+    }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        let this = self.project();
+        if let Some(f) = this.f.take() {
+            f(act, ctx);
+        }
+        this.future.cancel(act, ctx);
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    cell::RefCell,
+    fmt, mem,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+use crate::{actor::Actor, fut::ActorFuture};
+
+enum State<Fut: ActorFuture<A>, A: Actor> {
+    Pending(Pin<Box<Fut>>),
+    Polling,
+    Done(Fut::Output),
+}
+
+struct Inner<Fut: ActorFuture<A>, A: Actor> {
+    state: State<Fut, A>,
+    wakers: Vec<Waker>,
+}
+
+impl<Fut: ActorFuture<A>, A: Actor> Inner<Fut, A> {
+    /// Registers a waker to wake once the output is available.
+    fn register(&mut self, waker: &Waker) {
+        if !self.wakers.iter().any(|w| w.will_wake(waker)) {
+            self.wakers.push(waker.clone());
+        }
+    }
+}
+
+/// Future for the [`shared`](super::ActorFutureExt::shared) method.
+///
+/// Clones resolve to clones of the output of the same underlying future, which is polled by
+/// whichever clone is polled first.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Shared<Fut: ActorFuture<A>, A: Actor> {
+    inner: Rc<RefCell<Inner<Fut, A>>>,
+}
+
+impl<Fut: ActorFuture<A>, A: Actor> Shared<Fut, A> {
+    pub(super) fn new(future: Fut) -> Self {
+        Shared {
+            inner: Rc::new(RefCell::new(Inner {
+                state: State::Pending(Box::pin(future)),
+                wakers: Vec::new(),
+            })),
+        }
+    }
+}
+
+impl<Fut: ActorFuture<A>, A: Actor> Clone for Shared<Fut, A> {
+    fn clone(&self) -> Self {
+        Shared {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+impl<Fut: ActorFuture<A>, A: Actor> fmt::Debug for Shared<Fut, A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let done = matches!(self.inner.borrow().state, State::Done(_));
+        fmt.debug_struct("Shared").field("done", &done).finish()
+    }
+}
+
+impl<Fut, A> ActorFuture<A> for Shared<Fut, A>
+where
+    Fut: ActorFuture<A>,
+    Fut::Output: Clone,
+    A: Actor,
+{
+    type Output = Fut::Output;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        // take the future out of the cell while polling it, so it may poll a clone of itself
+        let mut future = {
+            let mut inner = self.inner.borrow_mut();
+            match mem::replace(&mut inner.state, State::Polling) {
+                State::Pending(future) => future,
+                State::Polling => {
+                    inner.register(task.waker());
+                    return Poll::Pending;
+                }
+                State::Done(output) => {
+                    inner.state = State::Done(output.clone());
+                    return Poll::Ready(output);
+                }
+            }
+        };
+
+        let poll = future.as_mut().poll(act, ctx, task);
+
+        let mut inner = self.inner.borrow_mut();
+        match poll {
+            Poll::Ready(output) => {
+                inner.state = State::Done(output.clone());
+                for waker in inner.wakers.drain(..) {
+                    waker.wake();
+                }
+                Poll::Ready(output)
+            }
+            Poll::Pending => {
+                // clones polled before are not polled again unless woken on completion
+                inner.state = State::Pending(future);
+                inner.register(task.waker());
+                Poll::Pending
+            }
+        }
+    }
+}
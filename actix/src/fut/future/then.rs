@@ -73,4 +73,12 @@ where
             ThenProj::Empty => panic!("ActorFuture polled after finish"),
         }
     }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut Act, ctx: &mut Act::Context) {
+        match self.project() {
+            ThenProj::First { fut1, .. } => fut1.cancel(act, ctx),
+            ThenProj::Second { fut2 } => fut2.cancel(act, ctx),
+            ThenProj::Empty => {}
+        }
+    }
 }
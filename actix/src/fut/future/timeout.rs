@@ -56,4 +56,85 @@ where
             Poll::Pending => this.timeout.poll(task).map(Err),
         }
     }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        self.project().fut.cancel(act, ctx)
+    }
+}
+
+pin_project! {
+    /// Future for the [`timeout_or`](super::ActorFutureExt::timeout_or) combinator, resolving to
+    /// a fallback value if the inner future takes more than the given duration.
+    ///
+    /// This is created by the [`timeout_or`](super::ActorFutureExt::timeout_or) method.
+    #[project = TimeoutOrProj]
+    #[project_replace = TimeoutOrProjReplace]
+    #[derive(Debug)]
+    #[must_use = "futures do nothing unless polled"]
+    pub enum TimeoutOr<F, D> {
+        Incomplete {
+            #[pin]
+            fut: F,
+            #[pin]
+            timeout: Sleep,
+            default: D,
+        },
+        Complete,
+    }
+}
+
+impl<F, D> TimeoutOr<F, D> {
+    pub(super) fn new(future: F, timeout: Duration, default: D) -> Self {
+        Self::Incomplete {
+            fut: future,
+            timeout: sleep(timeout),
+            default,
+        }
+    }
+}
+
+impl<F, D, A> ActorFuture<A> for TimeoutOr<F, D>
+where
+    F: ActorFuture<A>,
+    D: FnOnce(&mut A, &mut A::Context) -> F::Output,
+    A: Actor,
+{
+    type Output = F::Output;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        match self.as_mut().project() {
+            TimeoutOrProj::Incomplete { fut, timeout, .. } => {
+                if let Poll::Ready(res) = fut.poll(act, ctx, task) {
+                    self.project_replace(TimeoutOr::Complete);
+                    return Poll::Ready(res);
+                }
+
+                if timeout.poll(task).is_pending() {
+                    return Poll::Pending;
+                }
+
+                // drops the inner future
+                match self.project_replace(TimeoutOr::Complete) {
+                    TimeoutOrProjReplace::Incomplete { default, .. } => {
+                        Poll::Ready(default(act, ctx))
+                    }
+                    TimeoutOrProjReplace::Complete => unreachable!(),
+                }
+            }
+            TimeoutOrProj::Complete => {
+                panic!("TimeoutOr must not be polled after it returned `Poll::Ready`")
+            }
+        }
+    }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        if let TimeoutOrProj::Incomplete { fut, .. } = self.project() {
+            fut.cancel(act, ctx);
+        }
+    }
 }
@@ -0,0 +1,140 @@
+//! Definition of the [`Turnstile`] lock, for taking turns on a shared resource.
+
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::{
+    actor::Actor,
+    fut::{wrap_future, ActorFuture},
+};
+
+/// An asynchronous lock which gives actors exclusive access to a shared resource in turns.
+///
+/// Clones share the same resource, also between actors running on different arbiters. Acquiring
+/// the lock with [`lock`] is an [`ActorFuture`], so the actor keeps handling messages while it
+/// waits for its turn. Waiters are served in the order they asked for the lock, and a waiter
+/// that is dropped, e.g. because the actor stopped, gives up its place.
+///
+/// # Examples
+/// ```
+/// use actix::{fut::Turnstile, prelude::*};
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Write(&'static str);
+///
+/// struct Writer {
+///     log: Turnstile<Vec<&'static str>>,
+/// }
+///
+/// impl Actor for Writer {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Write> for Writer {
+///     type Result = ResponseActFuture<Self, ()>;
+///
+///     fn handle(&mut self, Write(line): Write, _: &mut Context<Self>) -> Self::Result {
+///         Box::pin(fut::lock(&self.log).map(move |mut log, _, _| log.push(line)))
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let log = Turnstile::new(Vec::new());
+///     let a = Writer { log: log.clone() }.start();
+///     let b = Writer { log: log.clone() }.start();
+///
+///     a.send(Write("a")).await.unwrap();
+///     b.send(Write("b")).await.unwrap();
+///     assert_eq!(*log.try_lock().unwrap(), ["a", "b"]);
+/// }
+/// ```
+pub struct Turnstile<T> {
+    inner: Arc<Mutex<T>>,
+}
+
+impl<T> Turnstile<T> {
+    /// Creates a new turnstile guarding `value`.
+    pub fn new(value: T) -> Self {
+        Turnstile {
+            inner: Arc::new(Mutex::new(value)),
+        }
+    }
+
+    /// Acquires the lock, resolving to a guard once it is this caller's turn.
+    ///
+    /// The lock is released when the guard is dropped.
+    pub fn lock<A: Actor>(&self) -> impl ActorFuture<A, Output = TurnstileGuard<T>>
+    where
+        T: 'static,
+    {
+        let inner = Arc::clone(&self.inner);
+        wrap_future(async move { TurnstileGuard(inner.lock_owned().await) })
+    }
+
+    /// Acquires the lock if it is free and nobody is waiting for it.
+    pub fn try_lock(&self) -> Option<TurnstileGuard<T>> {
+        Arc::clone(&self.inner)
+            .try_lock_owned()
+            .ok()
+            .map(TurnstileGuard)
+    }
+}
+
+impl<T> Clone for Turnstile<T> {
+    fn clone(&self) -> Self {
+        Turnstile {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: Default> Default for Turnstile<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for Turnstile<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Turnstile").finish()
+    }
+}
+
+/// Exclusive access to the resource of a [`Turnstile`], released on drop.
+pub struct TurnstileGuard<T>(OwnedMutexGuard<T>);
+
+impl<T> Deref for TurnstileGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for TurnstileGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TurnstileGuard<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, fmt)
+    }
+}
+
+/// Acquires the lock of `turnstile`, see [`Turnstile::lock`].
+pub fn lock<T, A>(turnstile: &Turnstile<T>) -> impl ActorFuture<A, Output = TurnstileGuard<T>>
+where
+    T: 'static,
+    A: Actor,
+{
+    turnstile.lock()
+}
@@ -1,14 +1,19 @@
 //! Custom `Future` and `Stream` implementation with `Actix` support
 
+mod blocking;
 pub mod future;
+mod lock;
 pub mod stream;
 pub mod try_future;
 
 pub use self::{
+    blocking::blocking,
     future::{
+        loop_fn,
         result::{err, ok, ready, result, Ready},
-        wrap_future, ActorFuture, ActorFutureExt, LocalBoxActorFuture, WrapFuture,
+        wrap_future, ActorFuture, ActorFutureExt, LocalBoxActorFuture, Loop, WrapFuture,
     },
+    lock::{lock, Turnstile, TurnstileGuard},
     stream::{wrap_stream, ActorStream, ActorStreamExt, WrapStream},
-    try_future::{ActorTryFuture, ActorTryFutureExt},
+    try_future::{retry, ActorTryFuture, ActorTryFutureExt},
 };
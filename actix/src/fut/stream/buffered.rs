@@ -0,0 +1,118 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+
+use crate::{
+    actor::Actor,
+    fut::{ActorFuture, ActorStream},
+};
+
+enum Slot<Fut, O> {
+    Pending(Pin<Box<Fut>>),
+    Ready(O),
+}
+
+type Output<S, A> = <<S as ActorStream<A>>::Item as ActorFuture<A>>::Output;
+
+pin_project! {
+    /// Stream for the [`buffered`](super::ActorStreamExt::buffered) method.
+    #[must_use = "streams do nothing unless polled"]
+    pub struct Buffered<S, A>
+    where
+        S: ActorStream<A>,
+        S::Item: ActorFuture<A>,
+        A: Actor,
+    {
+        #[pin]
+        stream: S,
+        done: bool,
+        in_flight: VecDeque<Slot<S::Item, Output<S, A>>>,
+        max: usize,
+    }
+}
+
+impl<S, A> Buffered<S, A>
+where
+    S: ActorStream<A>,
+    S::Item: ActorFuture<A>,
+    A: Actor,
+{
+    pub(super) fn new(stream: S, max: usize) -> Self {
+        assert!(max > 0, "buffered requires a limit greater than zero");
+
+        Self {
+            stream,
+            done: false,
+            in_flight: VecDeque::with_capacity(max),
+            max,
+        }
+    }
+}
+
+impl<S, A> fmt::Debug for Buffered<S, A>
+where
+    S: ActorStream<A> + fmt::Debug,
+    S::Item: ActorFuture<A>,
+    A: Actor,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Buffered")
+            .field("stream", &self.stream)
+            .field("in_flight", &self.in_flight.len())
+            .field("max", &self.max)
+            .finish()
+    }
+}
+
+impl<S, A> ActorStream<A> for Buffered<S, A>
+where
+    S: ActorStream<A>,
+    S::Item: ActorFuture<A>,
+    A: Actor,
+{
+    type Item = Output<S, A>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        // pull from the source only while there is room
+        while !*this.done && this.in_flight.len() < *this.max {
+            match this.stream.as_mut().poll_next(act, ctx, task) {
+                Poll::Ready(Some(fut)) => this.in_flight.push_back(Slot::Pending(Box::pin(fut))),
+                Poll::Ready(None) => *this.done = true,
+                Poll::Pending => break,
+            }
+        }
+
+        // futures are polled one after another, so each borrows the actor in turn
+        for slot in this.in_flight.iter_mut() {
+            if let Slot::Pending(fut) = slot {
+                if let Poll::Ready(output) = fut.as_mut().poll(act, ctx, task) {
+                    *slot = Slot::Ready(output);
+                }
+            }
+        }
+
+        if let Some(Slot::Ready(_)) = this.in_flight.front() {
+            if let Some(Slot::Ready(output)) = this.in_flight.pop_front() {
+                return Poll::Ready(Some(output));
+            }
+        }
+
+        if *this.done && this.in_flight.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -75,4 +75,10 @@ where
             }
         })
     }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        if let Some(fut) = self.project().future.as_pin_mut() {
+            fut.cancel(act, ctx);
+        }
+    }
 }
@@ -5,6 +5,7 @@ use std::{
     time::Duration,
 };
 
+pub use buffered::Buffered;
 pub use collect::Collect;
 pub use finish::Finish;
 pub use fold::Fold;
@@ -19,6 +20,7 @@ pub use timeout::Timeout;
 use super::future::ActorFuture;
 use crate::actor::Actor;
 
+mod buffered;
 mod collect;
 mod finish;
 mod fold;
@@ -128,6 +130,67 @@ pub trait ActorStreamExt<A: Actor>: ActorStream<A> {
         skip_while::new(self, f)
     }
 
+    /// Runs up to `n` of this stream's actor futures concurrently, yielding their outputs in the
+    /// order of the stream.
+    ///
+    /// Once `n` futures are in flight, the underlying stream is not polled until the oldest one
+    /// has completed and its output was yielded. The in-flight futures are polled one after
+    /// another, so each of them gets exclusive access to the actor and its context while it is
+    /// polled.
+    ///
+    /// # Panics
+    /// Panics if `n` is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// use actix::prelude::*;
+    /// use futures_util::stream::iter;
+    ///
+    /// struct MyActor {
+    ///     in_flight: usize,
+    /// }
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// # #[actix::main] async fn main() {
+    /// # let addr = MyActor { in_flight: 0 }.start();
+    /// # addr.send(Go).await.unwrap();
+    /// # }
+    /// # #[derive(Message)] #[rtype(result = "()")] struct Go;
+    /// impl Handler<Go> for MyActor {
+    ///     type Result = ResponseActFuture<Self, ()>;
+    ///
+    ///     fn handle(&mut self, _: Go, _: &mut Context<Self>) -> Self::Result {
+    ///         let fut = iter(0..5u64)
+    ///             .into_actor(self)
+    ///             .map(|i, act: &mut Self, _| {
+    ///                 act.in_flight += 1;
+    ///                 actix::clock::sleep(Duration::from_millis(10 - i))
+    ///                     .into_actor(act)
+    ///                     .map(move |_, act, _| {
+    ///                         assert!(act.in_flight <= 2);
+    ///                         act.in_flight -= 1;
+    ///                         i
+    ///                     })
+    ///             })
+    ///             .buffered(2)
+    ///             .collect::<Vec<_>>()
+    ///             .map(|done, _, _| assert_eq!(done, [0, 1, 2, 3, 4]));
+    ///         Box::pin(fut)
+    ///     }
+    /// }
+    /// ```
+    fn buffered(self, n: usize) -> Buffered<Self, A>
+    where
+        Self::Item: ActorFuture<A>,
+        Self: Sized,
+    {
+        Buffered::new(self, n)
+    }
+
     /// Add timeout to stream.
     ///
     /// `Err(())` returned as a timeout error.
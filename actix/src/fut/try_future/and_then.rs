@@ -76,4 +76,12 @@ where
             AndThenProj::Empty => panic!("ActorFuture polled after finish"),
         }
     }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut Act, ctx: &mut Act::Context) {
+        match self.project() {
+            AndThenProj::First { fut1, .. } => fut1.cancel(act, ctx),
+            AndThenProj::Second { fut2 } => fut2.cancel(act, ctx),
+            AndThenProj::Empty => {}
+        }
+    }
 }
@@ -62,4 +62,10 @@ where
             }
         }
     }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        if let MapProj::Incomplete { future, .. } = self.project() {
+            future.cancel(act, ctx);
+        }
+    }
 }
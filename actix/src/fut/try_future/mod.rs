@@ -8,10 +8,12 @@ use crate::{actor::Actor, fut::future::ActorFuture};
 mod and_then;
 mod map_err;
 mod map_ok;
+mod retry;
 
 pub use and_then::AndThen;
 pub use map_err::MapErr;
 pub use map_ok::MapOk;
+pub use retry::{retry, Retry};
 
 mod private_try_act_future {
     use super::{Actor, ActorFuture};
@@ -0,0 +1,149 @@
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_core::ready;
+
+use crate::{
+    actor::Actor,
+    clock::{sleep, Sleep},
+    fut::{future::ActorFuture, try_future::ActorTryFuture},
+};
+
+/// Creates an actor future which runs the future created by `factory`, and creates and runs
+/// it again after `backoff` if it fails, giving up after `attempts` attempts.
+///
+/// The factory is called with the actor, its context and the number of the attempt, starting at
+/// `1`. The future resolves to the first success, or to the error of the last attempt. The
+/// backoff follows the [`clock`](crate::clock), so it can be tested with a paused clock.
+///
+/// # Panics
+/// The future panics when polled if `attempts` is zero.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use actix::prelude::*;
+///
+/// #[derive(Message)]
+/// #[rtype(result = "Result<usize, ()>")]
+/// struct Fetch;
+///
+/// struct Client {
+///     failures: usize,
+/// }
+///
+/// impl Actor for Client {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Fetch> for Client {
+///     type Result = ResponseActFuture<Self, Result<usize, ()>>;
+///
+///     fn handle(&mut self, _: Fetch, _: &mut Context<Self>) -> Self::Result {
+///         Box::pin(fut::retry(3, Duration::from_millis(1), |act: &mut Self, _, attempt| {
+///             // a flaky downstream call
+///             let res = if act.failures > 0 { Err(()) } else { Ok(attempt) };
+///             act.failures = act.failures.saturating_sub(1);
+///             fut::ready(res)
+///         }))
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let addr = Client { failures: 2 }.start();
+///     assert_eq!(addr.send(Fetch).await.unwrap(), Ok(3));
+/// }
+/// ```
+pub fn retry<A, F, Fut>(attempts: usize, backoff: Duration, factory: F) -> Retry<F, Fut>
+where
+    A: Actor,
+    F: FnMut(&mut A, &mut A::Context, usize) -> Fut,
+    Fut: ActorTryFuture<A>,
+{
+    Retry {
+        factory,
+        attempts,
+        attempt: 0,
+        backoff,
+        fut: None,
+        delay: None,
+    }
+}
+
+/// Future for the [`retry`] function.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Retry<F, Fut> {
+    factory: F,
+    attempts: usize,
+    attempt: usize,
+    backoff: Duration,
+    fut: Option<Pin<Box<Fut>>>,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl<F, Fut> fmt::Debug for Retry<F, Fut> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Retry")
+            .field("attempts", &self.attempts)
+            .field("attempt", &self.attempt)
+            .field("backoff", &self.backoff)
+            .finish()
+    }
+}
+
+impl<F, Fut> Unpin for Retry<F, Fut> {}
+
+impl<A, F, Fut> ActorFuture<A> for Retry<F, Fut>
+where
+    A: Actor,
+    F: FnMut(&mut A, &mut A::Context, usize) -> Fut,
+    Fut: ActorTryFuture<A>,
+{
+    type Output = Result<Fut::Ok, Fut::Error>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        assert!(this.attempts > 0, "retry requires at least one attempt");
+
+        loop {
+            if let Some(delay) = this.delay.as_mut() {
+                ready!(delay.as_mut().poll(task));
+                this.delay = None;
+            }
+
+            let fut = match this.fut {
+                Some(ref mut fut) => fut,
+                None => {
+                    this.attempt += 1;
+                    this.fut
+                        .insert(Box::pin((this.factory)(act, ctx, this.attempt)))
+                }
+            };
+
+            match ready!(fut.as_mut().try_poll(act, ctx, task)) {
+                Err(_) if this.attempt < this.attempts => {
+                    this.fut = None;
+                    this.delay = Some(Box::pin(sleep(this.backoff)));
+                }
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+
+    fn cancel(self: Pin<&mut Self>, act: &mut A, ctx: &mut A::Context) {
+        if let Some(fut) = self.get_mut().fut.as_mut() {
+            fut.as_mut().cancel(act, ctx);
+        }
+    }
+}
@@ -1,11 +1,16 @@
 use std::{fmt, future::Future, pin::Pin, sync::Arc};
 
+use futures_util::FutureExt as _;
+
 pub use tokio::sync::oneshot::Sender as OneshotSender;
 
 use crate::{
     actor::{Actor, AsyncContext},
-    address::Addr,
+    address::{Addr, MailboxError, ToEnvelope},
+    contextimpl,
     fut::{ActorFuture, ActorFutureExt, LocalBoxActorFuture},
+    handler_timeout::{self, abortable, Abortable},
+    replies::{self, CancelGuard, ReplyState},
 };
 
 /// Describes how to handle messages of a specific type.
@@ -30,14 +35,86 @@ where
     fn handle(&mut self, msg: M, ctx: &mut Self::Context) -> Self::Result;
 }
 
+/// Describes how to handle the errors of futures and streams of results.
+///
+/// Futures and streams registered with
+/// [`AsyncContext::add_result_future`](crate::AsyncContext::add_result_future) and
+/// [`AsyncContext::add_result_stream`](crate::AsyncContext::add_result_stream) pass `Ok` values to
+/// [`Handler::handle`], and errors to [`ErrorHandler::error`].
+pub trait ErrorHandler<E>
+where
+    Self: Actor,
+{
+    /// Called for every error yielded by a future or stream of results.
+    ///
+    /// Returning [`Running::Stop`](crate::Running::Stop) drops the stream the error came from,
+    /// while [`Running::Continue`](crate::Running::Continue) keeps handling its items. The
+    /// return value has no effect for futures, which are done once they yielded an error.
+    fn error(&mut self, err: E, ctx: &mut Self::Context) -> crate::Running;
+}
+
 /// Represent message that can be handled by an actor.
+///
+/// # Ownership
+/// [`Addr::send`](crate::Addr::send), [`Addr::try_send`](crate::Addr::try_send) and
+/// [`Addr::do_send`](crate::Addr::do_send) (and the same methods on [`Recipient`](crate::Recipient))
+/// take the message by value and move it through the mailbox into the handler, so a single
+/// recipient receives the very buffer that was sent and the payload is never cloned on the way.
+/// Cloning only becomes unavoidable when one message is delivered to several recipients.
+///
+/// # Broadcasting large payloads
+/// `Arc<M>` is a message whenever `M` is, with the same result type. To deliver a large
+/// immutable payload to many actors, wrap it in an [`Arc`] once and send a clone of the `Arc` to
+/// every recipient: each send only bumps the reference count, the payload itself is never
+/// cloned and does not need to implement [`Clone`]. Handlers receive the `Arc<M>` and can use
+/// the payload through [`Deref`](std::ops::Deref).
+///
+/// ```
+/// use std::sync::Arc;
+/// use actix::prelude::*;
+///
+/// // not `Clone`
+/// struct Snapshot {
+///     data: Vec<u8>,
+/// }
+///
+/// impl Message for Snapshot {
+///     type Result = usize;
+/// }
+///
+/// struct Worker;
+///
+/// impl Actor for Worker {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Arc<Snapshot>> for Worker {
+///     type Result = usize;
+///
+///     fn handle(&mut self, msg: Arc<Snapshot>, _: &mut Context<Self>) -> usize {
+///         msg.data.len()
+///     }
+/// }
+///
+/// # #[actix::main] async fn main() {
+/// let snapshot = Arc::new(Snapshot { data: vec![0; 1024] });
+/// let workers: Vec<Recipient<Arc<Snapshot>>> =
+///     (0..4).map(|_| Worker.start().recipient()).collect();
+///
+/// for worker in &workers {
+///     assert_eq!(worker.send(Arc::clone(&snapshot)).await.unwrap(), 1024);
+/// }
+/// # }
+/// ```
 pub trait Message {
     /// The type of value that this message will resolved with if it is
     /// successful.
     type Result: 'static;
 }
 
-/// Allow users to use `Arc<M>` as a message without having to re-impl `Message`
+/// Allow users to use `Arc<M>` as a message without having to re-impl `Message`.
+///
+/// This is the recommended way of broadcasting large payloads, see [`Message`].
 impl<M> Message for Arc<M>
 where
     M: Message,
@@ -149,7 +226,7 @@ where
     A::Context: AsyncContext<A>,
 {
     fn handle(self, ctx: &mut A::Context, tx: Option<OneshotSender<M::Result>>) {
-        ctx.wait(self.0.map(|res, _, _| tx.send(res)));
+        ctx.wait(abortable(self.0.map(|res, _, _| tx.send(res))));
     }
 }
 
@@ -327,7 +404,7 @@ where
     A::Context: AsyncContext<A>,
 {
     fn handle(self, ctx: &mut A::Context, tx: Option<OneshotSender<M::Result>>) {
-        ctx.spawn(self.map(|res, _, _| tx.send(res)));
+        ctx.spawn(abortable(self.map(|res, _, _| tx.send(res))));
     }
 }
 
@@ -393,13 +470,18 @@ where
     M: Message,
 {
     fn handle(self, _: &mut A::Context, tx: Option<OneshotSender<M::Result>>) {
-        actix_rt::spawn(async { tx.send(self.await) });
+        actix_rt::spawn(abortable(async { tx.send(self.await) }));
     }
 }
 
+type BoxedRequest<I> = Pin<Box<dyn Future<Output = Result<I, MailboxError>>>>;
+
 enum ResponseTypeItem<I> {
     Result(I),
     Fut(Pin<Box<dyn Future<Output = I>>>),
+    Forward(BoxedRequest<I>),
+    /// A request to be sent once the receiving mailbox has room.
+    Deliver(Pin<Box<dyn Future<Output = BoxedRequest<I>>>>),
 }
 
 /// Helper type for representing different type of message responses
@@ -413,6 +495,9 @@ impl<I> fmt::Debug for Response<I> {
         match self.item {
             ResponseTypeItem::Result(_) => fmt.field("item", &"Result(_)".to_string()),
             ResponseTypeItem::Fut(_) => fmt.field("item", &"Fut(_)".to_string()),
+            ResponseTypeItem::Forward(_) | ResponseTypeItem::Deliver(_) => {
+                fmt.field("item", &"Forward(_)".to_string())
+            }
         }
         .finish()
     }
@@ -435,6 +520,133 @@ impl<I> Response<I> {
             item: ResponseTypeItem::Result(val),
         }
     }
+
+    /// Creates a response that is the response to a request sent to another actor.
+    ///
+    /// If the request fails, e.g. because the other actor has stopped, no response is sent and
+    /// the request of the original message fails with the same [`MailboxError`].
+    pub fn from_request<F>(req: F) -> Self
+    where
+        F: Future<Output = Result<I, MailboxError>> + 'static,
+    {
+        Self {
+            item: ResponseTypeItem::Forward(Box::pin(req)),
+        }
+    }
+
+    /// Forwards `msg` to the actor at `addr` and responds with its response.
+    ///
+    /// This is a shorthand for [`from_request`](Self::from_request) with
+    /// [`addr.send(msg)`](Addr::send), which makes proxy actors trivial.
+    ///
+    /// Backpressure of the other actor carries over to the proxy: while its mailbox is full,
+    /// the proxy stops handling messages until there is room for `msg`, so that the proxy's
+    /// own mailbox fills up instead of requests piling up in between.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// #[derive(Message)]
+    /// #[rtype(result = "usize")]
+    /// struct Len(String);
+    ///
+    /// struct Backend;
+    ///
+    /// impl Actor for Backend {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Len> for Backend {
+    ///     type Result = usize;
+    ///
+    ///     fn handle(&mut self, msg: Len, _: &mut Context<Self>) -> usize {
+    ///         msg.0.len()
+    ///     }
+    /// }
+    ///
+    /// struct Proxy(Addr<Backend>);
+    ///
+    /// impl Actor for Proxy {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Len> for Proxy {
+    ///     type Result = Response<usize>;
+    ///
+    ///     fn handle(&mut self, msg: Len, _: &mut Context<Self>) -> Self::Result {
+    ///         Response::forward(&self.0, msg)
+    ///     }
+    /// }
+    ///
+    /// # #[actix::main] async fn main() {
+    /// let proxy = Proxy(Backend.start()).start();
+    /// assert_eq!(proxy.send(Len("four".to_owned())).await.unwrap(), 4);
+    /// # }
+    /// ```
+    pub fn forward<B, M>(addr: &Addr<B>, msg: M) -> Self
+    where
+        B: Handler<M>,
+        B::Context: ToEnvelope<B, M>,
+        M: Message<Result = I> + Send + 'static,
+        I: Send,
+    {
+        match addr.ready() {
+            None => Self::from_request(addr.send(msg)),
+            Some(ready) => Self {
+                item: ResponseTypeItem::Deliver(Box::pin(ready.map(|addr| {
+                    let req: BoxedRequest<I> = Box::pin(addr.send(msg));
+                    req
+                }))),
+            },
+        }
+    }
+
+    /// Maps the item of this response with `f`.
+    ///
+    /// `f` is applied right away to an immediate response, and once the future resolves to an
+    /// asynchronous one.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix::prelude::*;
+    /// let res: Response<String> = Response::reply(4).map(|n: usize| n.to_string());
+    /// let fut: Response<String> = Response::fut(async { 4 }).map(|n: usize| n.to_string());
+    /// ```
+    pub fn map<U, F>(self, f: F) -> Response<U>
+    where
+        I: 'static,
+        F: FnOnce(I) -> U + 'static,
+    {
+        let item = match self.item {
+            ResponseTypeItem::Result(res) => ResponseTypeItem::Result(f(res)),
+            ResponseTypeItem::Fut(fut) => ResponseTypeItem::Fut(Box::pin(async { f(fut.await) })),
+            ResponseTypeItem::Forward(req) => {
+                ResponseTypeItem::Forward(Box::pin(async { req.await.map(f) }))
+            }
+            ResponseTypeItem::Deliver(deliver) => {
+                ResponseTypeItem::Deliver(Box::pin(deliver.map(|req| {
+                    let req: BoxedRequest<U> = Box::pin(async { req.await.map(f) });
+                    req
+                })))
+            }
+        };
+        Response { item }
+    }
+}
+
+impl<T, E> Response<Result<T, E>> {
+    /// Maps the error of this response with `f`, leaving a successful item untouched.
+    ///
+    /// Like [`map`](Self::map), `f` is applied right away to an immediate response, and once
+    /// the future resolves to an asynchronous one.
+    pub fn map_err<E2, F>(self, f: F) -> Response<Result<T, E2>>
+    where
+        T: 'static,
+        E: 'static,
+        F: FnOnce(E) -> E2 + 'static,
+    {
+        self.map(|res| res.map_err(f))
+    }
 }
 
 impl<A, M> MessageResponse<A, M> for Response<M::Result>
@@ -445,13 +657,48 @@ where
     fn handle(self, _: &mut A::Context, tx: Option<OneshotSender<M::Result>>) {
         match self.item {
             ResponseTypeItem::Fut(fut) => {
-                actix_rt::spawn(async { tx.send(fut.await) });
+                actix_rt::spawn(abortable(async { tx.send(fut.await) }));
+            }
+            ResponseTypeItem::Forward(req) => {
+                let forward = forward_response(req, tx, replies::current());
+                actix_rt::spawn(abortable(forward));
+            }
+            ResponseTypeItem::Deliver(deliver) => {
+                let abort = handler_timeout::current();
+                let reply = replies::current();
+                // counted as pending until the forwarded request completes
+                let cancelled = CancelGuard::current();
+                let delivering = Abortable::new(
+                    async {
+                        let forward = forward_response(deliver.await, tx, reply);
+                        actix_rt::spawn(Abortable::new(forward, abort, cancelled));
+                    },
+                    handler_timeout::current(),
+                    CancelGuard::forwarding(replies::current()),
+                );
+                contextimpl::suspend(Box::pin(delivering));
             }
             ResponseTypeItem::Result(res) => tx.send(res),
         }
     }
 }
 
+/// Replies with the response to `req`, failing the request with its error otherwise.
+async fn forward_response<I>(
+    req: BoxedRequest<I>,
+    tx: Option<OneshotSender<I>>,
+    reply: Option<Arc<ReplyState>>,
+) {
+    match req.await {
+        Ok(res) => tx.send(res),
+        Err(err) => {
+            if let Some(reply) = reply {
+                reply.fail(err);
+            }
+        }
+    }
+}
+
 enum ActorResponseTypeItem<A, I> {
     Result(I),
     Fut(Pin<Box<dyn ActorFuture<A, Output = I>>>),
@@ -502,7 +749,7 @@ where
         match self.item {
             ActorResponseTypeItem::Fut(fut) => {
                 let fut = fut.map(|res, _, _| tx.send(res));
-                ctx.spawn(fut);
+                ctx.spawn(abortable(fut));
             }
             ActorResponseTypeItem::Result(res) => tx.send(res),
         }
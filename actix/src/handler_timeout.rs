@@ -0,0 +1,198 @@
+use std::{
+    any::type_name,
+    cell::RefCell,
+    future::{poll_fn, Future},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use log::warn;
+use pin_project_lite::pin_project;
+use tokio::sync::oneshot::{self, error::TryRecvError, Sender};
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+use crate::{
+    actor::Actor,
+    address::MailboxError,
+    clock::{sleep_until, Instant},
+    fut::ActorFuture,
+    handler::Message,
+    replies::{self, CancelGuard, ReplyState},
+};
+
+thread_local! {
+    /// Token aborting the response of the message being handled on this thread, if the
+    /// handler has a timeout.
+    static ABORT: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// Calls `handle` with a reply sender, aborting the asynchronous response if no reply was sent
+/// within `timeout` of the handler being called.
+///
+/// The request is failed with [`MailboxError::HandlerTimeout`] in `reply` once the timeout
+/// elapses, letting the sender of the message tell the timeout apart from other failures.
+pub(crate) fn guard<M, F>(
+    timeout: Option<Duration>,
+    reply: Option<Arc<ReplyState>>,
+    tx: Option<Sender<M::Result>>,
+    handle: F,
+) where
+    M: Message + 'static,
+    F: FnOnce(Option<Sender<M::Result>>),
+{
+    let Some(timeout) = timeout else {
+        return handle(tx);
+    };
+
+    let abort = CancellationToken::new();
+    let (inner_tx, mut rx) = oneshot::channel();
+    let started = Instant::now();
+
+    let prev = ABORT.with(|current| current.replace(Some(abort.clone())));
+    handle(Some(inner_tx));
+    ABORT.with(|current| *current.borrow_mut() = prev);
+
+    let elapsed = started.elapsed();
+    if elapsed > timeout {
+        warn!(
+            "Handler of {} blocked the actor for {:?}, exceeding its timeout of {:?}",
+            type_name::<M>(),
+            elapsed,
+            timeout
+        );
+    }
+
+    match rx.try_recv() {
+        Ok(res) => {
+            if let Some(tx) = tx {
+                let _ = tx.send(res);
+            }
+        }
+        Err(TryRecvError::Empty) => {
+            let deadline = started + timeout;
+            actix_rt::spawn(async move {
+                let mut expired = Box::pin(sleep_until(deadline));
+                // `None` once the timeout elapsed, `Some(None)` if the response got dropped
+                let res = poll_fn(|cx| match Pin::new(&mut rx).poll(cx) {
+                    Poll::Ready(res) => Poll::Ready(Some(res.ok())),
+                    Poll::Pending => expired.as_mut().poll(cx).map(|_| None),
+                })
+                .await;
+
+                match res {
+                    Some(Some(res)) => {
+                        if let Some(tx) = tx {
+                            let _ = tx.send(res);
+                        }
+                    }
+                    Some(None) => {}
+                    None => {
+                        warn!(
+                            "Response to {} timed out after {:?}, aborting it",
+                            type_name::<M>(),
+                            timeout
+                        );
+                        if let Some(reply) = reply {
+                            reply.fail(MailboxError::HandlerTimeout);
+                        }
+                        abort.cancel();
+                    }
+                }
+            });
+        }
+        Err(TryRecvError::Closed) => {}
+    }
+}
+
+pin_project! {
+    /// A response future, stopped once the handler timeout of its message elapses.
+    ///
+    /// Dropping it before it completes fails the request with [`MailboxError::Closed`].
+    pub(crate) struct Abortable<F> {
+        cancelled: CancelGuard,
+        #[pin]
+        fut: F,
+        #[pin]
+        aborted: Option<WaitForCancellationFutureOwned>,
+    }
+}
+
+/// Returns the token aborting the response of the message being handled, if the handler has
+/// a timeout.
+pub(crate) fn current() -> Option<CancellationToken> {
+    ABORT.with(|current| current.borrow().clone())
+}
+
+/// Makes the response `fut` of the message being handled abortable, if the handler has a
+/// timeout, counting its reply as pending until it completes.
+pub(crate) fn abortable<F>(fut: F) -> Abortable<F> {
+    Abortable::new(fut, current(), CancelGuard::current())
+}
+
+/// Like [`abortable`], for a future passing on the reply of another response of the message
+/// being handled.
+pub(crate) fn forwarding<F>(fut: F) -> Abortable<F> {
+    Abortable::new(fut, current(), CancelGuard::forwarding(replies::current()))
+}
+
+impl<F> Abortable<F> {
+    /// Makes `fut` stop once `abort` is cancelled, if any, guarding its reply with `cancelled`.
+    pub(crate) fn new(fut: F, abort: Option<CancellationToken>, cancelled: CancelGuard) -> Self {
+        Abortable {
+            cancelled,
+            fut,
+            aborted: abort.map(CancellationToken::cancelled_owned),
+        }
+    }
+
+    fn aborted(self: Pin<&mut Self>, cx: &mut Context<'_>) -> bool {
+        match self.project().aborted.as_pin_mut() {
+            Some(aborted) => aborted.poll(cx).is_ready(),
+            None => false,
+        }
+    }
+
+    fn done(self: Pin<&mut Self>) -> Poll<()> {
+        self.project().cancelled.disarm();
+        Poll::Ready(())
+    }
+}
+
+impl<F: Future<Output = ()>> Future for Abortable<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.as_mut().aborted(cx) || self.as_mut().project().fut.poll(cx).is_ready() {
+            return self.done();
+        }
+        Poll::Pending
+    }
+}
+
+impl<A, F> ActorFuture<A> for Abortable<F>
+where
+    A: Actor,
+    F: ActorFuture<A, Output = ()>,
+{
+    type Output = ();
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        srv: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<()> {
+        if self.as_mut().aborted(task)
+            || self.as_mut().project().fut.poll(srv, ctx, task).is_ready()
+        {
+            return self.done();
+        }
+        Poll::Pending
+    }
+
+    fn cancel(self: Pin<&mut Self>, srv: &mut A, ctx: &mut A::Context) {
+        self.project().fut.cancel(srv, ctx)
+    }
+}
@@ -0,0 +1,178 @@
+use std::{collections::HashMap, time::Duration};
+
+use actix_rt::{ArbiterHandle, System};
+use futures_core::future::BoxFuture;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tokio::{sync::oneshot, time::timeout_at};
+
+use crate::{
+    actor::ActorState,
+    clock::Instant,
+    directory::{self, ActorDirectory},
+    registry, shutdown,
+};
+
+/// How long [`HealthCheck::health`] waits for arbiters and services to answer.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Worker arbiters of the systems built with `SystemBuilder`, keyed by system id.
+static WORKERS: Lazy<Mutex<HashMap<usize, Vec<ArbiterHandle>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Records the worker arbiters of the system `id`, to be pinged by health checks.
+pub(crate) fn register_workers(system: usize, arbiters: Vec<ArbiterHandle>) {
+    WORKERS.lock().insert(system, arbiters);
+}
+
+/// Forgets the worker arbiters of the system `id`.
+pub(crate) fn release(system: usize) {
+    WORKERS.lock().remove(&system);
+}
+
+/// Aggregate health of a [`System`], for liveness and readiness probes.
+///
+/// A health check pings the system arbiter and the worker arbiters of a system built with
+/// [`SystemBuilder`](crate::SystemBuilder), and asks every registered
+/// [`SystemService`](crate::SystemService) for its state. Both round-trips are bounded by a
+/// timeout, so a blocked arbiter or a service stuck in a handler shows up as unhealthy instead
+/// of stalling the check. The result is plain data, to be served by an endpoint of your choice.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// #[derive(Default)]
+/// struct Config;
+///
+/// impl Actor for Config {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Supervised for Config {}
+/// impl SystemService for Config {}
+///
+/// #[actix::main]
+/// async fn main() {
+///     let _config = Config::from_registry();
+///
+///     let health = System::current().health().await;
+///     assert!(health.is_live());
+///     assert!(health.is_ready());
+/// }
+/// ```
+pub trait HealthCheck {
+    /// Checks the health of the system, waiting up to a second for answers.
+    fn health(&self) -> BoxFuture<'static, SystemHealth>;
+
+    /// Checks the health of the system like [`health`](Self::health), waiting up to `timeout`
+    /// for answers.
+    fn health_timeout(&self, timeout: Duration) -> BoxFuture<'static, SystemHealth>;
+}
+
+/// Outcome of a [`HealthCheck`].
+#[derive(Debug, Clone, Default)]
+pub struct SystemHealth {
+    /// Number of live actors, or `None` if the [`ActorDirectory`] of the system is not enabled.
+    pub actors: Option<usize>,
+    /// Number of live actors that are stopping or have stopped, or `None` if the
+    /// [`ActorDirectory`] of the system is not enabled.
+    pub stopping: Option<usize>,
+    /// Number of arbiters that were pinged.
+    pub arbiters: usize,
+    /// Number of arbiters that did not answer the ping in time, or are gone.
+    pub unresponsive_arbiters: usize,
+    /// Number of registered system services.
+    pub services: usize,
+    /// Type names of the system services that are not running, or did not answer in time.
+    pub unready_services: Vec<&'static str>,
+    /// Whether a [`PhasedShutdown`](crate::PhasedShutdown) of the system is in progress.
+    pub shutting_down: bool,
+}
+
+impl SystemHealth {
+    /// Returns `true` if every arbiter answered the ping in time.
+    pub fn is_live(&self) -> bool {
+        self.unresponsive_arbiters == 0
+    }
+
+    /// Returns `true` if every system service is running and the system is not shutting down.
+    pub fn is_ready(&self) -> bool {
+        self.unready_services.is_empty() && !self.shutting_down
+    }
+}
+
+impl HealthCheck for System {
+    fn health(&self) -> BoxFuture<'static, SystemHealth> {
+        self.health_timeout(DEFAULT_TIMEOUT)
+    }
+
+    fn health_timeout(&self, timeout: Duration) -> BoxFuture<'static, SystemHealth> {
+        Box::pin(check(self.clone(), Instant::now() + timeout))
+    }
+}
+
+async fn check(system: System, deadline: Instant) -> SystemHealth {
+    let mut health = SystemHealth {
+        shutting_down: shutdown::in_progress(system.id()),
+        ..SystemHealth::default()
+    };
+
+    let mut arbiters = vec![system.arbiter().clone()];
+    arbiters.extend(
+        WORKERS
+            .lock()
+            .get(&system.id())
+            .into_iter()
+            .flatten()
+            .cloned(),
+    );
+    let pings = arbiters
+        .iter()
+        .map(|arbiter| {
+            let (tx, rx) = oneshot::channel();
+            arbiter
+                .spawn(async move {
+                    let _ = tx.send(());
+                })
+                .then_some(rx)
+        })
+        .collect::<Vec<_>>();
+    let probes = registry::probe_services(system.id());
+
+    health.arbiters = pings.len();
+    for ping in pings {
+        let answered = match ping {
+            Some(rx) => matches!(timeout_at(deadline, rx).await, Ok(Ok(()))),
+            None => false,
+        };
+        if !answered {
+            health.unresponsive_arbiters += 1;
+        }
+    }
+
+    health.services = probes.len();
+    for (name, probe) in probes {
+        let running = match probe {
+            Some(rx) => matches!(timeout_at(deadline, rx).await, Ok(Ok(ActorState::Running))),
+            None => false,
+        };
+        if !running {
+            health.unready_services.push(name);
+        }
+    }
+
+    // taken last, so that actors started right before the check are listed
+    if directory::enabled(system.id()) {
+        let actors = system.actors();
+        health.actors = Some(actors.len());
+        health.stopping = Some(
+            actors
+                .iter()
+                .filter(|info| matches!(info.state, ActorState::Stopping | ActorState::Stopped))
+                .count(),
+        );
+    }
+
+    health
+}
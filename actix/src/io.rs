@@ -1,6 +1,8 @@
 use std::{
+    any::type_name,
     cell::RefCell,
     collections::VecDeque,
+    future::Future,
     io,
     marker::PhantomData,
     ops::DerefMut,
@@ -8,19 +10,27 @@ use std::{
     rc::Rc,
     task,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use bitflags::bitflags;
 use bytes::BytesMut;
+use futures_core::ready;
 use futures_sink::Sink;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio_util::codec::Encoder;
 
 use crate::{
     actor::{Actor, ActorContext, AsyncContext, Running, SpawnHandle},
-    fut::ActorFuture,
+    clock::{sleep, Instant, Sleep},
+    contextimpl::AsyncContextParts,
+    fut::{wrap_future, ActorFuture},
+    shutdown,
 };
 
+/// Default time a [`SinkWrite`] is given to flush its queued items once its actor stops.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// A helper trait for write handling.
 ///
 /// `WriteHandler` is a helper for `AsyncWrite` types. Implementation
@@ -415,6 +425,11 @@ impl<I, T: AsyncWrite + Unpin, U: Encoder<I>> Drop for FramedWrite<I, T, U> {
 }
 
 /// A wrapper for the `Sink` type.
+///
+/// When the actor stops, the items still queued are flushed to the sink before the actor is
+/// dropped, for up to [5 seconds](Self::set_drain_timeout) and no longer than the deadline of a
+/// [`PhasedShutdown`](crate::PhasedShutdown) in progress. Items that could not be delivered are
+/// logged.
 pub struct SinkWrite<I, S: Sink<I> + Unpin> {
     inner: Rc<RefCell<InnerSinkWrite<I, S>>>,
 }
@@ -423,7 +438,7 @@ impl<I: 'static, S: Sink<I> + Unpin + 'static> SinkWrite<I, S> {
     pub fn new<A, C>(sink: S, ctxt: &mut C) -> Self
     where
         A: Actor<Context = C> + WriteHandler<S::Error>,
-        C: AsyncContext<A>,
+        C: AsyncContextParts<A>,
     {
         let inner = Rc::new(RefCell::new(InnerSinkWrite {
             _i: PhantomData,
@@ -432,12 +447,31 @@ impl<I: 'static, S: Sink<I> + Unpin + 'static> SinkWrite<I, S> {
             task: None,
             handle: SpawnHandle::default(),
             buffer: VecDeque::new(),
+            drain_timeout: DRAIN_TIMEOUT,
         }));
 
         let handle = ctxt.spawn(SinkWriteFuture {
             inner: inner.clone(),
         });
 
+        let drain = Rc::downgrade(&inner);
+        ctxt.parts().drains().push(Box::new(move || {
+            let inner = drain.upgrade()?;
+            if inner.borrow().closing_flag.contains(Flags::CLOSED) {
+                return None;
+            }
+
+            let mut timeout = inner.borrow().drain_timeout;
+            if let Some(deadline) = shutdown::deadline() {
+                timeout = timeout.min(deadline.saturating_duration_since(Instant::now()));
+            }
+            Some(Box::pin(wrap_future(SinkDrain {
+                inner,
+                actor: type_name::<A>(),
+                deadline: Box::pin(sleep(timeout)),
+            })))
+        }));
+
         inner.borrow_mut().handle = handle;
         SinkWrite { inner }
     }
@@ -468,6 +502,12 @@ impl<I: 'static, S: Sink<I> + Unpin + 'static> SinkWrite<I, S> {
         self.inner.borrow_mut().closing_flag.contains(Flags::CLOSED)
     }
 
+    /// Sets how long the queued items are flushed for once the actor stops, 5 seconds by
+    /// default.
+    pub fn set_drain_timeout(&mut self, timeout: Duration) {
+        self.inner.borrow_mut().drain_timeout = timeout;
+    }
+
     fn notify_task(&self) {
         if let Some(task) = &self.inner.borrow().task {
             task.wake_by_ref()
@@ -490,6 +530,56 @@ struct InnerSinkWrite<I, S: Sink<I>> {
     // buffer of items to be sent so that multiple
     // calls to start_send don't silently skip items
     buffer: VecDeque<I>,
+    drain_timeout: Duration,
+}
+
+impl<I, S: Sink<I> + Unpin> InnerSinkWrite<I, S> {
+    /// Sends the queued items to the sink and flushes it.
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        while !self.buffer.is_empty() {
+            ready!(Pin::new(&mut self.sink).poll_ready(cx))?;
+            let item = self.buffer.pop_front().unwrap();
+            Pin::new(&mut self.sink).start_send(item)?;
+        }
+        Pin::new(&mut self.sink).poll_flush(cx)
+    }
+}
+
+/// Flushes the items queued in a [`SinkWrite`] once its actor stops.
+struct SinkDrain<I, S: Sink<I>> {
+    inner: Rc<RefCell<InnerSinkWrite<I, S>>>,
+    actor: &'static str,
+    deadline: Pin<Box<Sleep>>,
+}
+
+impl<I, S: Sink<I> + Unpin> Future for SinkDrain<I, S> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut inner = this.inner.borrow_mut();
+
+        match inner.poll_drain(cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(()),
+            Poll::Ready(Err(_)) => {
+                log::error!(
+                    "Failed to flush the sink of stopped {}, {} queued items were not delivered",
+                    this.actor,
+                    inner.buffer.len()
+                );
+                Poll::Ready(())
+            }
+            Poll::Pending if this.deadline.as_mut().poll(cx).is_ready() => {
+                log::warn!(
+                    "Timed out flushing the sink of stopped {}, {} queued items were not delivered",
+                    this.actor,
+                    inner.buffer.len()
+                );
+                Poll::Ready(())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
 }
 
 struct SinkWriteFuture<I: 'static, S: Sink<I>> {
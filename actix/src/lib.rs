@@ -29,27 +29,56 @@
 doc_comment::doctest!("../README.md");
 
 mod actor;
+mod batch;
+mod command;
 mod context;
 mod contextimpl;
 mod contextitems;
+mod deadline;
+mod directory;
 mod handler;
+mod handler_timeout;
+mod health;
+mod limit;
 mod stream;
 mod supervisor;
+mod system;
 
 mod address;
+mod audit;
 mod mailbox;
+mod memo;
+mod middleware;
+mod observe;
+mod panic;
+mod pipeline;
+mod pool;
+mod progress;
+mod propagation;
+mod rate_limit;
+mod replies;
+mod semaphore;
+mod shutdown;
+mod stash;
 
 pub mod actors;
 pub mod clock;
 pub mod fut;
 pub mod io;
+#[cfg(feature = "record")]
+pub mod record;
 pub mod registry;
+#[cfg(feature = "state")]
+pub mod state;
 pub mod sync;
+#[cfg(feature = "test-util")]
+pub mod testing;
 pub mod utils;
 
 #[cfg(feature = "macros")]
 pub use actix_derive::{main, test, Message, MessageResponse};
 pub use actix_rt::{spawn, Arbiter, ArbiterHandle, System, SystemRunner};
+pub use tokio_util::sync::CancellationToken;
 
 #[doc(hidden)]
 pub mod __private {
@@ -60,21 +89,49 @@ pub mod __private {
 #[doc(hidden)]
 pub use crate::context::ContextFutureSpawner;
 pub use crate::{
-    actor::{Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle, Supervised},
-    address::{Addr, MailboxError, Recipient, WeakAddr, WeakRecipient},
-    context::Context,
+    actor::{
+        Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle, StartError, Supervised,
+    },
+    address::{
+        Addr, MailboxError, Recipient, Reply, ReplyFailed, SelfNotifier, WeakAddr, WeakRecipient,
+    },
+    audit::{AuditEntry, SystemAudit},
+    batch::{Batched, BatchingHandler, Responder},
+    command::{Command, DynHandler},
+    context::{ChildStarted, Context, Handover, Terminated},
+    contextitems::Scope,
+    directory::{ActorDirectory, ActorId, ActorInfo},
     fut::{
         ActorFuture, ActorFutureExt, ActorStream, ActorStreamExt, ActorTryFuture,
         ActorTryFutureExt, WrapFuture, WrapStream,
     },
     handler::{
-        ActorResponse, AtomicResponse, Handler, Message, MessageResult, Response,
+        ActorResponse, AtomicResponse, ErrorHandler, Handler, Message, MessageResult, Response,
         ResponseActFuture, ResponseFuture,
     },
+    health::{HealthCheck, SystemHealth},
+    limit::{ActorLimit, TooManyActors},
+    middleware::MessageMiddleware,
+    observe::{Observable, Observer},
+    panic::{panic_policy, set_panic_policy, PanicPolicy},
+    pipeline::{Pipeline, PipelineError},
+    pool::Pool,
+    progress::{ProgressResponder, WithProgress},
+    propagation::{set_context_propagator, ContextPropagator, SetPropagatorError},
+    rate_limit::{Rate, RateLimiter},
     registry::{ArbiterService, Registry, SystemRegistry, SystemService},
-    stream::StreamHandler,
+    semaphore::{Permit, Semaphore},
+    shutdown::{PhasedShutdown, ShutdownReport},
+    stream::{
+        AckStreamHandle, AckStreamHandler, GapAction, Sequenced, SequencedStreamHandler, StreamAck,
+        StreamHandler,
+    },
     supervisor::Supervisor,
-    sync::{SyncArbiter, SyncContext},
+    sync::{Resize, SyncArbiter, SyncContext},
+    system::{
+        ArbiterExt, ArbiterSpawnExt, Done, RuntimeKind, SystemBuilder, SystemExt, WorkerSystem,
+        Workers,
+    },
 };
 
 pub mod prelude {
@@ -93,29 +150,59 @@ pub mod prelude {
     pub use actix_derive::{Message, MessageResponse};
     pub use actix_rt::{Arbiter, ArbiterHandle, System, SystemRunner};
     pub use futures_core::stream::Stream;
+    pub use tokio_util::sync::CancellationToken;
 
     #[allow(deprecated)]
     pub use crate::utils::Condition;
     pub use crate::{
-        actor::{Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle, Supervised},
+        actor::{
+            Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle, StartError,
+            Supervised,
+        },
         actors,
-        address::{Addr, MailboxError, Recipient, RecipientRequest, Request, SendError},
-        context::{Context, ContextFutureSpawner},
-        dev, fut,
+        address::{
+            Addr, MailboxError, Recipient, RecipientRequest, Reply, ReplyFailed, Request,
+            SelfNotifier, SendError,
+        },
+        audit::{AuditEntry, SystemAudit},
+        batch::{Batched, BatchingHandler, Responder},
+        command::{Command, DynHandler},
+        context::{ChildStarted, Context, ContextFutureSpawner, Handover, Terminated},
+        contextitems::Scope,
+        dev,
+        directory::{ActorDirectory, ActorId, ActorInfo},
+        fut,
         fut::{
             ActorFuture, ActorFutureExt, ActorStream, ActorStreamExt, ActorTryFuture,
             ActorTryFutureExt, WrapFuture, WrapStream,
         },
         handler::{
-            ActorResponse, AtomicResponse, Handler, Message, MessageResult, Response,
+            ActorResponse, AtomicResponse, ErrorHandler, Handler, Message, MessageResult, Response,
             ResponseActFuture, ResponseFuture,
         },
+        health::{HealthCheck, SystemHealth},
         io,
+        limit::{ActorLimit, TooManyActors},
+        middleware::MessageMiddleware,
+        observe::{Observable, Observer},
+        pipeline::{Pipeline, PipelineError},
+        pool::Pool,
+        progress::{ProgressResponder, WithProgress},
+        rate_limit::{Rate, RateLimiter},
         registry::{ArbiterService, SystemService},
-        stream::StreamHandler,
+        semaphore::{Permit, Semaphore},
+        shutdown::{PhasedShutdown, ShutdownReport},
+        stream::{
+            AckStreamHandle, AckStreamHandler, GapAction, Sequenced, SequencedStreamHandler,
+            StreamAck, StreamHandler,
+        },
         supervisor::Supervisor,
-        sync::{SyncArbiter, SyncContext},
-        utils::{IntervalFunc, TimerFunc},
+        sync::{Resize, SyncArbiter, SyncContext},
+        system::{
+            ArbiterExt, ArbiterSpawnExt, Done, RuntimeKind, SystemBuilder, SystemExt, WorkerSystem,
+            Workers,
+        },
+        utils::{IntervalFunc, MissedTicks, TimerFunc},
     };
 }
 
@@ -131,7 +218,10 @@ pub mod dev {
     //! ```
 
     pub use crate::{
-        address::{Envelope, EnvelopeProxy, RecipientRequest, Request, ToEnvelope},
+        address::{
+            AddrSink, BlockingSender, Envelope, EnvelopeProxy, RecipientRequest, Request, SendJoin,
+            ToEnvelope,
+        },
         prelude::*,
     };
     pub mod channel {
@@ -141,6 +231,7 @@ pub mod dev {
         contextimpl::{AsyncContextParts, ContextFut, ContextParts},
         handler::{MessageResponse, OneshotSender},
         mailbox::Mailbox,
+        pool::BroadcastCollect,
         registry::{Registry, SystemRegistry},
     };
 }
@@ -0,0 +1,162 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    error, fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use actix_rt::System;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+#[derive(Debug)]
+struct Counter {
+    count: AtomicUsize,
+    max: AtomicUsize,
+}
+
+/// Actor counters of every system, keyed by system id.
+static COUNTERS: Lazy<Mutex<HashMap<usize, Arc<Counter>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+thread_local! {
+    /// Counter of the system the current arbiter belongs to, to skip the global lock.
+    static CURRENT: RefCell<Option<(usize, Arc<Counter>)>> = const { RefCell::new(None) };
+}
+
+fn counter(system: &System) -> Arc<Counter> {
+    let id = system.id();
+    let cached = CURRENT.with(|current| match *current.borrow() {
+        Some((cached, ref counter)) if cached == id => Some(Arc::clone(counter)),
+        _ => None,
+    });
+    if let Some(counter) = cached {
+        return counter;
+    }
+
+    crate::system::track(system);
+    let counter = Arc::clone(COUNTERS.lock().entry(id).or_insert_with(|| {
+        Arc::new(Counter {
+            count: AtomicUsize::new(0),
+            max: AtomicUsize::new(usize::MAX),
+        })
+    }));
+    CURRENT.with(|current| *current.borrow_mut() = Some((id, Arc::clone(&counter))));
+    counter
+}
+
+/// Forgets the actor counter of the system `id`.
+pub(crate) fn release(system: usize) {
+    COUNTERS.lock().remove(&system);
+    // may run while the thread locals of the thread are destroyed, along with its arbiter
+    let _ = CURRENT.try_with(|current| {
+        let mut current = current.borrow_mut();
+        if matches!(*current, Some((cached, _)) if cached == system) {
+            *current = None;
+        }
+    });
+}
+
+/// Place of a running actor in the actor count of its system, released on drop.
+#[derive(Debug)]
+pub(crate) struct Slot(Option<Arc<Counter>>);
+
+impl Slot {
+    /// Counts a new actor, failing if its system has reached its limit.
+    ///
+    /// Actors started outside of a system are not counted.
+    pub(crate) fn reserve() -> Result<Self, TooManyActors> {
+        let Some(system) = System::try_current() else {
+            return Ok(Slot(None));
+        };
+
+        let counter = counter(&system);
+        let max = counter.max.load(Ordering::Acquire);
+        counter
+            .count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < max).then_some(count + 1)
+            })
+            .map_err(|_| TooManyActors { max })?;
+        Ok(Slot(Some(counter)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some(ref counter) = self.0 {
+            counter.count.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+/// Error returned when an actor is started while its system runs the maximum number of actors,
+/// wrapped in [`StartError::TooManyActors`](crate::StartError::TooManyActors).
+///
+/// See [`ActorLimit::set_max_actors`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManyActors {
+    /// The limit that was reached.
+    pub max: usize,
+}
+
+impl fmt::Display for TooManyActors {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "Too many actors: the limit of {} is reached", self.max)
+    }
+}
+
+impl error::Error for TooManyActors {}
+
+/// Counting and limiting the actors of a [`System`].
+///
+/// Actors are counted, across all arbiters of the system, from the moment they are started
+/// until they have stopped. Actors running in a [`SyncContext`](crate::SyncContext) are not
+/// counted.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// struct Worker;
+///
+/// impl Actor for Worker {
+///     type Context = Context<Self>;
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     System::current().set_max_actors(2);
+///
+///     let _a = Worker.start();
+///     let _b = Worker.try_start().unwrap();
+///     assert!(Worker.try_start().is_err());
+///     assert_eq!(System::current().actor_count(), 2);
+/// }
+/// ```
+pub trait ActorLimit {
+    /// Limits the number of running actors to `max`.
+    ///
+    /// Once the limit is reached, [`Actor::try_start`](crate::Actor::try_start) fails with
+    /// [`StartError::TooManyActors`](crate::StartError::TooManyActors),
+    /// [`Actor::start_in_arbiter`](crate::Actor::start_in_arbiter) logs an
+    /// error and drops the actor, and the other ways of starting an actor panic. Actors that
+    /// are already running are not affected by lowering the limit.
+    fn set_max_actors(&self, max: usize);
+
+    /// Returns the number of running actors.
+    fn actor_count(&self) -> usize;
+}
+
+impl ActorLimit for System {
+    fn set_max_actors(&self, max: usize) {
+        counter(self).max.store(max, Ordering::Release);
+    }
+
+    fn actor_count(&self) -> usize {
+        counter(self).count.load(Ordering::Acquire)
+    }
+}
@@ -5,6 +5,7 @@ use futures_core::stream::Stream;
 use crate::{
     actor::{Actor, AsyncContext},
     address::{channel, Addr, AddressReceiver, AddressSenderProducer, EnvelopeProxy},
+    contextimpl::{run_deferred, suspending, AsyncContextParts},
 };
 
 /// Default address channel capacity
@@ -65,6 +66,11 @@ where
         self.msgs.connected()
     }
 
+    /// Returns whether no message is waiting to be handled.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.msgs.queued() == 0
+    }
+
     pub fn address(&self) -> Addr<A> {
         Addr::new(self.msgs.sender())
     }
@@ -72,15 +78,48 @@ where
     pub fn sender_producer(&self) -> AddressSenderProducer<A> {
         self.msgs.sender_producer()
     }
+}
 
+impl<A> Mailbox<A>
+where
+    A: Actor,
+    A::Context: AsyncContextParts<A>,
+{
     pub fn poll(&mut self, act: &mut A, ctx: &mut A::Context, task: &mut task::Context<'_>) {
+        self.poll_while(act, ctx, task, |_| true);
+    }
+
+    /// Processes incoming messages while the context is not waiting and `cond` holds.
+    ///
+    /// Returns whether any message has been handled.
+    pub(crate) fn poll_while<F>(
+        &mut self,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut task::Context<'_>,
+        mut cond: F,
+    ) -> bool
+    where
+        F: FnMut(&mut A::Context) -> bool,
+    {
         #[cfg(feature = "mailbox_assert")]
         let mut n_polls = 0u16;
+        let mut handled = false;
+
+        while !ctx.waiting() && cond(ctx) {
+            // unstashed messages go before the mailbox
+            if let Some(mut msg) = ctx.parts().stash().next() {
+                suspending(act, ctx, |act, ctx| msg.handle(act, ctx));
+                run_deferred(act, ctx);
+                handled = true;
+                continue;
+            }
 
-        while !ctx.waiting() {
             match Pin::new(&mut self.msgs).poll_next(task) {
                 Poll::Ready(Some(mut msg)) => {
-                    msg.handle(act, ctx);
+                    suspending(act, ctx, |act, ctx| msg.handle(act, ctx));
+                    run_deferred(act, ctx);
+                    handled = true;
                     #[cfg(feature = "mailbox_assert")]
                     {
                         n_polls += 1;
@@ -88,8 +127,10 @@ where
                         assert!(n_polls < 256u16, "Too many messages are being processed. Use Self::Context::notify() instead of direct use of address");
                     }
                 }
-                Poll::Ready(None) | Poll::Pending => return,
+                Poll::Ready(None) | Poll::Pending => return handled,
             }
         }
+
+        handled
     }
 }
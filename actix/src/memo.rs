@@ -0,0 +1,222 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    time::Duration,
+};
+
+use tokio::sync::oneshot::{self, error::TryRecvError, Sender};
+
+use crate::{
+    actor::{Actor, AsyncContext},
+    clock::Instant,
+    contextimpl::AsyncContextParts,
+    fut::{wrap_future, ActorFutureExt},
+    handler::{Handler, Message},
+    handler_timeout::forwarding,
+    stash,
+};
+
+/// A message whose result is to be cached, with the generation of its table at the time it
+/// was handled.
+struct Key<M> {
+    msg: M,
+    generation: u64,
+}
+
+/// Access to the memo table of messages of type `M`, without requiring the bounds of
+/// [`Table`] where the table is used.
+trait Memo<M: Message> {
+    /// Returns the result cached for `msg`, if it has not expired.
+    fn get(&mut self, msg: &M) -> Option<M::Result>;
+
+    fn key(&self, msg: &M) -> Key<M>;
+
+    /// Caches `result`, unless the table was invalidated since `key` was created.
+    fn insert(&mut self, key: Key<M>, result: &M::Result);
+
+    fn set_ttl(&mut self, ttl: Duration);
+
+    fn invalidate(&mut self, msg: &M);
+
+    fn clear(&mut self);
+}
+
+struct Table<M: Message> {
+    ttl: Duration,
+    generation: u64,
+    entries: HashMap<M, (Instant, M::Result)>,
+    /// Number of entries at which expired entries are dropped on the next insert.
+    sweep_at: usize,
+}
+
+impl<M> Memo<M> for Table<M>
+where
+    M: Message + Eq + Hash + Clone,
+    M::Result: Clone,
+{
+    fn get(&mut self, msg: &M) -> Option<M::Result> {
+        let (inserted, result) = self.entries.get(msg)?;
+        if inserted.elapsed() < self.ttl {
+            return Some(result.clone());
+        }
+
+        self.entries.remove(msg);
+        None
+    }
+
+    fn key(&self, msg: &M) -> Key<M> {
+        Key {
+            msg: msg.clone(),
+            generation: self.generation,
+        }
+    }
+
+    fn insert(&mut self, key: Key<M>, result: &M::Result) {
+        if key.generation != self.generation {
+            return;
+        }
+
+        if self.entries.len() >= self.sweep_at {
+            let ttl = self.ttl;
+            self.entries
+                .retain(|_, (inserted, _)| inserted.elapsed() < ttl);
+            self.sweep_at = (self.entries.len() * 2).max(32);
+        }
+        self.entries
+            .insert(key.msg, (Instant::now(), result.clone()));
+    }
+
+    fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+    }
+
+    fn invalidate(&mut self, msg: &M) {
+        self.generation += 1;
+        self.entries.remove(msg);
+    }
+
+    fn clear(&mut self) {
+        self.generation += 1;
+        self.entries.clear();
+    }
+}
+
+/// Memo tables of an actor, keyed by message type.
+#[derive(Default)]
+pub(crate) struct Memos {
+    tables: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl fmt::Debug for Memos {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Memos")
+            .field("messages", &self.tables.len())
+            .finish()
+    }
+}
+
+impl Memos {
+    pub(crate) fn enable<M>(&mut self, ttl: Duration)
+    where
+        M: Message + Eq + Hash + Clone + 'static,
+        M::Result: Clone,
+    {
+        match self.table::<M>() {
+            Some(table) => table.set_ttl(ttl),
+            None => {
+                let table: Box<dyn Memo<M>> = Box::new(Table::<M> {
+                    ttl,
+                    generation: 0,
+                    entries: HashMap::new(),
+                    sweep_at: 32,
+                });
+                self.tables.insert(TypeId::of::<M>(), Box::new(table));
+            }
+        }
+    }
+
+    pub(crate) fn invalidate<M: Message + 'static>(&mut self, msg: &M) {
+        if let Some(table) = self.table::<M>() {
+            table.invalidate(msg);
+        }
+    }
+
+    pub(crate) fn clear<M: Message + 'static>(&mut self) {
+        if let Some(table) = self.table::<M>() {
+            table.clear();
+        }
+    }
+
+    fn table<M: Message + 'static>(&mut self) -> Option<&mut Box<dyn Memo<M>>> {
+        if self.tables.is_empty() {
+            return None;
+        }
+
+        self.tables
+            .get_mut(&TypeId::of::<M>())
+            .and_then(|table| table.downcast_mut::<Box<dyn Memo<M>>>())
+    }
+}
+
+fn table<A, M>(ctx: &mut A::Context) -> Option<&mut Box<dyn Memo<M>>>
+where
+    A: Actor,
+    A::Context: AsyncContextParts<A>,
+    M: Message + 'static,
+{
+    ctx.parts().memos().table::<M>()
+}
+
+/// Responds with the cached result for `msg` if there is one, and handles it otherwise,
+/// caching the result if memoization is enabled for `M`.
+pub(crate) fn handle<A, M>(act: &mut A, msg: M, ctx: &mut A::Context, tx: Option<Sender<M::Result>>)
+where
+    A: Actor + Handler<M>,
+    A::Context: AsyncContextParts<A>,
+    M: Message + 'static,
+{
+    let key = match table::<A, M>(ctx) {
+        Some(table) => match table.get(&msg) {
+            Some(result) => {
+                if let Some(tx) = tx {
+                    let _ = tx.send(result);
+                }
+                return;
+            }
+            None => table.key(&msg),
+        },
+        None => return stash::handle(act, msg, ctx, tx),
+    };
+
+    let (res_tx, mut res_rx) = oneshot::channel();
+    stash::handle(act, msg, ctx, Some(res_tx));
+
+    match res_rx.try_recv() {
+        Ok(result) => store::<A, M>(ctx, key, result, tx),
+        Err(TryRecvError::Empty) => {
+            let fut = wrap_future::<_, A>(res_rx).map(move |result, _, ctx| {
+                if let Ok(result) = result {
+                    store::<A, M>(ctx, key, result, tx);
+                }
+            });
+            ctx.spawn(forwarding(fut));
+        }
+        Err(TryRecvError::Closed) => {}
+    }
+}
+
+fn store<A, M>(ctx: &mut A::Context, key: Key<M>, result: M::Result, tx: Option<Sender<M::Result>>)
+where
+    A: Actor,
+    A::Context: AsyncContextParts<A>,
+    M: Message + 'static,
+{
+    if let Some(table) = table::<A, M>(ctx) {
+        table.insert(key, &result);
+    }
+    if let Some(tx) = tx {
+        let _ = tx.send(result);
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+};
+
+use tokio::sync::oneshot::{self, error::TryRecvError, Sender as OneshotSender};
+
+use crate::{
+    actor::{Actor, AsyncContext},
+    contextimpl::AsyncContextParts,
+    fut::{wrap_future, ActorFutureExt},
+    handler::{Handler, Message},
+    handler_timeout::forwarding,
+    memo,
+};
+
+/// Reusable behavior that wraps message handling, like authorization, logging or metrics.
+///
+/// Middleware is registered per actor and message type with
+/// [`Context::add_middleware`](crate::Context::add_middleware). For every message, the
+/// [`before`](Self::before) hooks run in registration order before
+/// [`Handler::handle`](crate::Handler::handle), and once the result is available the
+/// [`after`](Self::after) hooks run in reverse order, before the result is delivered to the
+/// sender.
+///
+/// A `before` hook rejects a message by returning `Err` with a [`Rejection`](Self::Rejection),
+/// which is converted into the message result and delivered instead of calling the handler.
+/// Middleware that never rejects messages can use the message result as its rejection.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// #[derive(Message)]
+/// #[rtype(result = "Result<usize, &'static str>")]
+/// struct Query {
+///     token: &'static str,
+/// }
+///
+/// struct Auth;
+///
+/// impl<A: Actor> MessageMiddleware<A, Query> for Auth {
+///     type Rejection = Result<usize, &'static str>;
+///
+///     fn before(&mut self, _: &mut A, msg: &Query, _: &mut A::Context) -> Result<(), Self::Rejection> {
+///         // short-circuit unauthorized queries
+///         if msg.token == "secret" {
+///             Ok(())
+///         } else {
+///             Err(Err("unauthorized"))
+///         }
+///     }
+/// }
+///
+/// struct Db;
+///
+/// impl Actor for Db {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         ctx.add_middleware(Auth);
+///     }
+/// }
+///
+/// impl Handler<Query> for Db {
+///     type Result = Result<usize, &'static str>;
+///
+///     fn handle(&mut self, _: Query, _: &mut Context<Self>) -> Self::Result {
+///         Ok(42)
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let db = Db.start();
+///     assert_eq!(db.send(Query { token: "secret" }).await.unwrap(), Ok(42));
+///     assert_eq!(db.send(Query { token: "guess" }).await.unwrap(), Err("unauthorized"));
+/// }
+/// ```
+#[allow(unused_variables)]
+pub trait MessageMiddleware<A, M>: 'static
+where
+    A: Actor,
+    M: Message,
+{
+    /// The rejection returned by [`before`](Self::before), delivered as the response.
+    type Rejection: Into<M::Result>;
+
+    /// Called before the message is handled.
+    ///
+    /// Returning `Err(rejection)` skips the handler and the `before` hooks of middleware
+    /// registered later, and responds with the rejection instead. The `after` hooks of this and
+    /// earlier middleware still observe it.
+    fn before(
+        &mut self,
+        act: &mut A,
+        msg: &M,
+        ctx: &mut A::Context,
+    ) -> Result<(), Self::Rejection> {
+        Ok(())
+    }
+
+    /// Called with the result of the message, including a rejection, before it is delivered to
+    /// the sender.
+    ///
+    /// For asynchronous responses, this is called once the response resolved. If the actor
+    /// stops before that, the response is cancelled.
+    fn after(&mut self, act: &mut A, result: &M::Result, ctx: &mut A::Context) {}
+}
+
+/// [`MessageMiddleware`] with its rejection converted into the message result, so middleware
+/// with different rejection types can be chained.
+trait Hooks<A: Actor, M: Message> {
+    fn before(&mut self, act: &mut A, msg: &M, ctx: &mut A::Context) -> Option<M::Result>;
+
+    fn after(&mut self, act: &mut A, result: &M::Result, ctx: &mut A::Context);
+}
+
+impl<A, M, T> Hooks<A, M> for T
+where
+    A: Actor,
+    M: Message,
+    T: MessageMiddleware<A, M>,
+{
+    fn before(&mut self, act: &mut A, msg: &M, ctx: &mut A::Context) -> Option<M::Result> {
+        MessageMiddleware::before(self, act, msg, ctx)
+            .err()
+            .map(Into::into)
+    }
+
+    fn after(&mut self, act: &mut A, result: &M::Result, ctx: &mut A::Context) {
+        MessageMiddleware::after(self, act, result, ctx)
+    }
+}
+
+type Chain<A, M> = Vec<Box<dyn Hooks<A, M>>>;
+
+/// Middleware registered with an actor, by message type.
+pub(crate) struct Middlewares<A: Actor> {
+    chains: HashMap<TypeId, Box<dyn Any>>,
+    _act: std::marker::PhantomData<A>,
+}
+
+impl<A: Actor> Default for Middlewares<A> {
+    fn default() -> Self {
+        Middlewares {
+            chains: HashMap::new(),
+            _act: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<A: Actor> fmt::Debug for Middlewares<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Middlewares")
+            .field("messages", &self.chains.len())
+            .finish()
+    }
+}
+
+impl<A: Actor> Middlewares<A> {
+    pub(crate) fn add<M, T>(&mut self, middleware: T)
+    where
+        M: Message + 'static,
+        T: MessageMiddleware<A, M>,
+    {
+        self.chains
+            .entry(TypeId::of::<M>())
+            .or_insert_with(|| Box::<Chain<A, M>>::default())
+            .downcast_mut::<Chain<A, M>>()
+            .unwrap()
+            .push(Box::new(middleware));
+    }
+
+    /// Takes the chain for `M` out, so it can be called with access to the context.
+    fn take<M: Message + 'static>(&mut self) -> Option<Chain<A, M>> {
+        if self.chains.is_empty() {
+            return None;
+        }
+
+        self.chains
+            .remove(&TypeId::of::<M>())
+            .and_then(|chain| chain.downcast::<Chain<A, M>>().ok())
+            .map(|chain| *chain)
+    }
+
+    /// Puts a chain back, in front of middleware registered while it was taken out.
+    fn restore<M: Message + 'static>(&mut self, mut chain: Chain<A, M>) {
+        if let Some(added) = self.take::<M>() {
+            chain.extend(added);
+        }
+        self.chains.insert(TypeId::of::<M>(), Box::new(chain));
+    }
+}
+
+fn take_chain<A, M>(ctx: &mut A::Context) -> Option<Chain<A, M>>
+where
+    A: Actor,
+    A::Context: AsyncContextParts<A>,
+    M: Message + 'static,
+{
+    ctx.parts().middlewares().take::<M>()
+}
+
+fn restore_chain<A, M>(ctx: &mut A::Context, chain: Chain<A, M>)
+where
+    A: Actor,
+    A::Context: AsyncContextParts<A>,
+    M: Message + 'static,
+{
+    ctx.parts().middlewares().restore(chain);
+}
+
+/// Runs the `after` hooks of `chain` in reverse order and delivers the result.
+fn finish<A, M>(
+    chain: &mut [Box<dyn Hooks<A, M>>],
+    act: &mut A,
+    ctx: &mut A::Context,
+    result: M::Result,
+    tx: Option<OneshotSender<M::Result>>,
+) where
+    A: Actor,
+    M: Message + 'static,
+{
+    for middleware in chain.iter_mut().rev() {
+        middleware.after(act, &result, ctx);
+    }
+
+    if let Some(tx) = tx {
+        let _ = tx.send(result);
+    }
+}
+
+/// Handles `msg` through the middleware registered for `M`.
+///
+/// Returns the message back if there is no middleware for `M`.
+pub(crate) fn handle<A, M>(
+    act: &mut A,
+    msg: M,
+    ctx: &mut A::Context,
+    tx: Option<OneshotSender<M::Result>>,
+) -> Option<(M, Option<OneshotSender<M::Result>>)>
+where
+    A: Actor + Handler<M>,
+    A::Context: AsyncContextParts<A>,
+    M: Message + 'static,
+{
+    let mut chain = match take_chain::<A, M>(ctx) {
+        Some(chain) => chain,
+        None => return Some((msg, tx)),
+    };
+
+    for idx in 0..chain.len() {
+        if let Some(result) = chain[idx].before(act, &msg, ctx) {
+            finish(&mut chain[..=idx], act, ctx, result, tx);
+            restore_chain(ctx, chain);
+            return None;
+        }
+    }
+
+    let (res_tx, mut res_rx) = oneshot::channel();
+    memo::handle(act, msg, ctx, Some(res_tx));
+
+    match res_rx.try_recv() {
+        Ok(result) => {
+            finish(&mut chain, act, ctx, result, tx);
+            restore_chain(ctx, chain);
+        }
+        Err(TryRecvError::Empty) => {
+            restore_chain(ctx, chain);
+
+            let fut = wrap_future::<_, A>(res_rx).map(move |result, act, ctx| {
+                if let Ok(result) = result {
+                    match take_chain::<A, M>(ctx) {
+                        Some(mut chain) => {
+                            finish(&mut chain, act, ctx, result, tx);
+                            restore_chain(ctx, chain);
+                        }
+                        None => finish::<A, M>(&mut [], act, ctx, result, tx),
+                    }
+                }
+            });
+            ctx.spawn(forwarding(fut));
+        }
+        Err(TryRecvError::Closed) => restore_chain(ctx, chain),
+    }
+
+    None
+}
@@ -0,0 +1,142 @@
+use std::{
+    any::{type_name, Any},
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::stream::Stream;
+use log::warn;
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::actor::Actor;
+
+/// An actor emitting events to the observers attached to its address.
+///
+/// Events are emitted with [`Context::emit`](crate::Context::emit), and observed through the
+/// stream returned by [`Addr::observe`](crate::Addr::observe).
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+/// use futures_util::stream::StreamExt;
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Progress(u8);
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Download;
+///
+/// struct Downloader;
+///
+/// impl Actor for Downloader {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Observable for Downloader {
+///     type Event = Progress;
+/// }
+///
+/// impl Handler<Download> for Downloader {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Download, ctx: &mut Context<Self>) {
+///         ctx.emit(Progress(50));
+///         ctx.emit(Progress(100));
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let addr = Downloader.start();
+///     let mut progress = addr.observe();
+///
+///     addr.send(Download).await.unwrap();
+///     assert_eq!(progress.next().await, Some(Progress(50)));
+///     assert_eq!(progress.next().await, Some(Progress(100)));
+/// }
+/// ```
+pub trait Observable: Actor {
+    /// The type of the emitted events, which is cloned for every observer.
+    type Event: Clone + Send + 'static;
+}
+
+/// Stream of the events emitted by an [`Observable`] actor.
+///
+/// Created by [`Addr::observe`](crate::Addr::observe). Dropping the stream detaches the
+/// observer. The stream ends once the actor has stopped.
+pub struct Observer<E> {
+    rx: mpsc::Receiver<E>,
+}
+
+impl<E> fmt::Debug for Observer<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Observer")
+            .field("event", &type_name::<E>())
+            .finish()
+    }
+}
+
+impl<E> Stream for Observer<E> {
+    type Item = E;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<E>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Observers attached to an actor, shared by its addresses.
+///
+/// The senders are stored type-erased, as the event type is only known for observable actors.
+#[derive(Default)]
+pub(crate) struct Observers {
+    senders: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+impl Observers {
+    /// Attaches an observer buffering up to `capacity` events.
+    pub(crate) fn attach<E: Send + 'static>(&self, capacity: usize) -> Observer<E> {
+        let (tx, rx) = mpsc::channel(capacity);
+        let mut senders = self.senders.lock();
+        senders
+            .get_or_insert_with(|| Box::new(Vec::<mpsc::Sender<E>>::new()))
+            .downcast_mut::<Vec<mpsc::Sender<E>>>()
+            .expect("observers of a single event type")
+            .push(tx);
+        Observer { rx }
+    }
+
+    /// Sends `event` to every attached observer, returning the number of observers it was
+    /// delivered to.
+    pub(crate) fn emit<E: Clone + Send + 'static>(&self, event: E) -> usize {
+        let mut senders = self.senders.lock();
+        let senders = match senders
+            .as_mut()
+            .and_then(|senders| senders.downcast_mut::<Vec<mpsc::Sender<E>>>())
+        {
+            Some(senders) => senders,
+            None => return 0,
+        };
+
+        let mut delivered = 0;
+        senders.retain(|tx| match tx.try_send(event.clone()) {
+            Ok(()) => {
+                delivered += 1;
+                true
+            }
+            Err(TrySendError::Full(_)) => {
+                warn!("Observer of {} is full, event dropped", type_name::<E>());
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        });
+        delivered
+    }
+
+    /// Detaches all observers, ending their streams.
+    pub(crate) fn clear(&self) {
+        self.senders.lock().take();
+    }
+}
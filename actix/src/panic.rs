@@ -0,0 +1,99 @@
+use std::{
+    any::{type_name, Any},
+    cell::Cell,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    process,
+};
+
+use log::error;
+
+use crate::{
+    actor::{Actor, ActorContext, AsyncContext},
+    audit,
+    handler::Message,
+};
+
+thread_local! {
+    static POLICY: Cell<PanicPolicy> = const { Cell::new(PanicPolicy::Propagate) };
+}
+
+/// What happens when a handler panics, see [`set_panic_policy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Resumes the panic, which stops the arbiter the actor runs on.
+    #[default]
+    Propagate,
+    /// Terminates the panicking actor and keeps the arbiter running.
+    ///
+    /// Pending requests to the actor fail with [`MailboxError::Closed`](crate::MailboxError), and
+    /// a supervised actor is restarted.
+    Isolate,
+    /// Aborts the process.
+    Abort,
+}
+
+/// Sets what happens when a handler of an actor running on the current arbiter panics.
+///
+/// Whatever the policy, a panic while handling a message from the mailbox is logged as an
+/// error naming the actor's type and [`ActorId`](crate::ActorId) and the message type. The
+/// policy applies to the current thread; set it for another arbiter with
+/// [`Arbiter::spawn_fn`](crate::Arbiter::spawn_fn). Panics can only be caught when unwinding
+/// is enabled.
+///
+/// # Examples
+/// ```
+/// use actix::{prelude::*, PanicPolicy};
+///
+/// # #[actix::main] async fn main() {
+/// let arbiter = Arbiter::new();
+/// arbiter.spawn_fn(|| actix::set_panic_policy(PanicPolicy::Isolate));
+/// # arbiter.stop();
+/// # }
+/// ```
+pub fn set_panic_policy(policy: PanicPolicy) {
+    POLICY.with(|current| current.set(policy));
+}
+
+/// Returns the panic policy of the current arbiter.
+pub fn panic_policy() -> PanicPolicy {
+    POLICY.with(Cell::get)
+}
+
+/// Runs `handle` for a message of type `M`, applying the panic policy if it panics.
+pub(crate) fn guard<A, M, F>(act: &mut A, ctx: &mut A::Context, handle: F)
+where
+    A: Actor,
+    A::Context: AsyncContext<A>,
+    M: Message,
+    F: FnOnce(&mut A, &mut A::Context),
+{
+    let payload = match catch_unwind(AssertUnwindSafe(|| handle(act, ctx))) {
+        Ok(()) => return,
+        Err(payload) => payload,
+    };
+
+    error!(
+        "Actor {} ({}) panicked while handling {}: {}",
+        type_name::<A>(),
+        ctx.address().actor_id(),
+        type_name::<M>(),
+        describe(&*payload),
+    );
+    audit::log_trail();
+
+    match panic_policy() {
+        PanicPolicy::Propagate => resume_unwind(payload),
+        PanicPolicy::Isolate => ctx.terminate(),
+        PanicPolicy::Abort => process::abort(),
+    }
+}
+
+fn describe(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&'static str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "Box<dyn Any>"
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    error, fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{
+    address::{Addr, MailboxError, ToEnvelope},
+    handler::{Handler, Message},
+};
+
+/// Error of a [`Pipeline`] with fallible steps.
+///
+/// Mailbox errors of any step and handler errors of the fallible steps are kept apart, so the
+/// caller can tell a delivery failure from a rejected request. All fallible steps of a pipeline
+/// share the handler error type `E`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PipelineError<E> {
+    /// A message of the pipeline could not be delivered or answered.
+    Mailbox(MailboxError),
+
+    /// A handler of a fallible step returned an error.
+    Handler(E),
+}
+
+impl<E> PipelineError<E> {
+    /// Returns the handler error, if the pipeline failed on one.
+    pub fn into_handler(self) -> Option<E> {
+        match self {
+            PipelineError::Handler(err) => Some(err),
+            PipelineError::Mailbox(_) => None,
+        }
+    }
+}
+
+impl<E> From<MailboxError> for PipelineError<E> {
+    fn from(err: MailboxError) -> Self {
+        PipelineError::Mailbox(err)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for PipelineError<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::Mailbox(err) => write!(fmt, "PipelineError::Mailbox({:?})", err),
+            PipelineError::Handler(err) => write!(fmt, "PipelineError::Handler({:?})", err),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for PipelineError<E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::Mailbox(err) => fmt::Display::fmt(err, fmt),
+            PipelineError::Handler(err) => write!(fmt, "Handler failed: {}", err),
+        }
+    }
+}
+
+impl<E: error::Error + 'static> error::Error for PipelineError<E> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PipelineError::Mailbox(err) => Some(err),
+            PipelineError::Handler(err) => Some(err),
+        }
+    }
+}
+
+/// Chain of requests across actors, each sent with the result of the previous one.
+///
+/// A pipeline is started from a request with [`start`](Self::start), or with
+/// [`try_start`](Self::try_start) if its handler returns a `Result`, and extended with
+/// [`then_send`](Self::then_send) and [`then_try_send`](Self::then_try_send). Awaiting it runs
+/// the steps in order and resolves to the result of the last one, or to the first failure, in
+/// which case the remaining steps are not sent.
+///
+/// A pipeline of infallible steps fails with a [`MailboxError`]. The first fallible step turns
+/// the error type into [`PipelineError`], which carries the handler error as well.
+///
+/// ```
+/// # use actix::prelude::*;
+/// struct Parse(String);
+///
+/// impl Message for Parse {
+///     type Result = Result<u32, String>;
+/// }
+///
+/// struct Double(u32);
+///
+/// impl Message for Double {
+///     type Result = u32;
+/// }
+///
+/// struct Calc;
+///
+/// impl Actor for Calc {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Parse> for Calc {
+///     type Result = Result<u32, String>;
+///
+///     fn handle(&mut self, msg: Parse, _: &mut Context<Self>) -> Self::Result {
+///         msg.0.parse().map_err(|_| format!("not a number: {}", msg.0))
+///     }
+/// }
+///
+/// impl Handler<Double> for Calc {
+///     type Result = u32;
+///
+///     fn handle(&mut self, msg: Double, _: &mut Context<Self>) -> u32 {
+///         msg.0 * 2
+///     }
+/// }
+///
+/// # #[actix::main]
+/// # async fn main() {
+/// let parser = Calc.start();
+/// let doubler = Calc.start();
+///
+/// let res = Pipeline::try_start(parser.send(Parse("21".to_owned())))
+///     .then_send(move |_| doubler, Double)
+///     .await;
+/// assert_eq!(res, Ok(42));
+/// # }
+/// ```
+#[must_use = "pipelines do nothing unless awaited"]
+pub struct Pipeline<T, E> {
+    fut: Pin<Box<dyn Future<Output = Result<T, E>>>>,
+}
+
+impl<T: 'static> Pipeline<T, MailboxError> {
+    /// Starts a pipeline from `request`, usually the [`Request`](crate::dev::Request) returned
+    /// by [`Addr::send`].
+    pub fn start<F>(request: F) -> Self
+    where
+        F: Future<Output = Result<T, MailboxError>> + 'static,
+    {
+        Pipeline {
+            fut: Box::pin(request),
+        }
+    }
+}
+
+impl<T: 'static, E: 'static> Pipeline<T, PipelineError<E>> {
+    /// Starts a pipeline from `request` whose handler returns a `Result`, failing with
+    /// [`PipelineError::Handler`] if the handler does.
+    pub fn try_start<F>(request: F) -> Self
+    where
+        F: Future<Output = Result<Result<T, E>, MailboxError>> + 'static,
+    {
+        Pipeline {
+            fut: Box::pin(async move { request.await?.map_err(PipelineError::Handler) }),
+        }
+    }
+}
+
+impl<T: 'static, E: 'static> Pipeline<T, E> {
+    /// Sends the message built by `msg` to the actor returned by `addr`, both called with the
+    /// result of the previous step, and continues with the handler result.
+    pub fn then_send<A, M, FA, FM>(self, addr: FA, msg: FM) -> Pipeline<M::Result, E>
+    where
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+        FA: FnOnce(&T) -> Addr<A> + 'static,
+        FM: FnOnce(T) -> M + 'static,
+        E: From<MailboxError>,
+    {
+        Pipeline {
+            fut: Box::pin(async move {
+                let res = self.fut.await?;
+                let addr = addr(&res);
+                Ok(addr.send(msg(res)).await?)
+            }),
+        }
+    }
+
+    /// Like [`then_send`](Self::then_send), for a handler returning a `Result`.
+    ///
+    /// The handler error fails the pipeline with [`PipelineError::Handler`]. Once a pipeline
+    /// has a fallible step, the handler errors of all its later fallible steps must be of the
+    /// same type.
+    pub fn then_try_send<A, M, FA, FM, R, HE>(
+        self,
+        addr: FA,
+        msg: FM,
+    ) -> Pipeline<R, PipelineError<HE>>
+    where
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: Message<Result = Result<R, HE>> + Send + 'static,
+        R: Send + 'static,
+        HE: Send + 'static,
+        FA: FnOnce(&T) -> Addr<A> + 'static,
+        FM: FnOnce(T) -> M + 'static,
+        E: Into<PipelineError<HE>>,
+    {
+        Pipeline {
+            fut: Box::pin(async move {
+                let res = self.fut.await.map_err(Into::into)?;
+                let addr = addr(&res);
+                addr.send(msg(res)).await?.map_err(PipelineError::Handler)
+            }),
+        }
+    }
+}
+
+impl<T, E> Future for Pipeline<T, E> {
+    type Output = Result<T, E>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.fut.as_mut().poll(cx)
+    }
+}
+
+impl<T, E> fmt::Debug for Pipeline<T, E> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Pipeline").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{
+    actor::Actor,
+    address::{Addr, MailboxError, Request, ToEnvelope},
+    handler::{Handler, Message},
+};
+
+/// A fixed group of actors of the same type, for scattering a request across all of them.
+///
+/// A pool is typically made of workers that each own a part of the data, e.g. sync actors
+/// started on their own [`SyncArbiter`](crate::SyncArbiter), so that a query is answered by
+/// every worker in parallel and the replies are combined by the sender.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// #[derive(Clone, Message)]
+/// #[rtype(result = "usize")]
+/// struct Count(char);
+///
+/// /// Owns a shard of the words.
+/// struct Shard(Vec<&'static str>);
+///
+/// impl Actor for Shard {
+///     type Context = SyncContext<Self>;
+/// }
+///
+/// impl Handler<Count> for Shard {
+///     type Result = usize;
+///
+///     fn handle(&mut self, Count(ch): Count, _: &mut SyncContext<Self>) -> usize {
+///         self.0.iter().filter(|word| word.starts_with(ch)).count()
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let shards = [vec!["apple", "banana"], vec!["avocado"], vec!["cherry"]];
+///     let pool: Pool<Shard> = shards
+///         .into_iter()
+///         .map(|words| SyncArbiter::start(1, move || Shard(words.clone())))
+///         .collect();
+///
+///     let counts = pool.broadcast_collect(Count('a')).await;
+///     let total: usize = counts.into_iter().map(Result::unwrap).sum();
+///     assert_eq!(total, 2);
+/// }
+/// ```
+pub struct Pool<A: Actor> {
+    workers: Vec<Addr<A>>,
+}
+
+impl<A: Actor> Pool<A> {
+    /// Creates a pool of the actors at `workers`.
+    pub fn new(workers: Vec<Addr<A>>) -> Self {
+        Pool { workers }
+    }
+
+    /// Returns the addresses of the workers, in the order replies are collected in.
+    pub fn workers(&self) -> &[Addr<A>] {
+        &self.workers
+    }
+
+    /// Returns the number of workers.
+    pub fn len(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Returns `true` if the pool has no workers.
+    pub fn is_empty(&self) -> bool {
+        self.workers.is_empty()
+    }
+
+    /// Sends a clone of `msg` to every worker and collects their replies.
+    ///
+    /// The returned future resolves once every worker has replied or failed to, to a reply per
+    /// worker in the order of [`workers`](Self::workers). A worker that fails to reply, e.g.
+    /// because it has stopped, is reported by its [`MailboxError`] without failing the others.
+    pub fn broadcast_collect<M>(&self, msg: M) -> BroadcastCollect<A, M>
+    where
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: Message + Clone + Send + 'static,
+        M::Result: Send,
+    {
+        let replies = self
+            .workers
+            .iter()
+            .map(|addr| Reply::Pending(Box::pin(addr.send(msg.clone()))))
+            .collect();
+        BroadcastCollect { replies }
+    }
+}
+
+impl<A: Actor> FromIterator<Addr<A>> for Pool<A> {
+    fn from_iter<I: IntoIterator<Item = Addr<A>>>(iter: I) -> Self {
+        Pool::new(iter.into_iter().collect())
+    }
+}
+
+impl<A: Actor> Clone for Pool<A> {
+    fn clone(&self) -> Self {
+        Pool::new(self.workers.clone())
+    }
+}
+
+impl<A: Actor> fmt::Debug for Pool<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Pool")
+            .field("workers", &self.workers.len())
+            .finish()
+    }
+}
+
+enum Reply<A, M>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    Pending(Pin<Box<Request<A, M>>>),
+    Done(Result<M::Result, MailboxError>),
+    Taken,
+}
+
+/// Future returned by [`Pool::broadcast_collect`], resolving to the reply of every worker.
+#[must_use = "futures do nothing unless polled"]
+pub struct BroadcastCollect<A, M>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    replies: Vec<Reply<A, M>>,
+}
+
+// the requests are boxed, nothing is pinned in place
+impl<A, M> Unpin for BroadcastCollect<A, M>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+}
+
+impl<A, M> Future for BroadcastCollect<A, M>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    type Output = Vec<Result<M::Result, MailboxError>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let mut pending = false;
+        for reply in &mut this.replies {
+            if let Reply::Pending(req) = reply {
+                match req.as_mut().poll(cx) {
+                    Poll::Ready(res) => *reply = Reply::Done(res),
+                    Poll::Pending => pending = true,
+                }
+            }
+        }
+        if pending {
+            return Poll::Pending;
+        }
+
+        let replies = this
+            .replies
+            .iter_mut()
+            .map(|reply| match mem::replace(reply, Reply::Taken) {
+                Reply::Done(res) => res,
+                _ => panic!("BroadcastCollect polled after completion"),
+            })
+            .collect();
+        Poll::Ready(replies)
+    }
+}
+
+impl<A, M> fmt::Debug for BroadcastCollect<A, M>
+where
+    A: Handler<M>,
+    A::Context: ToEnvelope<A, M>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pending = self
+            .replies
+            .iter()
+            .filter(|reply| matches!(reply, Reply::Pending(_)))
+            .count();
+        fmt.debug_struct("BroadcastCollect")
+            .field("workers", &self.replies.len())
+            .field("pending", &pending)
+            .finish()
+    }
+}
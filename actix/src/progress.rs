@@ -0,0 +1,120 @@
+use std::{
+    fmt,
+    future::{poll_fn, Future},
+    pin::Pin,
+    task::Poll,
+};
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    address::{Addr, MailboxError, ToEnvelope},
+    handler::{Handler, Message},
+};
+
+/// Message `M` sent with [`Addr::send_with_progress`], carrying a [`ProgressResponder`] for
+/// reporting progress of type `P` before the final result.
+///
+/// The actor handles it with a `Handler<WithProgress<M, P>>`, which takes the responder out
+/// with [`into_parts`](Self::into_parts) and finishes it once the operation is done, usually
+/// from a spawned future.
+pub struct WithProgress<M: Message, P> {
+    msg: M,
+    responder: ProgressResponder<P, M::Result>,
+}
+
+impl<M: Message, P> WithProgress<M, P> {
+    /// Returns the message and the responder for its progress and final result.
+    pub fn into_parts(self) -> (M, ProgressResponder<P, M::Result>) {
+        (self.msg, self.responder)
+    }
+}
+
+impl<M: Message, P> Message for WithProgress<M, P> {
+    type Result = ();
+}
+
+impl<M: Message + fmt::Debug, P> fmt::Debug for WithProgress<M, P> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("WithProgress")
+            .field("msg", &self.msg)
+            .finish()
+    }
+}
+
+/// Reports progress of type `P` to the sender of a request, then its final result of type
+/// `R`, see [`Addr::send_with_progress`].
+///
+/// Dropping the responder without calling [`finish`](Self::finish) fails the request with
+/// [`MailboxError::NoResponse`], after the progress reported so far has been delivered.
+pub struct ProgressResponder<P, R> {
+    progress: mpsc::UnboundedSender<P>,
+    tx: oneshot::Sender<R>,
+}
+
+impl<P, R> ProgressResponder<P, R> {
+    /// Reports `progress` to the sender of the request.
+    pub fn progress(&self, progress: P) {
+        let _ = self.progress.send(progress);
+    }
+
+    /// Sends the final result to the sender of the request, resolving the request.
+    pub fn finish(self, res: R) {
+        let _ = self.tx.send(res);
+    }
+
+    /// Returns `true` if the sender does not wait for the result anymore.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+impl<P, R> fmt::Debug for ProgressResponder<P, R> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ProgressResponder")
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+/// Sends `msg` to `addr` wrapped in a [`WithProgress`], see [`Addr::send_with_progress`].
+pub(crate) fn send<A, M, P, F>(
+    addr: &Addr<A>,
+    msg: M,
+    mut on_progress: F,
+) -> impl Future<Output = Result<M::Result, MailboxError>>
+where
+    A: Handler<WithProgress<M, P>>,
+    A::Context: ToEnvelope<A, WithProgress<M, P>>,
+    M: Message + Send + 'static,
+    M::Result: Send,
+    P: Send + 'static,
+    F: FnMut(P),
+{
+    let (progress, mut progress_rx) = mpsc::unbounded_channel();
+    let (tx, mut rx) = oneshot::channel();
+    let sent = addr.send(WithProgress {
+        msg,
+        responder: ProgressResponder { progress, tx },
+    });
+
+    async move {
+        sent.await?;
+        poll_fn(|cx| {
+            while let Poll::Ready(Some(progress)) = progress_rx.poll_recv(cx) {
+                on_progress(progress);
+            }
+
+            let res = match Pin::new(&mut rx).poll(cx) {
+                Poll::Ready(res) => res,
+                Poll::Pending => return Poll::Pending,
+            };
+            // progress reported right before finishing is delivered first
+            while let Ok(progress) = progress_rx.try_recv() {
+                on_progress(progress);
+            }
+            Poll::Ready(res.map_err(|_| MailboxError::NoResponse))
+        })
+        .await
+    }
+}
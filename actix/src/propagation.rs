@@ -0,0 +1,212 @@
+use std::{
+    any::Any,
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use once_cell::sync::OnceCell;
+use pin_project_lite::pin_project;
+
+use crate::{actor::Actor, fut::ActorFuture};
+
+static PROPAGATOR: OnceCell<Box<dyn Propagate>> = OnceCell::new();
+
+/// Carrying a context, like a tracing span, across the async boundaries of actors.
+///
+/// Once installed with [`set_context_propagator`], the context current on the calling thread
+/// is captured when a message is sent, and entered while the message is handled. Likewise, it
+/// is captured when a future is spawned into a [`Context`](crate::Context), e.g. with
+/// [`spawn`](crate::AsyncContext::spawn), [`wait`](crate::AsyncContext::wait) or
+/// [`notify`](crate::AsyncContext::notify), and entered whenever the future is polled. Work
+/// done on behalf of a message, by the receiving actor or by the actors it sends messages to,
+/// thus runs in the context of the message.
+///
+/// # Examples
+/// Propagating a request id kept in a thread local:
+/// ```
+/// use std::cell::Cell;
+///
+/// use actix::{prelude::*, ContextPropagator};
+///
+/// thread_local! {
+///     static REQUEST_ID: Cell<Option<u64>> = const { Cell::new(None) };
+/// }
+///
+/// struct RequestIds;
+///
+/// /// Restores the previous request id once dropped.
+/// struct Restore(Option<u64>);
+///
+/// impl Drop for Restore {
+///     fn drop(&mut self) {
+///         REQUEST_ID.with(|id| id.set(self.0));
+///     }
+/// }
+///
+/// impl ContextPropagator for RequestIds {
+///     type Context = u64;
+///     type Guard = Restore;
+///
+///     fn capture(&self) -> Option<u64> {
+///         REQUEST_ID.with(Cell::get)
+///     }
+///
+///     fn enter(&self, id: &u64) -> Restore {
+///         Restore(REQUEST_ID.with(|current| current.replace(Some(*id))))
+///     }
+/// }
+///
+/// #[derive(Message)]
+/// #[rtype(result = "Option<u64>")]
+/// struct WhoAsks;
+///
+/// struct MyActor;
+///
+/// impl Actor for MyActor {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<WhoAsks> for MyActor {
+///     type Result = Option<u64>;
+///
+///     fn handle(&mut self, _: WhoAsks, _: &mut Context<Self>) -> Option<u64> {
+///         REQUEST_ID.with(Cell::get)
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     actix::set_context_propagator(RequestIds).unwrap();
+///
+///     let addr = MyActor.start();
+///     let req = {
+///         let _request = RequestIds.enter(&42);
+///         addr.send(WhoAsks)
+///     };
+///     assert_eq!(req.await.unwrap(), Some(42));
+/// }
+/// ```
+pub trait ContextPropagator: Send + Sync + 'static {
+    /// The captured context, e.g. a tracing span.
+    type Context: Send + 'static;
+
+    /// Guard keeping a context entered until it is dropped.
+    type Guard;
+
+    /// Captures the context current on the calling thread, if any.
+    fn capture(&self) -> Option<Self::Context>;
+
+    /// Enters `cx` on the calling thread, until the returned guard is dropped.
+    fn enter(&self, cx: &Self::Context) -> Self::Guard;
+}
+
+/// Installs the [`ContextPropagator`] of the process.
+///
+/// The propagator can only be installed once, later calls fail with
+/// [`SetPropagatorError`]. Until it is installed, nothing is captured.
+pub fn set_context_propagator<P: ContextPropagator>(
+    propagator: P,
+) -> Result<(), SetPropagatorError> {
+    PROPAGATOR
+        .set(Box::new(propagator))
+        .map_err(|_| SetPropagatorError(()))
+}
+
+/// Error returned by [`set_context_propagator`] if a propagator is already installed.
+#[derive(Debug)]
+pub struct SetPropagatorError(());
+
+impl fmt::Display for SetPropagatorError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "A context propagator is already installed")
+    }
+}
+
+impl std::error::Error for SetPropagatorError {}
+
+/// [`ContextPropagator`] with its types erased.
+trait Propagate: Send + Sync {
+    fn capture(&self) -> Option<Box<dyn Any + Send>>;
+
+    fn scope(&self, cx: &(dyn Any + Send), f: &mut dyn FnMut());
+}
+
+impl<P: ContextPropagator> Propagate for P {
+    fn capture(&self) -> Option<Box<dyn Any + Send>> {
+        ContextPropagator::capture(self).map(|cx| Box::new(cx) as Box<dyn Any + Send>)
+    }
+
+    fn scope(&self, cx: &(dyn Any + Send), f: &mut dyn FnMut()) {
+        match cx.downcast_ref::<P::Context>() {
+            Some(cx) => {
+                let _guard = self.enter(cx);
+                f();
+            }
+            None => f(),
+        }
+    }
+}
+
+/// A context captured by the installed [`ContextPropagator`].
+pub(crate) struct Captured(Box<dyn Any + Send>);
+
+impl Captured {
+    /// Runs `f` within the captured context.
+    pub(crate) fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        let Some(propagator) = PROPAGATOR.get() else {
+            return f();
+        };
+
+        let mut f = Some(f);
+        let mut res = None;
+        propagator.scope(&*self.0, &mut || res = f.take().map(|f| f()));
+        res.expect("context propagator did not run the scope")
+    }
+}
+
+/// Captures the context current on the calling thread, if a propagator is installed.
+#[inline]
+pub(crate) fn capture() -> Option<Captured> {
+    PROPAGATOR.get()?.capture().map(Captured)
+}
+
+pin_project! {
+    /// A future entering the context it was spawned in whenever it is polled.
+    pub(crate) struct Propagated<F> {
+        #[pin]
+        fut: F,
+        cx: Captured,
+    }
+}
+
+impl<F> Propagated<F> {
+    pub(crate) fn new(fut: F, cx: Captured) -> Self {
+        Propagated { fut, cx }
+    }
+}
+
+impl<A, F> ActorFuture<A> for Propagated<F>
+where
+    A: Actor,
+    F: ActorFuture<A>,
+{
+    type Output = F::Output;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        srv: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let this = self.project();
+        let fut = this.fut;
+        this.cx.scope(|| fut.poll(srv, ctx, task))
+    }
+
+    fn cancel(self: Pin<&mut Self>, srv: &mut A, ctx: &mut A::Context) {
+        let this = self.project();
+        let fut = this.fut;
+        this.cx.scope(|| fut.cancel(srv, ctx))
+    }
+}
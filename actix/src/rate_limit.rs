@@ -0,0 +1,131 @@
+use std::{cell::RefCell, fmt, rc::Rc, time::Duration};
+
+use crate::{
+    actor::Actor,
+    clock::{sleep_until, Instant},
+    fut::{wrap_future, ActorFuture},
+};
+
+/// Parameters of a [`RateLimiter`].
+///
+/// Tokens are refilled continuously at `tokens` per `period`, up to a burst capacity, which
+/// defaults to `tokens`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    per_sec: f64,
+    burst: u32,
+}
+
+impl Rate {
+    /// Allows `tokens` operations per `period`.
+    ///
+    /// # Panics
+    /// Panics if `tokens` is zero or `period` is zero.
+    pub fn new(tokens: u32, period: Duration) -> Self {
+        assert!(tokens > 0, "rate must allow at least one token");
+        assert!(!period.is_zero(), "rate period must not be zero");
+
+        Rate {
+            per_sec: f64::from(tokens) / period.as_secs_f64(),
+            burst: tokens,
+        }
+    }
+
+    /// Allows `tokens` operations per second.
+    pub fn per_second(tokens: u32) -> Self {
+        Self::new(tokens, Duration::from_secs(1))
+    }
+
+    /// Sets how many tokens can be accumulated while the limiter is unused.
+    ///
+    /// # Panics
+    /// Panics if `burst` is zero.
+    pub fn burst(mut self, burst: u32) -> Self {
+        assert!(burst > 0, "burst must allow at least one token");
+        self.burst = burst;
+        self
+    }
+}
+
+struct Bucket {
+    rate: Rate,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate.per_sec).min(f64::from(self.rate.burst));
+        self.updated = now;
+    }
+}
+
+/// Token bucket rate limiter, local to an actor.
+///
+/// Created with [`Context::rate_limiter`](crate::Context::rate_limiter). Clones share the same
+/// bucket. The limiter starts full, and follows the [`clock`](crate::clock), so it can be
+/// tested deterministically with a paused clock.
+#[derive(Clone)]
+pub struct RateLimiter {
+    bucket: Rc<RefCell<Bucket>>,
+}
+
+impl RateLimiter {
+    /// Creates a new, full rate limiter.
+    pub fn new(rate: Rate) -> Self {
+        RateLimiter {
+            bucket: Rc::new(RefCell::new(Bucket {
+                rate,
+                tokens: f64::from(rate.burst),
+                updated: Instant::now(),
+            })),
+        }
+    }
+
+    /// Returns the parameters of this limiter.
+    pub fn rate(&self) -> Rate {
+        self.bucket.borrow().rate
+    }
+
+    /// Takes a token if one is available, without waiting.
+    pub fn try_acquire(&self) -> bool {
+        let mut bucket = self.bucket.borrow_mut();
+        bucket.refill(Instant::now());
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes a token, resolving once it is available.
+    ///
+    /// The token is reserved right away, so concurrent callers are served in the order they
+    /// called this method.
+    pub fn acquire<A: Actor>(&self) -> impl ActorFuture<A, Output = ()> {
+        let mut bucket = self.bucket.borrow_mut();
+        let now = Instant::now();
+        bucket.refill(now);
+        bucket.tokens -= 1.0;
+
+        let deadline = if bucket.tokens >= 0.0 {
+            now
+        } else {
+            now + Duration::from_secs_f64(-bucket.tokens / bucket.rate.per_sec)
+        };
+        wrap_future(sleep_until(deadline))
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bucket = self.bucket.borrow();
+        fmt.debug_struct("RateLimiter")
+            .field("rate", &bucket.rate)
+            .field("tokens", &bucket.tokens)
+            .finish()
+    }
+}
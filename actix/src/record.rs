@@ -0,0 +1,284 @@
+//! Recording and replaying of the messages delivered to actors.
+//!
+//! A [`Recorder`] is attached to an actor per message type with
+//! [`Context::record`](crate::Context::record). It captures every message of that type as it is
+//! handled, in delivery order and tagged with the [`ActorId`] of the receiving actor. The
+//! resulting [`Recording`] can be serialized, e.g. to reproduce a bug later, and fed back into
+//! an actor's mailbox with [`Recording::replay`].
+//!
+//! Messages are identified in a recording by their type name, so a recording can only be
+//! replayed by a build that has the same message types.
+//!
+//! # Examples
+//! ```
+//! use actix::{prelude::*, record::Recorder};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Message, Serialize, Deserialize)]
+//! #[rtype(result = "()")]
+//! struct Add(usize);
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "usize")]
+//! struct Total;
+//!
+//! struct Counter {
+//!     total: usize,
+//!     recorder: Option<Recorder>,
+//! }
+//!
+//! impl Actor for Counter {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         if let Some(recorder) = &self.recorder {
+//!             ctx.record::<Add>(recorder);
+//!         }
+//!     }
+//! }
+//!
+//! impl Handler<Add> for Counter {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, Add(n): Add, _: &mut Context<Self>) {
+//!         self.total += n;
+//!     }
+//! }
+//!
+//! impl Handler<Total> for Counter {
+//!     type Result = usize;
+//!
+//!     fn handle(&mut self, _: Total, _: &mut Context<Self>) -> usize {
+//!         self.total
+//!     }
+//! }
+//!
+//! #[actix::main]
+//! async fn main() {
+//!     let recorder = Recorder::new();
+//!     let counter = Counter { total: 0, recorder: Some(recorder.clone()) }.start();
+//!     counter.send(Add(2)).await.unwrap();
+//!     counter.send(Add(3)).await.unwrap();
+//!
+//!     let recording = recorder.recording();
+//!     let actor = recording.actors()[0];
+//!
+//!     let replayed = Counter { total: 0, recorder: None }.start();
+//!     let sent = recording.replay(actor, &replayed).message::<Add>().run().unwrap();
+//!     assert_eq!(sent, 2);
+//!     assert_eq!(replayed.send(Total).await.unwrap(), 5);
+//! }
+//! ```
+
+use std::{any::type_name, collections::HashMap, error::Error, fmt, sync::Arc};
+
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    actor::Actor,
+    address::{Addr, ToEnvelope},
+    directory::ActorId,
+    handler::{Handler, Message},
+    middleware::MessageMiddleware,
+};
+
+/// A message that can be recorded and replayed.
+///
+/// This is implemented for all messages that can be serialized and deserialized.
+pub trait RecordableMessage: Message + Serialize + DeserializeOwned + Send + 'static {}
+
+impl<M> RecordableMessage for M where M: Message + Serialize + DeserializeOwned + Send + 'static {}
+
+/// A message captured by a [`Recorder`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// The actor that handled the message.
+    pub actor: ActorId,
+    /// Type name of the message.
+    pub message: String,
+    /// The serialized message.
+    pub payload: Value,
+}
+
+/// A log of recorded messages, in the order they were handled.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    /// The recorded messages.
+    pub messages: Vec<RecordedMessage>,
+}
+
+impl Recording {
+    /// Returns the actors that handled recorded messages, in order of their first message.
+    pub fn actors(&self) -> Vec<ActorId> {
+        let mut actors = Vec::new();
+        for msg in &self.messages {
+            if !actors.contains(&msg.actor) {
+                actors.push(msg.actor);
+            }
+        }
+        actors
+    }
+
+    /// Starts replaying the messages handled by `actor` into the mailbox of `addr`.
+    ///
+    /// The message types to replay are registered with [`Replay::message`].
+    pub fn replay<A: Actor>(&self, actor: ActorId, addr: &Addr<A>) -> Replay<'_, A> {
+        Replay {
+            recording: self,
+            actor,
+            addr: addr.clone(),
+            decoders: HashMap::new(),
+        }
+    }
+}
+
+/// Shared, thread-safe sink of recorded messages.
+///
+/// Clones record into the same log.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    messages: Arc<Mutex<Vec<RecordedMessage>>>,
+}
+
+impl Recorder {
+    /// Creates an empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a snapshot of the messages recorded so far.
+    pub fn recording(&self) -> Recording {
+        Recording {
+            messages: self.messages.lock().clone(),
+        }
+    }
+
+    /// Takes the messages recorded so far, leaving the recorder empty.
+    pub fn take(&self) -> Recording {
+        Recording {
+            messages: std::mem::take(&mut *self.messages.lock()),
+        }
+    }
+}
+
+/// Middleware recording the messages of one actor.
+pub(crate) struct Record {
+    pub(crate) recorder: Recorder,
+    pub(crate) actor: ActorId,
+}
+
+impl<A, M> MessageMiddleware<A, M> for Record
+where
+    A: Actor,
+    M: RecordableMessage,
+{
+    type Rejection = M::Result;
+
+    fn before(&mut self, _: &mut A, msg: &M, _: &mut A::Context) -> Result<(), M::Result> {
+        match serde_json::to_value(msg) {
+            Ok(payload) => self.recorder.messages.lock().push(RecordedMessage {
+                actor: self.actor,
+                message: type_name::<M>().to_owned(),
+                payload,
+            }),
+            Err(err) => log::warn!("Can not record {}: {}", type_name::<M>(), err),
+        }
+        Ok(())
+    }
+}
+
+type Deliver<A> = Box<dyn FnOnce(&Addr<A>)>;
+type Decoder<A> = Box<dyn Fn(Value) -> Result<Deliver<A>, serde_json::Error>>;
+
+/// Replay of recorded messages, created with [`Recording::replay`].
+#[must_use = "nothing is replayed until `run` is called"]
+pub struct Replay<'a, A: Actor> {
+    recording: &'a Recording,
+    actor: ActorId,
+    addr: Addr<A>,
+    decoders: HashMap<&'static str, Decoder<A>>,
+}
+
+impl<'a, A: Actor> Replay<'a, A> {
+    /// Replays recorded messages of type `M`.
+    pub fn message<M>(mut self) -> Self
+    where
+        A: Handler<M>,
+        A::Context: ToEnvelope<A, M>,
+        M: RecordableMessage,
+        M::Result: Send,
+    {
+        self.decoders.insert(
+            type_name::<M>(),
+            Box::new(|payload| {
+                let msg = serde_json::from_value::<M>(payload)?;
+                Ok(Box::new(move |addr: &Addr<A>| addr.do_send(msg)) as Deliver<A>)
+            }),
+        );
+        self
+    }
+
+    /// Sends the recorded messages to the actor, in their recorded order.
+    ///
+    /// Returns the number of messages sent. Fails, before sending anything, if the recording
+    /// contains a message of a type that was not registered or can not be deserialized.
+    pub fn run(self) -> Result<usize, ReplayError> {
+        // decode everything first, so that a bad recording is not partially replayed
+        let mut messages = Vec::new();
+        for msg in &self.recording.messages {
+            if msg.actor != self.actor {
+                continue;
+            }
+
+            let decoder = self
+                .decoders
+                .get(msg.message.as_str())
+                .ok_or_else(|| ReplayError::Unregistered(msg.message.clone()))?;
+            messages.push(decoder(msg.payload.clone()).map_err(ReplayError::Decode)?);
+        }
+
+        let count = messages.len();
+        for send in messages {
+            send(&self.addr);
+        }
+        Ok(count)
+    }
+}
+
+impl<A: Actor> fmt::Debug for Replay<'_, A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Replay")
+            .field("actor", &self.actor)
+            .field("messages", &self.decoders.keys())
+            .finish()
+    }
+}
+
+/// Error returned by [`Replay::run`].
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The recording contains a message type that was not registered with [`Replay::message`].
+    Unregistered(String),
+    /// A recorded message could not be deserialized.
+    Decode(serde_json::Error),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Unregistered(name) => write!(fmt, "Message type not registered: {}", name),
+            ReplayError::Decode(err) => write!(fmt, "Can not decode recorded message: {}", err),
+        }
+    }
+}
+
+impl Error for ReplayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReplayError::Unregistered(_) => None,
+            ReplayError::Decode(err) => Some(err),
+        }
+    }
+}
@@ -3,22 +3,30 @@
 //! An Actor can register itself as a service. A Service can be defined as an
 //! `ArbiterService`, which is unique per arbiter, or a `SystemService`, which
 //! is unique per system.
+//!
+//! Actors can also register under a hierarchical [`ActorPath`] in the
+//! [`PathRegistry`] of their system.
 use std::{
-    any::{Any, TypeId},
+    any::{type_name, Any, TypeId},
     cell::RefCell,
     collections::HashMap,
     default::Default,
+    error, fmt,
     rc::Rc,
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use actix_rt::{ArbiterHandle, System};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
+use tokio::sync::oneshot;
 
 use crate::{
-    actor::{Actor, Supervised},
-    address::Addr,
+    actor::{Actor, ActorState, Supervised},
+    address::{Addr, Recipient, WeakRecipient},
     context::Context,
+    handler::Message,
     supervisor::Supervisor,
 };
 
@@ -91,11 +99,15 @@ pub struct Registry {
 }
 
 thread_local! {
-    static AREG: Registry = {
-        Registry {
-            registry: Rc::new(RefCell::new(AnyMap::new()))
-        }
-    };
+    /// Arbiter registries of the current thread, keyed by system id.
+    static AREG: RefCell<HashMap<usize, Rc<RefCell<AnyMap>>>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the registry of the current arbiter, separate for every system on this thread.
+fn arbiter_registry() -> Registry {
+    let system = System::current().id();
+    let registry = AREG.with(|areg| Rc::clone(areg.borrow_mut().entry(system).or_default()));
+    Registry { registry }
 }
 
 /// Trait defines arbiter's service.
@@ -115,7 +127,7 @@ pub trait ArbiterService: Actor<Context = Context<Self>> + Supervised + Default
 
     /// Get actor's address from arbiter registry
     fn from_registry() -> Addr<Self> {
-        AREG.with(|reg| reg.get())
+        arbiter_registry().get()
     }
 }
 
@@ -151,16 +163,15 @@ impl Registry {
 
     /// Add new actor to the registry by address, panic if actor is already running
     pub fn set<A: ArbiterService + Actor<Context = Context<A>>>(addr: Addr<A>) {
-        AREG.with(|reg| {
-            let id = TypeId::of::<A>();
-            if let Some(addr) = reg.registry.borrow().get(&id) {
-                if addr.downcast_ref::<Addr<A>>().is_some() {
-                    panic!("Actor already started");
-                }
+        let reg = arbiter_registry();
+        let id = TypeId::of::<A>();
+        if let Some(addr) = reg.registry.borrow().get(&id) {
+            if addr.downcast_ref::<Addr<A>>().is_some() {
+                panic!("Actor already started");
             }
+        }
 
-            reg.registry.borrow_mut().insert(id, Box::new(addr));
-        })
+        reg.registry.borrow_mut().insert(id, Box::new(addr));
     }
 }
 
@@ -221,7 +232,31 @@ impl Registry {
 #[derive(Debug)]
 pub struct SystemRegistry {
     system: ArbiterHandle,
-    registry: HashMap<TypeId, Box<dyn Any + Send>>,
+    registry: HashMap<TypeId, Box<dyn Service>>,
+}
+
+/// Type-erased address of a system service.
+trait Service: Send + fmt::Debug {
+    fn as_any(&self) -> &dyn Any;
+
+    fn type_name(&self) -> &'static str;
+
+    /// Asks the service for its execution state, see [`probe_services`].
+    fn probe(&self) -> Option<oneshot::Receiver<ActorState>>;
+}
+
+impl<A: SystemService> Service for Addr<A> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        type_name::<A>()
+    }
+
+    fn probe(&self) -> Option<oneshot::Receiver<ActorState>> {
+        self.probe_state()
+    }
 }
 
 static SREG: Lazy<Mutex<HashMap<usize, SystemRegistry>>> = Lazy::new(|| Mutex::new(HashMap::new()));
@@ -244,6 +279,7 @@ pub trait SystemService: Actor<Context = Context<Self>> + Supervised + Default {
     /// Get actor's address from system registry
     fn from_registry() -> Addr<Self> {
         let sys = System::current();
+        crate::system::track(&sys);
 
         let mut sreg = SREG.lock();
         let reg = sreg
@@ -251,7 +287,7 @@ pub trait SystemService: Actor<Context = Context<Self>> + Supervised + Default {
             .or_insert_with(|| SystemRegistry::new(sys.arbiter().clone()));
 
         if let Some(addr) = reg.registry.get(&TypeId::of::<Self>()) {
-            if let Some(addr) = addr.downcast_ref::<Addr<Self>>() {
+            if let Some(addr) = addr.as_any().downcast_ref::<Addr<Self>>() {
                 return addr.clone();
             }
         }
@@ -263,6 +299,130 @@ pub trait SystemService: Actor<Context = Context<Self>> + Supervised + Default {
     }
 }
 
+/// Identifies a [`SystemService`] that an actor requires, see [`Actor::requires`].
+#[derive(Clone, Copy)]
+pub struct ServiceId {
+    type_id: fn() -> TypeId,
+    name: fn() -> &'static str,
+    requires: fn() -> &'static [ServiceId],
+    start: fn(),
+}
+
+impl ServiceId {
+    /// Returns the id of the service `S`.
+    pub const fn of<S: SystemService>() -> Self {
+        ServiceId {
+            type_id: TypeId::of::<S>,
+            name: type_name::<S>,
+            requires: <S as Actor>::requires,
+            start: start_service::<S>,
+        }
+    }
+
+    /// Returns the type name of the service.
+    pub fn name(&self) -> &'static str {
+        (self.name)()
+    }
+}
+
+impl PartialEq for ServiceId {
+    fn eq(&self, other: &Self) -> bool {
+        (self.type_id)() == (other.type_id)()
+    }
+}
+
+impl Eq for ServiceId {}
+
+impl fmt::Debug for ServiceId {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("ServiceId").field(&self.name()).finish()
+    }
+}
+
+fn start_service<S: SystemService>() {
+    S::from_registry();
+}
+
+/// Error returned when an actor is started while a service it requires can not be provided.
+///
+/// See [`Actor::requires`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingDependency {
+    /// Type name of the service that can not be provided.
+    pub service: &'static str,
+    /// The services requiring each other in a cycle, starting and ending with `service`.
+    ///
+    /// Empty if the service can not be provided because no system is running.
+    pub cycle: Vec<&'static str>,
+}
+
+impl fmt::Display for MissingDependency {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.cycle.is_empty() {
+            write!(
+                fmt,
+                "Can not provide service {}: no system is running",
+                self.service
+            )
+        } else {
+            write!(
+                fmt,
+                "Can not provide service {}: circular dependency {}",
+                self.service,
+                self.cycle.join(" -> ")
+            )
+        }
+    }
+}
+
+impl error::Error for MissingDependency {}
+
+/// Starts the services required by the actor `A`, along with the services they require in
+/// turn.
+pub(crate) fn provide_services<A: Actor>() -> Result<(), MissingDependency> {
+    let requires = A::requires();
+    if requires.is_empty() {
+        return Ok(());
+    }
+
+    let mut path = vec![(TypeId::of::<A>(), type_name::<A>())];
+    provide(requires, &mut path)
+}
+
+fn provide(
+    services: &[ServiceId],
+    path: &mut Vec<(TypeId, &'static str)>,
+) -> Result<(), MissingDependency> {
+    for service in services {
+        let type_id = (service.type_id)();
+        if let Some(pos) = path.iter().position(|(id, _)| *id == type_id) {
+            let mut cycle = path[pos..]
+                .iter()
+                .map(|(_, name)| *name)
+                .collect::<Vec<_>>();
+            cycle.push(service.name());
+            return Err(MissingDependency {
+                service: service.name(),
+                cycle,
+            });
+        }
+
+        path.push((type_id, service.name()));
+        provide((service.requires)(), path)?;
+        path.pop();
+
+        if System::try_current().is_none() {
+            return Err(MissingDependency {
+                service: service.name(),
+                cycle: Vec::new(),
+            });
+        }
+        (service.start)();
+    }
+
+    Ok(())
+}
+
 impl SystemRegistry {
     pub(crate) fn new(system: ArbiterHandle) -> Self {
         Self {
@@ -275,7 +435,7 @@ impl SystemRegistry {
     /// it get started in the system.
     pub fn get<A: SystemService + Actor<Context = Context<A>>>(&mut self) -> Addr<A> {
         if let Some(addr) = self.registry.get(&TypeId::of::<A>()) {
-            match addr.downcast_ref::<Addr<A>>() {
+            match addr.as_any().downcast_ref::<Addr<A>>() {
                 Some(addr) => return addr.clone(),
                 None => panic!("Got unknown value: {:?}", addr),
             }
@@ -290,7 +450,7 @@ impl SystemRegistry {
     /// Check if actor is in registry, if so, return its address
     pub fn query<A: SystemService + Actor<Context = Context<A>>>(&self) -> Option<Addr<A>> {
         if let Some(addr) = self.registry.get(&TypeId::of::<A>()) {
-            match addr.downcast_ref::<Addr<A>>() {
+            match addr.as_any().downcast_ref::<Addr<A>>() {
                 Some(addr) => return Some(addr.clone()),
                 None => return None,
             }
@@ -302,6 +462,7 @@ impl SystemRegistry {
     /// Add new actor to the registry by address, panic if actor is already running
     pub fn set<A: SystemService + Actor<Context = Context<A>>>(addr: Addr<A>) {
         let sys = System::current();
+        crate::system::track(&sys);
 
         let mut sreg = SREG.lock();
         let reg = sreg
@@ -309,7 +470,7 @@ impl SystemRegistry {
             .or_insert_with(|| SystemRegistry::new(sys.arbiter().clone()));
 
         if let Some(addr) = reg.registry.get(&TypeId::of::<A>()) {
-            if addr.downcast_ref::<Addr<A>>().is_some() {
+            if addr.as_any().downcast_ref::<Addr<A>>().is_some() {
                 panic!("Actor already started");
             }
         }
@@ -317,3 +478,426 @@ impl SystemRegistry {
         reg.registry.insert(TypeId::of::<A>(), Box::new(addr));
     }
 }
+
+/// Asks every service registered in the system `id` for its execution state.
+///
+/// Yields the type name of each service along with a receiver of its state, or `None` if the
+/// service is gone.
+pub(crate) fn probe_services(
+    system: usize,
+) -> Vec<(&'static str, Option<oneshot::Receiver<ActorState>>)> {
+    SREG.lock().get(&system).map_or_else(Vec::new, |reg| {
+        reg.registry
+            .values()
+            .map(|service| (service.type_name(), service.probe()))
+            .collect()
+    })
+}
+
+/// Drops the services and paths registered in the system `id`, and its arbiter registry on the
+/// current thread.
+pub(crate) fn release(system: usize) {
+    let services = SREG.lock().remove(&system);
+    let paths = PREG.lock().remove(&system);
+    // may run while the thread locals of the thread are destroyed, along with its arbiter
+    let arbiter = AREG
+        .try_with(|areg| areg.borrow_mut().remove(&system))
+        .ok()
+        .flatten();
+    drop((services, paths, arbiter));
+}
+
+/// Hierarchical actor path, like `/user/sessions/42`.
+///
+/// A path starts with `/` and consists of non-empty segments separated by `/`. The segments `*`
+/// and `**` are reserved for patterns, see [`PathRegistry::issue`]. The root path is `/`.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ActorPath(String);
+
+impl ActorPath {
+    /// Parses a path, e.g. `/user/sessions/42`.
+    pub fn new(path: &str) -> Result<Self, InvalidActorPath> {
+        let segments = match path.strip_prefix('/') {
+            Some("") => return Ok(Self::root()),
+            Some(segments) => segments,
+            None => return Err(InvalidActorPath(path.to_owned())),
+        };
+
+        if segments.split('/').all(valid_segment) {
+            Ok(ActorPath(path.to_owned()))
+        } else {
+            Err(InvalidActorPath(path.to_owned()))
+        }
+    }
+
+    /// Returns the root path `/`.
+    pub fn root() -> Self {
+        ActorPath("/".to_owned())
+    }
+
+    /// Returns a child path with `segment` appended.
+    pub fn join(&self, segment: &str) -> Result<Self, InvalidActorPath> {
+        if !valid_segment(segment) {
+            return Err(InvalidActorPath(format!("{}/{}", self, segment)));
+        }
+
+        if self.is_root() {
+            Ok(ActorPath(format!("/{}", segment)))
+        } else {
+            Ok(ActorPath(format!("{}/{}", self.0, segment)))
+        }
+    }
+
+    /// Returns the parent path, or `None` for the root path.
+    pub fn parent(&self) -> Option<Self> {
+        if self.is_root() {
+            return None;
+        }
+
+        match self.0.rfind('/') {
+            Some(0) => Some(Self::root()),
+            Some(idx) => Some(ActorPath(self.0[..idx].to_owned())),
+            None => None,
+        }
+    }
+
+    /// Returns whether this is the root path.
+    pub fn is_root(&self) -> bool {
+        self.0 == "/"
+    }
+
+    /// Returns the segments of the path.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split('/').filter(|segment| !segment.is_empty())
+    }
+
+    /// Returns whether `prefix` is this path or one of its ancestors.
+    pub fn starts_with(&self, prefix: &ActorPath) -> bool {
+        let mut segments = self.segments();
+        prefix
+            .segments()
+            .all(|prefix| segments.next() == Some(prefix))
+    }
+
+    /// Returns the path as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Matches the path against a pattern, see [`PathRegistry::issue`].
+    fn matches(&self, pattern: &[&str]) -> bool {
+        let mut segments = self.segments();
+
+        for (idx, expected) in pattern.iter().enumerate() {
+            if *expected == "**" && idx == pattern.len() - 1 {
+                return segments.next().is_some();
+            }
+
+            match segments.next() {
+                Some(segment) if *expected == "*" || segment == *expected => {}
+                _ => return false,
+            }
+        }
+
+        segments.next().is_none()
+    }
+}
+
+fn valid_segment(segment: &str) -> bool {
+    !segment.is_empty() && !segment.contains('/') && segment != "*" && segment != "**"
+}
+
+impl fmt::Debug for ActorPath {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "ActorPath({})", self.0)
+    }
+}
+
+impl fmt::Display for ActorPath {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.0)
+    }
+}
+
+impl FromStr for ActorPath {
+    type Err = InvalidActorPath;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        ActorPath::new(path)
+    }
+}
+
+/// The error returned when parsing an invalid [`ActorPath`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidActorPath(String);
+
+impl fmt::Display for InvalidActorPath {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "invalid actor path: {:?}", self.0)
+    }
+}
+
+impl error::Error for InvalidActorPath {}
+
+/// Type-erased weak recipient registered under a path.
+trait PathEntry: Send {
+    fn alive(&self) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<M> PathEntry for WeakRecipient<M>
+where
+    M: Message + Send + 'static,
+    M::Result: Send,
+{
+    fn alive(&self) -> bool {
+        self.upgrade().map_or(false, |rx| rx.connected())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Recipient registered under a path, tagged with its registration.
+struct Registered {
+    id: u64,
+    entry: Box<dyn PathEntry>,
+}
+
+type Paths = HashMap<ActorPath, HashMap<TypeId, Registered>>;
+
+static PREG: Lazy<Mutex<HashMap<usize, Paths>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_REGISTRATION: AtomicU64 = AtomicU64::new(0);
+
+/// Drops the entries of stopped actors.
+fn prune(paths: &mut Paths) {
+    paths.retain(|_, entries| {
+        entries.retain(|_, registered| registered.entry.alive());
+        !entries.is_empty()
+    });
+}
+
+/// Registration of an actor in the [`PathRegistry`], removed when the actor stops.
+pub(crate) struct PathRegistration {
+    system: usize,
+    path: ActorPath,
+    type_id: TypeId,
+    id: u64,
+}
+
+impl PathRegistration {
+    /// Removes the registration, unless it has been replaced in the meantime.
+    pub(crate) fn remove(self) {
+        let mut preg = PREG.lock();
+        let Some(paths) = preg.get_mut(&self.system) else {
+            return;
+        };
+        let Some(entries) = paths.get_mut(&self.path) else {
+            return;
+        };
+        if entries.get(&self.type_id).map(|registered| registered.id) == Some(self.id) {
+            entries.remove(&self.type_id);
+            if entries.is_empty() {
+                paths.remove(&self.path);
+            }
+        }
+    }
+}
+
+/// System wide registry of actors by hierarchical [`ActorPath`].
+///
+/// Actors register a [`Recipient`] per message type they want to be reachable with, usually
+/// from [`Actor::started`] with [`Context::register_path`]. Lookups are type-checked:
+/// [`resolve`](Self::resolve) only finds recipients registered for the requested message type.
+/// The registry holds weak references, so registering does not keep an actor alive.
+///
+/// Registrations made with [`Context::register_path`] are removed when the actor stops.
+/// Recipients registered with [`register`](Self::register) are removed once they are found
+/// stopped, by [`resolve`](Self::resolve) for their own entry and by [`list`](Self::list) and
+/// [`issue`](Self::issue) for all entries. Registering and resolving do not depend on the
+/// number of registered paths.
+///
+/// # Examples
+///
+/// ```
+/// use actix::{prelude::*, registry::{ActorPath, PathRegistry}};
+///
+/// #[derive(Message, Clone)]
+/// #[rtype(result = "()")]
+/// struct Notify;
+///
+/// struct Session(u32);
+///
+/// impl Actor for Session {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         let path = ActorPath::new("/user/sessions").unwrap();
+///         ctx.register_path::<Notify>(path.join(&self.0.to_string()).unwrap());
+///     }
+/// }
+///
+/// impl Handler<Notify> for Session {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Notify, _: &mut Context<Self>) {}
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let _one = Session::create(|ctx| Session(1));
+///     let _two = Session::create(|ctx| Session(2));
+///     actix_rt::task::yield_now().await;
+///
+///     let path = ActorPath::new("/user/sessions/1").unwrap();
+///     assert!(PathRegistry::resolve::<Notify>(&path).is_some());
+///
+///     let sessions = ActorPath::new("/user/sessions").unwrap();
+///     assert_eq!(PathRegistry::list(&sessions).len(), 2);
+///     assert_eq!(PathRegistry::issue("/user/sessions/*", Notify), 2);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PathRegistry;
+
+impl PathRegistry {
+    fn with_paths<F, R>(f: F) -> R
+    where
+        F: FnOnce(&mut Paths) -> R,
+    {
+        let system = System::current();
+        crate::system::track(&system);
+
+        let mut preg = PREG.lock();
+        f(preg.entry(system.id()).or_default())
+    }
+
+    /// Registers `recipient` under `path`.
+    ///
+    /// An actor can be registered under the same path once per message type. A recipient
+    /// registered for the same path and message type before is replaced. The entry is removed
+    /// lazily once the actor has stopped, see [`Context::register_path`] to remove it when the
+    /// actor stops.
+    pub fn register<M>(path: ActorPath, recipient: Recipient<M>)
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        Self::insert(path, recipient);
+    }
+
+    pub(crate) fn insert<M>(path: ActorPath, recipient: Recipient<M>) -> PathRegistration
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        let id = NEXT_REGISTRATION.fetch_add(1, Ordering::Relaxed);
+        let registered = Registered {
+            id,
+            entry: Box::new(recipient.downgrade()),
+        };
+        Self::with_paths(|paths| {
+            paths
+                .entry(path.clone())
+                .or_default()
+                .insert(TypeId::of::<M>(), registered);
+        });
+
+        PathRegistration {
+            system: System::current().id(),
+            path,
+            type_id: TypeId::of::<M>(),
+            id,
+        }
+    }
+
+    /// Removes everything registered under `path`.
+    pub fn unregister(path: &ActorPath) {
+        Self::with_paths(|paths| {
+            paths.remove(path);
+        })
+    }
+
+    /// Returns the recipient for message `M` registered under `path`.
+    pub fn resolve<M>(path: &ActorPath) -> Option<Recipient<M>>
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        Self::with_paths(|paths| {
+            let entries = paths.get_mut(path)?;
+            let recipient = entries
+                .get(&TypeId::of::<M>())?
+                .entry
+                .as_any()
+                .downcast_ref::<WeakRecipient<M>>()?
+                .upgrade()
+                .filter(Recipient::connected);
+
+            // drop the entry of a stopped actor
+            if recipient.is_none() {
+                entries.remove(&TypeId::of::<M>());
+                if entries.is_empty() {
+                    paths.remove(path);
+                }
+            }
+            recipient
+        })
+    }
+
+    /// Returns all registered paths at or below `prefix`, in order.
+    pub fn list(prefix: &ActorPath) -> Vec<ActorPath> {
+        let mut list = Self::with_paths(|paths| {
+            prune(paths);
+            paths
+                .keys()
+                .filter(|path| path.starts_with(prefix))
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+        list.sort();
+        list
+    }
+
+    /// Sends `msg` to every recipient for `M` whose path matches `pattern`, returning the number
+    /// of recipients.
+    ///
+    /// In the pattern, a `*` segment matches any single segment, and a trailing `**` segment
+    /// matches one or more segments. For example, `/user/sessions/*` matches the children of
+    /// `/user/sessions` while `/user/**` matches all of its descendants. Messages are sent as
+    /// with [`Recipient::do_send`].
+    pub fn issue<M>(pattern: &str, msg: M) -> usize
+    where
+        M: Message + Send + Clone + 'static,
+        M::Result: Send,
+    {
+        let pattern = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+
+        let recipients = Self::with_paths(|paths| {
+            prune(paths);
+            paths
+                .iter()
+                .filter(|(path, _)| path.matches(&pattern))
+                .filter_map(|(_, entries)| {
+                    entries
+                        .get(&TypeId::of::<M>())?
+                        .entry
+                        .as_any()
+                        .downcast_ref::<WeakRecipient<M>>()?
+                        .upgrade()
+                })
+                .collect::<Vec<_>>()
+        });
+
+        for recipient in &recipients {
+            recipient.do_send(msg.clone());
+        }
+        recipients.len()
+    }
+}
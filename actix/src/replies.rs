@@ -0,0 +1,190 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use crate::address::MailboxError;
+
+thread_local! {
+    /// The request being handled on this thread, if any.
+    static CURRENT: RefCell<Option<Current>> = const { RefCell::new(None) };
+}
+
+/// A request being handled, along with the pending replies of the actor handling it.
+struct Current {
+    reply: Arc<ReplyState>,
+    replies: Option<PendingReplies>,
+}
+
+/// The message has not been passed to its handler yet.
+const QUEUED: u8 = 0;
+/// The handler of the message has returned, and its response owes a reply.
+const HANDLED: u8 = 1;
+// the request failed with the corresponding error
+const CLOSED: u8 = 2;
+const TIMEOUT: u8 = 3;
+const NO_RESPONSE: u8 = 4;
+const HANDLER_TIMEOUT: u8 = 5;
+
+/// What became of a request, shared between its envelope and the sender waiting for the reply.
+///
+/// Once the reply is dropped, the sender reads the error to report from it: a message that
+/// never got handled, or whose response was cancelled, fails with [`MailboxError::Closed`],
+/// while a response that completed without replying fails with [`MailboxError::NoResponse`].
+#[derive(Debug, Default)]
+pub struct ReplyState(AtomicU8);
+
+impl ReplyState {
+    /// Marks the handler of the message as returned.
+    pub(crate) fn handled(&self) {
+        let _ = self
+            .0
+            .compare_exchange(QUEUED, HANDLED, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    /// Records `err` as the failure of the request, unless one is recorded already.
+    pub(crate) fn fail(&self, err: MailboxError) {
+        let code = match err {
+            MailboxError::Closed => CLOSED,
+            MailboxError::Timeout => TIMEOUT,
+            MailboxError::NoResponse => NO_RESPONSE,
+            MailboxError::HandlerTimeout => HANDLER_TIMEOUT,
+        };
+        let _ = self
+            .0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+                (state <= HANDLED).then_some(code)
+            });
+    }
+
+    /// Returns the error for the reply having been dropped.
+    pub(crate) fn error(&self) -> MailboxError {
+        match self.0.load(Ordering::Acquire) {
+            HANDLED | NO_RESPONSE => MailboxError::NoResponse,
+            TIMEOUT => MailboxError::Timeout,
+            HANDLER_TIMEOUT => MailboxError::HandlerTimeout,
+            _ => MailboxError::Closed,
+        }
+    }
+}
+
+/// Runs `f` with `reply` as the state of the request being handled, whose reply is counted
+/// in `replies` while a response holds on to it.
+pub(crate) fn scope<R>(
+    reply: Option<Arc<ReplyState>>,
+    replies: Option<PendingReplies>,
+    f: impl FnOnce() -> R,
+) -> R {
+    /// Restores the previous request once dropped, also if `f` panics.
+    struct Restore(Option<Current>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+        }
+    }
+
+    let request = reply.map(|reply| Current { reply, replies });
+    let _restore = Restore(CURRENT.with(|current| current.replace(request)));
+    f()
+}
+
+/// Returns the state of the request being handled on the calling thread.
+pub(crate) fn current() -> Option<Arc<ReplyState>> {
+    CURRENT.with(|current| current.borrow().as_ref().map(|cur| Arc::clone(&cur.reply)))
+}
+
+/// Marks the handler of the request being handled as returned, right before its response is
+/// given the reply.
+pub(crate) fn handled() {
+    CURRENT.with(|current| {
+        if let Some(ref cur) = *current.borrow() {
+            cur.reply.handled();
+        }
+    });
+}
+
+/// Number of requests an actor has received but not answered yet.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PendingReplies(Rc<Cell<usize>>);
+
+impl PendingReplies {
+    pub(crate) fn count(&self) -> usize {
+        self.0.get()
+    }
+}
+
+/// Counts the reply to a request as pending until dropped, for responses holding on to it.
+#[derive(Debug)]
+pub(crate) struct PendingReply(Option<PendingReplies>);
+
+impl PendingReply {
+    /// Counts the reply to the request being handled, if there is one.
+    pub(crate) fn current() -> Self {
+        let replies = CURRENT.with(|current| {
+            current
+                .borrow()
+                .as_ref()
+                .and_then(|cur| cur.replies.clone())
+        });
+        if let Some(ref replies) = replies {
+            replies.0.set(replies.0.get() + 1);
+        }
+        PendingReply(replies)
+    }
+}
+
+impl Drop for PendingReply {
+    fn drop(&mut self) {
+        if let Some(ref replies) = self.0 {
+            replies.0.set(replies.0.get() - 1);
+        }
+    }
+}
+
+/// Fails a request with [`MailboxError::Closed`] if dropped before being disarmed, for
+/// responses that are cancelled before replying, e.g. along with their actor.
+///
+/// Declared before the reply it guards, so that the request is failed before the reply is
+/// dropped.
+#[derive(Debug)]
+pub(crate) struct CancelGuard {
+    reply: Option<Arc<ReplyState>>,
+    pending: Option<PendingReply>,
+}
+
+impl CancelGuard {
+    /// Guards the reply to the request being handled, counting it as pending meanwhile.
+    pub(crate) fn current() -> Self {
+        CancelGuard {
+            reply: current(),
+            pending: Some(PendingReply::current()),
+        }
+    }
+
+    /// Guards a reply passing on the reply of another response, which is counted already.
+    pub(crate) fn forwarding(reply: Option<Arc<ReplyState>>) -> Self {
+        CancelGuard {
+            reply,
+            pending: None,
+        }
+    }
+
+    /// Marks the response as completed.
+    pub(crate) fn disarm(&mut self) {
+        self.reply = None;
+        self.pending = None;
+    }
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        if let Some(reply) = self.reply.take() {
+            reply.fail(MailboxError::Closed);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::{fmt, sync::Arc};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore as Permits};
+
+use crate::{
+    actor::Actor,
+    fut::{wrap_future, ActorFuture, ActorFutureExt},
+};
+
+/// Semaphore capping how many operations of an actor run concurrently.
+///
+/// Created with [`Context::semaphore`](crate::Context::semaphore). Clones share the same
+/// permits. Waiters are served in the order they asked for a permit, and the actor keeps handling
+/// messages while they wait.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Fetch(&'static str);
+///
+/// struct Crawler;
+///
+/// impl Actor for Crawler {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Fetch> for Crawler {
+///     type Result = ();
+///
+///     fn handle(&mut self, Fetch(url): Fetch, ctx: &mut Context<Self>) {
+///         // at most 10 fetches are in flight, the others wait for their turn
+///         let fetches = ctx.semaphore("fetch", 10);
+///         let fetch = async move { /* fetch `url` */ };
+///         ctx.spawn(fetches.run(fetch.into_actor(self)));
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let crawler = Crawler.start();
+///     crawler.send(Fetch("https://actix.rs")).await.unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Semaphore {
+    permits: Arc<Permits>,
+    total: usize,
+}
+
+impl Semaphore {
+    /// Creates a semaphore with `permits` permits.
+    ///
+    /// # Panics
+    /// Panics if `permits` is zero.
+    pub fn new(permits: usize) -> Self {
+        assert!(permits > 0, "semaphore must have at least one permit");
+
+        Semaphore {
+            permits: Arc::new(Permits::new(permits)),
+            total: permits,
+        }
+    }
+
+    /// Returns the number of permits of this semaphore.
+    pub fn permits(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the number of permits that are currently not held.
+    pub fn available_permits(&self) -> usize {
+        self.permits.available_permits()
+    }
+
+    /// Takes a permit if one is available and nobody is waiting for one.
+    pub fn try_acquire(&self) -> Option<Permit> {
+        Arc::clone(&self.permits)
+            .try_acquire_owned()
+            .ok()
+            .map(|permit| Permit { _permit: permit })
+    }
+
+    /// Takes a permit, resolving once one is available.
+    ///
+    /// The permit is released when it is dropped.
+    pub fn acquire<A: Actor>(&self) -> impl ActorFuture<A, Output = Permit> {
+        let permits = Arc::clone(&self.permits);
+        wrap_future(async move {
+            let permit = permits.acquire_owned().await;
+            Permit {
+                _permit: permit.expect("the semaphore is never closed"),
+            }
+        })
+    }
+
+    /// Runs `fut` once a permit is available, holding the permit until `fut` resolves.
+    ///
+    /// Dropping the returned future, e.g. by cancelling it, releases the permit or gives up the
+    /// place in line.
+    pub fn run<A, F>(&self, fut: F) -> impl ActorFuture<A, Output = F::Output>
+    where
+        A: Actor,
+        F: ActorFuture<A>,
+    {
+        self.acquire()
+            .then(move |permit, _, _| fut.map(move |res, _, _| (res, permit)))
+            .map(|(res, _permit), _, _| res)
+    }
+}
+
+impl fmt::Debug for Semaphore {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Semaphore")
+            .field("permits", &self.total)
+            .field("available", &self.available_permits())
+            .finish()
+    }
+}
+
+/// Permit of a [`Semaphore`], released on drop.
+pub struct Permit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl fmt::Debug for Permit {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Permit").finish()
+    }
+}
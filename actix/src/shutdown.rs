@@ -0,0 +1,226 @@
+use std::{
+    any::type_name,
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use actix_rt::System;
+use futures_core::future::BoxFuture;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tokio::{sync::oneshot, time::timeout_at};
+
+use crate::{
+    actor::{Actor, ActorState},
+    address::WeakAddr,
+    clock::Instant,
+    context::Context,
+    directory::{ActorId, ActorInfo},
+};
+
+/// Actors that joined a shutdown phase, keyed by system id.
+static PHASES: Lazy<Mutex<HashMap<usize, Vec<Member>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Deadlines of the shutdowns in progress, keyed by system id.
+static DEADLINES: Lazy<Mutex<HashMap<usize, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Ids of the systems with a shutdown in progress.
+static IN_PROGRESS: Lazy<Mutex<HashSet<usize>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+trait Stoppable: Send {
+    fn alive(&self) -> bool;
+
+    /// Stops the actor, returning a receiver that resolves once it has stopped.
+    fn stop(&self) -> Option<oneshot::Receiver<()>>;
+
+    /// Returns a snapshot of the actor, which is asked to stop.
+    fn info(&self) -> Option<ActorInfo>;
+}
+
+impl<A> Stoppable for WeakAddr<A>
+where
+    A: Actor<Context = Context<A>>,
+{
+    fn alive(&self) -> bool {
+        self.upgrade().is_some()
+    }
+
+    fn stop(&self) -> Option<oneshot::Receiver<()>> {
+        self.upgrade()?.wait_stopped(true)
+    }
+
+    fn info(&self) -> Option<ActorInfo> {
+        let addr = self.upgrade()?;
+        Some(ActorInfo {
+            id: addr.actor_id(),
+            type_name: type_name::<A>(),
+            state: ActorState::Stopping,
+            mailbox_len: addr.queued(),
+        })
+    }
+}
+
+struct Member {
+    id: ActorId,
+    phase: u32,
+    addr: Box<dyn Stoppable>,
+}
+
+/// Adds `addr` to shutdown `phase` of the current system, replacing an earlier phase of the
+/// same actor.
+pub(crate) fn join<A>(id: ActorId, phase: u32, addr: WeakAddr<A>)
+where
+    A: Actor<Context = Context<A>>,
+{
+    let system = match System::try_current() {
+        Some(system) => system,
+        None => return,
+    };
+    crate::system::track(&system);
+
+    let mut phases = PHASES.lock();
+    let members = phases.entry(system.id()).or_default();
+    members.retain(|member| member.id != id && member.addr.alive());
+    members.push(Member {
+        id,
+        phase,
+        addr: Box::new(addr),
+    });
+}
+
+/// Forgets the shutdown phases of the system `id`.
+pub(crate) fn release(system: usize) {
+    let members = PHASES.lock().remove(&system);
+    drop(members);
+    DEADLINES.lock().remove(&system);
+    IN_PROGRESS.lock().remove(&system);
+}
+
+/// Returns `true` if a shutdown of the system `id` is in progress.
+pub(crate) fn in_progress(system: usize) -> bool {
+    IN_PROGRESS.lock().contains(&system)
+}
+
+/// Returns the deadline of the shutdown of the current system, if one is in progress.
+pub(crate) fn deadline() -> Option<Instant> {
+    let system = System::try_current()?;
+    DEADLINES.lock().get(&system.id()).copied()
+}
+
+/// Shutdown of a [`System`] in phases.
+///
+/// Actors join a phase with [`Context::shutdown_phase`]. [`shutdown`](Self::shutdown) then stops
+/// the actors of each phase in ascending order, waiting until all actors of a phase have
+/// reached [`ActorState::Stopped`](crate::ActorState::Stopped) before stopping the next one,
+/// and finally stops the system. Actors that did not join a phase simply stop together with
+/// the system, as they do with [`System::stop`].
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// struct Acceptor;
+///
+/// impl Actor for Acceptor {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         // stop accepting new connections first
+///         ctx.shutdown_phase(0);
+///     }
+/// }
+///
+/// struct Pool;
+///
+/// impl Actor for Pool {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         ctx.shutdown_phase(1);
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     let _acceptor = Acceptor.start();
+///     let _pool = Pool.start();
+///     actix_rt::task::yield_now().await;
+///
+///     let report = System::current().shutdown().await;
+///     assert_eq!(report.stopped, 2);
+/// }
+/// ```
+pub trait PhasedShutdown {
+    /// Stops the actors phase by phase, then stops the system.
+    ///
+    /// Actors joining a phase while the shutdown is in progress are not waited for.
+    fn shutdown(&self) -> BoxFuture<'static, ShutdownReport>;
+
+    /// Stops the actors phase by phase like [`shutdown`](Self::shutdown), but gives up waiting
+    /// on the actors that have not stopped after `timeout`.
+    ///
+    /// The remaining phases are still stopped, without waiting for them either. The returned
+    /// report lists the actors that did not stop in time.
+    fn shutdown_timeout(&self, timeout: Duration) -> BoxFuture<'static, ShutdownReport>;
+}
+
+/// Outcome of a [`PhasedShutdown`].
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    /// Number of actors that have stopped.
+    pub stopped: usize,
+    /// Actors that did not stop before the timeout, in phase order.
+    pub timed_out: Vec<ActorInfo>,
+}
+
+impl PhasedShutdown for System {
+    fn shutdown(&self) -> BoxFuture<'static, ShutdownReport> {
+        Box::pin(shutdown(self.clone(), None))
+    }
+
+    fn shutdown_timeout(&self, timeout: Duration) -> BoxFuture<'static, ShutdownReport> {
+        Box::pin(shutdown(self.clone(), Some(Instant::now() + timeout)))
+    }
+}
+
+async fn shutdown(system: System, deadline: Option<Instant>) -> ShutdownReport {
+    crate::system::track(&system);
+    IN_PROGRESS.lock().insert(system.id());
+    if let Some(deadline) = deadline {
+        DEADLINES.lock().insert(system.id(), deadline);
+    }
+    let mut members = PHASES.lock().remove(&system.id()).unwrap_or_default();
+    members.sort_by_key(|member| member.phase);
+
+    let mut report = ShutdownReport::default();
+    let mut members = members.into_iter().peekable();
+    while let Some(first) = members.next() {
+        let phase = first.phase;
+        let mut stopping = vec![(first.addr.stop(), first)];
+        while let Some(member) = members.next_if(|member| member.phase == phase) {
+            stopping.push((member.addr.stop(), member));
+        }
+
+        for (rx, member) in stopping {
+            let stopped = match (rx, deadline) {
+                (None, _) => true,
+                (Some(rx), None) => {
+                    let _ = rx.await;
+                    true
+                }
+                (Some(rx), Some(deadline)) => timeout_at(deadline, rx).await.is_ok(),
+            };
+
+            if stopped {
+                report.stopped += 1;
+            } else {
+                report.timed_out.extend(member.addr.info());
+            }
+        }
+    }
+
+    DEADLINES.lock().remove(&system.id());
+    IN_PROGRESS.lock().remove(&system.id());
+    system.stop();
+    report
+}
@@ -0,0 +1,151 @@
+use std::{
+    any::{Any, TypeId},
+    collections::VecDeque,
+    mem,
+};
+
+use tokio::sync::oneshot::Sender;
+
+use crate::{
+    actor::Actor,
+    address::{Envelope, EnvelopeProxy},
+    contextimpl::AsyncContextParts,
+    handler::{Handler, Message, MessageResponse},
+    replies,
+};
+
+/// The message being handled, whose reply moves along if it is stashed.
+struct Current {
+    msg_type: TypeId,
+    reply: Option<Box<dyn Any>>,
+    stashed: bool,
+    attempt: u32,
+}
+
+/// Messages set aside by an actor with [`Context::stash`](crate::Context::stash), along with
+/// the number of times they were retried.
+pub(crate) struct Stash<A: Actor> {
+    current: Option<Current>,
+    stashed: Vec<(Envelope<A>, u32)>,
+    unstashed: VecDeque<(Envelope<A>, u32)>,
+    next_attempt: u32,
+}
+
+impl<A: Actor> Default for Stash<A> {
+    fn default() -> Self {
+        Stash {
+            current: None,
+            stashed: Vec::new(),
+            unstashed: VecDeque::new(),
+            next_attempt: 0,
+        }
+    }
+}
+
+impl<A: Actor> Stash<A> {
+    /// Sets `msg` aside, together with the reply to the message being handled if `msg` is of
+    /// its type.
+    pub(crate) fn push<M>(&mut self, msg: M)
+    where
+        A: Handler<M>,
+        A::Context: AsyncContextParts<A>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        let stashed = self.detach(msg);
+        self.stashed.push(stashed);
+    }
+
+    /// Packs `msg`, together with the reply to the message being handled and its number of
+    /// retries if `msg` is of its type.
+    pub(crate) fn detach<M>(&mut self, msg: M) -> (Envelope<A>, u32)
+    where
+        A: Handler<M>,
+        A::Context: AsyncContextParts<A>,
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        let (tx, attempt) = match self.current {
+            Some(ref mut current) if current.msg_type == TypeId::of::<M>() && !current.stashed => {
+                current.stashed = true;
+                let tx = current
+                    .reply
+                    .take()
+                    .and_then(|reply| reply.downcast::<Sender<M::Result>>().ok())
+                    .map(|tx| *tx);
+                (tx, current.attempt)
+            }
+            _ => (None, 0),
+        };
+        let reply = tx.as_ref().and_then(|_| replies::current());
+        let mut env = Envelope::new(msg, tx);
+        if let Some(reply) = reply {
+            // the reply keeps the state of its request
+            env.set_reply_state(reply);
+        }
+        (env, attempt)
+    }
+
+    /// Queues a detached message to be handled before the mailbox, after the messages
+    /// unstashed earlier.
+    pub(crate) fn requeue(&mut self, env: Envelope<A>, attempt: u32) {
+        self.unstashed.push_back((env, attempt));
+    }
+
+    /// Moves the stashed messages in front of the messages unstashed earlier.
+    pub(crate) fn unstash_all(&mut self) -> usize {
+        let count = self.stashed.len();
+        for stashed in self.stashed.drain(..).rev() {
+            self.unstashed.push_front(stashed);
+        }
+        count
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.stashed.len()
+    }
+
+    /// Returns the number of times the message being handled was retried.
+    pub(crate) fn attempt(&self) -> u32 {
+        self.current.as_ref().map_or(0, |current| current.attempt)
+    }
+
+    /// Returns the next unstashed message, which is handled before the mailbox.
+    pub(crate) fn next(&mut self) -> Option<Envelope<A>> {
+        let (env, attempt) = self.unstashed.pop_front().unzip();
+        self.next_attempt = attempt.unwrap_or(0);
+        env
+    }
+}
+
+/// Calls the handler of `msg` and delivers its response to `tx`, unless the handler stashed
+/// `msg`, in which case the reply is sent once it is handled after being unstashed.
+pub(crate) fn handle<A, M>(act: &mut A, msg: M, ctx: &mut A::Context, tx: Option<Sender<M::Result>>)
+where
+    A: Actor + Handler<M>,
+    A::Context: AsyncContextParts<A>,
+    M: Message + 'static,
+{
+    let stash = ctx.parts().stash();
+    let attempt = mem::take(&mut stash.next_attempt);
+    let prev = stash.current.replace(Current {
+        msg_type: TypeId::of::<M>(),
+        reply: tx.map(|tx| Box::new(tx) as Box<dyn Any>),
+        stashed: false,
+        attempt,
+    });
+
+    let res = <A as Handler<M>>::handle(act, msg, ctx);
+
+    let current = mem::replace(&mut ctx.parts().stash().current, prev);
+    if let Some(current) = current {
+        if !current.stashed {
+            replies::handled();
+            let tx = current
+                .reply
+                .and_then(|reply| reply.downcast::<Sender<M::Result>>().ok())
+                .map(|tx| *tx);
+            res.handle(ctx, tx);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Uniform snapshots of actor state, for debugging and administration.
+//!
+//! An actor opts in by implementing [`StateSnapshot`], which gives it a `Handler<GetState>`.
+//! Sending [`GetState`] then returns a [`Snapshot`] of the actor, holding its type name and
+//! [`ActorState`] along with the state it chose to expose. This lets tooling such as an admin
+//! endpoint introspect any opted-in actor the same way.
+//!
+//! # Examples
+//! ```
+//! use actix::{prelude::*, state::{GetState, StateSnapshot}};
+//! use serde_json::json;
+//!
+//! struct Counter {
+//!     total: usize,
+//! }
+//!
+//! impl Actor for Counter {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl StateSnapshot for Counter {
+//!     fn snapshot(&self) -> serde_json::Value {
+//!         json!({ "total": self.total })
+//!     }
+//! }
+//!
+//! #[actix::main]
+//! async fn main() {
+//!     let counter = Counter { total: 3 }.start();
+//!
+//!     let snapshot = counter.send(GetState).await.unwrap();
+//!     assert_eq!(snapshot.state, ActorState::Running);
+//!     assert_eq!(snapshot.data, json!({ "total": 3 }));
+//! }
+//! ```
+
+use serde::Serialize;
+
+use crate::{
+    actor::{Actor, ActorContext, ActorState},
+    handler::{Handler, Message, MessageResult},
+};
+
+/// An actor that exposes a snapshot of its state through [`GetState`].
+pub trait StateSnapshot: Actor {
+    /// Returns the state of the actor to expose.
+    fn snapshot(&self) -> serde_json::Value;
+}
+
+/// Message requesting a [`Snapshot`] of an actor implementing [`StateSnapshot`].
+#[derive(Debug, Clone, Copy)]
+pub struct GetState;
+
+impl Message for GetState {
+    type Result = Snapshot;
+}
+
+/// Snapshot of an actor, returned for [`GetState`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snapshot {
+    /// Type name of the actor.
+    pub actor: &'static str,
+
+    /// Execution state of the actor when the snapshot was taken.
+    pub state: ActorState,
+
+    /// State exposed by the actor through [`StateSnapshot::snapshot`].
+    pub data: serde_json::Value,
+}
+
+impl<A: StateSnapshot> Handler<GetState> for A {
+    type Result = MessageResult<GetState>;
+
+    fn handle(&mut self, _: GetState, ctx: &mut A::Context) -> Self::Result {
+        MessageResult(Snapshot {
+            actor: std::any::type_name::<A>(),
+            state: ctx.state(),
+            data: self.snapshot(),
+        })
+    }
+}
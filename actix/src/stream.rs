@@ -1,10 +1,13 @@
 use std::{
+    any::type_name,
+    cell::{Cell, RefCell},
     pin::Pin,
-    task::{Context, Poll},
+    rc::Rc,
+    task::{Context, Poll, Waker},
 };
 
 use futures_core::{ready, stream::Stream};
-use log::error;
+use log::{error, warn};
 use pin_project_lite::pin_project;
 
 use crate::{
@@ -86,33 +89,50 @@ where
             error!("Context::add_stream called for stopped actor.");
             SpawnHandle::default()
         } else {
-            ctx.spawn(ActorStream::new(stream))
+            ctx.spawn(ActorStream::new(
+                stream,
+                <Self as StreamHandler<S::Item>>::finished,
+            ))
         }
     }
 }
 
 pin_project! {
-    pub(crate) struct ActorStream<S> {
+    pub(crate) struct ActorStream<S, D> {
         #[pin]
         stream: S,
         started: bool,
+        done: Option<D>,
     }
 }
 
-impl<S> ActorStream<S> {
-    pub fn new(fut: S) -> Self {
+impl<S, D> ActorStream<S, D> {
+    /// Creates a stream item that calls `done` once the stream is exhausted.
+    pub fn new(fut: S, done: D) -> Self {
         Self {
             stream: fut,
             started: false,
+            done: Some(done),
+        }
+    }
+
+    /// Creates a stream item taking over from another one, which `StreamHandler::started` has
+    /// already been called for.
+    pub fn switched(fut: S, done: D) -> Self {
+        Self {
+            stream: fut,
+            started: true,
+            done: Some(done),
         }
     }
 }
 
-impl<A, S> ActorFuture<A> for ActorStream<S>
+impl<A, S, D> ActorFuture<A> for ActorStream<S, D>
 where
     S: Stream,
     A: Actor + StreamHandler<S::Item>,
     A::Context: AsyncContext<A>,
+    D: FnOnce(&mut A, &mut A::Context),
 {
     type Output = ();
 
@@ -147,7 +167,441 @@ where
             }
         }
 
+        if let Some(done) = this.done.take() {
+            done(act, ctx);
+        }
+        Poll::Ready(())
+    }
+}
+
+/// Flow control decision returned by [`AckStreamHandler::handle`] for every item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamAck {
+    /// Keep polling the stream.
+    Continue,
+    /// Stop polling the stream until [`AckStreamHandle::resume`] is called.
+    Pause,
+    /// Drop the stream. [`AckStreamHandler::finished`] is not called.
+    Stop,
+}
+
+/// Stream handling with per-item flow control.
+///
+/// Like [`StreamHandler`], but [`handle`](Self::handle) acknowledges every item with a
+/// [`StreamAck`] that decides whether the stream is polled further. This allows credit-based
+/// protocols where the actor only pulls the next item once it is ready for it.
+///
+/// A paused stream is still registered with the context, so it keeps the actor alive like any
+/// other stream. When the actor stops, paused streams are dropped along with its other
+/// futures and streams, and resuming them has no effect.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+/// use futures_util::stream::iter;
+///
+/// #[derive(Message)]
+/// #[rtype(result = "()")]
+/// struct Credit;
+///
+/// #[derive(Default)]
+/// struct Consumer {
+///     source: Option<AckStreamHandle>,
+///     received: Vec<u32>,
+/// }
+///
+/// impl Actor for Consumer {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         self.source = Some(ctx.add_ack_stream(iter([1, 2, 3])));
+///     }
+/// }
+///
+/// impl AckStreamHandler<u32> for Consumer {
+///     fn handle(&mut self, item: u32, _: &mut Context<Self>) -> StreamAck {
+///         self.received.push(item);
+///         // wait for the next credit
+///         StreamAck::Pause
+///     }
+/// #   fn finished(&mut self, _: &mut Context<Self>) { System::current().stop() }
+/// }
+///
+/// impl Handler<Credit> for Consumer {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Credit, _: &mut Context<Self>) {
+///         self.source.as_ref().unwrap().resume();
+///     }
+/// }
+/// # #[actix::main] async fn main() {
+/// # let addr = Consumer::default().start();
+/// # for _ in 0..3 { addr.do_send(Credit); }
+/// # }
+/// ```
+#[allow(unused_variables)]
+pub trait AckStreamHandler<I>
+where
+    Self: Actor,
+{
+    /// Called for every item emitted by the stream.
+    fn handle(&mut self, item: I, ctx: &mut Self::Context) -> StreamAck;
+
+    /// Called when stream emits first item.
+    ///
+    /// Default implementation does nothing.
+    fn started(&mut self, ctx: &mut Self::Context) {}
+
+    /// Called when stream finishes.
+    ///
+    /// Default implementation stops Actor execution.
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        ctx.stop()
+    }
+
+    /// Register a Stream to the actor context.
+    fn add_ack_stream<S>(stream: S, ctx: &mut Self::Context) -> AckStreamHandle
+    where
+        S: Stream + 'static,
+        Self: AckStreamHandler<S::Item>,
+        Self::Context: AsyncContext<Self>,
+    {
+        let state = Rc::new(AckState::default());
+
+        let handle = if ctx.state() == ActorState::Stopped {
+            error!("Context::add_ack_stream called for stopped actor.");
+            SpawnHandle::default()
+        } else {
+            ctx.spawn(ActorAckStream {
+                stream,
+                started: false,
+                state: Rc::clone(&state),
+            })
+        };
+
+        AckStreamHandle { handle, state }
+    }
+}
+
+/// Handle of a stream registered with [`AsyncContext::add_ack_stream`], used to resume it.
+#[derive(Clone, Debug)]
+pub struct AckStreamHandle {
+    handle: SpawnHandle,
+    state: Rc<AckState>,
+}
+
+impl AckStreamHandle {
+    /// Resumes polling a stream paused with [`StreamAck::Pause`].
+    pub fn resume(&self) {
+        if self.state.paused.replace(false) {
+            if let Some(waker) = self.state.waker.borrow_mut().take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Returns whether the stream is paused.
+    pub fn is_paused(&self) -> bool {
+        self.state.paused.get()
+    }
+
+    /// Returns the handle of the stream in the actor context, e.g. for
+    /// [`cancel_future`](AsyncContext::cancel_future).
+    pub fn spawn_handle(&self) -> SpawnHandle {
+        self.handle
+    }
+}
+
+#[derive(Debug, Default)]
+struct AckState {
+    paused: Cell<bool>,
+    waker: RefCell<Option<Waker>>,
+}
+
+pin_project! {
+    struct ActorAckStream<S> {
+        #[pin]
+        stream: S,
+        started: bool,
+        state: Rc<AckState>,
+    }
+}
+
+impl<A, S> ActorFuture<A> for ActorAckStream<S>
+where
+    S: Stream,
+    A: Actor + AckStreamHandler<S::Item>,
+    A::Context: AsyncContext<A>,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if !*this.started {
+            *this.started = true;
+            <A as AckStreamHandler<S::Item>>::started(act, ctx);
+        }
+
+        let mut polled = 0;
+
+        loop {
+            if this.state.paused.get() {
+                *this.state.waker.borrow_mut() = Some(task.waker().clone());
+                return Poll::Pending;
+            }
+
+            let msg = match ready!(this.stream.as_mut().poll_next(task)) {
+                Some(msg) => msg,
+                None => break,
+            };
+
+            match A::handle(act, msg, ctx) {
+                StreamAck::Continue => {}
+                StreamAck::Pause => this.state.paused.set(true),
+                StreamAck::Stop => return Poll::Ready(()),
+            }
+
+            polled += 1;
+
+            if ctx.waiting() {
+                return Poll::Pending;
+            } else if polled == 16 {
+                // Yield after 16 consecutive polls on this stream and self wake up.
+                task.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+
+        A::finished(act, ctx);
+        Poll::Ready(())
+    }
+}
+
+/// An item of an ordered stream, carrying its sequence number. See [`SequencedStreamHandler`].
+pub trait Sequenced {
+    /// Returns the sequence number of the item.
+    fn seq(&self) -> u64;
+}
+
+/// Decision returned by [`SequencedStreamHandler::on_gap`] for an item out of sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GapAction {
+    /// Handle the item and keep polling the stream, expecting the item following it next.
+    Deliver,
+    /// Drop the item and the stream. [`SequencedStreamHandler::finished`] is not called.
+    Stop,
+}
+
+/// Stream handling with gap detection, for ordered streams like replication logs.
+///
+/// Like [`StreamHandler`], but every item carries a sequence number, see [`Sequenced`]. Each
+/// item is expected to follow the previous one, i.e. to have its sequence number plus one.
+/// An item that does not, because items were lost or replayed, is first passed to
+/// [`on_gap`](Self::on_gap), which decides whether the item is handled or the stream is
+/// dropped. The first item sets the sequence, whatever its number.
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+/// use futures_util::stream::iter;
+///
+/// struct Event {
+///     seq: u64,
+/// }
+///
+/// impl Sequenced for Event {
+///     fn seq(&self) -> u64 {
+///         self.seq
+///     }
+/// }
+///
+/// #[derive(Default)]
+/// struct Replica {
+///     applied: Vec<u64>,
+/// }
+///
+/// impl Actor for Replica {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         let log = [1, 2, 4, 5].map(|seq| Event { seq });
+///         ctx.add_sequenced_stream(iter(log));
+///     }
+/// }
+///
+/// impl SequencedStreamHandler<Event> for Replica {
+///     fn handle(&mut self, event: Event, _: &mut Context<Self>) {
+///         self.applied.push(event.seq);
+///     }
+///
+///     fn on_gap(&mut self, expected: u64, got: u64, _: &mut Context<Self>) -> GapAction {
+///         assert_eq!((expected, got), (3, 4));
+///         assert_eq!(self.applied, [1, 2]);
+///
+///         // the replica can not apply events out of order
+///         System::current().stop();
+///         GapAction::Stop
+///     }
+/// }
+///
+/// #[actix::main]
+/// async fn main() {
+///     Replica::default().start();
+/// }
+/// ```
+#[allow(unused_variables)]
+pub trait SequencedStreamHandler<I>
+where
+    Self: Actor,
+    I: Sequenced,
+{
+    /// Called for every item emitted by the stream, in sequence or accepted by
+    /// [`on_gap`](Self::on_gap).
+    fn handle(&mut self, item: I, ctx: &mut Self::Context);
+
+    /// Called with the expected and actual sequence number of an item out of sequence, before
+    /// the item is handled.
+    ///
+    /// Default implementation logs a warning and handles the item.
+    fn on_gap(&mut self, expected: u64, got: u64, ctx: &mut Self::Context) -> GapAction {
+        warn!(
+            "Sequence gap in stream of {}: expected {}, got {}",
+            type_name::<I>(),
+            expected,
+            got
+        );
+        GapAction::Deliver
+    }
+
+    /// Called when stream emits first item.
+    ///
+    /// Default implementation does nothing.
+    fn started(&mut self, ctx: &mut Self::Context) {}
+
+    /// Called when stream finishes.
+    ///
+    /// Default implementation stops Actor execution.
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        ctx.stop()
+    }
+
+    /// Register a Stream to the actor context.
+    fn add_sequenced_stream<S>(stream: S, ctx: &mut Self::Context) -> SpawnHandle
+    where
+        S: Stream<Item = I> + 'static,
+        Self::Context: AsyncContext<Self>,
+    {
+        if ctx.state() == ActorState::Stopped {
+            error!("Context::add_sequenced_stream called for stopped actor.");
+            SpawnHandle::default()
+        } else {
+            ctx.spawn(ActorSeqStream {
+                stream,
+                started: false,
+                expected: None,
+            })
+        }
+    }
+}
+
+pin_project! {
+    struct ActorSeqStream<S> {
+        #[pin]
+        stream: S,
+        started: bool,
+        expected: Option<u64>,
+    }
+}
+
+impl<A, S> ActorFuture<A> for ActorSeqStream<S>
+where
+    S: Stream,
+    S::Item: Sequenced,
+    A: Actor + SequencedStreamHandler<S::Item>,
+    A::Context: AsyncContext<A>,
+{
+    type Output = ();
+
+    fn poll(
+        self: Pin<&mut Self>,
+        act: &mut A,
+        ctx: &mut A::Context,
+        task: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if !*this.started {
+            *this.started = true;
+            <A as SequencedStreamHandler<S::Item>>::started(act, ctx);
+        }
+
+        let mut polled = 0;
+
+        while let Some(msg) = ready!(this.stream.as_mut().poll_next(task)) {
+            let seq = msg.seq();
+            if let Some(expected) = *this.expected {
+                if seq != expected && A::on_gap(act, expected, seq, ctx) == GapAction::Stop {
+                    return Poll::Ready(());
+                }
+            }
+            *this.expected = Some(seq.wrapping_add(1));
+
+            A::handle(act, msg, ctx);
+
+            polled += 1;
+
+            if ctx.waiting() {
+                return Poll::Pending;
+            } else if polled == 16 {
+                // Yield after 16 consecutive polls on this stream and self wake up.
+                task.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+
         A::finished(act, ctx);
         Poll::Ready(())
     }
 }
+
+pin_project! {
+    /// Yields the `Ok` items of a stream of results and ends at the first error, which is kept
+    /// in `err`.
+    pub(crate) struct UntilErr<S, E> {
+        #[pin]
+        stream: S,
+        err: Rc<Cell<Option<E>>>,
+    }
+}
+
+impl<S, E> UntilErr<S, E> {
+    pub fn new(stream: S, err: Rc<Cell<Option<E>>>) -> Self {
+        Self { stream, err }
+    }
+}
+
+impl<S, T, E> Stream for UntilErr<S, E>
+where
+    S: Stream<Item = Result<T, E>>,
+{
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, task: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.project();
+
+        match ready!(this.stream.poll_next(task)) {
+            Some(Ok(item)) => Poll::Ready(Some(item)),
+            Some(Err(err)) => {
+                this.err.set(Some(err));
+                Poll::Ready(None)
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
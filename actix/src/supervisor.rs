@@ -5,6 +5,7 @@ use std::{
 };
 
 use actix_rt::ArbiterHandle;
+use log::error;
 use pin_project_lite::pin_project;
 
 use crate::{
@@ -27,6 +28,11 @@ pin_project! {
     /// a supervisor gets dropped and its actor does not execute anything, the supervisor
     /// terminates.
     ///
+    /// The mailbox is kept across restarts: addresses stay connected, and messages sent while
+    /// the actor is restarting are queued and handled by the restarted actor, so producers do
+    /// not need to buffer messages on their side. As usual, [`Addr::send`] waits for room once
+    /// the mailbox is full.
+    ///
     /// Supervisors can not guarantee that their actors successfully processes incoming
     /// messages. If the actor fails during message processing, the message can not be
     /// recovered. The sender would receive an `Err(Cancelled)` error in this situation.
@@ -87,6 +93,10 @@ where
 {
     /// Start new supervised actor in current tokio runtime.
     ///
+    /// If the system has reached its [actor limit](crate::ActorLimit), or a service the actor
+    /// [requires](Actor::requires) can not be provided, the failure is logged and the returned
+    /// address is closed, like with [`Actor::start`].
+    ///
     /// Type of returned address depends on variable type. For example to get
     /// `Addr<Syn, _>` of newly created actor, use explicitly `Addr<Syn,
     /// _>` type as type of a variable.
@@ -116,10 +126,7 @@ where
         let mut ctx = Context::new();
         let act = f(&mut ctx);
         let addr = ctx.address();
-        let fut = ctx.into_future(act);
-
-        // create supervisor
-        actix_rt::spawn(Self { fut });
+        Self::spawn(ctx, act);
 
         addr
     }
@@ -135,13 +142,21 @@ where
         sys.spawn_fn(move || {
             let mut ctx = Context::with_receiver(rx);
             let act = f(&mut ctx);
-            let fut = ctx.into_future(act);
-
-            actix_rt::spawn(Self { fut });
+            Self::spawn(ctx, act);
         });
 
         Addr::new(tx)
     }
+
+    /// Spawns the supervisor of `act`, logging a failure to start it.
+    fn spawn(ctx: Context<A>, act: A) {
+        match ctx.try_into_future(act) {
+            Ok(fut) => {
+                actix_rt::spawn(Self { fut });
+            }
+            Err(err) => error!("Can not start {}: {}", std::any::type_name::<A>(), err),
+        }
+    }
 }
 
 #[doc(hidden)]
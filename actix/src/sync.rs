@@ -6,7 +6,7 @@
 //! Actor type A and B, sharing the same thread pool. You need to create two
 //! [`SyncArbiter`]s and have A and B spawn on unique `SyncArbiter`s respectively.
 //! For more information and examples, see `SyncArbiter`
-use std::{future::Future, pin::Pin, sync::Arc, task, task::Poll, thread};
+use std::{pin::Pin, sync::Arc, task, task::Poll, thread};
 
 use actix_rt::System;
 use crossbeam_channel as cb_channel;
@@ -15,12 +15,14 @@ use log::warn;
 use tokio::sync::oneshot::Sender as SyncSender;
 
 use crate::{
-    actor::{Actor, ActorContext, ActorState, Running},
+    actor::{Actor, ActorContext, ActorState, AsyncContext, Running},
     address::{
         channel, Addr, AddressReceiver, AddressSenderProducer, Envelope, EnvelopeProxy, ToEnvelope,
     },
     context::Context,
+    fut::ActorFuture,
     handler::{Handler, Message, MessageResponse},
+    replies::ReplyState,
 };
 
 /// [`SyncArbiter`] provides the resources for a single Sync Actor to run on a dedicated
@@ -32,6 +34,13 @@ use crate::{
 /// Sync Actors have a different lifecycle compared to Actors on the System
 /// Arbiter. For more, see `SyncContext`.
 ///
+/// All workers take messages from a single shared queue, so a message is picked up by the
+/// first idle worker. A slow message only holds up the worker handling it, while the other
+/// workers keep draining the queue. Messages are not ordered across workers.
+///
+/// Messages cross threads on their way to the pool, and so do their results on the way back,
+/// so both must be `Send`. Sending a message that is not fails to compile at the call site.
+///
 /// # Examples
 ///
 /// ```
@@ -94,8 +103,38 @@ pub struct SyncArbiter<A>
 where
     A: Actor<Context = SyncContext<A>>,
 {
-    queue: Option<cb_channel::Sender<Envelope<A>>>,
-    msgs: AddressReceiver<A>,
+    queue: Option<cb_channel::Sender<Job<A>>>,
+    jobs: cb_channel::Receiver<Job<A>>,
+    address: AddressSenderProducer<A>,
+    factory: Arc<dyn Fn() -> A + Send + Sync>,
+    workers: usize,
+    // number of workers asked for by the latest resize
+    target: usize,
+}
+
+/// Item of the queue shared by the workers of a [`SyncArbiter`].
+enum Job<A: Actor> {
+    Handle(Envelope<A>),
+    Retire,
+}
+
+/// Message resizing a [`SyncArbiter`] to the given number of worker threads, see
+/// [`SyncArbiter::start_resizable`].
+///
+/// Growing the pool spawns new workers, which start taking messages right away. Shrinking it
+/// retires workers gracefully: a retiring worker finishes the message it is handling, stops its
+/// actor and lets its thread exit. Retirements queue up behind the messages sent before the
+/// resize, so that every message already sent is still handled, even when shrinking below the
+/// number of messages in flight. While the pool is resized to zero workers, messages queue up
+/// until it grows again.
+///
+/// Workers added by a resize are spawned from a default [`std::thread::Builder`] and are not
+/// pinned to a CPU core.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resize(pub usize);
+
+impl Message for Resize {
+    type Result = ();
 }
 
 impl<A> SyncArbiter<A>
@@ -121,37 +160,182 @@ where
     /// the next available Actor in the `SyncArbiter`.
     pub fn start_with_thread_builder<F, BF>(
         threads: usize,
-        mut thread_builder_factory: BF,
+        thread_builder_factory: BF,
         factory: F,
     ) -> Addr<A>
     where
         F: Fn() -> A + Send + Sync + 'static,
         BF: FnMut() -> thread::Builder,
     {
-        let factory = Arc::new(factory);
+        Self::start_workers(vec![None; threads], thread_builder_factory, factory).0
+    }
+
+    /// Start a new `SyncArbiter` with specified number of worker threads, which can be grown and
+    /// shrunk later on by sending [`Resize`] to the returned address of the `SyncArbiter`.
+    ///
+    /// Messages sent to the returned address of the actor are handled by the next available
+    /// worker of the current set.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// struct Cruncher;
+    ///
+    /// impl Actor for Cruncher {
+    ///     type Context = SyncContext<Self>;
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let (addr, pool) = SyncArbiter::start_resizable(2, || Cruncher);
+    ///
+    ///     // under load, grow the pool to 8 workers
+    ///     pool.send(Resize(8)).await.unwrap();
+    /// #   drop(addr);
+    /// }
+    /// ```
+    pub fn start_resizable<F>(threads: usize, factory: F) -> (Addr<A>, Addr<Self>)
+    where
+        F: Fn() -> A + Send + Sync + 'static,
+    {
+        Self::start_workers(vec![None; threads], thread::Builder::new, factory)
+    }
+
+    /// Start a new `SyncArbiter` with one worker thread per entry of `cores`, pinning each
+    /// worker to the CPU core with that index.
+    ///
+    /// Pinning keeps a worker's data in the caches of its core, which helps heavy, CPU bound
+    /// actors, e.g. on NUMA machines. A core may be listed more than once to pin several workers
+    /// to it. If the OS rejects pinning a worker, e.g. because the core does not exist or is not
+    /// available to the process, a warning is logged and the worker runs unpinned.
+    ///
+    /// Pinning only has effect on Linux. Elsewhere, this is the same as
+    /// [`start(cores.len(), factory)`](Self::start).
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// struct Cruncher;
+    ///
+    /// impl Actor for Cruncher {
+    ///     type Context = SyncContext<Self>;
+    /// }
+    ///
+    /// # #[actix::main]
+    /// # async fn main() {
+    /// // two workers, on the first and second core
+    /// let addr = SyncArbiter::start_pinned(&[0, 1], || Cruncher);
+    /// # drop(addr);
+    /// # }
+    /// ```
+    pub fn start_pinned<F>(cores: &[usize], factory: F) -> Addr<A>
+    where
+        F: Fn() -> A + Send + Sync + 'static,
+    {
+        let cores = cores.iter().copied().map(Some).collect();
+        Self::start_workers(cores, thread::Builder::new, factory).0
+    }
+
+    /// Starts a worker for each entry of `cores`, pinned to the given core if any.
+    fn start_workers<F, BF>(
+        cores: Vec<Option<usize>>,
+        mut thread_builder_factory: BF,
+        factory: F,
+    ) -> (Addr<A>, Addr<Self>)
+    where
+        F: Fn() -> A + Send + Sync + 'static,
+        BF: FnMut() -> thread::Builder,
+    {
         let (sender, receiver) = cb_channel::unbounded();
         let (tx, rx) = channel::channel(0);
 
-        for _ in 0..threads {
-            let f = Arc::clone(&factory);
-            let sys = System::current();
-            let actor_queue = receiver.clone();
-            let inner_rx = rx.sender_producer();
-
-            thread_builder_factory()
-                .spawn(move || {
-                    System::set_current(sys);
-                    SyncContext::new(f, actor_queue, inner_rx).run();
-                })
-                .expect("failed to spawn thread");
+        let mut arbiter = Self {
+            queue: Some(sender),
+            jobs: receiver,
+            address: rx.sender_producer(),
+            factory: Arc::new(factory),
+            workers: 0,
+            target: cores.len(),
+        };
+        for core in cores {
+            arbiter.spawn_worker(thread_builder_factory(), core);
         }
 
-        System::current().arbiter().spawn(Self {
-            queue: Some(sender),
-            msgs: rx,
+        let pool = Self::start_in_arbiter(System::current().arbiter(), move |ctx| {
+            ctx.spawn(Forward { msgs: rx });
+            arbiter
         });
 
-        Addr::new(tx)
+        (Addr::new(tx), pool)
+    }
+
+    fn spawn_worker(&mut self, builder: thread::Builder, core: Option<usize>) {
+        let f = Arc::clone(&self.factory);
+        let sys = System::current();
+        let actor_queue = self.jobs.clone();
+        let inner_rx = self.address.clone();
+
+        builder
+            .spawn(move || {
+                if let Some(core) = core {
+                    if let Err(err) = affinity::pin_current_thread(core) {
+                        warn!("failed to pin sync actor worker to core {core}: {err}");
+                    }
+                }
+                System::set_current(sys);
+                SyncContext::new(f, actor_queue, inner_rx).run();
+            })
+            .expect("failed to spawn thread");
+        self.workers += 1;
+    }
+
+    /// Spawns or retires workers to match the latest resize.
+    fn resize(&mut self) {
+        while self.workers < self.target {
+            self.spawn_worker(thread::Builder::new(), None);
+        }
+
+        if let Some(ref queue) = self.queue {
+            for _ in self.target..self.workers {
+                assert!(queue.send(Job::Retire).is_ok());
+            }
+        }
+        self.workers = self.target;
+    }
+}
+
+/// Pinning of threads to CPU cores, which only has effect on Linux.
+mod affinity {
+    use std::io;
+
+    /// Pins the calling thread to the CPU core with index `core`.
+    #[cfg(target_os = "linux")]
+    pub(super) fn pin_current_thread(core: usize) -> io::Result<()> {
+        // `CPU_SET` indexes out of bounds for cores beyond the fixed size set
+        if core >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::from_raw_os_error(libc::EINVAL));
+        }
+
+        // SAFETY: `cpu_set_t` is a plain bit mask, for which all zeroes is the empty set, and
+        // `core` was checked to be within it. Pid 0 refers to the calling thread.
+        let res = unsafe {
+            let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+            libc::CPU_SET(core, &mut set);
+            libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+        };
+
+        if res == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn pin_current_thread(_core: usize) -> io::Result<()> {
+        Ok(())
     }
 }
 
@@ -162,33 +346,56 @@ where
     type Context = Context<Self>;
 }
 
-#[doc(hidden)]
-impl<A> Future for SyncArbiter<A>
+impl<A> Handler<Resize> for SyncArbiter<A>
+where
+    A: Actor<Context = SyncContext<A>>,
+{
+    type Result = ();
+
+    fn handle(&mut self, Resize(workers): Resize, _: &mut Self::Context) {
+        // applied by `Forward` once it queued the messages sent before
+        self.target = workers;
+    }
+}
+
+/// Forwards the messages sent to the sync actor to the queue of its workers.
+struct Forward<A: Actor> {
+    msgs: AddressReceiver<A>,
+}
+
+impl<A> ActorFuture<SyncArbiter<A>> for Forward<A>
 where
     A: Actor<Context = SyncContext<A>>,
 {
     type Output = ();
 
-    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+    fn poll(
+        self: Pin<&mut Self>,
+        arbiter: &mut SyncArbiter<A>,
+        ctx: &mut Context<SyncArbiter<A>>,
+        task: &mut task::Context<'_>,
+    ) -> Poll<Self::Output> {
         let this = self.get_mut();
         loop {
-            match Pin::new(&mut this.msgs).poll_next(cx) {
+            match Pin::new(&mut this.msgs).poll_next(task) {
                 Poll::Ready(Some(msg)) => {
-                    if let Some(ref queue) = this.queue {
-                        assert!(queue.send(msg).is_ok());
+                    if let Some(ref queue) = arbiter.queue {
+                        assert!(queue.send(Job::Handle(msg)).is_ok());
                     }
                 }
                 Poll::Pending => break,
                 Poll::Ready(None) => unreachable!(),
             }
         }
+        arbiter.resize();
 
         // stop condition
         if this.msgs.connected() {
             Poll::Pending
         } else {
             // stop sync arbiters
-            this.queue = None;
+            arbiter.queue = None;
+            ctx.stop();
             Poll::Ready(())
         }
     }
@@ -239,7 +446,7 @@ where
     A: Actor<Context = SyncContext<A>>,
 {
     act: Option<A>,
-    queue: cb_channel::Receiver<Envelope<A>>,
+    queue: cb_channel::Receiver<Job<A>>,
     stopping: bool,
     state: ActorState,
     factory: Arc<dyn Fn() -> A>,
@@ -252,7 +459,7 @@ where
 {
     fn new(
         factory: Arc<dyn Fn() -> A>,
-        queue: cb_channel::Receiver<Envelope<A>>,
+        queue: cb_channel::Receiver<Job<A>>,
         address: AddressSenderProducer<A>,
     ) -> Self {
         let act = factory();
@@ -275,10 +482,10 @@ where
 
         loop {
             match self.queue.recv() {
-                Ok(mut env) => {
+                Ok(Job::Handle(mut env)) => {
                     env.handle(&mut act, self);
                 }
-                Err(_) => {
+                Ok(Job::Retire) | Err(_) => {
                     self.state = ActorState::Stopping;
                     if A::stopping(&mut act, self) != Running::Stop {
                         warn!("stopping method is not supported for sync actors");
@@ -341,6 +548,7 @@ where
 {
     msg: Option<M>,
     tx: Option<SyncSender<M::Result>>,
+    reply: Option<Arc<ReplyState>>,
 }
 
 impl<M> SyncContextEnvelope<M>
@@ -349,7 +557,11 @@ where
     M::Result: Send,
 {
     pub fn new(msg: M, tx: Option<SyncSender<M::Result>>) -> Self {
-        Self { tx, msg: Some(msg) }
+        Self {
+            tx,
+            msg: Some(msg),
+            reply: None,
+        }
     }
 }
 
@@ -366,9 +578,17 @@ where
         }
 
         if let Some(msg) = self.msg.take() {
-            <A as Handler<M>>::handle(act, msg, ctx).handle(ctx, tx)
+            let res = <A as Handler<M>>::handle(act, msg, ctx);
+            if let Some(ref reply) = self.reply {
+                reply.handled();
+            }
+            res.handle(ctx, tx)
         }
     }
+
+    fn set_reply_state(&mut self, reply: Arc<ReplyState>) {
+        self.reply = Some(reply);
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,847 @@
+#[cfg(feature = "signal")]
+use std::time::Duration;
+use std::{
+    any::type_name,
+    cell::RefCell,
+    collections::HashSet,
+    error::Error,
+    fmt,
+    future::{self, Future},
+    io,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+use actix_rt::{Arbiter, ArbiterHandle, System, SystemRunner};
+use log::{error, warn};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::{
+    address::{dead_letter, Recipient},
+    handler::Message,
+};
+
+/// Builder for a [`System`] with a fixed pool of named worker arbiters.
+///
+/// Each worker is an arbiter running on a dedicated OS thread created with the configured name
+/// prefix and stack size, which makes thread dumps easier to read and allows deep recursive
+/// handlers to run without overflowing the default stack.
+///
+/// # Isolation
+/// Several systems can run in one process, e.g. in libraries embedding actix without owning
+/// the process. Every system has its own registries, like [`SystemService`] and
+/// [`ArbiterService`] instances, [`PathRegistry`] entries, actor limits and shutdown phases,
+/// so actors never leak from one system into another. The state of a system is released once
+/// its [`WorkerSystem`] is dropped, or for a system created with [`System::new`], once its
+/// system arbiter stops.
+///
+/// Building a system makes it the current system of the calling thread, so each system is
+/// built on the thread that runs it.
+///
+/// [`SystemService`]: crate::SystemService
+/// [`ArbiterService`]: crate::ArbiterService
+/// [`PathRegistry`]: crate::registry::PathRegistry
+///
+/// # Examples
+/// ```
+/// use actix::prelude::*;
+///
+/// struct MyActor;
+///
+/// impl Actor for MyActor {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, _: &mut Self::Context) {
+///         assert_eq!(std::thread::current().name(), Some("app-worker-0"));
+///         System::current().stop();
+///     }
+/// }
+///
+/// let sys = SystemBuilder::new()
+///     .workers(2)
+///     .thread_name_prefix("app-worker")
+///     .stack_size(4 * 1024 * 1024)
+///     .build()
+///     .unwrap();
+///
+/// let workers = sys.workers().clone();
+/// sys.block_on(async move {
+///     MyActor::start_in_arbiter(&workers.arbiter(), |_| MyActor);
+/// });
+/// sys.run().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SystemBuilder {
+    workers: usize,
+    name_prefix: String,
+    stack_size: Option<usize>,
+    runtime: RuntimeKind,
+    shared: Option<Arc<tokio::runtime::Runtime>>,
+    #[cfg(feature = "signal")]
+    signals: Option<Duration>,
+}
+
+/// The flavor of the Tokio runtime driving the system arbiter of a [`SystemBuilder`].
+///
+/// Actors are not affected by the choice: every actor runs on the single thread of the arbiter
+/// it was started in, so actors and their messages never need to be `Send`, and an [`Addr`]
+/// works the same with either runtime. The runtime only decides where futures spawned with
+/// `tokio::spawn` run, which is where a multi-threaded runtime adds parallelism. Those futures
+/// must be `Send`, and talk to actors through their addresses.
+///
+/// [`Addr`]: crate::Addr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum RuntimeKind {
+    /// A runtime running everything on the thread of the system arbiter.
+    #[default]
+    CurrentThread,
+
+    /// A work-stealing runtime with a pool of `worker_threads` threads, next to the thread of
+    /// the system arbiter.
+    ///
+    /// Requires the `rt-multi-thread` feature.
+    #[cfg(feature = "rt-multi-thread")]
+    MultiThread {
+        /// The number of threads of the pool.
+        worker_threads: usize,
+    },
+}
+
+/// Extension of [`System`] with a constructor for a [`SystemBuilder`].
+pub trait SystemExt {
+    /// Creates a builder for a new system, same as [`SystemBuilder::new`].
+    fn builder() -> SystemBuilder;
+}
+
+impl SystemExt for System {
+    fn builder() -> SystemBuilder {
+        SystemBuilder::new()
+    }
+}
+
+impl Default for SystemBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemBuilder {
+    /// Creates a builder with one worker arbiter per available CPU.
+    pub fn new() -> Self {
+        Self {
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            name_prefix: "actix-worker".to_owned(),
+            stack_size: None,
+            runtime: RuntimeKind::CurrentThread,
+            shared: None,
+            #[cfg(feature = "signal")]
+            signals: Some(Duration::from_secs(30)),
+        }
+    }
+
+    /// Sets the number of worker arbiters.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Sets the name prefix of worker threads.
+    ///
+    /// Threads are named `{prefix}-{index}`. The default prefix is `actix-worker`.
+    pub fn thread_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.name_prefix = prefix.into();
+        self
+    }
+
+    /// Sets the stack size of worker threads, in bytes.
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Sets the flavor of the runtime driving the system arbiter.
+    ///
+    /// Worker arbiters always run on a runtime of their own thread, unless the system runs on a
+    /// [`shared_runtime`](Self::shared_runtime). The default is [`RuntimeKind::CurrentThread`].
+    pub fn runtime(mut self, runtime: RuntimeKind) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    /// Runs the system on an existing Tokio runtime owned by the application, instead of
+    /// creating runtimes of its own.
+    ///
+    /// The system arbiter and the worker arbiters then share the I/O and timer drivers of
+    /// `runtime`, as well as its blocking pool, with the rest of the application. Each arbiter
+    /// still runs its actors on its own thread, which drives the actors with
+    /// [`Runtime::block_on`](tokio::runtime::Runtime::block_on), so a multi-threaded runtime
+    /// is recommended. This overrides [`runtime`](Self::runtime).
+    ///
+    /// The application keeps owning the runtime: stopping the system only stops its arbiters
+    /// and drops their actors, while the runtime and the tasks spawned on it outside of actix
+    /// keep running until the application shuts the runtime down. The system holds on to
+    /// `runtime` until the [`WorkerSystem`] is dropped, so the runtime can not be shut down
+    /// before. As with other runtimes, the system is built and run from outside of `runtime`.
+    ///
+    /// Additional arbiters can share the runtime as well, with
+    /// [`Arbiter::with_tokio_rt`](actix_rt::Arbiter::with_tokio_rt).
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use actix::prelude::*;
+    ///
+    /// let runtime = Arc::new(
+    ///     tokio::runtime::Builder::new_current_thread()
+    ///         .enable_all()
+    ///         .build()
+    ///         .unwrap(),
+    /// );
+    ///
+    /// let sys = SystemBuilder::new()
+    ///     .workers(1)
+    ///     .shared_runtime(Arc::clone(&runtime))
+    ///     .build()
+    ///     .unwrap();
+    /// sys.block_on(async { System::current().stop() });
+    /// sys.run().unwrap();
+    ///
+    /// // the runtime outlives the system
+    /// assert_eq!(runtime.block_on(async { 42 }), 42);
+    /// ```
+    pub fn shared_runtime(mut self, runtime: Arc<tokio::runtime::Runtime>) -> Self {
+        self.shared = Some(runtime);
+        self
+    }
+
+    /// Sets whether [`WorkerSystem::run`] shuts the system down on SIGINT and SIGTERM, or
+    /// Ctrl-C on Windows.
+    ///
+    /// Enabled by default. Disable it when embedding the system in a process that handles
+    /// signals itself. The signals are listened for from [`build`](Self::build) on, so a signal
+    /// received before `run` is not lost.
+    ///
+    /// Requires the `signal` feature.
+    #[cfg(feature = "signal")]
+    pub fn handle_signals(mut self, enabled: bool) -> Self {
+        self.signals = match enabled {
+            true => Some(self.signals.unwrap_or(Duration::from_secs(30))),
+            false => None,
+        };
+        self
+    }
+
+    /// Sets how long the shutdown started by a signal waits for actors to stop, see
+    /// [`PhasedShutdown::shutdown_timeout`](crate::PhasedShutdown::shutdown_timeout).
+    ///
+    /// The default is 30 seconds. This also enables [`handle_signals`](Self::handle_signals).
+    ///
+    /// Requires the `signal` feature.
+    #[cfg(feature = "signal")]
+    pub fn shutdown_grace(mut self, grace: Duration) -> Self {
+        self.signals = Some(grace);
+        self
+    }
+
+    /// Creates the system and starts its worker arbiters.
+    ///
+    /// # Panics
+    /// Panics if a system is already running on the current thread.
+    pub fn build(self) -> io::Result<WorkerSystem> {
+        let runner = match (self.shared.clone(), self.runtime) {
+            (Some(rt), _) => System::with_tokio_rt(move || rt),
+            (None, RuntimeKind::CurrentThread) => System::new(),
+            #[cfg(feature = "rt-multi-thread")]
+            (None, RuntimeKind::MultiThread { worker_threads }) => {
+                let rt = tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(worker_threads)
+                    .enable_all()
+                    .build()?;
+                System::with_tokio_rt(|| rt)
+            }
+        };
+        let sys = System::current();
+
+        #[cfg(feature = "signal")]
+        let signals = self.signals.and_then(|grace| {
+            match runner.block_on(async { signal::Signals::new() }) {
+                Ok(signals) => Some((signals, grace)),
+                Err(err) => {
+                    error!("Failed to listen for shutdown signals: {}", err);
+                    None
+                }
+            }
+        });
+
+        let mut threads = Vec::with_capacity(self.workers);
+        let mut arbiters = Vec::with_capacity(self.workers);
+
+        for idx in 0..self.workers {
+            let mut builder = thread::Builder::new().name(format!("{}-{}", self.name_prefix, idx));
+            if let Some(size) = self.stack_size {
+                builder = builder.stack_size(size);
+            }
+
+            let (tx, rx) = mpsc::channel();
+            let sys = sys.clone();
+            let shared = self.shared.clone();
+            let handle = builder.spawn(move || {
+                // the private runtime hosts the arbiter event loop of this thread,
+                // actors started on it belong to the parent system
+                let runner = match shared {
+                    Some(rt) => System::with_tokio_rt(move || rt),
+                    None => System::new(),
+                };
+                let local = System::current();
+                let arbiter = runner.block_on(async { Arbiter::current() });
+                System::set_current(sys);
+
+                let _ = tx.send((arbiter, local));
+                let _ = runner.run();
+            })?;
+
+            let (arbiter, local) = rx
+                .recv()
+                .map_err(|_| io::Error::new(io::ErrorKind::Other, "worker failed to start"))?;
+            arbiters.push(arbiter);
+            threads.push((local, handle));
+        }
+        crate::health::register_workers(sys.id(), arbiters.clone());
+
+        Ok(WorkerSystem {
+            runner: Some(runner),
+            system: sys,
+            #[cfg(feature = "signal")]
+            signals,
+            workers: Workers {
+                inner: Arc::new(WorkersInner {
+                    arbiters,
+                    next: AtomicUsize::new(0),
+                }),
+            },
+            threads,
+        })
+    }
+}
+
+/// A [`System`] created by [`SystemBuilder`].
+///
+/// Worker threads are stopped and joined once the system finishes running, or when this value is
+/// dropped, which also releases the state kept for the system, like its registries.
+pub struct WorkerSystem {
+    runner: Option<SystemRunner>,
+    system: System,
+    #[cfg(feature = "signal")]
+    signals: Option<(signal::Signals, Duration)>,
+    workers: Workers,
+    threads: Vec<(System, thread::JoinHandle<()>)>,
+}
+
+impl fmt::Debug for WorkerSystem {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("WorkerSystem")
+            .field("workers", &self.workers)
+            .finish()
+    }
+}
+
+impl WorkerSystem {
+    /// Returns the worker arbiters of the system.
+    pub fn workers(&self) -> &Workers {
+        &self.workers
+    }
+
+    /// Runs the provided future on the system arbiter, blocking the current thread until the
+    /// future completes.
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        self.runner.as_ref().unwrap().block_on(fut)
+    }
+
+    /// Runs the system until it is stopped, then stops the worker arbiters.
+    ///
+    /// With the `signal` feature, and unless disabled with `SystemBuilder::handle_signals`, the
+    /// first SIGINT or SIGTERM starts a [`PhasedShutdown`](crate::PhasedShutdown) of the system,
+    /// and a second signal before the shutdown has completed exits the process right away with
+    /// status 1.
+    pub fn run(mut self) -> io::Result<()> {
+        #[cfg(feature = "signal")]
+        if let Some((signals, grace)) = self.signals.take() {
+            let system = self.system.clone();
+            self.system
+                .arbiter()
+                .spawn(signal::shutdown_on_signal(signals, system, grace));
+        }
+        self.runner.take().unwrap().run()
+    }
+}
+
+impl Drop for WorkerSystem {
+    fn drop(&mut self) {
+        for (local, _) in &self.threads {
+            local.stop();
+        }
+        for (_, handle) in self.threads.drain(..) {
+            let _ = handle.join();
+        }
+        release(self.system.id());
+    }
+}
+
+/// Systems with a release guard running on their system arbiter, see [`track`].
+static TRACKED: Lazy<Mutex<HashSet<usize>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Releases the state kept for `system` once its system arbiter stops.
+///
+/// Called by the system-wide facilities when they first store state for a system, so systems
+/// not built with [`SystemBuilder`] release it too. Must not be called while holding one of
+/// their locks, since the state is released right away if the system arbiter is already gone.
+pub(crate) fn track(system: &System) {
+    /// Releases the state of a system when dropped along with the futures of its arbiter.
+    struct Release(usize);
+
+    impl Drop for Release {
+        fn drop(&mut self) {
+            release(self.0);
+        }
+    }
+
+    if !TRACKED.lock().insert(system.id()) {
+        return;
+    }
+
+    let guard = Release(system.id());
+    system.arbiter().spawn(async move {
+        let _guard = guard;
+        future::pending::<()>().await
+    });
+}
+
+/// Drops the state kept for the system `id` by registries and other system-wide facilities.
+fn release(system: usize) {
+    TRACKED.lock().remove(&system);
+    crate::registry::release(system);
+    crate::shutdown::release(system);
+    crate::limit::release(system);
+    crate::directory::release(system);
+    crate::audit::release(system);
+    crate::health::release(system);
+}
+
+/// Handles to the worker arbiters of a [`WorkerSystem`].
+#[derive(Clone)]
+pub struct Workers {
+    inner: Arc<WorkersInner>,
+}
+
+struct WorkersInner {
+    arbiters: Vec<ArbiterHandle>,
+    next: AtomicUsize,
+}
+
+impl fmt::Debug for Workers {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Workers")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Workers {
+    /// Returns the next worker arbiter, in round-robin order.
+    ///
+    /// This is intended as the default placement for
+    /// [`Actor::start_in_arbiter`](crate::Actor::start_in_arbiter).
+    ///
+    /// # Panics
+    /// Panics if the system was built without workers.
+    pub fn arbiter(&self) -> ArbiterHandle {
+        let arbiters = &self.inner.arbiters;
+        assert!(!arbiters.is_empty(), "system has no worker arbiters");
+        let idx = self.inner.next.fetch_add(1, Ordering::Relaxed) % arbiters.len();
+        arbiters[idx].clone()
+    }
+
+    /// Returns the worker arbiter at `idx`.
+    pub fn get(&self, idx: usize) -> Option<&ArbiterHandle> {
+        self.inner.arbiters.get(idx)
+    }
+
+    /// Returns the number of worker arbiters.
+    pub fn len(&self) -> usize {
+        self.inner.arbiters.len()
+    }
+
+    /// Returns `true` if there are no worker arbiters.
+    pub fn is_empty(&self) -> bool {
+        self.inner.arbiters.is_empty()
+    }
+}
+
+/// Message delivered by [`ArbiterSpawnExt::spawn_and_notify`] once the spawned future has
+/// completed, holding its output.
+#[derive(Debug)]
+pub struct Done<T>(pub T);
+
+impl<T: 'static> Message for Done<T> {
+    type Result = ();
+}
+
+/// Detached spawning on an arbiter, reporting back to an actor.
+///
+/// Implemented for [`Arbiter`] and [`ArbiterHandle`].
+pub trait ArbiterSpawnExt {
+    /// Spawns `fut` on the arbiter and sends its output to `recipient` as a [`Done`] message.
+    ///
+    /// The future is detached: no actor owns it, so it runs to completion even if `recipient`
+    /// stops in the meantime. If the recipient is gone by then, the output is dropped and a
+    /// warning is logged.
+    ///
+    /// Returns `false` if the arbiter has stopped, as does [`Arbiter::spawn`].
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Self::Context) {
+    ///         let recipient = ctx.address().recipient();
+    ///         Arbiter::current().spawn_and_notify(async { 6 * 7 }, recipient);
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Done<i32>> for MyActor {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, Done(answer): Done<i32>, _: &mut Self::Context) {
+    ///         assert_eq!(answer, 42);
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     MyActor.start();
+    /// }
+    /// ```
+    fn spawn_and_notify<Fut>(&self, fut: Fut, recipient: Recipient<Done<Fut::Output>>) -> bool
+    where
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static;
+
+    /// Spawns a fallible `fut` on the arbiter, reporting its error to the error handler of the
+    /// arbiter.
+    ///
+    /// The error handler is set with [`ArbiterExt::with_error_handler`]. Arbiters created
+    /// otherwise log the error.
+    ///
+    /// Returns `false` if the arbiter has stopped, as does [`Arbiter::spawn`].
+    ///
+    /// # Examples
+    /// ```
+    /// use std::{io, sync::mpsc};
+    ///
+    /// use actix::prelude::*;
+    ///
+    /// let _sys = System::new();
+    ///
+    /// let (tx, rx) = mpsc::channel();
+    /// let arbiter = Arbiter::with_error_handler(move |err| tx.send(err.to_string()).unwrap());
+    ///
+    /// arbiter.spawn_fallible(async {
+    ///     Err(io::Error::new(io::ErrorKind::Other, "connection reset"))
+    /// });
+    /// assert_eq!(rx.recv().unwrap(), "connection reset");
+    ///
+    /// arbiter.stop();
+    /// arbiter.join().unwrap();
+    /// ```
+    fn spawn_fallible<Fut, E>(&self, fut: Fut) -> bool
+    where
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<Box<dyn Error>> + 'static;
+}
+
+impl ArbiterSpawnExt for ArbiterHandle {
+    fn spawn_and_notify<Fut>(&self, fut: Fut, recipient: Recipient<Done<Fut::Output>>) -> bool
+    where
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static,
+    {
+        self.spawn(notify_done(fut, recipient))
+    }
+
+    fn spawn_fallible<Fut, E>(&self, fut: Fut) -> bool
+    where
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<Box<dyn Error>> + 'static,
+    {
+        self.spawn(report_error(fut))
+    }
+}
+
+impl ArbiterSpawnExt for Arbiter {
+    fn spawn_and_notify<Fut>(&self, fut: Fut, recipient: Recipient<Done<Fut::Output>>) -> bool
+    where
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static,
+    {
+        self.spawn(notify_done(fut, recipient))
+    }
+
+    fn spawn_fallible<Fut, E>(&self, fut: Fut) -> bool
+    where
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<Box<dyn Error>> + 'static,
+    {
+        self.spawn(report_error(fut))
+    }
+}
+
+async fn notify_done<Fut>(fut: Fut, recipient: Recipient<Done<Fut::Output>>)
+where
+    Fut: Future,
+    Fut::Output: Send + 'static,
+{
+    let output = fut.await;
+
+    if recipient.connected() {
+        recipient.do_send(Done(output));
+    } else {
+        warn!(
+            "Done<{}> dropped, its recipient is gone",
+            type_name::<Fut::Output>()
+        );
+    }
+}
+
+type ErrorHandler = Rc<dyn Fn(Box<dyn Error>)>;
+
+thread_local! {
+    /// The error handler of the arbiter running on the current thread.
+    static ERROR_HANDLER: RefCell<Option<ErrorHandler>> = const { RefCell::new(None) };
+}
+
+/// Extension of [`Arbiter`] with a constructor setting the error handler of
+/// [`ArbiterSpawnExt::spawn_fallible`], and the dead-letter recipients of the current arbiter.
+pub trait ArbiterExt {
+    /// Spawns a new arbiter, like [`Arbiter::new`], passing the errors of the futures spawned on
+    /// it with [`spawn_fallible`](ArbiterSpawnExt::spawn_fallible) to `handler`.
+    ///
+    /// `handler` runs on the thread of the arbiter.
+    fn with_error_handler<F>(handler: F) -> Arbiter
+    where
+        F: Fn(Box<dyn Error>) + Send + 'static;
+
+    /// Sets the dead-letter recipient for messages of type `M` sent from the current arbiter,
+    /// returning the previous one.
+    ///
+    /// A message sent with [`Addr::do_send`] or [`Recipient::do_send`] to an actor whose
+    /// mailbox is closed, e.g. because the actor has stopped, goes to `recipient` instead of
+    /// being dropped. This allows recovering from lost messages of a specific type, e.g. by
+    /// persisting them to be retried later. Messages of other types are dropped as before, and
+    /// so is a dead letter whose recipient is gone as well.
+    ///
+    /// A message sent with [`Addr::do_send_retry`] also becomes a dead letter if the mailbox is
+    /// still full after the last attempt.
+    ///
+    /// The other ways of sending report a closed mailbox to the sender, so their messages are
+    /// never dead letters.
+    ///
+    /// [`Addr::do_send`]: crate::Addr::do_send
+    /// [`Addr::do_send_retry`]: crate::Addr::do_send_retry
+    ///
+    /// # Examples
+    /// ```
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Message, Debug, PartialEq)]
+    /// #[rtype(result = "()")]
+    /// struct WriteCommand(u32);
+    ///
+    /// #[derive(Message)]
+    /// #[rtype(result = "()")]
+    /// struct Shutdown;
+    ///
+    /// struct Storage;
+    ///
+    /// impl Actor for Storage {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<WriteCommand> for Storage {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: WriteCommand, _: &mut Context<Self>) {}
+    /// }
+    ///
+    /// impl Handler<Shutdown> for Storage {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: Shutdown, ctx: &mut Context<Self>) {
+    ///         ctx.stop();
+    ///     }
+    /// }
+    ///
+    /// /// Keeps failed writes to retry them later.
+    /// #[derive(Default)]
+    /// struct RetryQueue(Vec<WriteCommand>);
+    ///
+    /// impl Actor for RetryQueue {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<WriteCommand> for RetryQueue {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, msg: WriteCommand, _: &mut Context<Self>) {
+    ///         assert_eq!(msg, WriteCommand(1));
+    ///         self.0.push(msg);
+    ///         System::current().stop();
+    ///     }
+    /// }
+    ///
+    /// #[actix::main]
+    /// async fn main() {
+    ///     let retry = RetryQueue::default().start();
+    ///     Arbiter::set_dead_letter_for::<WriteCommand>(retry.recipient());
+    ///
+    ///     let storage = Storage.start();
+    ///     storage.send(Shutdown).await.unwrap();
+    ///     storage.do_send(WriteCommand(1));
+    /// }
+    /// ```
+    fn set_dead_letter_for<M>(recipient: Recipient<M>) -> Option<Recipient<M>>
+    where
+        M: Message + Send + 'static,
+        M::Result: Send;
+
+    /// Removes the dead-letter recipient for messages of type `M` sent from the current arbiter,
+    /// see [`set_dead_letter_for`](Self::set_dead_letter_for).
+    fn remove_dead_letter_for<M>() -> Option<Recipient<M>>
+    where
+        M: Message + Send + 'static,
+        M::Result: Send;
+}
+
+impl ArbiterExt for Arbiter {
+    fn with_error_handler<F>(handler: F) -> Arbiter
+    where
+        F: Fn(Box<dyn Error>) + Send + 'static,
+    {
+        let arbiter = Arbiter::new();
+        // runs before any future spawned on the arbiter afterwards
+        arbiter.spawn_fn(move || {
+            ERROR_HANDLER.with(|cell| *cell.borrow_mut() = Some(Rc::new(handler)));
+        });
+        arbiter
+    }
+
+    fn set_dead_letter_for<M>(recipient: Recipient<M>) -> Option<Recipient<M>>
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        dead_letter::set(recipient)
+    }
+
+    fn remove_dead_letter_for<M>() -> Option<Recipient<M>>
+    where
+        M: Message + Send + 'static,
+        M::Result: Send,
+    {
+        dead_letter::remove()
+    }
+}
+
+async fn report_error<Fut, E>(fut: Fut)
+where
+    Fut: Future<Output = Result<(), E>>,
+    E: Into<Box<dyn Error>>,
+{
+    if let Err(err) = fut.await {
+        let err = err.into();
+        match ERROR_HANDLER.with(|cell| cell.borrow().clone()) {
+            Some(handler) => handler(err),
+            None => error!("Future spawned on the arbiter failed: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "signal")]
+mod signal {
+    use std::{io, pin::pin, process, time::Duration};
+
+    use actix_rt::System;
+    use futures_util::future::{self, Either};
+    use log::{error, warn};
+
+    use crate::shutdown::PhasedShutdown;
+
+    /// Shuts `system` down on the first signal, and exits the process on the second one.
+    pub(super) async fn shutdown_on_signal(mut signals: Signals, system: System, grace: Duration) {
+        signals.recv().await;
+        warn!("Shutting down, signal again to exit immediately");
+
+        let shutdown = system.shutdown_timeout(grace);
+        let second = pin!(signals.recv());
+        match future::select(shutdown, second).await {
+            Either::Left((report, _)) => {
+                if !report.timed_out.is_empty() {
+                    warn!(
+                        "{} actors did not stop within the shutdown grace period",
+                        report.timed_out.len()
+                    );
+                }
+            }
+            Either::Right(_) => {
+                error!("Exiting before the shutdown has completed");
+                process::exit(1);
+            }
+        }
+    }
+
+    pub(super) struct Signals {
+        #[cfg(unix)]
+        interrupt: actix_rt::signal::unix::Signal,
+        #[cfg(unix)]
+        terminate: actix_rt::signal::unix::Signal,
+    }
+
+    impl Signals {
+        /// Starts listening for the shutdown signals, must be called within the runtime.
+        #[cfg(unix)]
+        pub(super) fn new() -> io::Result<Self> {
+            use actix_rt::signal::unix::{signal, SignalKind};
+
+            Ok(Signals {
+                interrupt: signal(SignalKind::interrupt())?,
+                terminate: signal(SignalKind::terminate())?,
+            })
+        }
+
+        #[cfg(not(unix))]
+        pub(super) fn new() -> io::Result<Self> {
+            Ok(Signals {})
+        }
+
+        #[cfg(unix)]
+        async fn recv(&mut self) {
+            future::select(pin!(self.interrupt.recv()), pin!(self.terminate.recv())).await;
+        }
+
+        #[cfg(not(unix))]
+        async fn recv(&mut self) {
+            let _ = actix_rt::signal::ctrl_c().await;
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Deterministic testing of actors.
+//!
+//! Requires the `test-util` feature.
+//!
+//! [`TestSystem`] runs actors on a system that is only driven while the test asks it to, with
+//! a clock that only moves when the test says so. This allows multi-actor tests without sleeps
+//! or races.
+//!
+//! # Examples
+//! Testing a request-reply protocol with a timeout between two actors:
+//! ```
+//! use std::time::Duration;
+//!
+//! use actix::{prelude::*, testing::TestSystem};
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "()")]
+//! struct Ping(Addr<Client>);
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "()")]
+//! struct Pong;
+//!
+//! /// Replies to pings after a delay.
+//! struct Server;
+//!
+//! impl Actor for Server {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<Ping> for Server {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, Ping(client): Ping, ctx: &mut Context<Self>) {
+//!         ctx.run_later(Duration::from_secs(2), move |_, _| client.do_send(Pong));
+//!     }
+//! }
+//!
+//! /// Pings the server and gives up if no pong arrives within 5 seconds.
+//! #[derive(Default)]
+//! struct Client {
+//!     pongs: usize,
+//!     timed_out: bool,
+//! }
+//!
+//! impl Actor for Client {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<Pong> for Client {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, _: Pong, _: &mut Context<Self>) {
+//!         self.pongs += 1;
+//!     }
+//! }
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "()")]
+//! struct Start(Addr<Server>);
+//!
+//! impl Handler<Start> for Client {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, Start(server): Start, ctx: &mut Context<Self>) {
+//!         server.do_send(Ping(ctx.address()));
+//!         ctx.run_later(Duration::from_secs(5), |act, _| act.timed_out = act.pongs == 0);
+//!     }
+//! }
+//!
+//! #[derive(Message)]
+//! #[rtype(result = "(usize, bool)")]
+//! struct State;
+//!
+//! impl Handler<State> for Client {
+//!     type Result = MessageResult<State>;
+//!
+//!     fn handle(&mut self, _: State, _: &mut Context<Self>) -> Self::Result {
+//!         MessageResult((self.pongs, self.timed_out))
+//!     }
+//! }
+//!
+//! let sys = TestSystem::new();
+//! let (server, client) = sys.block_on(async { (Server.start(), Client::default().start()) });
+//!
+//! client.do_send(Start(server));
+//! sys.run_until_idle();
+//!
+//! // the pong is not due yet
+//! sys.advance_time(Duration::from_millis(1999));
+//! assert_eq!(sys.block_on(client.send(State)).unwrap(), (0, false));
+//!
+//! sys.advance_time(Duration::from_millis(1));
+//! assert_eq!(sys.block_on(client.send(State)).unwrap(), (1, false));
+//!
+//! sys.advance_time(Duration::from_secs(3));
+//! assert_eq!(sys.block_on(client.send(State)).unwrap(), (1, false));
+//! ```
+
+use std::{
+    cell::Cell,
+    fmt,
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use actix_rt::{System, SystemRunner};
+use tokio::{runtime, task::yield_now, time};
+
+use crate::clock::Instant;
+
+thread_local! {
+    /// Number of times any actor of the current thread has been polled.
+    static ACTOR_POLLS: Cell<u64> = const { Cell::new(0) };
+
+    /// The step in progress on the current thread, or zero if not stepping.
+    static STEP: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Returns whether an actor, last polled in step `last`, may be polled now.
+///
+/// While stepping, every actor is polled at most once per step.
+pub(crate) fn enter_poll(last: &mut u64) -> bool {
+    let step = STEP.with(Cell::get);
+    if step != 0 && *last == step {
+        return false;
+    }
+
+    *last = step;
+    ACTOR_POLLS.with(|polls| polls.set(polls.get() + 1));
+    true
+}
+
+fn actor_polls() -> u64 {
+    ACTOR_POLLS.with(Cell::get)
+}
+
+/// A system for deterministic tests, driven manually.
+///
+/// The system runs on the current thread and makes progress only inside the calls of this
+/// type. Its clock starts paused: timers, like [`run_later`](crate::AsyncContext::run_later)
+/// or [`sleep`](crate::clock::sleep), fire only once the test moves the clock with
+/// [`advance_time`](Self::advance_time).
+///
+/// Actors are started inside [`block_on`](Self::block_on), as they need a running system.
+/// Messages can be sent from outside of it, and are delivered by the next call driving the
+/// system.
+///
+/// See the [module documentation](self) for an example.
+pub struct TestSystem {
+    sys: SystemRunner,
+    start: Instant,
+}
+
+impl TestSystem {
+    /// Creates a new test system with a paused clock.
+    ///
+    /// # Panics
+    /// Panics if the underlying runtime cannot be created.
+    pub fn new() -> Self {
+        let sys = System::with_tokio_rt(|| {
+            runtime::Builder::new_current_thread()
+                .enable_all()
+                .start_paused(true)
+                .build()
+                .unwrap()
+        });
+        let start = sys.block_on(async { Instant::now() });
+
+        TestSystem { sys, start }
+    }
+
+    /// Returns the clock of this system.
+    pub fn clock(&self) -> TestClock<'_> {
+        TestClock { sys: self }
+    }
+
+    /// Runs `fut` to completion on this system, driving actors while it is pending.
+    ///
+    /// If `fut` and all actors are waiting on timers only, the clock advances to the next timer
+    /// on its own, as in [`tokio::time::pause`].
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        self.sys.block_on(fut)
+    }
+
+    /// Gives every actor that is ready to make progress a single poll.
+    ///
+    /// Each actor is polled at most once per step: an actor woken again after it was polled,
+    /// e.g. by a reply to a message it sent, is polled by the next step. Returns `false` if no
+    /// actor was polled, i.e. all actors are idle.
+    pub fn step(&self) -> bool {
+        static NEXT_STEP: AtomicU64 = AtomicU64::new(1);
+
+        let polls = actor_polls();
+        STEP.with(|step| step.set(NEXT_STEP.fetch_add(1, Ordering::Relaxed)));
+        self.sys.block_on(yield_now());
+        STEP.with(|step| step.set(0));
+        actor_polls() != polls
+    }
+
+    /// Steps the system until all actors are idle, without advancing the clock.
+    ///
+    /// This never returns if an actor keeps itself busy forever, e.g. by handling an endless
+    /// stream that is always ready.
+    pub fn run_until_idle(&self) {
+        // futures spawned outside of actors may need a step of their own to wake an actor
+        while self.step() || self.step() {}
+    }
+
+    /// Moves the clock ahead by `dur`, firing the timers that are due, and steps the system
+    /// until all actors are idle.
+    pub fn advance_time(&self, dur: Duration) {
+        self.run_until_idle();
+        self.sys.block_on(time::advance(dur));
+        self.run_until_idle();
+    }
+}
+
+/// The paused clock of a [`TestSystem`], created with [`TestSystem::clock`].
+///
+/// All timers of actors, like [`run_later`](crate::AsyncContext::run_later),
+/// [`run_interval`](crate::AsyncContext::run_interval) and timeouts, as well as
+/// [`clock::Instant::now`](crate::clock::Instant::now), read the clock of the runtime they run
+/// on, so they follow this clock without any change to the actors under test. Outside of
+/// tests, the same timers use the real clock at no extra cost.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix::{prelude::*, testing::TestSystem};
+///
+/// /// Closes itself after five minutes of inactivity.
+/// struct Session;
+///
+/// impl Actor for Session {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         ctx.run_later(Duration::from_secs(300), |_, ctx| ctx.stop());
+///     }
+/// }
+///
+/// let sys = TestSystem::new();
+/// let clock = sys.clock();
+/// let addr = sys.block_on(async { Session.start() });
+///
+/// clock.advance(Duration::from_secs(299));
+/// assert!(addr.connected());
+///
+/// clock.advance(Duration::from_secs(1));
+/// assert!(!addr.connected());
+/// assert_eq!(clock.elapsed(), Duration::from_secs(300));
+/// ```
+#[derive(Clone, Copy)]
+pub struct TestClock<'a> {
+    sys: &'a TestSystem,
+}
+
+impl TestClock<'_> {
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> Instant {
+        self.sys.block_on(async { Instant::now() })
+    }
+
+    /// Returns the time passed since the system was created.
+    pub fn elapsed(&self) -> Duration {
+        self.now() - self.sys.start
+    }
+
+    /// Moves the clock ahead by `dur`, firing the timers that are due on the way.
+    ///
+    /// Unlike [`TestSystem::advance_time`], which moves the clock in a single jump, the clock
+    /// stops at the deadline of every timer that is due, and the system is stepped until all
+    /// actors are idle before moving on. Timers therefore fire in order and at their own
+    /// deadline, including timers set up by actors while the clock advances, and intervals
+    /// tick once per period. All timers due by the end have fired once this returns.
+    pub fn advance(&self, dur: Duration) {
+        self.sys.run_until_idle();
+        self.sys.block_on(async { time::sleep(dur).await });
+        self.sys.run_until_idle();
+    }
+}
+
+impl fmt::Debug for TestClock<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("TestClock")
+            .field("elapsed", &self.elapsed())
+            .finish()
+    }
+}
+
+impl Default for TestSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TestSystem {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("TestSystem").finish()
+    }
+}
@@ -1,6 +1,8 @@
 use std::{
+    cell::Cell,
     future::Future,
     pin::Pin,
+    rc::Rc,
     task::{Context, Poll},
     time::Duration,
 };
@@ -11,7 +13,7 @@ use tokio::sync::oneshot;
 
 use crate::{
     actor::Actor,
-    clock::{sleep, Sleep},
+    clock::{sleep, Instant, Sleep},
     fut::{ActorFuture, ActorStream},
 };
 
@@ -133,6 +135,18 @@ where
     }
 }
 
+/// What an interval does when it falls behind, e.g. because its handler or the actor was busy
+/// for longer than the interval.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissedTicks {
+    /// Ticks once for all missed periods, and keeps ticking at the original schedule.
+    #[default]
+    Coalesce,
+
+    /// Ticks once for every missed period, in a burst, until it caught up.
+    CatchUp,
+}
+
 pin_project! {
     /// An `ActorStream` that periodically runs a function in the actor's context.
     ///
@@ -174,25 +188,50 @@ pin_project! {
     #[must_use = "future do nothing unless polled"]
     #[allow(clippy::type_complexity)]
     pub struct IntervalFunc<A: Actor> {
-        f: Box<dyn FnMut(&mut A, &mut A::Context)>,
+        f: Box<dyn FnMut(&mut A, &mut A::Context, u32)>,
         dur: Duration,
+        missed: MissedTicks,
         #[pin]
         timer: Sleep,
+        deadline: Rc<Cell<Instant>>,
     }
 }
 
 impl<A: Actor> IntervalFunc<A> {
     /// Creates a new `IntervalFunc` with the given interval duration.
-    pub fn new<F>(dur: Duration, f: F) -> IntervalFunc<A>
+    pub fn new<F>(dur: Duration, mut f: F) -> IntervalFunc<A>
     where
         F: FnMut(&mut A, &mut A::Context) + 'static,
     {
+        Self::with_skipped(dur, move |act, ctx, _| f(act, ctx))
+    }
+
+    /// Creates a new `IntervalFunc` whose function also receives the number of ticks that were
+    /// coalesced into the current one, see [`MissedTicks::Coalesce`].
+    pub fn with_skipped<F>(dur: Duration, f: F) -> IntervalFunc<A>
+    where
+        F: FnMut(&mut A, &mut A::Context, u32) + 'static,
+    {
+        let timer = sleep(dur);
         Self {
             f: Box::new(f),
             dur,
-            timer: sleep(dur),
+            missed: MissedTicks::default(),
+            deadline: Rc::new(Cell::new(timer.deadline())),
+            timer,
         }
     }
+
+    /// Sets what happens when the interval falls behind, [`MissedTicks::Coalesce`] by default.
+    pub fn missed_ticks(mut self, missed: MissedTicks) -> Self {
+        self.missed = missed;
+        self
+    }
+
+    /// Returns the deadline of the next tick, kept up to date as the interval ticks.
+    pub(crate) fn deadline(&self) -> Rc<Cell<Instant>> {
+        Rc::clone(&self.deadline)
+    }
 }
 
 impl<A: Actor> ActorStream<A> for IntervalFunc<A> {
@@ -207,9 +246,20 @@ impl<A: Actor> ActorStream<A> for IntervalFunc<A> {
         let mut this = self.project();
         loop {
             ready!(this.timer.as_mut().poll(task));
-            let now = this.timer.deadline();
-            this.timer.as_mut().reset(now + *this.dur);
-            (this.f)(act, ctx);
+            let deadline = this.timer.deadline();
+
+            let skipped = match *this.missed {
+                MissedTicks::Coalesce if !this.dur.is_zero() => {
+                    let late = Instant::now().saturating_duration_since(deadline);
+                    (late.as_nanos() / this.dur.as_nanos()).min(u128::from(u32::MAX - 1)) as u32
+                }
+                _ => 0,
+            };
+
+            let next = deadline + *this.dur * (skipped + 1);
+            this.timer.as_mut().reset(next);
+            this.deadline.set(next);
+            (this.f)(act, ctx, skipped);
         }
     }
 }
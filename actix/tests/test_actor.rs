@@ -8,7 +8,7 @@ use std::{
     time::Duration,
 };
 
-use actix::prelude::*;
+use actix::{prelude::*, PanicPolicy};
 use actix_rt::time::{sleep, Instant};
 
 #[derive(Clone, Debug)]
@@ -25,7 +25,7 @@ impl Actor for MyActor {
 
     fn stopping(&mut self, _: &mut Self::Context) -> Running {
         System::current().stop();
-        Running::Stop
+        self.2
     }
 }
 
@@ -252,3 +252,133 @@ fn test_run_interval() {
     // We wait 10 intervals by ~100ms
     assert_eq!(result.elapsed().as_secs(), 1);
 }
+
+struct Listed {
+    paused: bool,
+}
+
+impl Actor for Listed {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if self.paused {
+            // keep messages queued
+            ctx.pause();
+        }
+    }
+}
+
+impl Handler<Num> for Listed {
+    type Result = ();
+
+    fn handle(&mut self, _: Num, _: &mut Self::Context) {}
+}
+
+#[actix::test]
+async fn test_actor_directory() {
+    let sys = System::current();
+    let _before = Listed { paused: false }.start();
+
+    sys.enable_actor_directory();
+    let first = Listed { paused: true }.start();
+    let second = Listed { paused: false }.start();
+    actix_rt::task::yield_now().await;
+
+    for i in 0..3 {
+        first.do_send(Num(i));
+    }
+
+    let actors = sys.actors();
+    assert_eq!(actors.len(), 2);
+    assert!(actors[0].id < actors[1].id);
+    assert!(actors.iter().all(|info| info.type_name.ends_with("Listed")));
+    assert!(actors.iter().all(|info| info.state == ActorState::Running));
+    assert_eq!(actors[0].mailbox_len, 3);
+    assert_eq!(actors[1].mailbox_len, 0);
+
+    // stopped actors are removed
+    second.stop_and_wait().await;
+    let ids = sys.actors().iter().map(|info| info.id).collect::<Vec<_>>();
+    assert_eq!(ids, [actors[0].id]);
+}
+
+struct Counted(Arc<AtomicUsize>);
+
+impl Actor for Counted {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl actix::Supervised for Counted {}
+
+#[actix::test]
+async fn test_max_actors() {
+    let sys = System::current();
+    let started = Arc::new(AtomicUsize::new(0));
+    sys.set_max_actors(2);
+
+    let first = Counted(Arc::clone(&started)).try_start().unwrap();
+    let _second = Counted(Arc::clone(&started)).start();
+    let err = Counted(Arc::clone(&started)).try_start().unwrap_err();
+    assert_eq!(err, StartError::TooManyActors(TooManyActors { max: 2 }));
+
+    // the infallible entry points log the failure and return a closed address
+    assert!(!Counted(Arc::clone(&started)).start().connected());
+    assert!(!Counted::create(|_| Counted(Arc::clone(&started))).connected());
+    let supervised = Arc::clone(&started);
+    assert!(!Supervisor::start(move |_| Counted(supervised)).connected());
+    actix_rt::task::yield_now().await;
+
+    // the rejected actor was never started
+    assert_eq!(started.load(Ordering::SeqCst), 2);
+    assert_eq!(sys.actor_count(), 2);
+
+    first.stop_and_wait().await;
+    actix_rt::task::yield_now().await;
+    assert_eq!(sys.actor_count(), 1);
+
+    Counted(Arc::clone(&started)).try_start().unwrap();
+    assert_eq!(sys.actor_count(), 2);
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Boom;
+
+struct Fragile;
+
+impl Actor for Fragile {
+    type Context = Context<Self>;
+}
+
+impl Handler<Boom> for Fragile {
+    type Result = ();
+
+    fn handle(&mut self, _: Boom, _: &mut Self::Context) {
+        panic!("boom");
+    }
+}
+
+impl Handler<Num> for Fragile {
+    type Result = ();
+
+    fn handle(&mut self, _: Num, _: &mut Self::Context) {}
+}
+
+#[actix::test]
+async fn test_panic_policy_isolates_actor() {
+    assert_eq!(actix::panic_policy(), PanicPolicy::Propagate);
+    actix::set_panic_policy(PanicPolicy::Isolate);
+
+    let fragile = Fragile.start();
+    let bystander = Fragile.start();
+
+    assert_eq!(fragile.send(Boom).await, Err(MailboxError::Closed));
+    assert!(!fragile.connected());
+
+    // the arbiter keeps running other actors
+    bystander.send(Num(1)).await.unwrap();
+}
@@ -11,7 +11,8 @@ use std::{
 use actix::{prelude::*, WeakRecipient};
 use actix_rt::time::sleep;
 
-#[derive(Debug)]
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
 struct Ping(usize);
 
 impl Message for Ping {
@@ -452,6 +453,8 @@ fn test_address_eq() {
     });
 }
 
+// addresses hash by the identity of their actor, not by its mutable state
+#[allow(clippy::mutable_key_type)]
 #[test]
 fn test_address_hash() {
     let count0 = Arc::new(AtomicUsize::new(0));
@@ -543,3 +546,1474 @@ fn test_recipient_hash() {
         System::current().stop();
     });
 }
+
+#[actix::test]
+async fn test_recipient_set_remove() {
+    let addr1 = MyActor3.start();
+    let addr2 = MyActor3.start();
+
+    let mut subscribers = HashSet::new();
+    subscribers.insert(addr1.clone().recipient::<Ping>());
+    subscribers.insert(addr1.clone().recipient::<Ping>());
+    subscribers.insert(addr2.clone().recipient::<Ping>());
+    assert_eq!(subscribers.len(), 2);
+
+    assert!(subscribers.remove(&addr1.recipient()));
+    assert_eq!(
+        subscribers.into_iter().collect::<Vec<_>>(),
+        [addr2.recipient()]
+    );
+}
+
+#[actix::test]
+async fn test_recipient_debug() {
+    System::current().enable_actor_directory();
+    let recipient = MyActor3.start().recipient::<Ping>();
+    actix_rt::task::yield_now().await;
+
+    let id = System::current().actors()[0].id;
+    assert_eq!(
+        format!("{:?}", recipient),
+        format!(
+            "Recipient {{ actor: {:?}, message: \"test_address::Ping\" }}",
+            id
+        ),
+    );
+}
+
+#[actix::test]
+async fn test_send_join() {
+    let addr = PingCounterActor::default().start();
+
+    let (ping, count) = addr.send_join((Ping(0), CountPings)).await;
+    ping.unwrap();
+    assert_eq!(count.unwrap(), 1);
+
+    let (_, _, _, count) = addr
+        .send_join((Ping(1), Ping(2), Ping(3), CountPings))
+        .await;
+    assert_eq!(count.unwrap(), 4);
+}
+
+#[actix::test]
+async fn test_stop_and_wait() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let addr = MyActor(Arc::clone(&count)).start();
+
+    for i in 0..5 {
+        addr.do_send(Ping(i));
+    }
+    let stopped = addr.stop_and_wait();
+    let late = addr.send(Ping(5));
+
+    stopped.await;
+    assert_eq!(count.load(Ordering::Relaxed), 5);
+    assert_eq!(late.await, Err(MailboxError::Closed));
+
+    // actor is already stopped
+    addr.stop_and_wait().await;
+}
+
+/// Keeps its mailbox paused until `resume` fires.
+struct Paused(Option<tokio::sync::oneshot::Receiver<()>>);
+
+impl Actor for Paused {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.pause();
+        fut::wrap_future::<_, Self>(self.0.take().unwrap())
+            .map(|_, _, ctx| ctx.resume())
+            .spawn(ctx);
+    }
+}
+
+#[actix::test]
+async fn test_stop_and_wait_paused() {
+    use futures_util::FutureExt as _;
+
+    let (resume, rx) = tokio::sync::oneshot::channel();
+    let addr = Paused(Some(rx)).start();
+    let mut stopped = Box::pin(addr.stop_and_wait());
+
+    // the stop waits behind the paused mailbox
+    for _ in 0..3 {
+        actix_rt::task::yield_now().await;
+    }
+    assert!((&mut stopped).now_or_never().is_none());
+
+    resume.send(()).unwrap();
+    stopped.await;
+}
+
+mod path_registry {
+    use actix::registry::{ActorPath, PathRegistry};
+
+    use super::*;
+
+    fn path(path: &str) -> ActorPath {
+        ActorPath::new(path).unwrap()
+    }
+
+    #[test]
+    fn actor_path() {
+        assert!(ActorPath::new("user").is_err());
+        assert!(ActorPath::new("/user//sessions").is_err());
+        assert!(ActorPath::new("/user/*").is_err());
+
+        let sessions = path("/user/sessions");
+        let session = sessions.join("42").unwrap();
+        assert_eq!(session.as_str(), "/user/sessions/42");
+        assert_eq!(session.parent(), Some(sessions.clone()));
+        assert_eq!(path("/user").parent(), Some(ActorPath::root()));
+        assert!(session.starts_with(&sessions));
+        assert!(!path("/user/sessions2").starts_with(&sessions));
+        assert!(sessions.join("a/b").is_err());
+    }
+
+    #[actix::test]
+    async fn resolve_list_and_issue() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let one = MyActor(Arc::clone(&count)).start();
+        let two = MyActor(Arc::clone(&count)).start();
+        let other = MyActor(Arc::clone(&count)).start();
+
+        PathRegistry::register(path("/user/sessions/1"), one.clone().recipient::<Ping>());
+        PathRegistry::register(path("/user/sessions/2"), two.clone().recipient::<Ping>());
+        PathRegistry::register(path("/user/other"), other.clone().recipient::<Ping>());
+
+        // lookups are checked against the message type
+        assert!(PathRegistry::resolve::<Ping>(&path("/user/sessions/1")).is_some());
+        assert!(PathRegistry::resolve::<CountPings>(&path("/user/sessions/1")).is_none());
+        assert!(PathRegistry::resolve::<Ping>(&path("/user/sessions/3")).is_none());
+
+        assert_eq!(
+            PathRegistry::list(&path("/user/sessions")),
+            [path("/user/sessions/1"), path("/user/sessions/2")]
+        );
+        assert_eq!(PathRegistry::list(&ActorPath::root()).len(), 3);
+
+        assert_eq!(PathRegistry::issue("/user/sessions/*", Ping(0)), 2);
+        assert_eq!(PathRegistry::issue("/user/**", Ping(0)), 3);
+        assert_eq!(PathRegistry::issue("/user/*", Ping(0)), 1);
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(count.load(Ordering::Relaxed), 6);
+
+        // stopped actors are removed
+        two.stop_and_wait().await;
+        assert_eq!(
+            PathRegistry::list(&path("/user/sessions")),
+            [path("/user/sessions/1")]
+        );
+
+        PathRegistry::unregister(&path("/user/other"));
+        assert!(PathRegistry::resolve::<Ping>(&path("/user/other")).is_none());
+    }
+
+    /// Registers itself under its path once started.
+    struct Session(&'static str, Arc<AtomicUsize>);
+
+    impl Actor for Session {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Context<Self>) {
+            ctx.register_path::<Ping>(path(self.0));
+        }
+    }
+
+    impl Handler<Ping> for Session {
+        type Result = ();
+
+        fn handle(&mut self, _: Ping, _: &mut Context<Self>) {
+            self.1.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[actix::test]
+    async fn registered_from_the_context() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let first = Session("/user/session", Arc::clone(&count)).start();
+        first.wait_state(ActorState::Running).await;
+        assert!(PathRegistry::resolve::<Ping>(&path("/user/session")).is_some());
+
+        // stopping the first session leaves the replacing registration in place
+        let second = Session("/user/session", Arc::clone(&count)).start();
+        second.wait_state(ActorState::Running).await;
+        first.stop_and_wait().await;
+        let session = PathRegistry::resolve::<Ping>(&path("/user/session")).unwrap();
+        session.send(Ping(0)).await.unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+
+        second.stop_and_wait().await;
+        assert!(PathRegistry::list(&ActorPath::root()).is_empty());
+    }
+}
+
+mod retry_on_closed {
+    use super::*;
+
+    #[derive(Clone, Message)]
+    #[rtype(result = "usize")]
+    struct Attempt;
+
+    /// Restarts while handling the first `fail` attempts, dropping their responses.
+    struct Flaky {
+        fail: usize,
+        attempts: usize,
+    }
+
+    impl Actor for Flaky {
+        type Context = Context<Self>;
+    }
+
+    impl Supervised for Flaky {}
+
+    impl Handler<Attempt> for Flaky {
+        type Result = ResponseActFuture<Self, usize>;
+
+        fn handle(&mut self, _: Attempt, ctx: &mut Self::Context) -> Self::Result {
+            self.attempts += 1;
+            if self.attempts <= self.fail {
+                ctx.stop();
+            }
+            Box::pin(fut::ready(self.attempts))
+        }
+    }
+
+    #[actix::test]
+    async fn retries_until_delivered() {
+        let addr = Supervisor::start(|_| Flaky {
+            fail: 2,
+            attempts: 0,
+        });
+        let res = addr
+            .send_retry_on_closed(Attempt, 3, Duration::from_millis(1))
+            .await;
+        assert_eq!(res, Ok(3));
+    }
+
+    #[actix::test]
+    async fn gives_up_after_attempts() {
+        let addr = Supervisor::start(|_| Flaky {
+            fail: 4,
+            attempts: 0,
+        });
+        let res = addr
+            .send_retry_on_closed(Attempt, 3, Duration::from_millis(1))
+            .await;
+        assert_eq!(res, Err(MailboxError::Closed));
+        assert_eq!(addr.send(Attempt).await, Err(MailboxError::Closed));
+        assert_eq!(addr.send(Attempt).await, Ok(5));
+    }
+}
+
+mod addr_sink {
+    use futures_util::{stream, StreamExt as _};
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Item;
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct Count;
+
+    /// Keeps its mailbox paused until `resume` fires.
+    struct Consumer {
+        resume: Option<oneshot::Receiver<()>>,
+        handled: usize,
+    }
+
+    impl Actor for Consumer {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.pause();
+            fut::wrap_future::<_, Self>(self.resume.take().unwrap())
+                .map(|_, _, ctx| ctx.resume())
+                .spawn(ctx);
+        }
+    }
+
+    impl Handler<Item> for Consumer {
+        type Result = ();
+
+        fn handle(&mut self, _: Item, _: &mut Self::Context) {
+            self.handled += 1;
+        }
+    }
+
+    impl Handler<Count> for Consumer {
+        type Result = usize;
+
+        fn handle(&mut self, _: Count, _: &mut Self::Context) -> usize {
+            self.handled
+        }
+    }
+
+    #[actix::test]
+    async fn backpressure_across_threads() {
+        let (resume, rx) = oneshot::channel();
+        let addr = Consumer::create(|ctx| {
+            ctx.set_mailbox_capacity(2);
+            Consumer {
+                resume: Some(rx),
+                handled: 0,
+            }
+        });
+
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let producer = {
+            let sink = addr.sink::<Item>();
+            let pulled = Arc::clone(&pulled);
+            std::thread::spawn(move || {
+                actix_rt::Runtime::new().unwrap().block_on(
+                    stream::iter(0..20)
+                        .inspect(move |_| {
+                            pulled.fetch_add(1, Ordering::SeqCst);
+                        })
+                        .map(|_| Ok(Item))
+                        .forward(sink),
+                )
+            })
+        };
+
+        // the source is throttled while the mailbox is full
+        sleep(Duration::from_millis(100)).await;
+        assert!(pulled.load(Ordering::SeqCst) <= 3);
+
+        resume.send(()).unwrap();
+        actix_rt::task::spawn_blocking(move || producer.join().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(pulled.load(Ordering::SeqCst), 20);
+        assert_eq!(addr.send(Count).await.unwrap(), 20);
+    }
+
+    #[actix::test]
+    async fn closed_mailbox_fails() {
+        let (resume, rx) = oneshot::channel();
+        let addr = Consumer {
+            resume: Some(rx),
+            handled: 0,
+        }
+        .start();
+        resume.send(()).unwrap();
+
+        let sink = addr.sink::<Item>();
+        addr.stop_and_wait().await;
+
+        let res = stream::iter([Ok(Item)]).forward(sink).await;
+        assert_eq!(res, Err(MailboxError::Closed));
+    }
+}
+
+mod request_default {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Lookup(u32);
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Slow;
+
+    struct Cache;
+
+    impl Actor for Cache {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Lookup> for Cache {
+        type Result = u32;
+
+        fn handle(&mut self, Lookup(n): Lookup, _: &mut Self::Context) -> u32 {
+            n * 2
+        }
+    }
+
+    impl Handler<Slow> for Cache {
+        type Result = ResponseFuture<u32>;
+
+        fn handle(&mut self, _: Slow, _: &mut Self::Context) -> Self::Result {
+            Box::pin(async {
+                sleep(Duration::from_secs(10)).await;
+                0
+            })
+        }
+    }
+
+    #[actix::test]
+    async fn answers_are_kept() {
+        let addr = Cache.start();
+        assert_eq!(addr.send(Lookup(2)).or_default(0).await, 4);
+    }
+
+    #[actix::test]
+    async fn falls_back_on_timeout() {
+        let addr = Cache.start();
+        let res = addr
+            .send(Slow)
+            .timeout(Duration::from_millis(10))
+            .or_default(7)
+            .await;
+        assert_eq!(res, 7);
+    }
+
+    #[actix::test]
+    async fn falls_back_on_closed() {
+        let addr = Cache.start();
+        let recipient = addr.clone().recipient::<Lookup>();
+        addr.stop_and_wait().await;
+
+        let res = recipient
+            .send(Lookup(2))
+            .or_else_default(|err| {
+                assert_eq!(err, MailboxError::Closed);
+                1
+            })
+            .await;
+        assert_eq!(res, 1);
+    }
+}
+
+mod reply_to {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Double(u32);
+
+    struct Doubler;
+
+    impl Actor for Doubler {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Double> for Doubler {
+        type Result = u32;
+
+        fn handle(&mut self, Double(n): Double, _: &mut Self::Context) -> u32 {
+            n * 2
+        }
+    }
+
+    #[derive(Default)]
+    struct Collector {
+        replies: Arc<Mutex<Vec<u32>>>,
+        failures: Arc<Mutex<Vec<ReplyFailed>>>,
+    }
+
+    impl Actor for Collector {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Reply<u32>> for Collector {
+        type Result = ();
+
+        fn handle(&mut self, Reply(n): Reply<u32>, _: &mut Self::Context) {
+            self.replies.lock().unwrap().push(n);
+        }
+    }
+
+    impl Handler<ReplyFailed> for Collector {
+        type Result = ();
+
+        fn handle(&mut self, failed: ReplyFailed, _: &mut Self::Context) {
+            self.failures.lock().unwrap().push(failed);
+        }
+    }
+
+    #[actix::test]
+    async fn routes_result_to_continuation() {
+        let collector = Collector::default();
+        let replies = Arc::clone(&collector.replies);
+        let collector = collector.start();
+
+        let doubler = Doubler.start();
+        doubler.send_reply_to(Double(2), collector.clone().recipient());
+        doubler.send_reply_to(Double(5), collector.recipient());
+
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(*replies.lock().unwrap(), [4, 10]);
+    }
+
+    #[actix::test]
+    async fn routes_error_to_error_recipient() {
+        let collector = Collector::default();
+        let replies = Arc::clone(&collector.replies);
+        let failures = Arc::clone(&collector.failures);
+        let collector = collector.start();
+
+        let doubler = Doubler.start();
+        doubler.stop_and_wait().await;
+        doubler.send_reply_to_or(
+            Double(2),
+            collector.clone().recipient(),
+            collector.recipient(),
+        );
+
+        sleep(Duration::from_millis(20)).await;
+        assert!(replies.lock().unwrap().is_empty());
+        let failures = failures.lock().unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].error, MailboxError::Closed);
+        assert!(failures[0].message_type.ends_with("Double"));
+    }
+}
+
+mod blocking_sender {
+    use std::thread;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Item;
+
+    struct Slow(Arc<AtomicUsize>);
+
+    impl Actor for Slow {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.set_mailbox_capacity(1);
+        }
+    }
+
+    impl Handler<Item> for Slow {
+        type Result = ();
+
+        fn handle(&mut self, _: Item, _: &mut Self::Context) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[actix::test]
+    async fn blocks_while_mailbox_is_full() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let addr = Slow(Arc::clone(&count)).start();
+        let sender = addr.clone().into_sender::<Item>();
+
+        let producer = thread::spawn(move || {
+            for _ in 0..10 {
+                sender.send(Item).unwrap();
+            }
+            assert!(matches!(
+                sender.try_send(Item),
+                Ok(()) | Err(SendError::Full(_))
+            ));
+        });
+
+        while !producer.is_finished() {
+            sleep(Duration::from_millis(1)).await;
+        }
+        producer.join().unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert!(count.load(Ordering::SeqCst) >= 10);
+    }
+
+    #[actix::test]
+    async fn fails_once_mailbox_closed() {
+        let addr = Slow(Arc::new(AtomicUsize::new(0))).start();
+        let sender = addr.clone().into_sender::<Item>();
+        assert!(!sender.is_closed());
+
+        addr.stop_and_wait().await;
+        drop(addr);
+        sleep(Duration::from_millis(10)).await;
+
+        assert!(sender.is_closed());
+        let res = thread::spawn(move || sender.send(Item)).join().unwrap();
+        assert!(matches!(res, Err(SendError::Closed(Item))));
+    }
+}
+
+mod no_response {
+    use actix::dev::{MessageResponse, OneshotSender};
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "Forgotten")]
+    struct Forget;
+
+    #[derive(Message)]
+    #[rtype(result = "Forgotten")]
+    struct ForgetAndStop;
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Abandon;
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct StopPending;
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Echo(u32);
+
+    /// A response that never sends its reply.
+    #[derive(Debug, PartialEq)]
+    struct Forgotten;
+
+    impl<A: Actor, M: Message<Result = Forgotten>> MessageResponse<A, M> for Forgotten {
+        fn handle(self, _: &mut A::Context, _: Option<OneshotSender<M::Result>>) {}
+    }
+
+    struct Forgetful;
+
+    impl Actor for Forgetful {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Forget> for Forgetful {
+        type Result = Forgotten;
+
+        fn handle(&mut self, _: Forget, _: &mut Self::Context) -> Forgotten {
+            Forgotten
+        }
+    }
+
+    impl Handler<ForgetAndStop> for Forgetful {
+        type Result = Forgotten;
+
+        fn handle(&mut self, _: ForgetAndStop, ctx: &mut Self::Context) -> Forgotten {
+            ctx.stop();
+            Forgotten
+        }
+    }
+
+    impl Handler<StopPending> for Forgetful {
+        type Result = ResponseActFuture<Self, u32>;
+
+        fn handle(&mut self, _: StopPending, ctx: &mut Self::Context) -> Self::Result {
+            ctx.stop();
+            Box::pin(fut::wrap_future(std::future::pending()))
+        }
+    }
+
+    impl Handler<Abandon> for Forgetful {
+        type Result = Response<u32>;
+
+        fn handle(&mut self, _: Abandon, _: &mut Self::Context) -> Self::Result {
+            Response::from_request(async { Err(MailboxError::Timeout) })
+        }
+    }
+
+    impl Handler<Echo> for Forgetful {
+        type Result = u32;
+
+        fn handle(&mut self, msg: Echo, _: &mut Self::Context) -> u32 {
+            msg.0
+        }
+    }
+
+    #[actix::test]
+    async fn handler_without_reply() {
+        let addr = Forgetful.start();
+        assert_eq!(addr.send(Forget).await, Err(MailboxError::NoResponse));
+        assert_eq!(
+            addr.clone().recipient().send(Forget).await,
+            Err(MailboxError::NoResponse)
+        );
+        // the error of a forwarded request carries over
+        assert_eq!(addr.send(Abandon).await, Err(MailboxError::Timeout));
+
+        // the actor keeps handling messages
+        assert_eq!(addr.send(Echo(7)).await, Ok(7));
+    }
+
+    #[actix::test]
+    async fn handler_without_reply_stopping_the_actor() {
+        let addr = Forgetful.start();
+        assert_eq!(
+            addr.send(ForgetAndStop).await,
+            Err(MailboxError::NoResponse)
+        );
+        assert!(!addr.connected());
+    }
+
+    #[actix::test]
+    async fn response_cancelled_by_stopping() {
+        let addr = Forgetful.start();
+        assert_eq!(addr.send(StopPending).await, Err(MailboxError::Closed));
+    }
+}
+
+mod audit {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Relay;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Relayed;
+
+    struct Relayer(Option<Addr<Relayer>>);
+
+    impl Actor for Relayer {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Relay> for Relayer {
+        type Result = ();
+
+        fn handle(&mut self, _: Relay, _: &mut Self::Context) {
+            self.0.as_ref().unwrap().do_send(Relayed);
+        }
+    }
+
+    impl Handler<Relayed> for Relayer {
+        type Result = ();
+
+        fn handle(&mut self, _: Relayed, _: &mut Self::Context) {}
+    }
+
+    #[actix::test]
+    async fn records_senders_in_order() {
+        System::current().enable_audit(16);
+
+        let sink = Relayer(None).start();
+        let relay = Relayer(Some(sink.clone())).start();
+        relay.send(Relay).await.unwrap();
+        sink.send(Relayed).await.unwrap();
+
+        let audit = System::current().dump_audit();
+        let seqs = audit.iter().map(|entry| entry.seq).collect::<Vec<_>>();
+        assert_eq!(seqs, [0, 1, 2]);
+
+        // sent from outside of an actor, then by the relay
+        assert_eq!(audit[0].sender, None);
+        assert!(audit[0].message_type.ends_with("Relay"));
+        assert_eq!(audit[1].sender, Some(audit[0].receiver));
+        assert!(audit[1].message_type.ends_with("Relayed"));
+        assert_ne!(audit[1].receiver, audit[0].receiver);
+        assert_eq!(audit[2].receiver, audit[1].receiver);
+        assert!(audit[0].receiver_type.ends_with("Relayer"));
+        assert!(audit[1].time <= audit[2].time);
+    }
+
+    #[actix::test]
+    async fn keeps_latest_entries() {
+        let sink = Relayer(None).start();
+        sink.do_send(Relayed);
+        assert!(System::current().dump_audit().is_empty());
+
+        System::current().enable_audit(2);
+        for _ in 0..3 {
+            sink.do_send(Relayed);
+        }
+        let seqs = System::current()
+            .dump_audit()
+            .iter()
+            .map(|entry| entry.seq)
+            .collect::<Vec<_>>();
+        assert_eq!(seqs, [1, 2]);
+
+        System::current().disable_audit();
+        sink.do_send(Relayed);
+        assert!(System::current().dump_audit().is_empty());
+    }
+
+    #[test]
+    fn released_once_the_system_arbiter_stops() {
+        let sys = System::new();
+        let system = sys.block_on(async {
+            System::current().enable_audit(16);
+            Relayer(None).start().send(Relayed).await.unwrap();
+            assert_eq!(System::current().dump_audit().len(), 1);
+            System::current()
+        });
+
+        drop(sys);
+        assert!(system.dump_audit().is_empty());
+    }
+}
+
+mod send_then {
+    use std::{cell::RefCell, rc::Rc, sync::atomic::AtomicBool, thread};
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Double(u32);
+
+    struct Doubler;
+
+    impl Actor for Doubler {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Double> for Doubler {
+        type Result = ResponseFuture<u32>;
+
+        fn handle(&mut self, Double(n): Double, _: &mut Self::Context) -> Self::Result {
+            Box::pin(async move {
+                sleep(Duration::from_millis(n as u64)).await;
+                n * 2
+            })
+        }
+    }
+
+    #[actix::test]
+    async fn calls_back_on_sender_thread() {
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let sender = thread::current().id();
+
+        let addr = Doubler.start();
+        for n in [5, 1] {
+            let results = Rc::clone(&results);
+            addr.send_then(Double(n), move |res| {
+                assert_eq!(thread::current().id(), sender);
+                results.borrow_mut().push(res);
+            });
+        }
+        assert!(results.borrow().is_empty());
+
+        sleep(Duration::from_millis(30)).await;
+        assert_eq!(*results.borrow(), [Ok(2), Ok(10)]);
+    }
+
+    #[actix::test]
+    async fn calls_back_with_error() {
+        let addr = Doubler.start();
+        addr.stop_and_wait().await;
+
+        let result = Rc::new(RefCell::new(None));
+        let res = Rc::clone(&result);
+        addr.send_then(Double(1), move |r| *res.borrow_mut() = Some(r));
+
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(*result.borrow(), Some(Err(MailboxError::Closed)));
+    }
+
+    /// Sets its flag when dropped.
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[actix::test]
+    async fn drops_callback_when_sender_arbiter_stops() {
+        let called = Arc::new(AtomicBool::new(false));
+        let dropped = Arc::new(AtomicBool::new(false));
+
+        let addr = Doubler.start();
+        let arbiter = Arbiter::new();
+        {
+            let called = Arc::clone(&called);
+            let flag = DropFlag(Arc::clone(&dropped));
+            arbiter.spawn_fn(move || {
+                addr.send_then(Double(50), move |_| {
+                    let _flag = flag;
+                    called.store(true, Ordering::SeqCst);
+                });
+            });
+        }
+
+        sleep(Duration::from_millis(10)).await;
+        arbiter.stop();
+
+        sleep(Duration::from_millis(80)).await;
+        assert!(dropped.load(Ordering::SeqCst));
+        assert!(!called.load(Ordering::SeqCst));
+    }
+}
+
+mod dead_letter {
+    use super::*;
+
+    #[derive(Message, Debug, PartialEq)]
+    #[rtype(result = "()")]
+    struct Write(u32);
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Other;
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<u32>")]
+    struct Received;
+
+    #[derive(Default)]
+    struct Store(Vec<u32>);
+
+    impl Actor for Store {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Write> for Store {
+        type Result = ();
+
+        fn handle(&mut self, Write(n): Write, _: &mut Self::Context) {
+            self.0.push(n);
+        }
+    }
+
+    impl Handler<Other> for Store {
+        type Result = ();
+
+        fn handle(&mut self, _: Other, _: &mut Self::Context) {}
+    }
+
+    impl Handler<Received> for Store {
+        type Result = MessageResult<Received>;
+
+        fn handle(&mut self, _: Received, _: &mut Self::Context) -> Self::Result {
+            MessageResult(self.0.clone())
+        }
+    }
+
+    #[actix::test]
+    async fn routes_undeliverable_messages_by_type() {
+        let letters = Store::default().start();
+        assert!(Arbiter::set_dead_letter_for::<Write>(letters.clone().recipient()).is_none());
+
+        let live = Store::default().start();
+        let stopped = Store::default().start();
+        let recipient = stopped.clone().recipient::<Write>();
+        stopped.stop_and_wait().await;
+
+        live.do_send(Write(1));
+        stopped.do_send(Write(2));
+        recipient.do_send(Write(3));
+        stopped.do_send(Other);
+
+        assert_eq!(live.send(Received).await.unwrap(), [1]);
+        assert_eq!(letters.send(Received).await.unwrap(), [2, 3]);
+
+        assert!(Arbiter::remove_dead_letter_for::<Write>().is_some());
+        stopped.do_send(Write(4));
+        assert_eq!(letters.send(Received).await.unwrap(), [2, 3]);
+    }
+
+    #[actix::test]
+    async fn keeps_recipients_per_arbiter() {
+        let letters = Store::default().start();
+        Arbiter::set_dead_letter_for::<Write>(letters.clone().recipient());
+
+        let stopped = Store::default().start();
+        stopped.stop_and_wait().await;
+
+        let arbiter = Arbiter::new();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        arbiter.spawn_fn(move || {
+            stopped.do_send(Write(1));
+            tx.send(()).unwrap();
+        });
+        rx.await.unwrap();
+
+        assert!(letters.send(Received).await.unwrap().is_empty());
+        arbiter.stop();
+    }
+
+    #[actix::test]
+    async fn drops_dead_letter_if_its_recipient_is_gone() {
+        let letters = Store::default().start();
+        Arbiter::set_dead_letter_for::<Write>(letters.clone().recipient());
+        letters.stop_and_wait().await;
+
+        let stopped = Store::default().start();
+        stopped.stop_and_wait().await;
+        stopped.do_send(Write(1));
+    }
+
+    /// Starts a store with a mailbox for a single message, which it only handles after `busy`.
+    fn busy_store(busy: Duration) -> Addr<Store> {
+        Store::create(move |ctx| {
+            ctx.set_mailbox_capacity(1);
+            ctx.wait(fut::wrap_future(sleep(busy)));
+            Store::default()
+        })
+    }
+
+    #[actix::test]
+    async fn retries_until_mailbox_frees_up() {
+        let letters = Store::default().start();
+        Arbiter::set_dead_letter_for::<Write>(letters.clone().recipient());
+
+        let store = busy_store(Duration::from_millis(30));
+        store.try_send(Write(1)).unwrap();
+        assert!(matches!(store.try_send(Write(2)), Err(SendError::Full(_))));
+
+        store.do_send_retry(Write(2), 10, Duration::from_millis(10));
+        sleep(Duration::from_millis(80)).await;
+
+        assert_eq!(store.send(Received).await.unwrap(), [1, 2]);
+        assert!(letters.send(Received).await.unwrap().is_empty());
+    }
+
+    #[actix::test]
+    async fn dead_letters_after_last_retry() {
+        let letters = Store::default().start();
+        Arbiter::set_dead_letter_for::<Write>(letters.clone().recipient());
+
+        let store = busy_store(Duration::from_millis(200));
+        store.try_send(Write(1)).unwrap();
+
+        store.do_send_retry(Write(2), 3, Duration::from_millis(5));
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(letters.send(Received).await.unwrap(), [2]);
+
+        assert_eq!(store.send(Received).await.unwrap(), [1]);
+    }
+
+    #[test]
+    fn dead_letters_right_away_outside_runtime() {
+        let sys = System::new();
+        let (letters, store) = sys.block_on(async {
+            let letters = Store::default().start();
+            Arbiter::set_dead_letter_for::<Write>(letters.clone().recipient());
+
+            let store = busy_store(Duration::from_millis(200));
+            store.try_send(Write(1)).unwrap();
+            (letters, store)
+        });
+
+        // the system is not running, so there is nothing to retry on
+        store.do_send_retry(Write(2), 3, Duration::from_millis(5));
+
+        sys.block_on(async move {
+            assert_eq!(letters.send(Received).await.unwrap(), [2]);
+            assert_eq!(store.send(Received).await.unwrap(), [1]);
+        });
+    }
+}
+
+mod pool {
+    use super::*;
+
+    #[derive(Clone, Message)]
+    #[rtype(result = "usize")]
+    struct Scale(usize);
+
+    struct Worker(usize);
+
+    impl Actor for Worker {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Scale> for Worker {
+        type Result = ResponseFuture<usize>;
+
+        fn handle(&mut self, Scale(n): Scale, _: &mut Context<Self>) -> Self::Result {
+            // later workers reply first
+            let (factor, delay) = (self.0, 10 * (3 - self.0 as u64));
+            Box::pin(async move {
+                sleep(Duration::from_millis(delay)).await;
+                n * factor
+            })
+        }
+    }
+
+    #[actix::test]
+    async fn collects_replies_in_worker_order() {
+        let pool: Pool<Worker> = (1..=3).map(|i| Worker(i).start()).collect();
+        assert_eq!(pool.len(), 3);
+
+        let replies = pool.broadcast_collect(Scale(10)).await;
+        assert_eq!(replies, [Ok(10), Ok(20), Ok(30)]);
+    }
+
+    #[actix::test]
+    async fn reports_failed_workers() {
+        let pool = Pool::new(vec![Worker(1).start(), Worker(2).start()]);
+        pool.workers()[0].stop_and_wait().await;
+
+        let replies = pool.broadcast_collect(Scale(10)).await;
+        assert_eq!(replies, [Err(MailboxError::Closed), Ok(20)]);
+    }
+
+    #[actix::test]
+    async fn empty_pool_resolves_right_away() {
+        let pool = Pool::<Worker>::new(Vec::new());
+        assert!(pool.is_empty());
+        assert!(pool.broadcast_collect(Scale(1)).await.is_empty());
+    }
+}
+
+mod progress {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "&'static str")]
+    struct Download(u32, bool);
+
+    struct Downloader;
+
+    impl Actor for Downloader {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<WithProgress<Download, u32>> for Downloader {
+        type Result = ();
+
+        fn handle(&mut self, msg: WithProgress<Download, u32>, ctx: &mut Context<Self>) {
+            let (Download(chunks, finish), responder) = msg.into_parts();
+            ctx.spawn(
+                async move {
+                    for chunk in 1..=chunks {
+                        actix_rt::time::sleep(Duration::from_millis(1)).await;
+                        responder.progress(chunk);
+                    }
+                    if finish {
+                        responder.finish("done");
+                    }
+                }
+                .into_actor(self),
+            );
+        }
+    }
+
+    #[actix::test]
+    async fn test_progress_then_final_result() {
+        let addr = Downloader.start();
+
+        let mut progress = Vec::new();
+        let res = addr
+            .send_with_progress(Download(3, true), |chunk| progress.push(chunk))
+            .await;
+        assert_eq!(res, Ok("done"));
+        assert_eq!(progress, [1, 2, 3]);
+    }
+
+    #[actix::test]
+    async fn test_dropped_responder_fails_request() {
+        let addr = Downloader.start();
+
+        let mut progress = Vec::new();
+        let res = addr
+            .send_with_progress(Download(2, false), |chunk| progress.push(chunk))
+            .await;
+        assert_eq!(res, Err(MailboxError::NoResponse));
+        assert_eq!(progress, [1, 2]);
+    }
+}
+
+mod deadline {
+    use actix::clock::Instant;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "Option<Instant>")]
+    struct Deadline;
+
+    struct Back;
+
+    impl Actor for Back {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Deadline> for Back {
+        type Result = Option<Instant>;
+
+        fn handle(&mut self, _: Deadline, ctx: &mut Context<Self>) -> Self::Result {
+            ctx.message_deadline()
+        }
+    }
+
+    struct Front(Addr<Back>);
+
+    impl Actor for Front {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Deadline> for Front {
+        type Result = ResponseActFuture<Self, Option<Instant>>;
+
+        fn handle(&mut self, _: Deadline, ctx: &mut Context<Self>) -> Self::Result {
+            let own = ctx.message_deadline();
+            let direct = self.0.send(Deadline);
+            let back = self.0.clone();
+
+            Box::pin(
+                async move {
+                    // sent from a future spawned by the handler
+                    sleep(Duration::from_millis(10)).await;
+                    back.send(Deadline).await.unwrap()
+                }
+                .into_actor(self)
+                .then(move |later, _, _| {
+                    fut::wrap_future(async move {
+                        let direct = direct.await.unwrap();
+                        assert_eq!(direct, own);
+                        assert_eq!(later, own);
+                        own
+                    })
+                }),
+            )
+        }
+    }
+
+    #[actix::test]
+    async fn propagates_to_downstream_requests() {
+        let front = Front(Back.start()).start();
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        let seen = front.send_deadline(Deadline, deadline).await.unwrap();
+        assert_eq!(seen, Some(deadline));
+
+        // without a deadline, none is propagated
+        assert_eq!(front.send(Deadline).await.unwrap(), None);
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Slow;
+
+    impl Handler<Slow> for Back {
+        type Result = ResponseFuture<()>;
+
+        fn handle(&mut self, _: Slow, _: &mut Context<Self>) -> Self::Result {
+            Box::pin(sleep(Duration::from_millis(200)))
+        }
+    }
+
+    #[actix::test]
+    async fn request_fails_past_deadline() {
+        let back = Back.start();
+        let deadline = Instant::now() + Duration::from_millis(20);
+
+        let res = back.send_deadline(Slow, deadline).await;
+        assert!(matches!(res, Err(MailboxError::Timeout)));
+    }
+}
+
+mod wait_state {
+    use super::*;
+
+    /// Prevents the first attempt to stop it.
+    struct Stubborn {
+        refused: bool,
+    }
+
+    impl Actor for Stubborn {
+        type Context = Context<Self>;
+
+        fn stopping(&mut self, _: &mut Context<Self>) -> Running {
+            if self.refused {
+                Running::Stop
+            } else {
+                self.refused = true;
+                Running::Continue
+            }
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    enum Stop {
+        Graceful,
+        Terminate,
+    }
+
+    impl Handler<Stop> for Stubborn {
+        type Result = ();
+
+        fn handle(&mut self, msg: Stop, ctx: &mut Context<Self>) {
+            match msg {
+                Stop::Graceful => ctx.stop(),
+                Stop::Terminate => ctx.terminate(),
+            }
+        }
+    }
+
+    #[actix::test]
+    async fn resolves_for_reached_states() {
+        let addr = Stubborn { refused: false }.start();
+
+        addr.wait_state(ActorState::Started).await;
+        addr.wait_state(ActorState::Running).await;
+        assert!(addr.connected());
+    }
+
+    #[actix::test]
+    async fn resolves_on_stopping_even_if_prevented() {
+        let addr = Stubborn { refused: false }.start();
+        let stopping = addr.wait_state(ActorState::Stopping);
+        let stopped = addr.wait_state(ActorState::Stopped);
+
+        addr.do_send(Stop::Graceful);
+        stopping.await;
+        assert!(addr.connected());
+
+        addr.do_send(Stop::Graceful);
+        stopped.await;
+    }
+
+    #[actix::test]
+    async fn skipped_stopping_resolves_on_stop() {
+        let addr = Stubborn { refused: false }.start();
+        let stopping = addr.wait_state(ActorState::Stopping);
+
+        addr.do_send(Stop::Terminate);
+        stopping.await;
+        assert!(!addr.connected());
+
+        // the actor is gone
+        addr.wait_state(ActorState::Running).await;
+    }
+}
+
+mod pipeline {
+    use super::*;
+
+    struct Calc {
+        handled: Arc<AtomicUsize>,
+    }
+
+    impl Actor for Calc {
+        type Context = Context<Self>;
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "Result<u32, String>")]
+    struct Parse(&'static str);
+
+    impl Handler<Parse> for Calc {
+        type Result = Result<u32, String>;
+
+        fn handle(&mut self, msg: Parse, _: &mut Context<Self>) -> Self::Result {
+            self.handled.fetch_add(1, Ordering::SeqCst);
+            msg.0
+                .parse()
+                .map_err(|_| format!("not a number: {}", msg.0))
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Double(u32);
+
+    impl Handler<Double> for Calc {
+        type Result = u32;
+
+        fn handle(&mut self, msg: Double, _: &mut Context<Self>) -> u32 {
+            self.handled.fetch_add(1, Ordering::SeqCst);
+            msg.0 * 2
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "Result<u32, String>")]
+    struct Limit(u32);
+
+    impl Handler<Limit> for Calc {
+        type Result = Result<u32, String>;
+
+        fn handle(&mut self, msg: Limit, _: &mut Context<Self>) -> Self::Result {
+            self.handled.fetch_add(1, Ordering::SeqCst);
+            if msg.0 > 100 {
+                Err(format!("too large: {}", msg.0))
+            } else {
+                Ok(msg.0)
+            }
+        }
+    }
+
+    fn calc() -> (Addr<Calc>, Arc<AtomicUsize>) {
+        let handled = Arc::new(AtomicUsize::new(0));
+        let addr = Calc {
+            handled: Arc::clone(&handled),
+        }
+        .start();
+        (addr, handled)
+    }
+
+    #[actix::test]
+    async fn chains_results_across_actors() {
+        let (a, _) = calc();
+        let (b, _) = calc();
+        let (c, _) = calc();
+
+        let res = Pipeline::try_start(a.send(Parse("21")))
+            .then_send(move |_| b, Double)
+            .then_try_send(move |_| c, Limit)
+            .await;
+        assert_eq!(res, Ok(42));
+    }
+
+    #[actix::test]
+    async fn infallible_steps_fail_with_mailbox_error() {
+        let (a, _) = calc();
+        let (b, _) = calc();
+
+        let running = b.clone();
+        let res = Pipeline::start(a.send(Double(1)))
+            .then_send(move |_| running, Double)
+            .await;
+        assert_eq!(res, Ok(4));
+
+        b.stop_and_wait().await;
+        let res: Result<u32, MailboxError> = Pipeline::start(a.send(Double(1)))
+            .then_send(move |_| b, Double)
+            .await;
+        assert_eq!(res, Err(MailboxError::Closed));
+    }
+
+    #[actix::test]
+    async fn short_circuits_on_handler_error() {
+        let (a, _) = calc();
+        let (b, doubled) = calc();
+
+        let res = Pipeline::try_start(a.send(Parse("nope")))
+            .then_send(move |_| b, Double)
+            .await;
+        assert_eq!(
+            res,
+            Err(PipelineError::Handler("not a number: nope".to_owned()))
+        );
+        assert_eq!(doubled.load(Ordering::SeqCst), 0);
+    }
+
+    #[actix::test]
+    async fn short_circuits_on_mailbox_error() {
+        let (a, _) = calc();
+        let (b, _) = calc();
+        let (c, limited) = calc();
+
+        let stopped = b.clone();
+        b.stop_and_wait().await;
+
+        let res = Pipeline::try_start(a.send(Parse("21")))
+            .then_send(move |_| stopped, Double)
+            .then_try_send(move |_| c, Limit)
+            .await;
+        assert_eq!(res, Err(PipelineError::Mailbox(MailboxError::Closed)));
+        assert_eq!(limited.load(Ordering::SeqCst), 0);
+    }
+
+    #[actix::test]
+    async fn routes_by_previous_result() {
+        let (a, _) = calc();
+        let (small, small_handled) = calc();
+        let (large, large_handled) = calc();
+
+        let res = Pipeline::try_start(a.send(Parse("300")))
+            .then_try_send(move |n| if *n > 100 { large } else { small }, Limit)
+            .await;
+        assert_eq!(
+            res,
+            Err(PipelineError::Handler("too large: 300".to_owned()))
+        );
+        assert_eq!(small_handled.load(Ordering::SeqCst), 0);
+        assert_eq!(large_handled.load(Ordering::SeqCst), 1);
+    }
+}
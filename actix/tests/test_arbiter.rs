@@ -1,11 +1,16 @@
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
+use std::{
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, ThreadId},
 };
 
 use actix::prelude::*;
 use tokio::sync::oneshot;
 
+#[allow(dead_code)]
 #[derive(Debug)]
 struct Ping(usize);
 
@@ -59,3 +64,575 @@ fn test_start_actor_message() {
 
     assert_eq!(count.load(Ordering::Relaxed), 1);
 }
+
+struct ThreadName;
+
+impl Actor for ThreadName {
+    type Context = Context<Self>;
+}
+
+struct GetThreadName;
+
+impl Message for GetThreadName {
+    type Result = (Option<String>, usize);
+}
+
+impl Handler<GetThreadName> for ThreadName {
+    type Result = MessageResult<GetThreadName>;
+
+    fn handle(&mut self, _: GetThreadName, _: &mut Self::Context) -> Self::Result {
+        let name = std::thread::current().name().map(ToOwned::to_owned);
+        MessageResult((name, System::current().id()))
+    }
+}
+
+#[test]
+fn test_system_builder_workers() {
+    let sys = SystemBuilder::new()
+        .workers(2)
+        .thread_name_prefix("test-worker")
+        .stack_size(1024 * 1024)
+        .build()
+        .unwrap();
+
+    let workers = sys.workers().clone();
+    assert_eq!(workers.len(), 2);
+
+    sys.block_on(async move {
+        let mut names = Vec::new();
+        for _ in 0..3 {
+            let addr = ThreadName::start_in_arbiter(&workers.arbiter(), |_| ThreadName);
+            let (name, sys_id) = addr.send(GetThreadName).await.unwrap();
+
+            // workers belong to the built system
+            assert_eq!(sys_id, System::current().id());
+            names.push(name.unwrap());
+        }
+
+        assert_eq!(names, ["test-worker-0", "test-worker-1", "test-worker-0"]);
+        System::current().stop();
+    });
+
+    sys.run().unwrap();
+}
+
+#[cfg(feature = "rt-multi-thread")]
+#[test]
+fn test_system_builder_multi_thread_runtime() {
+    let sys = SystemBuilder::new()
+        .workers(0)
+        .runtime(RuntimeKind::MultiThread { worker_threads: 2 })
+        .build()
+        .unwrap();
+
+    sys.block_on(async {
+        let main = thread::current().id();
+        let addr = ThreadName.start();
+
+        // `Send` futures run on the pool and reach actors through their addresses
+        let (thread, res) = tokio::spawn(async move {
+            let res = addr.send(GetThreadName).await.unwrap();
+            (thread::current().id(), res)
+        })
+        .await
+        .unwrap();
+
+        assert_ne!(thread, main);
+        assert_eq!(res.1, System::current().id());
+        System::current().stop();
+    });
+
+    sys.run().unwrap();
+}
+
+/// Holds `!Send` state and records the threads of its lifecycle.
+struct Pinned {
+    _local: Rc<()>,
+    threads: Arc<Mutex<Vec<ThreadId>>>,
+}
+
+impl Pinned {
+    fn record(&self) {
+        let id = thread::current().id();
+        self.threads.lock().unwrap().push(id);
+    }
+}
+
+impl Actor for Pinned {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        self.record();
+    }
+}
+
+impl Handler<Ping> for Pinned {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) {
+        self.record();
+        ctx.stop();
+    }
+}
+
+impl Drop for Pinned {
+    fn drop(&mut self) {
+        self.record();
+    }
+}
+
+#[test]
+fn test_start_in_arbiter_keeps_actor_on_its_thread() {
+    let threads = Arc::new(Mutex::new(Vec::new()));
+
+    let sys = System::new();
+    let arbiter = Arbiter::new();
+    let arbiter_thread = {
+        let (tx, rx) = mpsc::channel();
+        arbiter.spawn_fn(move || tx.send(thread::current().id()).unwrap());
+        rx.recv().unwrap()
+    };
+
+    let recorded = Arc::clone(&threads);
+    sys.block_on(async move {
+        let addr = Pinned::start_in_arbiter(&arbiter.handle(), move |_| {
+            let act = Pinned {
+                _local: Rc::new(()),
+                threads: recorded,
+            };
+            act.record();
+            act
+        });
+        addr.send(Ping(0)).await.unwrap();
+        while addr.connected() {
+            actix_rt::task::yield_now().await;
+        }
+        arbiter.stop();
+        arbiter.join().unwrap();
+    });
+
+    // created, started, handling and dropped on the arbiter thread
+    let threads = threads.lock().unwrap();
+    assert_eq!(*threads, [arbiter_thread; 4]);
+    assert_ne!(arbiter_thread, thread::current().id());
+}
+
+struct Collector(Arc<AtomicUsize>);
+
+impl Actor for Collector {
+    type Context = Context<Self>;
+}
+
+impl Handler<Done<usize>> for Collector {
+    type Result = ();
+
+    fn handle(&mut self, Done(n): Done<usize>, _: &mut Self::Context) {
+        self.0.store(n, Ordering::SeqCst);
+        System::current().stop();
+    }
+}
+
+#[test]
+fn test_spawn_and_notify() {
+    let result = Arc::new(AtomicUsize::new(0));
+    let act_result = Arc::clone(&result);
+
+    let sys = System::new();
+
+    sys.block_on(async move {
+        let arbiter = Arbiter::new();
+        let addr = Collector(act_result).start();
+
+        // the recipient being gone does not affect the detached future
+        let gone = Collector(Arc::new(AtomicUsize::new(0))).start();
+        let recipient = gone.clone().recipient();
+        gone.stop_and_wait().await;
+        drop(gone);
+        assert!(arbiter.spawn_and_notify(async { 1 }, recipient));
+
+        assert!(arbiter.spawn_and_notify(async { 42 }, addr.recipient()));
+    });
+
+    sys.run().unwrap();
+
+    assert_eq!(result.load(Ordering::SeqCst), 42);
+}
+
+#[test]
+fn test_spawn_fallible() {
+    let (tx, rx) = mpsc::channel();
+    let _sys = System::new();
+
+    let arbiter = Arbiter::with_error_handler(move |err| {
+        tx.send((err.to_string(), thread::current().id())).unwrap();
+    });
+    let handle = arbiter.handle();
+
+    assert!(arbiter.spawn_fallible(async { Ok::<_, String>(()) }));
+    assert!(handle.spawn_fallible(async { Err("first") }));
+    assert!(arbiter.spawn_fallible(async { Err(std::fmt::Error) }));
+
+    let (first, first_thread) = rx.recv().unwrap();
+    let (second, second_thread) = rx.recv().unwrap();
+    assert_eq!(first, "first");
+    assert_eq!(second, std::fmt::Error.to_string());
+    assert_eq!(first_thread, second_thread);
+    assert_ne!(first_thread, thread::current().id());
+
+    // arbiters without a handler log the error and keep running
+    let plain = Arbiter::new();
+    assert!(plain.spawn_fallible(async { Err("logged") }));
+    let (done_tx, done_rx) = mpsc::channel();
+    assert!(plain.spawn_fn(move || done_tx.send(()).unwrap()));
+    done_rx.recv().unwrap();
+
+    arbiter.stop();
+    arbiter.join().unwrap();
+    plain.stop();
+    plain.join().unwrap();
+    assert!(rx.try_recv().is_err());
+}
+
+mod isolation {
+    use std::sync::Barrier;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Tally(usize);
+
+    impl Actor for Tally {
+        type Context = Context<Self>;
+    }
+
+    impl Supervised for Tally {}
+    impl SystemService for Tally {}
+    impl ArbiterService for Tally {}
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct Bump;
+
+    impl Handler<Bump> for Tally {
+        type Result = usize;
+
+        fn handle(&mut self, _: Bump, _: &mut Context<Self>) -> usize {
+            self.0 += 1;
+            self.0
+        }
+    }
+
+    /// Bumps the system and arbiter services of a new system twice, meeting the other
+    /// systems at `barrier` in between.
+    fn bump_twice(barrier: Arc<Barrier>) -> [usize; 4] {
+        let sys = System::builder().workers(1).build().unwrap();
+        sys.block_on(async move {
+            let first = [
+                <Tally as SystemService>::from_registry()
+                    .send(Bump)
+                    .await
+                    .unwrap(),
+                <Tally as ArbiterService>::from_registry()
+                    .send(Bump)
+                    .await
+                    .unwrap(),
+            ];
+            barrier.wait();
+            [
+                first[0],
+                first[1],
+                <Tally as SystemService>::from_registry()
+                    .send(Bump)
+                    .await
+                    .unwrap(),
+                <Tally as ArbiterService>::from_registry()
+                    .send(Bump)
+                    .await
+                    .unwrap(),
+            ]
+        })
+    }
+
+    #[test]
+    fn concurrent_systems_have_own_services() {
+        let barrier = Arc::new(Barrier::new(2));
+        let threads = (0..2)
+            .map(|_| {
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || bump_twice(barrier))
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), [1, 1, 2, 2]);
+        }
+    }
+
+    #[test]
+    fn successive_systems_on_one_thread_have_own_services() {
+        let barrier = Arc::new(Barrier::new(1));
+        assert_eq!(bump_twice(Arc::clone(&barrier)), [1, 1, 2, 2]);
+        assert_eq!(bump_twice(barrier), [1, 1, 2, 2]);
+    }
+}
+
+mod requires {
+    use std::{any::type_name, time::Duration};
+
+    use actix::registry::{MissingDependency, ServiceId};
+    use actix_rt::time::sleep;
+
+    use super::*;
+
+    static DB_STARTS: AtomicUsize = AtomicUsize::new(0);
+    static CACHE_STARTS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Default)]
+    struct Db;
+
+    impl Actor for Db {
+        type Context = Context<Self>;
+
+        fn started(&mut self, _: &mut Context<Self>) {
+            DB_STARTS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Supervised for Db {}
+    impl SystemService for Db {}
+
+    #[derive(Default)]
+    struct Cache;
+
+    impl Actor for Cache {
+        type Context = Context<Self>;
+
+        fn requires() -> &'static [ServiceId] {
+            const REQUIRES: &[ServiceId] = &[ServiceId::of::<Db>()];
+            REQUIRES
+        }
+
+        fn started(&mut self, _: &mut Context<Self>) {
+            CACHE_STARTS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Supervised for Cache {}
+    impl SystemService for Cache {}
+
+    struct Api;
+
+    impl Actor for Api {
+        type Context = Context<Self>;
+
+        fn requires() -> &'static [ServiceId] {
+            const REQUIRES: &[ServiceId] = &[ServiceId::of::<Cache>(), ServiceId::of::<Db>()];
+            REQUIRES
+        }
+    }
+
+    #[actix::test]
+    async fn starts_required_services_once() {
+        let _first = Api.try_start().unwrap();
+        let _second = Api.start();
+        sleep(Duration::from_millis(10)).await;
+
+        assert_eq!(DB_STARTS.load(Ordering::SeqCst), 1);
+        assert_eq!(CACHE_STARTS.load(Ordering::SeqCst), 1);
+    }
+
+    #[derive(Default)]
+    struct Left;
+
+    impl Actor for Left {
+        type Context = Context<Self>;
+
+        fn requires() -> &'static [ServiceId] {
+            const REQUIRES: &[ServiceId] = &[ServiceId::of::<Right>()];
+            REQUIRES
+        }
+    }
+
+    impl Supervised for Left {}
+    impl SystemService for Left {}
+
+    #[derive(Default)]
+    struct Right;
+
+    impl Actor for Right {
+        type Context = Context<Self>;
+
+        fn requires() -> &'static [ServiceId] {
+            const REQUIRES: &[ServiceId] = &[ServiceId::of::<Left>()];
+            REQUIRES
+        }
+    }
+
+    impl Supervised for Right {}
+    impl SystemService for Right {}
+
+    struct Client;
+
+    impl Actor for Client {
+        type Context = Context<Self>;
+
+        fn requires() -> &'static [ServiceId] {
+            const REQUIRES: &[ServiceId] = &[ServiceId::of::<Left>()];
+            REQUIRES
+        }
+    }
+
+    #[actix::test]
+    async fn reports_circular_dependencies() {
+        let left = type_name::<Left>();
+        let right = type_name::<Right>();
+
+        let err = Client.try_start().unwrap_err();
+        assert_eq!(
+            err,
+            StartError::MissingDependency(MissingDependency {
+                service: left,
+                cycle: vec![left, right, left],
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Can not provide service {}: circular dependency {} -> {} -> {}",
+                left, left, right, left
+            )
+        );
+
+        // a service requiring itself through others does not start either
+        let err = Left.try_start().unwrap_err();
+        assert_eq!(
+            err,
+            StartError::MissingDependency(MissingDependency {
+                service: left,
+                cycle: vec![left, right, left],
+            })
+        );
+    }
+
+    #[test]
+    fn requires_running_system() {
+        let err = Api.try_start().unwrap_err();
+        assert_eq!(
+            err,
+            StartError::MissingDependency(MissingDependency {
+                service: type_name::<Db>(),
+                cycle: Vec::new(),
+            })
+        );
+    }
+}
+
+mod health {
+    use std::time::Duration;
+
+    use actix::ActorDirectory;
+    use actix_rt::time::sleep;
+
+    use super::*;
+
+    /// Service that does not handle messages while it warms up.
+    #[derive(Default)]
+    struct Cache;
+
+    impl Actor for Cache {
+        type Context = Context<Self>;
+    }
+
+    impl Supervised for Cache {}
+
+    impl SystemService for Cache {
+        fn service_started(&mut self, ctx: &mut Context<Self>) {
+            ctx.wait(fut::wrap_future(sleep(Duration::from_millis(100))));
+        }
+    }
+
+    #[test]
+    fn reports_services_until_ready() {
+        let sys = System::new();
+
+        sys.block_on(async {
+            System::current().enable_actor_directory();
+            let _cache = Cache::from_registry();
+
+            let health = System::current()
+                .health_timeout(Duration::from_millis(20))
+                .await;
+            assert!(health.is_live());
+            assert!(!health.is_ready());
+            assert_eq!(health.arbiters, 1);
+            assert_eq!(health.services, 1);
+            assert_eq!(health.unready_services, [std::any::type_name::<Cache>()]);
+            assert_eq!(health.actors, Some(1));
+            assert_eq!(health.stopping, Some(0));
+            assert!(!health.shutting_down);
+
+            sleep(Duration::from_millis(150)).await;
+            let health = System::current().health().await;
+            assert!(health.is_ready());
+            assert!(health.unready_services.is_empty());
+        });
+    }
+
+    #[test]
+    fn reports_blocked_arbiters() {
+        let sys = SystemBuilder::new().workers(2).build().unwrap();
+        let workers = sys.workers().clone();
+
+        sys.block_on(async move {
+            let health = System::current().health().await;
+            assert!(health.is_live());
+            assert_eq!(health.arbiters, 3);
+            // the directory is not enabled
+            assert_eq!(health.actors, None);
+
+            workers
+                .get(1)
+                .unwrap()
+                .spawn_fn(|| thread::sleep(Duration::from_millis(200)));
+            let health = System::current()
+                .health_timeout(Duration::from_millis(50))
+                .await;
+            assert!(!health.is_live());
+            assert_eq!(health.unresponsive_arbiters, 1);
+        });
+    }
+}
+
+#[test]
+fn test_system_builder_shared_runtime() {
+    let runtime = Arc::new(
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap(),
+    );
+    let (app_tx, app_rx) = oneshot::channel();
+    runtime.spawn(async move { app_tx.send(()).unwrap() });
+
+    let sys = SystemBuilder::new()
+        .workers(1)
+        .shared_runtime(Arc::clone(&runtime))
+        .build()
+        .unwrap();
+    let workers = sys.workers().clone();
+
+    sys.block_on(async move {
+        // tasks of the application run alongside the system
+        app_rx.await.unwrap();
+
+        let addr = ThreadName::start_in_arbiter(&workers.arbiter(), |_| ThreadName);
+        let (name, sys_id) = addr.send(GetThreadName).await.unwrap();
+        assert_eq!(name.as_deref(), Some("actix-worker-0"));
+        assert_eq!(sys_id, System::current().id());
+        System::current().stop();
+    });
+    sys.run().unwrap();
+
+    // the application still owns the runtime
+    assert_eq!(runtime.block_on(async { 42 }), 42);
+}
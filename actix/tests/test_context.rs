@@ -560,3 +560,2595 @@ mod scheduled_task_is_cancelled_properly {
         sys.run().unwrap();
     }
 }
+
+mod scope {
+    use std::future::pending;
+
+    use super::*;
+
+    struct DropGuard(Arc<AtomicUsize>);
+
+    impl Drop for DropGuard {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct ScopeActor {
+        dropped: Arc<AtomicUsize>,
+        finished: Arc<AtomicUsize>,
+        handle: Option<SpawnHandle>,
+    }
+
+    impl Actor for ScopeActor {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            let finished = Arc::clone(&self.finished);
+            ctx.scope(move |scope| {
+                let finished2 = Arc::clone(&finished);
+                scope.spawn(fut::wrap_future(async move {
+                    finished.fetch_add(1, Ordering::SeqCst);
+                }));
+                scope.spawn(fut::wrap_future(async move {
+                    sleep(Duration::from_millis(10)).await;
+                    finished2.fetch_add(1, Ordering::SeqCst);
+                }));
+            });
+
+            let dropped = Arc::clone(&self.dropped);
+            self.handle = Some(ctx.scope(move |scope| {
+                for _ in 0..3 {
+                    let guard = DropGuard(Arc::clone(&dropped));
+                    scope.spawn(fut::wrap_future(async move {
+                        let _guard = guard;
+                        pending::<()>().await;
+                    }));
+                }
+            }));
+        }
+    }
+
+    struct CancelScope;
+
+    impl Message for CancelScope {
+        type Result = ();
+    }
+
+    impl Handler<CancelScope> for ScopeActor {
+        type Result = ();
+
+        fn handle(&mut self, _: CancelScope, ctx: &mut Self::Context) {
+            ctx.cancel_future(self.handle.take().unwrap());
+        }
+    }
+
+    #[actix::test]
+    async fn cancels_all_children() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let finished = Arc::new(AtomicUsize::new(0));
+
+        let addr = ScopeActor {
+            dropped: Arc::clone(&dropped),
+            finished: Arc::clone(&finished),
+            handle: None,
+        }
+        .start();
+
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(finished.load(Ordering::SeqCst), 2);
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+        addr.send(CancelScope).await.unwrap();
+        assert_eq!(dropped.load(Ordering::SeqCst), 3);
+    }
+}
+
+mod pause {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    struct PausedActor(Arc<Mutex<Vec<usize>>>);
+
+    impl Actor for PausedActor {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.pause();
+            assert!(ctx.paused());
+
+            // timers keep running while paused
+            ctx.run_later(Duration::from_millis(50), |_, ctx| ctx.resume());
+        }
+    }
+
+    struct Num(usize);
+
+    impl Message for Num {
+        type Result = ();
+    }
+
+    impl Handler<Num> for PausedActor {
+        type Result = ();
+
+        fn handle(&mut self, msg: Num, _: &mut Self::Context) {
+            self.0.lock().unwrap().push(msg.0);
+        }
+    }
+
+    #[actix::test]
+    async fn messages_are_processed_after_resume_in_order() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let addr = PausedActor(Arc::clone(&received)).start();
+
+        for n in 0..5 {
+            addr.do_send(Num(n));
+        }
+
+        sleep(Duration::from_millis(20)).await;
+        assert!(received.lock().unwrap().is_empty());
+        assert!(addr.connected());
+
+        sleep(Duration::from_millis(60)).await;
+        assert_eq!(*received.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+
+        addr.send(Num(5)).await.unwrap();
+        assert_eq!(received.lock().unwrap().len(), 6);
+    }
+}
+
+mod mailbox_high_water {
+    use super::*;
+
+    struct Counter;
+
+    impl Actor for Counter {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            // let messages pile up
+            ctx.pause();
+            ctx.run_later(Duration::from_millis(20), |_, ctx| ctx.resume());
+        }
+    }
+
+    struct HighWater;
+
+    impl Message for HighWater {
+        type Result = usize;
+    }
+
+    impl Handler<HighWater> for Counter {
+        type Result = usize;
+
+        fn handle(&mut self, _: HighWater, ctx: &mut Self::Context) -> usize {
+            ctx.mailbox_high_water()
+        }
+    }
+
+    impl Handler<Ping> for Counter {
+        type Result = ();
+
+        fn handle(&mut self, _: Ping, _: &mut Self::Context) {}
+    }
+
+    #[actix::test]
+    async fn tracks_peak_queue_depth() {
+        let addr = Counter.start();
+        for _ in 0..5 {
+            addr.do_send(Ping);
+        }
+
+        assert_eq!(addr.send(HighWater).await.unwrap(), 6);
+
+        // the mark does not go down once the queue drained
+        addr.send(Ping).await.unwrap();
+        assert_eq!(addr.send(HighWater).await.unwrap(), 6);
+    }
+}
+
+mod create_child {
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    struct Child(usize);
+
+    impl Actor for Child {
+        type Context = Context<Self>;
+    }
+
+    struct GetValue;
+
+    impl Message for GetValue {
+        type Result = usize;
+    }
+
+    impl Handler<GetValue> for Child {
+        type Result = usize;
+
+        fn handle(&mut self, _: GetValue, _: &mut Self::Context) -> usize {
+            self.0
+        }
+    }
+
+    struct Parent {
+        stop_early: bool,
+        created: Option<oneshot::Sender<Addr<Child>>>,
+        dropped: Option<oneshot::Sender<()>>,
+    }
+
+    impl Actor for Parent {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            let dropped = self.dropped.take();
+            ctx.create_child(move || async move {
+                let _dropped = dropped;
+                sleep(Duration::from_millis(20)).await;
+                Child(42)
+            });
+
+            if self.stop_early {
+                ctx.stop();
+            }
+        }
+    }
+
+    impl Handler<ChildStarted<Child>> for Parent {
+        type Result = ();
+
+        fn handle(&mut self, msg: ChildStarted<Child>, _: &mut Self::Context) {
+            let _ = self.created.take().unwrap().send(msg.0);
+        }
+    }
+
+    #[actix::test]
+    async fn delivers_child_address() {
+        let (tx, rx) = oneshot::channel();
+        let _addr = Parent {
+            stop_early: false,
+            created: Some(tx),
+            dropped: None,
+        }
+        .start();
+
+        let child = rx.await.unwrap();
+        assert_eq!(child.send(GetValue).await.unwrap(), 42);
+    }
+
+    #[actix::test]
+    async fn cancelled_when_parent_stops() {
+        let (tx, rx) = oneshot::channel();
+        let (dropped_tx, dropped_rx) = oneshot::channel();
+        let _addr = Parent {
+            stop_early: true,
+            created: Some(tx),
+            dropped: Some(dropped_tx),
+        }
+        .start();
+
+        // the pending creation is dropped together with the parent
+        assert!(dropped_rx.await.is_err());
+        assert!(rx.await.is_err());
+    }
+}
+
+mod stream_completion {
+    use futures_util::stream::iter;
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    struct Summer {
+        sum: usize,
+        finished: bool,
+        tx: Option<oneshot::Sender<Result<usize, String>>>,
+    }
+
+    impl Summer {
+        fn new(tx: oneshot::Sender<Result<usize, String>>) -> Self {
+            Summer {
+                sum: 0,
+                finished: false,
+                tx: Some(tx),
+            }
+        }
+    }
+
+    impl Actor for Summer {
+        type Context = Context<Self>;
+    }
+
+    impl StreamHandler<usize> for Summer {
+        fn handle(&mut self, item: usize, _: &mut Self::Context) {
+            self.sum += item;
+        }
+
+        fn finished(&mut self, _: &mut Self::Context) {
+            self.finished = true;
+        }
+    }
+
+    struct Done;
+
+    impl Message for Done {
+        type Result = ();
+    }
+
+    impl Handler<Done> for Summer {
+        type Result = ();
+
+        fn handle(&mut self, _: Done, _: &mut Self::Context) {
+            assert!(!self.finished);
+            let _ = self.tx.take().unwrap().send(Ok(self.sum));
+        }
+    }
+
+    struct Failed(String);
+
+    impl Message for Failed {
+        type Result = ();
+    }
+
+    impl Handler<Failed> for Summer {
+        type Result = ();
+
+        fn handle(&mut self, msg: Failed, _: &mut Self::Context) {
+            assert!(!self.finished);
+            let _ = self
+                .tx
+                .take()
+                .unwrap()
+                .send(Err(format!("{} after {}", msg.0, self.sum)));
+        }
+    }
+
+    #[actix::test]
+    async fn delivers_message_on_end() {
+        let (tx, rx) = oneshot::channel();
+        Summer::create(|ctx| {
+            ctx.add_stream_with_completion(iter([1, 2, 3]), Done);
+            Summer::new(tx)
+        });
+
+        assert_eq!(rx.await.unwrap(), Ok(6));
+    }
+
+    #[actix::test]
+    async fn try_stream_ends_cleanly() {
+        let (tx, rx) = oneshot::channel();
+        Summer::create(|ctx| {
+            let items = iter([Ok::<_, &str>(1), Ok(2)]);
+            ctx.add_try_stream_with_completion(items, Done, |err| Failed(err.to_owned()));
+            Summer::new(tx)
+        });
+
+        assert_eq!(rx.await.unwrap(), Ok(3));
+    }
+
+    #[actix::test]
+    async fn try_stream_delivers_error_message() {
+        let (tx, rx) = oneshot::channel();
+        Summer::create(|ctx| {
+            let items = iter([Ok(1), Err("boom"), Ok(2)]);
+            ctx.add_try_stream_with_completion(items, Done, |err| Failed(err.to_owned()));
+            Summer::new(tx)
+        });
+
+        assert_eq!(rx.await.unwrap(), Err("boom after 1".to_owned()));
+    }
+}
+
+mod ack_stream {
+    use std::sync::Mutex;
+
+    use futures_util::stream::iter;
+
+    use super::*;
+
+    struct Consumer {
+        source: Option<AckStreamHandle>,
+        stop_at: usize,
+        received: Arc<Mutex<Vec<usize>>>,
+        finished: Arc<AtomicUsize>,
+    }
+
+    impl Actor for Consumer {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            self.source = Some(ctx.add_ack_stream(iter(0..10)));
+        }
+    }
+
+    impl AckStreamHandler<usize> for Consumer {
+        fn handle(&mut self, item: usize, _: &mut Self::Context) -> StreamAck {
+            self.received.lock().unwrap().push(item);
+            if item == self.stop_at {
+                StreamAck::Stop
+            } else {
+                StreamAck::Pause
+            }
+        }
+
+        fn finished(&mut self, _: &mut Self::Context) {
+            self.finished.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct Credit;
+
+    impl Message for Credit {
+        type Result = bool;
+    }
+
+    impl Handler<Credit> for Consumer {
+        type Result = bool;
+
+        fn handle(&mut self, _: Credit, _: &mut Self::Context) -> bool {
+            let source = self.source.as_ref().unwrap();
+            let paused = source.is_paused();
+            source.resume();
+            paused
+        }
+    }
+
+    #[actix::test]
+    async fn pauses_until_resumed() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let finished = Arc::new(AtomicUsize::new(0));
+        let addr = Consumer {
+            source: None,
+            stop_at: 2,
+            received: Arc::clone(&received),
+            finished: Arc::clone(&finished),
+        }
+        .start();
+
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(*received.lock().unwrap(), [0]);
+
+        assert!(addr.send(Credit).await.unwrap());
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(*received.lock().unwrap(), [0, 1]);
+
+        // `Stop` drops the stream without calling `finished`
+        assert!(addr.send(Credit).await.unwrap());
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(*received.lock().unwrap(), [0, 1, 2]);
+        assert!(!addr.send(Credit).await.unwrap());
+        assert_eq!(finished.load(Ordering::SeqCst), 0);
+    }
+}
+
+mod linked_children {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Stop;
+
+    struct Child(Arc<AtomicUsize>);
+
+    impl Actor for Child {
+        type Context = Context<Self>;
+
+        fn stopped(&mut self, _: &mut Self::Context) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Handler<Stop> for Child {
+        type Result = ();
+
+        fn handle(&mut self, _: Stop, ctx: &mut Self::Context) {
+            ctx.stop();
+        }
+    }
+
+    #[derive(Default)]
+    struct Parent {
+        children: Vec<Addr<Child>>,
+        terminated: Arc<AtomicUsize>,
+    }
+
+    impl Actor for Parent {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Stop> for Parent {
+        type Result = ();
+
+        fn handle(&mut self, _: Stop, ctx: &mut Self::Context) {
+            ctx.stop();
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Link(Addr<Child>, bool);
+
+    impl Handler<Link> for Parent {
+        type Result = ();
+
+        fn handle(&mut self, Link(child, watch): Link, ctx: &mut Self::Context) {
+            ctx.link_child(&child);
+            if watch {
+                ctx.watch(&child);
+            }
+            self.children.push(child);
+        }
+    }
+
+    impl Handler<Terminated<Child>> for Parent {
+        type Result = ();
+
+        fn handle(&mut self, _: Terminated<Child>, _: &mut Self::Context) {
+            self.terminated.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[actix::test]
+    async fn parent_stops_linked_children() {
+        let stopped = Arc::new(AtomicUsize::new(0));
+        let parent = Parent::default().start();
+        let linked = Child(Arc::clone(&stopped)).start();
+        let unlinked = Child(Arc::clone(&stopped)).start();
+        parent.send(Link(linked.clone(), false)).await.unwrap();
+
+        // linking is one-directional
+        let other = Child(Arc::new(AtomicUsize::new(0))).start();
+        parent.send(Link(other.clone(), false)).await.unwrap();
+        other.stop_and_wait().await;
+        assert!(parent.connected());
+
+        parent.send(Stop).await.unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert!(!linked.connected());
+        assert!(unlinked.connected());
+        assert_eq!(stopped.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix::test]
+    async fn watch_delivers_terminated() {
+        let terminated = Arc::new(AtomicUsize::new(0));
+        let parent = Parent {
+            terminated: Arc::clone(&terminated),
+            ..Default::default()
+        }
+        .start();
+
+        let child = Child(Arc::new(AtomicUsize::new(0))).start();
+        parent.send(Link(child.clone(), true)).await.unwrap();
+        child.send(Stop).await.unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(terminated.load(Ordering::SeqCst), 1);
+
+        // watching an actor that has already stopped
+        parent.send(Link(child, true)).await.unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(terminated.load(Ordering::SeqCst), 2);
+    }
+}
+
+mod pending_replies {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Slow(Duration);
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct Pending;
+
+    struct Server;
+
+    impl Actor for Server {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Slow> for Server {
+        type Result = ResponseActFuture<Self, ()>;
+
+        fn handle(&mut self, Slow(dur): Slow, _: &mut Self::Context) -> Self::Result {
+            Box::pin(sleep(dur).into_actor(self))
+        }
+    }
+
+    impl Handler<Pending> for Server {
+        type Result = usize;
+
+        fn handle(&mut self, _: Pending, ctx: &mut Self::Context) -> usize {
+            ctx.pending_replies()
+        }
+    }
+
+    #[actix::test]
+    async fn counts_unanswered_requests() {
+        let addr = Server.start();
+        assert_eq!(addr.send(Pending).await.unwrap(), 0);
+
+        let slow = addr.send(Slow(Duration::from_millis(20)));
+        let slower = addr.send(Slow(Duration::from_millis(40)));
+        // not awaiting a reply
+        addr.do_send(Slow(Duration::from_millis(20)));
+        assert_eq!(addr.send(Pending).await.unwrap(), 2);
+
+        slow.await.unwrap();
+        assert_eq!(addr.send(Pending).await.unwrap(), 1);
+        slower.await.unwrap();
+        assert_eq!(addr.send(Pending).await.unwrap(), 0);
+    }
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Job;
+
+    /// Records whether the senders of each batch still wait for their replies.
+    struct Batcher(Arc<Mutex<Vec<bool>>>);
+
+    impl Actor for Batcher {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Pending> for Batcher {
+        type Result = usize;
+
+        fn handle(&mut self, _: Pending, ctx: &mut Self::Context) -> usize {
+            ctx.pending_replies()
+        }
+    }
+
+    impl Handler<Job> for Batcher {
+        type Result = Batched<Job>;
+
+        fn handle(&mut self, msg: Job, _: &mut Self::Context) -> Self::Result {
+            Batched(msg)
+        }
+    }
+
+    impl BatchingHandler<Job> for Batcher {
+        const LINGER: Duration = Duration::from_millis(50);
+
+        fn handle_batch(&mut self, batch: Vec<(Job, Responder<()>)>, _: &mut Self::Context) {
+            for (_, responder) in batch {
+                self.0.lock().unwrap().push(responder.is_closed());
+                responder.send(());
+            }
+        }
+    }
+
+    #[actix::test]
+    async fn handler_sees_abandoned_requests() {
+        let closed = Arc::new(Mutex::new(Vec::new()));
+        let addr = Batcher(Arc::clone(&closed)).start();
+
+        let kept = addr.send(Job);
+        let abandoned = addr.send(Job);
+        // both jobs wait for their batch
+        assert_eq!(addr.send(Pending).await.unwrap(), 2);
+
+        drop(abandoned);
+        kept.await.unwrap();
+        assert_eq!(*closed.lock().unwrap(), [false, true]);
+    }
+}
+
+mod idle_watch {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Work;
+
+    struct Worker(Arc<Mutex<Vec<&'static str>>>);
+
+    impl Actor for Worker {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.set_idle_debounce(Duration::from_millis(20));
+            ctx.on_idle(|act, _| act.0.lock().unwrap().push("idle"));
+            ctx.on_busy(|act, _| act.0.lock().unwrap().push("busy"));
+        }
+    }
+
+    impl Handler<Work> for Worker {
+        type Result = ();
+
+        fn handle(&mut self, _: Work, _: &mut Self::Context) {}
+    }
+
+    #[actix::test]
+    async fn fires_on_transitions() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let addr = Worker(Arc::clone(&events)).start();
+
+        sleep(Duration::from_millis(40)).await;
+        assert_eq!(*events.lock().unwrap(), ["idle"]);
+
+        // steady work does not flap
+        for _ in 0..5 {
+            addr.send(Work).await.unwrap();
+            sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(*events.lock().unwrap(), ["idle", "busy"]);
+
+        sleep(Duration::from_millis(40)).await;
+        assert_eq!(*events.lock().unwrap(), ["idle", "busy", "idle"]);
+    }
+}
+
+mod rate_limiter {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "(bool, bool)")]
+    struct TryCall;
+
+    struct Limited;
+
+    impl Actor for Limited {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<TryCall> for Limited {
+        type Result = MessageResult<TryCall>;
+
+        fn handle(&mut self, _: TryCall, ctx: &mut Self::Context) -> Self::Result {
+            let rate = Rate::new(1, Duration::from_secs(60)).burst(3);
+            let calls = ctx.rate_limiter("calls", rate).try_acquire();
+            let logs = ctx.rate_limiter("logs", rate).try_acquire();
+            MessageResult((calls, logs))
+        }
+    }
+
+    #[actix::test]
+    async fn try_acquire_is_keyed_by_name() {
+        let addr = Limited.start();
+
+        for _ in 0..3 {
+            assert_eq!(addr.send(TryCall).await.unwrap(), (true, true));
+        }
+        assert_eq!(addr.send(TryCall).await.unwrap(), (false, false));
+    }
+}
+
+mod cancellation_token {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "CancellationToken")]
+    struct Token;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Stop;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Terminate;
+
+    #[derive(Default)]
+    struct Worker {
+        refuse_stop: bool,
+    }
+
+    impl Actor for Worker {
+        type Context = Context<Self>;
+
+        fn stopping(&mut self, _: &mut Self::Context) -> Running {
+            if std::mem::take(&mut self.refuse_stop) {
+                Running::Continue
+            } else {
+                Running::Stop
+            }
+        }
+    }
+
+    impl Handler<Token> for Worker {
+        type Result = MessageResult<Token>;
+
+        fn handle(&mut self, _: Token, ctx: &mut Self::Context) -> Self::Result {
+            MessageResult(ctx.cancellation_token())
+        }
+    }
+
+    impl Handler<Stop> for Worker {
+        type Result = ();
+
+        fn handle(&mut self, _: Stop, ctx: &mut Self::Context) {
+            ctx.stop();
+        }
+    }
+
+    impl Handler<Terminate> for Worker {
+        type Result = ();
+
+        fn handle(&mut self, _: Terminate, ctx: &mut Self::Context) {
+            ctx.terminate();
+        }
+    }
+
+    #[actix::test]
+    async fn cancelled_on_stop() {
+        let addr = Worker::default().start();
+        let token = addr.send(Token).await.unwrap();
+        assert!(!token.is_cancelled());
+
+        addr.do_send(Stop);
+        token.cancelled().await;
+    }
+
+    #[actix::test]
+    async fn cancelled_on_terminate() {
+        let addr = Worker::default().start();
+        let token = addr.send(Token).await.unwrap();
+
+        addr.do_send(Terminate);
+        token.cancelled().await;
+    }
+
+    #[actix::test]
+    async fn renewed_when_stop_is_refused() {
+        let addr = Worker { refuse_stop: true }.start();
+        let token = addr.send(Token).await.unwrap();
+
+        addr.send(Stop).await.unwrap();
+        assert!(token.is_cancelled());
+
+        let renewed = addr.send(Token).await.unwrap();
+        assert!(!renewed.is_cancelled());
+
+        addr.do_send(Stop);
+        renewed.cancelled().await;
+    }
+}
+
+mod spawn_to {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Item(u32);
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<u32>")]
+    struct Items;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Failed(&'static str);
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<&'static str>")]
+    struct Errors;
+
+    #[derive(Default)]
+    struct Downstream(Vec<u32>);
+
+    impl Actor for Downstream {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Item> for Downstream {
+        type Result = ();
+
+        fn handle(&mut self, Item(n): Item, _: &mut Self::Context) {
+            self.0.push(n);
+        }
+    }
+
+    impl Handler<Items> for Downstream {
+        type Result = MessageResult<Items>;
+
+        fn handle(&mut self, _: Items, _: &mut Self::Context) -> Self::Result {
+            MessageResult(self.0.clone())
+        }
+    }
+
+    struct Worker {
+        downstream: Recipient<Item>,
+        errors: Vec<&'static str>,
+    }
+
+    impl Actor for Worker {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            for res in [Ok(Item(1)), Err("boom"), Ok(Item(2))] {
+                ctx.spawn_to(fut::ready(res), self.downstream.clone(), Failed);
+            }
+        }
+    }
+
+    impl Handler<Failed> for Worker {
+        type Result = ();
+
+        fn handle(&mut self, Failed(err): Failed, _: &mut Self::Context) {
+            self.errors.push(err);
+        }
+    }
+
+    impl Handler<Errors> for Worker {
+        type Result = MessageResult<Errors>;
+
+        fn handle(&mut self, _: Errors, _: &mut Self::Context) -> Self::Result {
+            MessageResult(self.errors.clone())
+        }
+    }
+
+    #[actix::test]
+    async fn delivers_items_downstream_and_errors_to_self() {
+        let downstream = Downstream::default().start();
+        let worker = Worker {
+            downstream: downstream.clone().recipient(),
+            errors: Vec::new(),
+        }
+        .start();
+        sleep(Duration::from_millis(10)).await;
+
+        assert_eq!(worker.send(Errors).await.unwrap(), ["boom"]);
+        assert_eq!(downstream.send(Items).await.unwrap(), [1, 2]);
+    }
+}
+
+mod emit {
+    use futures_util::stream::StreamExt;
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Progress(u32);
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct Emit(u32);
+
+    struct Download;
+
+    impl Actor for Download {
+        type Context = Context<Self>;
+    }
+
+    impl Observable for Download {
+        type Event = Progress;
+    }
+
+    impl Handler<Emit> for Download {
+        type Result = usize;
+
+        fn handle(&mut self, Emit(n): Emit, ctx: &mut Self::Context) -> usize {
+            ctx.emit(Progress(n))
+        }
+    }
+
+    #[actix::test]
+    async fn delivers_to_all_observers() {
+        let addr = Download.start();
+        assert_eq!(addr.send(Emit(0)).await.unwrap(), 0);
+
+        let mut first = addr.observe();
+        let mut second = addr.observe();
+        assert_eq!(addr.send(Emit(1)).await.unwrap(), 2);
+        assert_eq!(first.next().await, Some(Progress(1)));
+        assert_eq!(second.next().await, Some(Progress(1)));
+
+        drop(first);
+        assert_eq!(addr.send(Emit(2)).await.unwrap(), 1);
+        assert_eq!(second.next().await, Some(Progress(2)));
+    }
+
+    #[actix::test]
+    async fn full_observer_misses_events() {
+        let addr = Download.start();
+        let mut slow = addr.observe_with_capacity(1);
+
+        assert_eq!(addr.send(Emit(1)).await.unwrap(), 1);
+        assert_eq!(addr.send(Emit(2)).await.unwrap(), 0);
+        assert_eq!(slow.next().await, Some(Progress(1)));
+        assert_eq!(addr.send(Emit(3)).await.unwrap(), 1);
+        assert_eq!(slow.next().await, Some(Progress(3)));
+    }
+
+    #[actix::test]
+    async fn stream_ends_when_actor_stops() {
+        let addr = Download.start();
+        let mut observer = addr.observe();
+
+        addr.stop_and_wait().await;
+        assert_eq!(observer.next().await, None);
+    }
+}
+
+mod stash {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct Push(usize);
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Ready;
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<usize>")]
+    struct Handled;
+
+    #[derive(Default)]
+    struct Machine {
+        ready: bool,
+        handled: Vec<usize>,
+    }
+
+    impl Actor for Machine {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Push> for Machine {
+        type Result = usize;
+
+        fn handle(&mut self, msg: Push, ctx: &mut Self::Context) -> usize {
+            if !self.ready {
+                ctx.stash(msg);
+                return 0;
+            }
+            self.handled.push(msg.0);
+            msg.0 * 10
+        }
+    }
+
+    impl Handler<Ready> for Machine {
+        type Result = ();
+
+        fn handle(&mut self, _: Ready, ctx: &mut Self::Context) {
+            self.ready = true;
+            assert_eq!(ctx.stashed(), 3);
+            assert_eq!(ctx.unstash_all(), 3);
+            assert_eq!(ctx.stashed(), 0);
+        }
+    }
+
+    impl Handler<Handled> for Machine {
+        type Result = MessageResult<Handled>;
+
+        fn handle(&mut self, _: Handled, _: &mut Self::Context) -> Self::Result {
+            MessageResult(self.handled.clone())
+        }
+    }
+
+    #[actix::test]
+    async fn unstashed_messages_keep_order_and_replies() {
+        let addr = Machine::default().start();
+
+        let first = addr.send(Push(1));
+        addr.do_send(Push(2));
+        let third = addr.send(Push(3));
+        addr.do_send(Ready);
+        let fourth = addr.send(Push(4));
+
+        assert_eq!(first.await.unwrap(), 10);
+        assert_eq!(third.await.unwrap(), 30);
+        assert_eq!(fourth.await.unwrap(), 40);
+        assert_eq!(addr.send(Handled).await.unwrap(), [1, 2, 3, 4]);
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Note;
+
+    #[derive(Default)]
+    struct Delayed {
+        notes: usize,
+    }
+
+    impl Actor for Delayed {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Push> for Delayed {
+        type Result = usize;
+
+        fn handle(&mut self, msg: Push, ctx: &mut Self::Context) -> usize {
+            ctx.stash(Note);
+            ctx.run_later(Duration::from_millis(10), |_, ctx| {
+                ctx.unstash_all();
+            });
+            msg.0
+        }
+    }
+
+    impl Handler<Note> for Delayed {
+        type Result = ();
+
+        fn handle(&mut self, _: Note, _: &mut Self::Context) {
+            self.notes += 1;
+        }
+    }
+
+    impl Handler<Handled> for Delayed {
+        type Result = MessageResult<Handled>;
+
+        fn handle(&mut self, _: Handled, _: &mut Self::Context) -> Self::Result {
+            MessageResult(vec![self.notes])
+        }
+    }
+
+    #[actix::test]
+    async fn stashing_another_message_keeps_reply() {
+        let addr = Delayed::default().start();
+        assert_eq!(addr.send(Push(1)).await.unwrap(), 1);
+        assert_eq!(addr.send(Handled).await.unwrap(), [0]);
+
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(addr.send(Handled).await.unwrap(), [1]);
+    }
+
+    #[actix::test]
+    async fn stashed_requests_fail_when_stopped() {
+        let addr = Machine::default().start();
+        let pending = addr.send(Push(1));
+        addr.stop_and_wait().await;
+        assert_eq!(pending.await, Err(MailboxError::Closed));
+    }
+}
+
+mod defer {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Step(usize);
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<String>")]
+    struct Log;
+
+    #[derive(Default)]
+    struct Deferring {
+        log: Vec<String>,
+    }
+
+    impl Actor for Deferring {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.defer(|act, _| act.log.push("started".to_owned()));
+        }
+    }
+
+    impl Handler<Step> for Deferring {
+        type Result = ();
+
+        fn handle(&mut self, Step(n): Step, ctx: &mut Self::Context) {
+            self.log.push(format!("handle {}", n));
+            ctx.defer(move |act, ctx| {
+                act.log.push(format!("first {}", n));
+                ctx.defer(move |act, _| act.log.push(format!("nested {}", n)));
+            });
+            ctx.defer(move |act, _| act.log.push(format!("second {}", n)));
+        }
+    }
+
+    impl Handler<Log> for Deferring {
+        type Result = MessageResult<Log>;
+
+        fn handle(&mut self, _: Log, _: &mut Self::Context) -> Self::Result {
+            MessageResult(std::mem::take(&mut self.log))
+        }
+    }
+
+    #[actix::test]
+    async fn runs_before_next_message() {
+        let addr = Deferring::default().start();
+
+        // both messages are queued before the actor is polled
+        addr.do_send(Step(1));
+        addr.do_send(Step(2));
+        assert_eq!(
+            addr.send(Log).await.unwrap(),
+            [
+                "started", "handle 1", "first 1", "second 1", "nested 1", "handle 2", "first 2",
+                "second 2", "nested 2"
+            ]
+        );
+    }
+
+    #[actix::test]
+    async fn runs_after_notified_message() {
+        let addr = Deferring::create(|ctx| {
+            ctx.notify(Step(1));
+            Deferring::default()
+        });
+
+        // notifications are handled after the mailbox
+        addr.send(Step(0)).await.unwrap();
+        assert_eq!(
+            addr.send(Log).await.unwrap(),
+            [
+                "started", "handle 0", "first 0", "second 0", "nested 0", "handle 1", "first 1",
+                "second 1", "nested 1"
+            ]
+        );
+    }
+}
+
+mod retry_current {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "Result<u32, u32>")]
+    struct Acquire;
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Peek;
+
+    /// Locked for its first `locked` attempts at acquiring.
+    struct Resource {
+        locked: u32,
+    }
+
+    impl Actor for Resource {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Acquire> for Resource {
+        type Result = Result<u32, u32>;
+
+        fn handle(&mut self, msg: Acquire, ctx: &mut Self::Context) -> Self::Result {
+            let attempt = ctx.attempt();
+            if attempt < self.locked {
+                if attempt == 2 {
+                    return Err(attempt);
+                }
+                ctx.retry_current(msg, Duration::from_millis(10));
+                return Err(attempt);
+            }
+            Ok(attempt)
+        }
+    }
+
+    impl Handler<Peek> for Resource {
+        type Result = u32;
+
+        fn handle(&mut self, _: Peek, ctx: &mut Self::Context) -> u32 {
+            ctx.attempt()
+        }
+    }
+
+    #[actix::test]
+    async fn redelivers_with_reply() {
+        let addr = Resource { locked: 2 }.start();
+
+        let res = addr.send(Acquire);
+        // other messages are handled while waiting for the retry, and count as first delivery
+        assert_eq!(addr.send(Peek).await.unwrap(), 0);
+        assert_eq!(res.await.unwrap(), Ok(2));
+    }
+
+    #[actix::test]
+    async fn handler_gives_up() {
+        let addr = Resource { locked: 5 }.start();
+        assert_eq!(addr.send(Acquire).await.unwrap(), Err(2));
+    }
+
+    #[actix::test]
+    async fn cancelled_retry_drops_message() {
+        struct Canceller;
+
+        impl Actor for Canceller {
+            type Context = Context<Self>;
+        }
+
+        impl Handler<Acquire> for Canceller {
+            type Result = Result<u32, u32>;
+
+            fn handle(&mut self, msg: Acquire, ctx: &mut Self::Context) -> Self::Result {
+                let handle = ctx.retry_current(msg, Duration::from_millis(10));
+                ctx.cancel_future(handle);
+                Ok(0)
+            }
+        }
+
+        let addr = Canceller.start();
+        // the message is dropped before being handled again
+        assert_eq!(addr.send(Acquire).await, Err(MailboxError::Closed));
+    }
+}
+
+mod notifier {
+    use std::sync::{atomic::AtomicBool, Mutex};
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Fetched(u32);
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<u32>")]
+    struct Results;
+
+    struct Fetcher {
+        results: Vec<u32>,
+        notifier: Arc<Mutex<Option<SelfNotifier<Fetcher>>>>,
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Fetcher {
+        fn new() -> Self {
+            Fetcher {
+                results: Vec::new(),
+                notifier: Arc::default(),
+                stopped: Arc::default(),
+            }
+        }
+    }
+
+    impl Actor for Fetcher {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            let notifier = ctx.notifier();
+            *self.notifier.lock().unwrap() = Some(notifier.clone());
+
+            actix_rt::spawn(async move {
+                for n in 1..=3 {
+                    sleep(Duration::from_millis(5)).await;
+                    notifier.notify(Fetched(n));
+                }
+            });
+        }
+
+        fn stopped(&mut self, _: &mut Self::Context) {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl Handler<Fetched> for Fetcher {
+        type Result = ();
+
+        fn handle(&mut self, Fetched(n): Fetched, _: &mut Self::Context) {
+            self.results.push(n);
+        }
+    }
+
+    impl Handler<Results> for Fetcher {
+        type Result = MessageResult<Results>;
+
+        fn handle(&mut self, _: Results, _: &mut Self::Context) -> Self::Result {
+            MessageResult(self.results.clone())
+        }
+    }
+
+    #[actix::test]
+    async fn delivers_messages_from_spawned_work() {
+        let addr = Fetcher::new().start();
+
+        sleep(Duration::from_millis(40)).await;
+        assert_eq!(addr.send(Results).await.unwrap(), [1, 2, 3]);
+    }
+
+    #[actix::test]
+    async fn does_not_keep_actor_running() {
+        let fetcher = Fetcher::new();
+        let notifier = Arc::clone(&fetcher.notifier);
+        let stopped = Arc::clone(&fetcher.stopped);
+
+        drop(fetcher.start());
+        sleep(Duration::from_millis(10)).await;
+        assert!(stopped.load(Ordering::SeqCst));
+
+        let notifier = notifier.lock().unwrap().take().unwrap();
+        assert!(!notifier.connected());
+        notifier.notify(Fetched(4));
+    }
+}
+
+mod cancel_all {
+    use std::sync::atomic::AtomicBool;
+
+    use actix::fut::ActorFutureExt as _;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct CancelAll;
+
+    #[derive(Message)]
+    #[rtype(result = "(usize, usize, usize)")]
+    struct State;
+
+    #[derive(Default)]
+    struct Worker {
+        ticks: usize,
+        fired: usize,
+        cleaned_up: usize,
+    }
+
+    impl Actor for Worker {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.run_interval(Duration::from_millis(5), |act, _| act.ticks += 1);
+            ctx.run_later(Duration::from_millis(50), |act, _| act.fired += 1);
+            ctx.spawn(
+                fut::wrap_future(futures_util::future::pending::<()>())
+                    .on_cancel(|act: &mut Self, _| act.cleaned_up += 1),
+            );
+        }
+    }
+
+    impl Handler<CancelAll> for Worker {
+        type Result = usize;
+
+        fn handle(&mut self, _: CancelAll, ctx: &mut Self::Context) -> usize {
+            ctx.cancel_all()
+        }
+    }
+
+    impl Handler<State> for Worker {
+        type Result = MessageResult<State>;
+
+        fn handle(&mut self, _: State, _: &mut Self::Context) -> Self::Result {
+            MessageResult((self.ticks, self.fired, self.cleaned_up))
+        }
+    }
+
+    #[actix::test]
+    async fn cancels_spawned_work_and_keeps_running() {
+        let addr = Worker::default().start();
+        sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(addr.send(CancelAll).await.unwrap(), 3);
+        let (ticks, fired, cleaned_up) = addr.send(State).await.unwrap();
+        assert_eq!((fired, cleaned_up), (0, 1));
+
+        sleep(Duration::from_millis(60)).await;
+        assert_eq!(addr.send(State).await.unwrap(), (ticks, 0, 1));
+        assert_eq!(addr.send(CancelAll).await.unwrap(), 0);
+        assert!(addr.connected());
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<&'static str>")]
+    struct CancelSecond;
+
+    struct Timers {
+        fired: Vec<&'static str>,
+    }
+
+    impl Actor for Timers {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            for name in ["first", "second", "third"] {
+                ctx.run_later(Duration::from_millis(20), move |act, _| {
+                    act.fired.push(name)
+                });
+            }
+        }
+    }
+
+    impl Handler<CancelSecond> for Timers {
+        type Result = ResponseActFuture<Self, Vec<&'static str>>;
+
+        fn handle(&mut self, _: CancelSecond, ctx: &mut Self::Context) -> Self::Result {
+            let handles = ctx.spawned_handles();
+            assert_eq!(handles.len(), 3);
+            ctx.cancel_future(handles[1]);
+            assert_eq!(ctx.spawned_handles(), [handles[0], handles[2]]);
+
+            Box::pin(
+                fut::wrap_future(sleep(Duration::from_millis(40)))
+                    .map(|_, act: &mut Self, _| act.fired.clone()),
+            )
+        }
+    }
+
+    #[actix::test]
+    async fn spawned_handles_allow_selective_cancellation() {
+        let addr = Timers { fired: Vec::new() }.start();
+        assert_eq!(addr.send(CancelSecond).await.unwrap(), ["first", "third"]);
+    }
+
+    struct Ticker {
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Actor for Ticker {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.run_interval(Duration::from_millis(5), |_, _| {});
+            ctx.run_later(Duration::from_millis(20), |_, ctx| {
+                ctx.cancel_all();
+            });
+        }
+
+        fn stopped(&mut self, _: &mut Self::Context) {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[actix::test]
+    async fn actor_without_addresses_stops() {
+        let stopped = Arc::new(AtomicBool::new(false));
+        drop(
+            Ticker {
+                stopped: Arc::clone(&stopped),
+            }
+            .start(),
+        );
+
+        sleep(Duration::from_millis(10)).await;
+        assert!(!stopped.load(Ordering::SeqCst));
+
+        sleep(Duration::from_millis(30)).await;
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+}
+
+mod replace_stream {
+    use futures_util::stream::{iter, poll_fn};
+    use tokio::sync::mpsc::UnboundedSender;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "bool")]
+    struct Switch(UnboundedReceiver<u32>);
+
+    #[derive(Message)]
+    #[rtype(result = "(Vec<u32>, usize, usize)")]
+    struct Summary;
+
+    #[derive(Default)]
+    struct Feed {
+        source: Option<SpawnHandle>,
+        received: Vec<u32>,
+        started: usize,
+        finished: usize,
+        backup: Option<Vec<u32>>,
+    }
+
+    impl Feed {
+        fn with_source(ctx: &mut Context<Self>, rx: UnboundedReceiver<u32>) -> Self {
+            Feed {
+                source: Some(ctx.add_stream(feed(rx))),
+                ..Feed::default()
+            }
+        }
+    }
+
+    impl Actor for Feed {
+        type Context = Context<Self>;
+    }
+
+    impl StreamHandler<u32> for Feed {
+        fn handle(&mut self, item: u32, _: &mut Self::Context) {
+            self.received.push(item);
+        }
+
+        fn started(&mut self, _: &mut Self::Context) {
+            self.started += 1;
+        }
+
+        fn finished(&mut self, ctx: &mut Self::Context) {
+            self.finished += 1;
+            if let Some(backup) = self.backup.take() {
+                assert!(ctx.replace_stream(self.source.unwrap(), iter(backup)));
+            }
+        }
+    }
+
+    impl Handler<Switch> for Feed {
+        type Result = bool;
+
+        fn handle(&mut self, Switch(rx): Switch, ctx: &mut Self::Context) -> bool {
+            ctx.replace_stream(self.source.unwrap(), feed(rx))
+        }
+    }
+
+    impl Handler<Summary> for Feed {
+        type Result = MessageResult<Summary>;
+
+        fn handle(&mut self, _: Summary, _: &mut Self::Context) -> Self::Result {
+            MessageResult((self.received.clone(), self.started, self.finished))
+        }
+    }
+
+    fn channel() -> (UnboundedSender<u32>, UnboundedReceiver<u32>) {
+        unbounded_channel()
+    }
+
+    fn feed(mut rx: UnboundedReceiver<u32>) -> impl Stream<Item = u32> {
+        poll_fn(move |cx| rx.poll_recv(cx))
+    }
+
+    #[actix::test]
+    async fn switches_sources_under_same_handle() {
+        let (primary, rx) = channel();
+        let addr = Feed::create(|ctx| Feed::with_source(ctx, rx));
+
+        primary.send(1).unwrap();
+        sleep(Duration::from_millis(10)).await;
+
+        let (backup, rx) = channel();
+        backup.send(2).unwrap();
+        assert!(addr.send(Switch(rx)).await.unwrap());
+
+        // the old source is dropped
+        assert!(primary.send(100).is_err());
+        backup.send(3).unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(addr.send(Summary).await.unwrap(), (vec![1, 2, 3], 1, 0));
+
+        // finished is called once the new source ends
+        drop(backup);
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(addr.send(Summary).await.unwrap(), (vec![1, 2, 3], 1, 1));
+    }
+
+    #[actix::test]
+    async fn takes_over_from_finished() {
+        let (primary, rx) = channel();
+        let addr = Feed::create(|ctx| Feed {
+            backup: Some(vec![2, 3]),
+            ..Feed::with_source(ctx, rx)
+        });
+
+        primary.send(1).unwrap();
+        drop(primary);
+        sleep(Duration::from_millis(10)).await;
+
+        // the backup ran to its end, without another call to started
+        assert_eq!(addr.send(Summary).await.unwrap(), (vec![1, 2, 3], 1, 2));
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "(bool, bool)")]
+    struct ReplaceStale;
+
+    impl Handler<ReplaceStale> for Feed {
+        type Result = MessageResult<ReplaceStale>;
+
+        fn handle(&mut self, _: ReplaceStale, ctx: &mut Self::Context) -> Self::Result {
+            let cancelled = self.source.unwrap();
+            ctx.cancel_future(cancelled);
+            let unknown = cancelled.next().next();
+            MessageResult((
+                ctx.replace_stream(cancelled, iter([1])),
+                ctx.replace_stream(unknown, iter([1])),
+            ))
+        }
+    }
+
+    #[actix::test]
+    async fn rejects_unknown_handles() {
+        let (_primary, rx) = channel();
+        let addr = Feed::create(|ctx| Feed::with_source(ctx, rx));
+
+        assert_eq!(addr.send(ReplaceStale).await.unwrap(), (false, false));
+        assert_eq!(addr.send(Summary).await.unwrap(), (vec![], 1, 0));
+    }
+}
+
+mod sequenced_stream {
+    use futures_util::stream::iter;
+
+    use super::*;
+
+    struct Entry(u64);
+
+    impl Sequenced for Entry {
+        fn seq(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "(Vec<u64>, Vec<(u64, u64)>, bool)")]
+    struct Summary;
+
+    struct Consumer {
+        action: GapAction,
+        handled: Vec<u64>,
+        gaps: Vec<(u64, u64)>,
+        finished: bool,
+    }
+
+    impl Consumer {
+        fn start(action: GapAction, log: Vec<u64>) -> Addr<Self> {
+            Consumer::create(move |ctx| {
+                ctx.add_sequenced_stream(iter(log.into_iter().map(Entry)));
+                Consumer {
+                    action,
+                    handled: Vec::new(),
+                    gaps: Vec::new(),
+                    finished: false,
+                }
+            })
+        }
+    }
+
+    impl Actor for Consumer {
+        type Context = Context<Self>;
+    }
+
+    impl SequencedStreamHandler<Entry> for Consumer {
+        fn handle(&mut self, Entry(seq): Entry, _: &mut Self::Context) {
+            self.handled.push(seq);
+        }
+
+        fn on_gap(&mut self, expected: u64, got: u64, _: &mut Self::Context) -> GapAction {
+            self.gaps.push((expected, got));
+            self.action
+        }
+
+        fn finished(&mut self, _: &mut Self::Context) {
+            self.finished = true;
+        }
+    }
+
+    impl Handler<Summary> for Consumer {
+        type Result = MessageResult<Summary>;
+
+        fn handle(&mut self, _: Summary, _: &mut Self::Context) -> Self::Result {
+            MessageResult((self.handled.clone(), self.gaps.clone(), self.finished))
+        }
+    }
+
+    #[actix::test]
+    async fn reports_gaps_and_replays() {
+        let addr = Consumer::start(GapAction::Deliver, vec![5, 6, 8, 9, 7, 8]);
+        sleep(Duration::from_millis(10)).await;
+
+        let (handled, gaps, finished) = addr.send(Summary).await.unwrap();
+        assert_eq!(handled, [5, 6, 8, 9, 7, 8]);
+        assert_eq!(gaps, [(7, 8), (10, 7)]);
+        assert!(finished);
+    }
+
+    #[actix::test]
+    async fn stops_at_fatal_gap() {
+        let addr = Consumer::start(GapAction::Stop, vec![1, 2, 4, 5]);
+        sleep(Duration::from_millis(10)).await;
+
+        let (handled, gaps, finished) = addr.send(Summary).await.unwrap();
+        assert_eq!(handled, [1, 2]);
+        assert_eq!(gaps, [(3, 4)]);
+        assert!(!finished);
+    }
+
+    #[actix::test]
+    async fn first_item_sets_sequence() {
+        let addr = Consumer::start(GapAction::Stop, vec![42, 43]);
+        sleep(Duration::from_millis(10)).await;
+
+        let (handled, gaps, finished) = addr.send(Summary).await.unwrap();
+        assert_eq!(handled, [42, 43]);
+        assert!(gaps.is_empty());
+        assert!(finished);
+    }
+}
+
+mod idle_hook {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Item;
+
+    /// Waits on a future while handling the item.
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct WaitingItem;
+
+    /// Responds to the item after a while.
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct SlowItem;
+
+    #[derive(Default)]
+    struct Coalescer {
+        pending: usize,
+        flushed: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl Actor for Coalescer {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            // ticking timers are no reason to become idle
+            ctx.run_interval(Duration::from_millis(1), |_, _| {});
+        }
+
+        fn idle(&mut self, _: &mut Self::Context) {
+            let pending = std::mem::take(&mut self.pending);
+            self.flushed.lock().unwrap().push(pending);
+        }
+    }
+
+    impl Handler<Item> for Coalescer {
+        type Result = ();
+
+        fn handle(&mut self, _: Item, _: &mut Self::Context) {
+            self.pending += 1;
+        }
+    }
+
+    impl Handler<WaitingItem> for Coalescer {
+        type Result = ();
+
+        fn handle(&mut self, _: WaitingItem, ctx: &mut Self::Context) {
+            self.pending += 1;
+            ctx.wait(sleep(Duration::from_millis(20)).into_actor(self));
+        }
+    }
+
+    impl Handler<SlowItem> for Coalescer {
+        type Result = ResponseActFuture<Self, ()>;
+
+        fn handle(&mut self, _: SlowItem, _: &mut Self::Context) -> Self::Result {
+            self.pending += 1;
+            Box::pin(sleep(Duration::from_millis(20)).into_actor(self))
+        }
+    }
+
+    fn start() -> (Addr<Coalescer>, Arc<Mutex<Vec<usize>>>) {
+        let flushed = Arc::new(Mutex::new(Vec::new()));
+        let addr = Coalescer {
+            pending: 0,
+            flushed: Arc::clone(&flushed),
+        }
+        .start();
+        (addr, flushed)
+    }
+
+    #[actix::test]
+    async fn called_once_per_drained_burst() {
+        let (addr, flushed) = start();
+        sleep(Duration::from_millis(10)).await;
+        assert!(flushed.lock().unwrap().is_empty());
+
+        for _ in 0..5 {
+            addr.do_send(Item);
+        }
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(*flushed.lock().unwrap(), [5]);
+
+        for _ in 0..3 {
+            addr.do_send(Item);
+        }
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(*flushed.lock().unwrap(), [5, 3]);
+    }
+
+    #[actix::test]
+    async fn not_called_while_waiting() {
+        let (addr, flushed) = start();
+        addr.do_send(WaitingItem);
+        addr.do_send(Item);
+
+        sleep(Duration::from_millis(10)).await;
+        assert!(flushed.lock().unwrap().is_empty());
+
+        sleep(Duration::from_millis(30)).await;
+        assert_eq!(*flushed.lock().unwrap(), [2]);
+    }
+
+    #[actix::test]
+    async fn not_called_while_responding() {
+        let (addr, flushed) = start();
+        let reply = addr.send(SlowItem);
+        addr.do_send(Item);
+
+        sleep(Duration::from_millis(10)).await;
+        assert!(flushed.lock().unwrap().is_empty());
+
+        reply.await.unwrap();
+        sleep(Duration::from_millis(5)).await;
+        assert_eq!(*flushed.lock().unwrap(), [2]);
+    }
+}
+
+mod poll_budget {
+    use std::sync::Mutex;
+
+    use futures_util::stream::repeat;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Spin;
+
+    /// Keeps its mailbox busy by sending itself a message for every message it handles.
+    struct Spinner {
+        spins: usize,
+        polled_at: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl Actor for Spinner {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.set_mailbox_budget(10);
+            ctx.spawn(fut::ready(()).map(|(), act: &mut Self, _| {
+                *act.polled_at.lock().unwrap() = Some(act.spins);
+            }));
+        }
+    }
+
+    impl Handler<Spin> for Spinner {
+        type Result = ();
+
+        fn handle(&mut self, _: Spin, ctx: &mut Context<Self>) {
+            self.spins += 1;
+            if self.polled_at.lock().unwrap().is_none() {
+                ctx.address().do_send(Spin);
+            }
+        }
+    }
+
+    #[actix::test]
+    async fn test_busy_mailbox_does_not_starve_futures() {
+        let polled_at = Arc::new(Mutex::new(None));
+        let addr = Spinner {
+            spins: 0,
+            polled_at: Arc::clone(&polled_at),
+        }
+        .start();
+        addr.do_send(Spin);
+
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(*polled_at.lock().unwrap(), Some(10));
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Probe;
+
+    /// Handles several endless streams.
+    struct Streamer {
+        items: usize,
+        probed_at: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl Actor for Streamer {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.set_futures_budget(1);
+            for _ in 0..10 {
+                ctx.add_stream(repeat(()));
+            }
+        }
+    }
+
+    impl StreamHandler<()> for Streamer {
+        fn handle(&mut self, (): (), ctx: &mut Context<Self>) {
+            self.items += 1;
+            if self.items == 1 {
+                ctx.address().do_send(Probe);
+            }
+        }
+    }
+
+    impl Handler<Probe> for Streamer {
+        type Result = ();
+
+        fn handle(&mut self, _: Probe, ctx: &mut Context<Self>) {
+            *self.probed_at.lock().unwrap() = Some(self.items);
+            ctx.stop();
+        }
+    }
+
+    #[actix::test]
+    async fn test_busy_futures_do_not_starve_mailbox() {
+        let probed_at = Arc::new(Mutex::new(None));
+        let addr = Streamer {
+            items: 0,
+            probed_at: Arc::clone(&probed_at),
+        }
+        .start();
+
+        sleep(Duration::from_millis(20)).await;
+        // a single stream got polled, yielding after 16 items, before the probe got handled
+        assert_eq!(*probed_at.lock().unwrap(), Some(16));
+        assert!(!addr.connected());
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be zero")]
+    fn test_zero_budget_panics() {
+        struct Zero;
+
+        impl Actor for Zero {
+            type Context = Context<Self>;
+        }
+
+        let mut ctx = Context::<Zero>::new();
+        ctx.set_mailbox_budget(0);
+    }
+}
+
+mod semaphore {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Fetch;
+
+    #[derive(Default)]
+    struct Crawler {
+        running: usize,
+        peak: usize,
+        done: usize,
+    }
+
+    impl Actor for Crawler {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Fetch> for Crawler {
+        type Result = ();
+
+        fn handle(&mut self, _: Fetch, ctx: &mut Context<Self>) {
+            let fetches = ctx.semaphore("fetch", 3);
+            let fetch = fut::ready(())
+                .map(|(), act: &mut Self, _| {
+                    act.running += 1;
+                    act.peak = act.peak.max(act.running);
+                })
+                .then(|(), _, _| fut::wrap_future(sleep(Duration::from_millis(5))))
+                .map(|(), act: &mut Self, _| {
+                    act.running -= 1;
+                    act.done += 1;
+                });
+            ctx.spawn(fetches.run(fetch));
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "(usize, usize)")]
+    struct Stats;
+
+    impl Handler<Stats> for Crawler {
+        type Result = MessageResult<Stats>;
+
+        fn handle(&mut self, _: Stats, _: &mut Context<Self>) -> Self::Result {
+            MessageResult((self.peak, self.done))
+        }
+    }
+
+    #[actix::test]
+    async fn test_semaphore_caps_concurrency() {
+        let addr = Crawler::default().start();
+        for _ in 0..10 {
+            addr.do_send(Fetch);
+        }
+
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(addr.send(Stats).await.unwrap(), (3, 10));
+    }
+
+    #[actix::test]
+    async fn test_permit_released_on_drop() {
+        let semaphore = Semaphore::new(1);
+        let permit = semaphore.try_acquire().unwrap();
+        assert!(semaphore.try_acquire().is_none());
+        assert_eq!(semaphore.available_permits(), 0);
+
+        drop(permit);
+        assert_eq!(semaphore.available_permits(), 1);
+        assert!(semaphore.try_acquire().is_some());
+    }
+}
+
+mod result_routing {
+    use std::sync::Mutex;
+
+    use futures_util::stream::iter;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Value(u32);
+
+    struct Router {
+        log: Arc<Mutex<Vec<String>>>,
+        on_error: Running,
+    }
+
+    impl Actor for Router {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Value> for Router {
+        type Result = ();
+
+        fn handle(&mut self, Value(value): Value, _: &mut Context<Self>) {
+            self.log.lock().unwrap().push(format!("ok {}", value));
+        }
+    }
+
+    impl ErrorHandler<&'static str> for Router {
+        fn error(&mut self, err: &'static str, _: &mut Context<Self>) -> Running {
+            self.log.lock().unwrap().push(format!("err {}", err));
+            self.on_error
+        }
+    }
+
+    fn router(on_error: Running) -> (Router, Arc<Mutex<Vec<String>>>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let router = Router {
+            log: Arc::clone(&log),
+            on_error,
+        };
+        (router, log)
+    }
+
+    #[actix::test]
+    async fn test_result_future_routes_ok_and_err() {
+        let (router, log) = router(Running::Continue);
+        Router::create(move |ctx| {
+            ctx.add_result_future(async { Ok::<_, &'static str>(Value(1)) });
+            ctx.add_result_future(async { Err::<Value, _>("failed") });
+            router
+        });
+
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(*log.lock().unwrap(), ["ok 1", "err failed"]);
+    }
+
+    #[actix::test]
+    async fn test_result_stream_continues_after_error() {
+        let (router, log) = router(Running::Continue);
+        Router::create(move |ctx| {
+            ctx.add_result_stream(iter([Ok(Value(1)), Err("bad"), Ok(Value(2))]));
+            router
+        });
+
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(*log.lock().unwrap(), ["ok 1", "err bad", "ok 2"]);
+    }
+
+    #[actix::test]
+    async fn test_result_stream_dropped_on_stop() {
+        let (router, log) = router(Running::Stop);
+        Router::create(move |ctx| {
+            ctx.add_result_stream(iter([Ok(Value(1)), Err("bad"), Ok(Value(2))]));
+            router
+        });
+
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(*log.lock().unwrap(), ["ok 1", "err bad"]);
+    }
+}
+
+mod next_timer {
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "Option<Duration>")]
+    struct Next;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Cancel(SpawnHandle);
+
+    struct Scheduler;
+
+    impl Actor for Scheduler {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Next> for Scheduler {
+        type Result = Option<Duration>;
+
+        fn handle(&mut self, _: Next, ctx: &mut Context<Self>) -> Self::Result {
+            ctx.next_timer()
+        }
+    }
+
+    impl Handler<Cancel> for Scheduler {
+        type Result = ();
+
+        fn handle(&mut self, Cancel(handle): Cancel, ctx: &mut Context<Self>) {
+            ctx.cancel_future(handle);
+        }
+    }
+
+    #[actix::test]
+    async fn test_next_timer_tracks_soonest_timer() {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let addr = Scheduler::create(|ctx| {
+            assert_eq!(ctx.next_timer(), None);
+            ctx.run_later(Duration::from_millis(200), |_, _| {});
+            let soon = ctx.run_later(Duration::from_millis(50), |_, _| {});
+            tx.send(soon).unwrap();
+            Scheduler
+        });
+        let soon = rx.await.unwrap();
+
+        let next = addr.send(Next).await.unwrap().unwrap();
+        assert!(next <= Duration::from_millis(50));
+        assert!(next > Duration::from_millis(20));
+
+        addr.send(Cancel(soon)).await.unwrap();
+        let next = addr.send(Next).await.unwrap().unwrap();
+        assert!(next > Duration::from_millis(100));
+
+        sleep(Duration::from_millis(250)).await;
+        assert_eq!(addr.send(Next).await.unwrap(), None);
+    }
+
+    #[actix::test]
+    async fn test_next_timer_follows_interval() {
+        let addr = Scheduler::create(|ctx| {
+            ctx.run_interval(Duration::from_millis(40), |_, _| {});
+            ctx.notify_later(Next, Duration::from_millis(10));
+            Scheduler
+        });
+
+        let next = addr.send(Next).await.unwrap().unwrap();
+        assert!(next <= Duration::from_millis(10));
+
+        // the delayed message has been delivered, the interval is the only timer left
+        sleep(Duration::from_millis(50)).await;
+        let next = addr.send(Next).await.unwrap().unwrap();
+        assert!(next <= Duration::from_millis(40));
+        assert!(next > Duration::ZERO);
+    }
+}
+
+mod become_actor {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    struct Phase {
+        name: &'static str,
+        log: Log,
+    }
+
+    impl Phase {
+        fn new(name: &'static str, log: &Log) -> Self {
+            Phase {
+                name,
+                log: Arc::clone(log),
+            }
+        }
+
+        fn log(&self, event: &str) {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} {event}", self.name));
+        }
+    }
+
+    impl Actor for Phase {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Context<Self>) {
+            self.log("started");
+            ctx.run_interval(Duration::from_millis(20), |act, _| act.log("tick"));
+        }
+
+        fn stopped(&mut self, _: &mut Context<Self>) {
+            self.log("stopped");
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "&'static str")]
+    struct Become(&'static str, Handover);
+
+    impl Handler<Become> for Phase {
+        type Result = MessageResult<Become>;
+
+        fn handle(
+            &mut self,
+            Become(name, handover): Become,
+            ctx: &mut Context<Self>,
+        ) -> Self::Result {
+            ctx.become_actor(Phase::new(name, &self.log), handover);
+            MessageResult(self.name)
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "&'static str")]
+    struct Name;
+
+    impl Handler<Name> for Phase {
+        type Result = MessageResult<Name>;
+
+        fn handle(&mut self, _: Name, _: &mut Context<Self>) -> Self::Result {
+            MessageResult(self.name)
+        }
+    }
+
+    #[actix::test]
+    async fn swap_keeps_queued_messages_and_spawned_work() {
+        let log = Log::default();
+        let addr = Phase::new("a", &log).start();
+
+        // both are queued before the first one is handled
+        let swapped = addr.send(Become("b", Handover::Swap));
+        let name = addr.send(Name);
+        assert_eq!(swapped.await.unwrap(), "a");
+        assert_eq!(name.await.unwrap(), "b");
+
+        log.lock().unwrap().clear();
+        sleep(Duration::from_millis(50)).await;
+
+        // the interval started by "a" now ticks with "b"
+        let log = log.lock().unwrap();
+        assert!(!log.is_empty());
+        assert!(log.iter().all(|event| event == "b tick"), "{log:?}");
+    }
+
+    #[actix::test]
+    async fn restart_runs_lifecycle_and_cancels_spawned_work() {
+        let log = Log::default();
+        let addr = Phase::new("a", &log).start();
+
+        assert_eq!(
+            addr.send(Become("b", Handover::Restart)).await.unwrap(),
+            "a"
+        );
+        assert_eq!(addr.send(Name).await.unwrap(), "b");
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["a started", "a stopped", "b started"]
+        );
+
+        log.lock().unwrap().clear();
+        sleep(Duration::from_millis(50)).await;
+
+        // only the interval started by "b" is left
+        let ticks = log.lock().unwrap().len();
+        assert!((1..=3).contains(&ticks), "{ticks} ticks");
+        assert!(addr.connected());
+    }
+}
+
+mod handler_timeout {
+    use std::sync::atomic::AtomicBool;
+
+    use super::*;
+
+    struct Slow {
+        finished: Arc<AtomicBool>,
+    }
+
+    impl Actor for Slow {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Context<Self>) {
+            ctx.set_handler_timeout(Some(Duration::from_millis(50)));
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Wait(u64);
+
+    impl Handler<Wait> for Slow {
+        type Result = ResponseFuture<u32>;
+
+        fn handle(&mut self, Wait(millis): Wait, _: &mut Context<Self>) -> Self::Result {
+            let finished = Arc::clone(&self.finished);
+            Box::pin(async move {
+                sleep(Duration::from_millis(millis)).await;
+                finished.store(true, Ordering::SeqCst);
+                7
+            })
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Exclusive(u64);
+
+    impl Handler<Exclusive> for Slow {
+        type Result = AtomicResponse<Self, u32>;
+
+        fn handle(&mut self, Exclusive(millis): Exclusive, _: &mut Context<Self>) -> Self::Result {
+            AtomicResponse::new(Box::pin(
+                sleep(Duration::from_millis(millis))
+                    .into_actor(self)
+                    .map(|_, act, _| {
+                        act.finished.store(true, Ordering::SeqCst);
+                        7
+                    }),
+            ))
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "u32")]
+    struct Block(u64);
+
+    impl Handler<Block> for Slow {
+        type Result = u32;
+
+        fn handle(&mut self, Block(millis): Block, _: &mut Context<Self>) -> u32 {
+            std::thread::sleep(Duration::from_millis(millis));
+            7
+        }
+    }
+
+    fn start() -> (Addr<Slow>, Arc<AtomicBool>) {
+        let finished = Arc::new(AtomicBool::new(false));
+        let addr = Slow {
+            finished: Arc::clone(&finished),
+        }
+        .start();
+        (addr, finished)
+    }
+
+    #[actix::test]
+    async fn aborts_slow_response() {
+        let (addr, finished) = start();
+
+        assert_eq!(addr.send(Wait(10)).await, Ok(7));
+        finished.store(false, Ordering::SeqCst);
+
+        assert_eq!(
+            addr.send(Wait(200)).await,
+            Err(MailboxError::HandlerTimeout)
+        );
+        sleep(Duration::from_millis(250)).await;
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+
+    #[actix::test]
+    async fn aborted_atomic_response_releases_actor() {
+        let (addr, finished) = start();
+
+        let slow = addr.send(Exclusive(1_000));
+        let next = addr.send(Exclusive(10));
+        assert_eq!(slow.await, Err(MailboxError::HandlerTimeout));
+        assert_eq!(next.await, Ok(7));
+        assert!(finished.load(Ordering::SeqCst));
+    }
+
+    #[actix::test]
+    async fn aborts_response_without_waiter() {
+        let (addr, finished) = start();
+
+        addr.do_send(Wait(200));
+        sleep(Duration::from_millis(250)).await;
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+
+    #[actix::test]
+    async fn blocking_body_is_not_interrupted() {
+        let (addr, _) = start();
+        assert_eq!(addr.send(Block(100)).await, Ok(7));
+    }
+}
+
+mod notify_once {
+    use super::*;
+
+    #[derive(Default)]
+    struct Cache {
+        flushes: usize,
+        evictions: usize,
+    }
+
+    impl Actor for Cache {
+        type Context = Context<Self>;
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Flush;
+
+    impl Handler<Flush> for Cache {
+        type Result = ();
+
+        fn handle(&mut self, _: Flush, _: &mut Context<Self>) {
+            self.flushes += 1;
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Evict;
+
+    impl Handler<Evict> for Cache {
+        type Result = ();
+
+        fn handle(&mut self, _: Evict, _: &mut Context<Self>) {
+            self.evictions += 1;
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Burst(usize);
+
+    impl Handler<Burst> for Cache {
+        type Result = ();
+
+        fn handle(&mut self, Burst(n): Burst, ctx: &mut Context<Self>) {
+            for _ in 0..n {
+                ctx.notify_once(Flush);
+            }
+            ctx.notify_once(Evict);
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "(usize, usize)")]
+    struct Counts;
+
+    impl Handler<Counts> for Cache {
+        type Result = MessageResult<Counts>;
+
+        fn handle(&mut self, _: Counts, _: &mut Context<Self>) -> Self::Result {
+            MessageResult((self.flushes, self.evictions))
+        }
+    }
+
+    #[actix::test]
+    async fn coalesces_pending_notifications_by_type() {
+        let addr = Cache::default().start();
+
+        addr.send(Burst(5)).await.unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(addr.send(Counts).await.unwrap(), (1, 1));
+
+        // handled notifications no longer coalesce
+        addr.send(Burst(3)).await.unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(addr.send(Counts).await.unwrap(), (2, 2));
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct FlushThenCancel;
+
+    impl Handler<FlushThenCancel> for Cache {
+        type Result = ();
+
+        fn handle(&mut self, _: FlushThenCancel, ctx: &mut Context<Self>) {
+            ctx.notify_once(Flush);
+            ctx.cancel_all();
+        }
+    }
+
+    #[actix::test]
+    async fn cancelled_notification_is_no_longer_pending() {
+        let addr = Cache::default().start();
+
+        addr.send(FlushThenCancel).await.unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(addr.send(Counts).await.unwrap(), (0, 0));
+
+        addr.send(Burst(2)).await.unwrap();
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(addr.send(Counts).await.unwrap(), (1, 1));
+    }
+}
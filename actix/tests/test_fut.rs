@@ -1,7 +1,7 @@
 use std::{
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Mutex,
     },
     time::Duration,
 };
@@ -257,3 +257,624 @@ fn test_try_future() {
         assert_eq!(res.err().unwrap(), 996u32);
     })
 }
+
+struct TimeoutOrMsg {
+    delay: Duration,
+    dropped: tokio::sync::oneshot::Sender<()>,
+}
+
+impl Message for TimeoutOrMsg {
+    type Result = usize;
+}
+
+impl Handler<TimeoutOrMsg> for MyStreamActor2 {
+    type Result = ResponseActFuture<Self, usize>;
+
+    fn handle(&mut self, msg: TimeoutOrMsg, _: &mut Context<Self>) -> Self::Result {
+        let TimeoutOrMsg { delay, dropped } = msg;
+        async move {
+            let _dropped = dropped;
+            sleep(delay).await;
+            1
+        }
+        .into_actor(self)
+        .timeout_or(Duration::from_millis(20), |act, _| {
+            act.counter += 1;
+            act.counter * 100
+        })
+        .boxed_local()
+    }
+}
+
+#[actix::test]
+async fn test_fut_timeout_or() {
+    let addr = MyStreamActor2 { counter: 0 }.start();
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let res = addr
+        .send(TimeoutOrMsg {
+            delay: Duration::from_millis(1),
+            dropped: tx,
+        })
+        .await
+        .unwrap();
+    assert_eq!(res, 1);
+    assert!(rx.await.is_err());
+
+    let (tx, mut rx) = tokio::sync::oneshot::channel();
+    let res = addr
+        .send(TimeoutOrMsg {
+            delay: Duration::from_secs(10),
+            dropped: tx,
+        })
+        .await
+        .unwrap();
+    assert_eq!(res, 100);
+
+    // inner future has been dropped on timeout
+    assert_eq!(
+        rx.try_recv(),
+        Err(tokio::sync::oneshot::error::TryRecvError::Closed)
+    );
+}
+
+#[derive(Default)]
+struct BufferedActor {
+    in_flight: usize,
+    peak: usize,
+}
+
+impl Actor for BufferedActor {
+    type Context = Context<Self>;
+}
+
+struct BufferedMsg(usize);
+
+impl Message for BufferedMsg {
+    type Result = (Vec<u64>, usize);
+}
+
+impl Handler<BufferedMsg> for BufferedActor {
+    type Result = ResponseActFuture<Self, (Vec<u64>, usize)>;
+
+    fn handle(&mut self, msg: BufferedMsg, _: &mut Context<Self>) -> Self::Result {
+        futures_util::stream::iter(0..6u64)
+            .into_actor(self)
+            .map(|i, act, _| {
+                act.in_flight += 1;
+                act.peak = act.peak.max(act.in_flight);
+
+                // later items complete first
+                sleep(Duration::from_millis(30 - i * 5))
+                    .into_actor(act)
+                    .map(move |_, act, _| {
+                        act.in_flight -= 1;
+                        i
+                    })
+            })
+            .buffered(msg.0)
+            .collect()
+            .map(|items, act, _| (items, act.peak))
+            .boxed_local()
+    }
+}
+
+#[actix::test]
+async fn test_stream_buffered() {
+    let addr = BufferedActor::default().start();
+    let (items, peak) = addr.send(BufferedMsg(3)).await.unwrap();
+    assert_eq!(items, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(peak, 3);
+
+    let addr = BufferedActor::default().start();
+    let (items, peak) = addr.send(BufferedMsg(1)).await.unwrap();
+    assert_eq!(items, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(peak, 1);
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Take(u32);
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Ping;
+
+struct Holder {
+    turns: fut::Turnstile<Vec<u32>>,
+}
+
+impl Actor for Holder {
+    type Context = Context<Self>;
+}
+
+impl Handler<Take> for Holder {
+    type Result = ();
+
+    fn handle(&mut self, Take(n): Take, ctx: &mut Self::Context) {
+        fut::lock(&self.turns)
+            .then(move |mut turns, _: &mut Self, _| {
+                turns.push(n);
+                // hold the lock for a while
+                fut::wrap_future(async move {
+                    sleep(Duration::from_millis(10)).await;
+                    drop(turns);
+                })
+            })
+            .spawn(ctx);
+    }
+}
+
+impl Handler<Ping> for Holder {
+    type Result = u32;
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) -> u32 {
+        42
+    }
+}
+
+#[actix::test]
+async fn test_turnstile_serves_waiters_in_order() {
+    let turns = fut::Turnstile::new(Vec::new());
+    let actors = (0..3)
+        .map(|_| {
+            Holder {
+                turns: turns.clone(),
+            }
+            .start()
+        })
+        .collect::<Vec<_>>();
+
+    for n in 0..6 {
+        actors[n as usize % 3].do_send(Take(n));
+        actix_rt::task::yield_now().await;
+    }
+
+    // waiting actors keep handling messages
+    assert_eq!(actors[1].send(Ping).await.unwrap(), 42);
+    assert!(turns.try_lock().is_none());
+
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(*turns.try_lock().unwrap(), [0, 1, 2, 3, 4, 5]);
+}
+
+#[derive(Message)]
+#[rtype(result = "u32")]
+struct Iterations;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Interrupt;
+
+struct Reconnector {
+    failures: u32,
+    iterations: u32,
+    handle: Option<SpawnHandle>,
+    connected: Arc<AtomicBool>,
+}
+
+impl Actor for Reconnector {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let reconnect = fut::loop_fn(1, |attempt, act: &mut Self, _| {
+            act.iterations += 1;
+            let connected = act.iterations > act.failures;
+            fut::ready(if connected {
+                fut::Loop::Break(attempt)
+            } else {
+                fut::Loop::Continue(attempt + 1)
+            })
+        })
+        .map(|attempt, act, _| {
+            assert_eq!(attempt, act.iterations);
+            act.connected.store(true, Ordering::SeqCst);
+        });
+        self.handle = Some(ctx.spawn(reconnect));
+    }
+}
+
+impl Handler<Iterations> for Reconnector {
+    type Result = u32;
+
+    fn handle(&mut self, _: Iterations, _: &mut Self::Context) -> u32 {
+        self.iterations
+    }
+}
+
+impl Handler<Interrupt> for Reconnector {
+    type Result = ();
+
+    fn handle(&mut self, _: Interrupt, ctx: &mut Self::Context) {
+        ctx.cancel_future(self.handle.take().unwrap());
+    }
+}
+
+fn reconnector(failures: u32, connected: &Arc<AtomicBool>) -> Addr<Reconnector> {
+    Reconnector {
+        failures,
+        iterations: 0,
+        handle: None,
+        connected: Arc::clone(connected),
+    }
+    .start()
+}
+
+#[actix::test]
+async fn test_loop_fn_until_break() {
+    let connected = Arc::new(AtomicBool::new(false));
+    let addr = reconnector(3, &connected);
+
+    sleep(Duration::from_millis(20)).await;
+    assert!(connected.load(Ordering::SeqCst));
+    assert_eq!(addr.send(Iterations).await.unwrap(), 4);
+}
+
+#[actix::test]
+async fn test_loop_fn_cancelled_between_iterations() {
+    let connected = Arc::new(AtomicBool::new(false));
+    let addr = reconnector(u32::MAX, &connected);
+
+    actix_rt::task::yield_now().await;
+    addr.send(Interrupt).await.unwrap();
+    let iterations = addr.send(Iterations).await.unwrap();
+    assert!(iterations > 0);
+
+    sleep(Duration::from_millis(20)).await;
+    assert_eq!(addr.send(Iterations).await.unwrap(), iterations);
+    assert!(!connected.load(Ordering::SeqCst));
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<String>")]
+struct Tokens;
+
+#[derive(Default)]
+struct TokenUser {
+    fetches: u32,
+    tokens: Vec<String>,
+}
+
+impl Actor for TokenUser {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let token = fut::wrap_future::<_, Self>(sleep(Duration::from_millis(10)))
+            .map(|_, act, _| {
+                act.fetches += 1;
+                format!("token-{}", act.fetches)
+            })
+            .shared();
+
+        for _ in 0..3 {
+            ctx.spawn(token.clone().map(|token, act, _| act.tokens.push(token)));
+        }
+    }
+}
+
+impl Handler<Tokens> for TokenUser {
+    type Result = MessageResult<Tokens>;
+
+    fn handle(&mut self, _: Tokens, _: &mut Self::Context) -> Self::Result {
+        assert!(self.fetches <= 1);
+        MessageResult(self.tokens.clone())
+    }
+}
+
+#[actix::test]
+async fn test_shared_polls_once() {
+    let addr = TokenUser::default().start();
+    assert!(addr.send(Tokens).await.unwrap().is_empty());
+
+    sleep(Duration::from_millis(30)).await;
+    assert_eq!(addr.send(Tokens).await.unwrap(), ["token-1"; 3]);
+}
+
+#[derive(Default)]
+struct Uploader {
+    finished: Vec<&'static str>,
+    cancelled: Vec<&'static str>,
+}
+
+impl Actor for Uploader {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let upload = |name: &'static str, dur| {
+            fut::wrap_future::<_, Self>(sleep(dur))
+                .map(move |_, act, _| act.finished.push(name))
+                .on_cancel(move |act, _| act.cancelled.push(name))
+        };
+
+        ctx.spawn(upload("quick", Duration::from_millis(1)));
+        let slow = ctx.spawn(upload("slow", Duration::from_millis(50)));
+        ctx.run_later(Duration::from_millis(10), move |_, ctx| {
+            ctx.cancel_future(slow);
+        });
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "(Vec<&'static str>, Vec<&'static str>)")]
+struct Uploads;
+
+impl Handler<Uploads> for Uploader {
+    type Result = MessageResult<Uploads>;
+
+    fn handle(&mut self, _: Uploads, _: &mut Self::Context) -> Self::Result {
+        MessageResult((self.finished.clone(), self.cancelled.clone()))
+    }
+}
+
+#[actix::test]
+async fn test_on_cancel() {
+    let addr = Uploader::default().start();
+
+    sleep(Duration::from_millis(80)).await;
+    let (finished, cancelled) = addr.send(Uploads).await.unwrap();
+    assert_eq!(finished, ["quick"]);
+    assert_eq!(cancelled, ["slow"]);
+}
+
+/// A hand-written `ActorFuture` that never completes and reports when it is dropped.
+struct Pending(Arc<AtomicBool>);
+
+impl<A: Actor> ActorFuture<A> for Pending {
+    type Output = ();
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        _: &mut A,
+        _: &mut A::Context,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<()> {
+        std::task::Poll::Pending
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+struct Waiter(Arc<AtomicBool>);
+
+impl Actor for Waiter {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.spawn(Pending(Arc::clone(&self.0)));
+        ctx.run_later(Duration::from_millis(5), |_, ctx| ctx.stop());
+    }
+}
+
+#[actix::test]
+async fn test_custom_future_dropped_on_stop() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let addr = Waiter(Arc::clone(&dropped)).start();
+
+    actix_rt::task::yield_now().await;
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    sleep(Duration::from_millis(20)).await;
+    assert!(!addr.connected());
+    assert!(dropped.load(Ordering::SeqCst));
+}
+
+/// Abandons futures which never complete, recording their cancellation.
+struct Abandoner(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for Abandoner {
+    type Context = Context<Self>;
+}
+
+fn abandoned(name: &'static str) -> impl ActorFuture<Abandoner, Output = ()> {
+    fut::wrap_future::<_, Abandoner>(std::future::pending::<()>())
+        .on_cancel(move |act, _| act.0.lock().unwrap().push(name))
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct CancelNested;
+
+impl Handler<CancelNested> for Abandoner {
+    type Result = ();
+
+    fn handle(&mut self, _: CancelNested, ctx: &mut Context<Self>) {
+        let chain = ctx.spawn(
+            abandoned("chain")
+                .timeout(Duration::from_secs(60))
+                .map(|_, _, _| ()),
+        );
+        let scope = ctx.scope(|scope| {
+            scope.spawn(abandoned("first"));
+            scope.spawn(abandoned("second"));
+        });
+        ctx.cancel_future(chain);
+        ctx.cancel_future(scope);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Flush;
+
+impl Handler<Flush> for Abandoner {
+    type Result = ();
+
+    fn handle(&mut self, _: Flush, _: &mut Context<Self>) {}
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct AbandonOnStop;
+
+impl Handler<AbandonOnStop> for Abandoner {
+    type Result = ();
+
+    fn handle(&mut self, _: AbandonOnStop, ctx: &mut Context<Self>) {
+        ctx.spawn(abandoned("spawned"));
+        ctx.wait(abandoned("waited"));
+        ctx.stop();
+    }
+}
+
+#[actix::test]
+async fn test_on_cancel_forwarded_by_combinators_and_scopes() {
+    let cancelled = Arc::new(Mutex::new(Vec::new()));
+    let addr = Abandoner(Arc::clone(&cancelled)).start();
+
+    addr.send(CancelNested).await.unwrap();
+    // cancelled handles are reaped once the handler has returned
+    addr.send(Flush).await.unwrap();
+
+    let mut cancelled = cancelled.lock().unwrap().clone();
+    cancelled.sort_unstable();
+    assert_eq!(cancelled, ["chain", "first", "second"]);
+}
+
+#[actix::test]
+async fn test_on_cancel_on_stop() {
+    let cancelled = Arc::new(Mutex::new(Vec::new()));
+    let addr = Abandoner(Arc::clone(&cancelled)).start();
+
+    let stopped = addr.wait_state(ActorState::Stopped);
+    addr.send(AbandonOnStop).await.unwrap();
+    stopped.await;
+
+    let mut cancelled = cancelled.lock().unwrap().clone();
+    cancelled.sort_unstable();
+    assert_eq!(cancelled, ["spawned", "waited"]);
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<&'static str>")]
+struct Block(std::sync::mpsc::Receiver<()>);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Release(std::sync::mpsc::Sender<()>);
+
+#[derive(Default)]
+struct Blocker(Vec<&'static str>);
+
+impl Actor for Blocker {
+    type Context = Context<Self>;
+}
+
+impl Handler<Block> for Blocker {
+    type Result = ResponseActFuture<Self, Vec<&'static str>>;
+
+    fn handle(&mut self, Block(rx): Block, _: &mut Context<Self>) -> Self::Result {
+        Box::pin(
+            fut::blocking(move || rx.recv().unwrap()).map(|(), act: &mut Self, _| {
+                act.0.push("unblocked");
+                act.0.clone()
+            }),
+        )
+    }
+}
+
+impl Handler<Release> for Blocker {
+    type Result = ();
+
+    fn handle(&mut self, Release(tx): Release, _: &mut Context<Self>) {
+        self.0.push("released");
+        tx.send(()).unwrap();
+    }
+}
+
+#[actix::test]
+async fn test_blocking_keeps_actor_responsive() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let addr = Blocker::default().start();
+
+    let blocked = addr.send(Block(rx));
+    addr.send(Release(tx)).await.unwrap();
+    assert_eq!(blocked.await.unwrap(), ["released", "unblocked"]);
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Work;
+
+#[derive(Default)]
+struct Worker {
+    running: Arc<std::sync::atomic::AtomicUsize>,
+    peak: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+impl Handler<Work> for Worker {
+    type Result = ResponseActFuture<Self, ()>;
+
+    fn handle(&mut self, _: Work, _: &mut Context<Self>) -> Self::Result {
+        let (running, peak) = (Arc::clone(&self.running), Arc::clone(&self.peak));
+        Box::pin(fut::blocking(move || {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(10));
+            running.fetch_sub(1, Ordering::SeqCst);
+        }))
+    }
+}
+
+#[actix::test]
+async fn test_blocking_pool_is_bounded() {
+    let bound = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .max(4);
+    let worker = Worker::default();
+    let peak = Arc::clone(&worker.peak);
+    let addr = worker.start();
+
+    let replies = futures_util::future::join_all((0..bound * 3).map(|_| addr.send(Work))).await;
+    assert!(replies.into_iter().all(|res| res.is_ok()));
+    assert!(peak.load(Ordering::SeqCst) <= bound);
+}
+
+#[derive(Default)]
+struct Stages(Vec<&'static str>);
+
+impl Actor for Stages {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        fut::wrap_future::<_, Self>(sleep(Duration::from_millis(1)))
+            .map(|_, act, _| {
+                act.0.push("outer");
+                fut::wrap_future::<_, Self>(sleep(Duration::from_millis(1)))
+                    .map(|_, act: &mut Self, _| act.0.push("inner"))
+            })
+            .flatten()
+            .map(|_, act, _| act.0.push("done"))
+            .spawn(ctx);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "Vec<&'static str>")]
+struct GetStages;
+
+impl Handler<GetStages> for Stages {
+    type Result = MessageResult<GetStages>;
+
+    fn handle(&mut self, _: GetStages, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.0.clone())
+    }
+}
+
+#[actix::test]
+async fn test_flatten() {
+    let addr = Stages::default().start();
+
+    sleep(Duration::from_millis(30)).await;
+    assert_eq!(
+        addr.send(GetStages).await.unwrap(),
+        ["outer", "inner", "done"]
+    );
+}
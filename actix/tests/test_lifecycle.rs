@@ -10,7 +10,7 @@ use std::{
 
 use actix::prelude::*;
 use actix_rt::time::sleep;
-use tokio::sync::oneshot::{channel, Sender};
+use tokio::sync::oneshot::{channel, Receiver, Sender};
 
 struct MyActor {
     started: Arc<AtomicBool>,
@@ -277,3 +277,285 @@ fn test_stop_restore_after_stopping() {
     assert!(stopping.load(Ordering::Relaxed), "Not stopping");
     assert!(!stopped.load(Ordering::Relaxed), "Stopped");
 }
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Shutdown;
+
+struct Daemon {
+    events: Arc<Mutex<Vec<&'static str>>>,
+    keep_running: bool,
+}
+
+impl Actor for Daemon {
+    type Context = Context<Self>;
+
+    fn no_listeners(&mut self, _: &mut Self::Context) -> Running {
+        self.events.lock().unwrap().push("no_listeners");
+        if self.keep_running {
+            Running::Continue
+        } else {
+            Running::Stop
+        }
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.events.lock().unwrap().push("stopped");
+    }
+}
+
+impl Handler<Shutdown> for Daemon {
+    type Result = ();
+
+    fn handle(&mut self, _: Shutdown, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+#[actix::test]
+async fn test_no_listeners_stops_by_default() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let addr = Daemon {
+        events: Arc::clone(&events),
+        keep_running: false,
+    }
+    .start();
+
+    drop(addr);
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(*events.lock().unwrap(), ["no_listeners", "stopped"]);
+}
+
+#[actix::test]
+async fn test_no_listeners_keeps_running() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let addr = Daemon {
+        events: Arc::clone(&events),
+        keep_running: true,
+    }
+    .start();
+    let weak = addr.downgrade();
+
+    drop(addr);
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(*events.lock().unwrap(), ["no_listeners"]);
+
+    // the actor is still reachable
+    weak.upgrade().unwrap().send(Shutdown).await.unwrap();
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(*events.lock().unwrap(), ["no_listeners", "stopped"]);
+}
+
+struct Deregistering {
+    ack: Option<Receiver<()>>,
+    timeout: Duration,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Actor for Deregistering {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+        let ack = self.ack.take().unwrap();
+        ctx.delay_stop(
+            async {
+                let _ = ack.await;
+            }
+            .into_actor(self),
+            self.timeout,
+        );
+        Running::Stop
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+#[actix::test]
+async fn test_delay_stop_waits_for_ack() {
+    let (ack, rx) = channel();
+    let stopped = Arc::new(AtomicBool::new(false));
+    let addr = Deregistering {
+        ack: Some(rx),
+        timeout: Duration::from_secs(10),
+        stopped: Arc::clone(&stopped),
+    }
+    .start();
+
+    drop(addr);
+    sleep(Duration::from_millis(20)).await;
+    assert!(!stopped.load(Ordering::SeqCst));
+
+    ack.send(()).unwrap();
+    sleep(Duration::from_millis(10)).await;
+    assert!(stopped.load(Ordering::SeqCst));
+}
+
+#[actix::test]
+async fn test_delay_stop_times_out() {
+    let (_ack, rx) = channel();
+    let stopped = Arc::new(AtomicBool::new(false));
+    let addr = Deregistering {
+        ack: Some(rx),
+        timeout: Duration::from_millis(20),
+        stopped: Arc::clone(&stopped),
+    }
+    .start();
+
+    drop(addr);
+    sleep(Duration::from_millis(10)).await;
+    assert!(!stopped.load(Ordering::SeqCst));
+
+    sleep(Duration::from_millis(30)).await;
+    assert!(stopped.load(Ordering::SeqCst));
+}
+
+struct Phased {
+    name: &'static str,
+    phase: Option<u32>,
+    linger: Duration,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Actor for Phased {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if let Some(phase) = self.phase {
+            ctx.shutdown_phase(phase);
+        }
+    }
+
+    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+        ctx.delay_stop(sleep(self.linger).into_actor(self), Duration::from_secs(10));
+        Running::Stop
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.log.lock().unwrap().push(self.name);
+    }
+}
+
+#[test]
+fn test_shutdown_in_phase_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let sys = System::new();
+
+    let log2 = Arc::clone(&log);
+    sys.block_on(async move {
+        let phased = |name, phase, linger| Phased {
+            name,
+            phase,
+            linger: Duration::from_millis(linger),
+            log: Arc::clone(&log2),
+        };
+        // joins phase 0 first, then moves to phase 2
+        let pool = phased("pool", Some(0), 0).start();
+        let _accept = phased("accept", Some(0), 30).start();
+        let _sessions = phased("sessions", Some(1), 0).start();
+        let _other = phased("other", None, 0).start();
+        actix_rt::task::yield_now().await;
+        pool.send(Move(2)).await.unwrap();
+
+        let report = System::current().shutdown().await;
+        assert_eq!(*log2.lock().unwrap(), ["accept", "sessions", "pool"]);
+        assert_eq!(report.stopped, 3);
+        assert!(report.timed_out.is_empty());
+    });
+    sys.run().unwrap();
+}
+
+#[test]
+fn test_shutdown_reports_stuck_actors() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let sys = System::new();
+
+    let log2 = Arc::clone(&log);
+    sys.block_on(async move {
+        let phased = |name, linger| Phased {
+            name,
+            phase: Some(0),
+            linger: Duration::from_millis(linger),
+            log: Arc::clone(&log2),
+        };
+        let _quick = phased("quick", 0).start();
+        let _stuck = phased("stuck", 5_000).start();
+        actix_rt::task::yield_now().await;
+
+        let report = System::current()
+            .shutdown_timeout(Duration::from_millis(50))
+            .await;
+        assert_eq!(report.stopped, 1);
+        assert_eq!(report.timed_out.len(), 1);
+        assert!(report.timed_out[0].type_name.ends_with("Phased"));
+        assert_eq!(report.timed_out[0].state, ActorState::Stopping);
+        assert_eq!(*log2.lock().unwrap(), ["quick"]);
+    });
+    sys.run().unwrap();
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Move(u32);
+
+impl Handler<Move> for Phased {
+    type Result = ();
+
+    fn handle(&mut self, Move(phase): Move, ctx: &mut Self::Context) {
+        ctx.shutdown_phase(phase);
+    }
+}
+
+struct Housekeeper {
+    housekeeping: bool,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Actor for Housekeeper {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let chores = sleep(Duration::from_secs(10)).into_actor(self);
+        if self.housekeeping {
+            ctx.spawn_detached_housekeeping(chores);
+        } else {
+            ctx.spawn(chores);
+        }
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+#[actix::test]
+async fn test_housekeeping_does_not_keep_alive() {
+    let stopped = Arc::new(AtomicBool::new(false));
+    let addr = Housekeeper {
+        housekeeping: true,
+        stopped: Arc::clone(&stopped),
+    }
+    .start();
+
+    sleep(Duration::from_millis(10)).await;
+    assert!(!stopped.load(Ordering::SeqCst));
+
+    drop(addr);
+    sleep(Duration::from_millis(10)).await;
+    assert!(stopped.load(Ordering::SeqCst));
+}
+
+#[actix::test]
+async fn test_spawned_future_keeps_alive() {
+    let stopped = Arc::new(AtomicBool::new(false));
+    let addr = Housekeeper {
+        housekeeping: false,
+        stopped: Arc::clone(&stopped),
+    }
+    .start();
+
+    drop(addr);
+    sleep(Duration::from_millis(10)).await;
+    assert!(!stopped.load(Ordering::SeqCst));
+}
@@ -1,9 +1,17 @@
 #![cfg(feature = "macros")]
 
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use actix::prelude::*;
 
+#[allow(dead_code)]
 #[derive(Clone, Debug)]
 struct Num(usize);
 
@@ -138,3 +146,654 @@ async fn test_different_message_result_types() {
         "Session with id `1` should already have been inserted"
     );
 }
+
+struct Payload(Vec<u8>);
+
+impl Message for Payload {
+    type Result = Arc<Payload>;
+}
+
+struct PayloadActor;
+
+impl Actor for PayloadActor {
+    type Context = Context<Self>;
+}
+
+impl Handler<Arc<Payload>> for PayloadActor {
+    type Result = Arc<Payload>;
+
+    fn handle(&mut self, msg: Arc<Payload>, _: &mut Context<Self>) -> Self::Result {
+        assert_eq!(msg.0.len(), 4096);
+        msg
+    }
+}
+
+#[actix::test]
+async fn test_shared_message_is_not_cloned() {
+    let payload = Arc::new(Payload(vec![7; 4096]));
+    let recipients: Vec<Recipient<Arc<Payload>>> =
+        (0..3).map(|_| PayloadActor.start().recipient()).collect();
+
+    for recipient in &recipients {
+        let res = recipient.send(Arc::clone(&payload)).await.unwrap();
+        assert!(Arc::ptr_eq(&res, &payload));
+    }
+
+    assert_eq!(Arc::strong_count(&payload), 1);
+}
+
+struct SessionProxy(Addr<SessionActor>);
+
+impl Actor for SessionProxy {
+    type Context = Context<Self>;
+}
+
+impl Handler<AddSession> for SessionProxy {
+    type Result = Response<Result<usize, String>>;
+
+    fn handle(&mut self, msg: AddSession, _: &mut Context<Self>) -> Self::Result {
+        Response::forward(&self.0, msg)
+    }
+}
+
+#[actix::test]
+async fn test_response_forward() {
+    let backend = SessionActor::new().start();
+    let proxy = SessionProxy(backend.clone()).start();
+
+    assert_eq!(proxy.send(AddSession(1)).await.unwrap(), Ok(1));
+    assert!(proxy.send(AddSession(1)).await.unwrap().is_err());
+    assert_eq!(backend.send(GetSessionCount).await.unwrap(), 1);
+
+    // a failed request is reported to the original sender
+    backend.stop_and_wait().await;
+    assert_eq!(proxy.send(AddSession(2)).await, Err(MailboxError::Closed));
+}
+
+struct Adapter(Addr<SessionActor>);
+
+impl Actor for Adapter {
+    type Context = Context<Self>;
+}
+
+#[derive(Message)]
+#[rtype(result = "Result<String, usize>")]
+struct Describe(usize, bool);
+
+impl Handler<Describe> for Adapter {
+    type Result = Response<Result<String, usize>>;
+
+    fn handle(&mut self, Describe(id, forward): Describe, _: &mut Context<Self>) -> Self::Result {
+        let res = if forward {
+            Response::forward(&self.0, AddSession(id))
+        } else {
+            Response::reply(Ok(id))
+        };
+        res.map(|res| res.map(|id| format!("session {}", id)))
+            .map_err(|err| err.len())
+    }
+}
+
+#[actix::test]
+async fn test_response_map() {
+    let backend = SessionActor::new().start();
+    let adapter = Adapter(backend).start();
+
+    assert_eq!(
+        adapter.send(Describe(1, false)).await.unwrap(),
+        Ok("session 1".to_owned())
+    );
+    assert_eq!(
+        adapter.send(Describe(1, true)).await.unwrap(),
+        Ok("session 1".to_owned())
+    );
+    assert_eq!(
+        adapter.send(Describe(1, true)).await.unwrap(),
+        Err("Duplicate session ID".len())
+    );
+}
+
+#[derive(Message)]
+#[rtype(result = "usize")]
+struct Square(usize);
+
+/// Handles no message before it is opened.
+struct Gated(Option<tokio::sync::oneshot::Receiver<()>>);
+
+impl Actor for Gated {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_mailbox_capacity(1);
+        let gate = self.0.take().unwrap();
+        ctx.wait(fut::wrap_future(gate).map(|_, _, _| ()));
+    }
+}
+
+impl Handler<Square> for Gated {
+    type Result = usize;
+
+    fn handle(&mut self, Square(n): Square, _: &mut Context<Self>) -> usize {
+        n * n
+    }
+}
+
+struct Facade {
+    backend: Addr<Gated>,
+    forwarded: Arc<AtomicUsize>,
+}
+
+impl Actor for Facade {
+    type Context = Context<Self>;
+}
+
+impl Handler<Square> for Facade {
+    type Result = Response<usize>;
+
+    fn handle(&mut self, msg: Square, _: &mut Context<Self>) -> Self::Result {
+        self.forwarded.fetch_add(1, Ordering::SeqCst);
+        Response::forward(&self.backend, msg)
+    }
+}
+
+#[actix::test]
+async fn test_response_forward_backpressure() {
+    let (open, gate) = tokio::sync::oneshot::channel();
+    let forwarded = Arc::new(AtomicUsize::new(0));
+    let facade = Facade {
+        backend: Gated(Some(gate)).start(),
+        forwarded: Arc::clone(&forwarded),
+    }
+    .start();
+
+    let reqs = (1..=8).map(|n| facade.send(Square(n))).collect::<Vec<_>>();
+    let all = actix_rt::spawn(futures_util::future::join_all(reqs));
+    actix_rt::time::sleep(Duration::from_millis(20)).await;
+
+    // the first request filled the backend's mailbox, the second waits for room
+    assert_eq!(forwarded.load(Ordering::SeqCst), 2);
+
+    open.send(()).unwrap();
+    let squares = all.await.unwrap().into_iter().map(Result::unwrap);
+    assert_eq!(squares.collect::<Vec<_>>(), [1, 4, 9, 16, 25, 36, 49, 64]);
+    assert_eq!(forwarded.load(Ordering::SeqCst), 8);
+}
+
+mod batching {
+    use futures_util::future::{join, join_all};
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct Square(usize);
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<usize>")]
+    struct BatchSizes;
+
+    #[derive(Default)]
+    struct Squares {
+        sizes: Vec<usize>,
+    }
+
+    impl Actor for Squares {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<Square> for Squares {
+        type Result = Batched<Square>;
+
+        fn handle(&mut self, msg: Square, _: &mut Self::Context) -> Self::Result {
+            Batched(msg)
+        }
+    }
+
+    impl BatchingHandler<Square> for Squares {
+        const MAX_BATCH: usize = 3;
+
+        fn handle_batch(&mut self, batch: Vec<(Square, Responder<usize>)>, _: &mut Self::Context) {
+            self.sizes.push(batch.len());
+            for (Square(n), responder) in batch {
+                // zero is left without a response
+                if n != 0 {
+                    responder.send(n * n);
+                }
+            }
+        }
+    }
+
+    impl Handler<BatchSizes> for Squares {
+        type Result = MessageResult<BatchSizes>;
+
+        fn handle(&mut self, _: BatchSizes, _: &mut Self::Context) -> Self::Result {
+            MessageResult(std::mem::take(&mut self.sizes))
+        }
+    }
+
+    #[actix::test]
+    async fn handles_requests_in_batches() {
+        let addr = Squares::default().start();
+
+        let res = join_all((1..=7).map(|n| addr.send(Square(n)))).await;
+        let res = res.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(res, [1, 4, 9, 16, 25, 36, 49]);
+
+        // full batches are handled right away, the rest after lingering
+        assert_eq!(addr.send(BatchSizes).await.unwrap(), [3, 3, 1]);
+
+        assert_eq!(addr.send(Square(3)).await.unwrap(), 9);
+        assert_eq!(addr.send(BatchSizes).await.unwrap(), [1]);
+    }
+
+    #[actix::test]
+    async fn dropped_responder_fails_request() {
+        let addr = Squares::default().start();
+
+        let (zero, two) = join(addr.send(Square(0)), addr.send(Square(2))).await;
+        assert_eq!(zero, Err(MailboxError::NoResponse));
+        assert_eq!(two, Ok(4));
+    }
+}
+
+mod middleware {
+    use std::time::Duration;
+
+    use actix_rt::time::sleep;
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct Double(usize);
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct SlowDouble(usize);
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<String>")]
+    struct Log;
+
+    #[derive(Default)]
+    struct Calc {
+        log: Vec<String>,
+        guarded: bool,
+    }
+
+    impl Actor for Calc {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Context<Self>) {
+            ctx.add_middleware::<Double, _>(Trace("first"));
+            if self.guarded {
+                ctx.add_middleware(Odd);
+            }
+            ctx.add_middleware::<Double, _>(Trace("second"));
+            ctx.add_middleware(Limit(100));
+            ctx.add_middleware::<Double, _>(Trace("last"));
+
+            ctx.add_middleware::<SlowDouble, _>(Trace("first"));
+            ctx.add_middleware::<SlowDouble, _>(Trace("last"));
+        }
+    }
+
+    impl Handler<Double> for Calc {
+        type Result = usize;
+
+        fn handle(&mut self, msg: Double, _: &mut Context<Self>) -> usize {
+            self.log.push("handle".to_owned());
+            msg.0 * 2
+        }
+    }
+
+    impl Handler<SlowDouble> for Calc {
+        type Result = ResponseActFuture<Self, usize>;
+
+        fn handle(&mut self, msg: SlowDouble, _: &mut Context<Self>) -> Self::Result {
+            Box::pin(
+                sleep(Duration::from_millis(5))
+                    .into_actor(self)
+                    .map(move |_, act, _| {
+                        act.log.push("handle".to_owned());
+                        msg.0 * 2
+                    }),
+            )
+        }
+    }
+
+    impl Handler<Log> for Calc {
+        type Result = MessageResult<Log>;
+
+        fn handle(&mut self, _: Log, _: &mut Context<Self>) -> Self::Result {
+            MessageResult(std::mem::take(&mut self.log))
+        }
+    }
+
+    struct Trace(&'static str);
+
+    impl MessageMiddleware<Calc, Double> for Trace {
+        type Rejection = usize;
+
+        fn before(
+            &mut self,
+            act: &mut Calc,
+            msg: &Double,
+            _: &mut Context<Calc>,
+        ) -> Result<(), usize> {
+            act.log.push(format!("{} before {}", self.0, msg.0));
+            Ok(())
+        }
+
+        fn after(&mut self, act: &mut Calc, res: &usize, _: &mut Context<Calc>) {
+            act.log.push(format!("{} after {}", self.0, res));
+        }
+    }
+
+    impl MessageMiddleware<Calc, SlowDouble> for Trace {
+        type Rejection = usize;
+
+        fn after(&mut self, act: &mut Calc, res: &usize, _: &mut Context<Calc>) {
+            act.log.push(format!("{} after {}", self.0, res));
+        }
+    }
+
+    /// Short-circuits large inputs.
+    struct Limit(usize);
+
+    impl MessageMiddleware<Calc, Double> for Limit {
+        type Rejection = usize;
+
+        fn before(
+            &mut self,
+            _: &mut Calc,
+            msg: &Double,
+            _: &mut Context<Calc>,
+        ) -> Result<(), usize> {
+            if msg.0 > self.0 {
+                Err(0)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// Rejects odd inputs with a typed rejection.
+    struct Odd;
+
+    struct OddInput;
+
+    impl From<OddInput> for usize {
+        fn from(_: OddInput) -> usize {
+            usize::MAX
+        }
+    }
+
+    impl MessageMiddleware<Calc, Double> for Odd {
+        type Rejection = OddInput;
+
+        fn before(
+            &mut self,
+            _: &mut Calc,
+            msg: &Double,
+            _: &mut Context<Calc>,
+        ) -> Result<(), OddInput> {
+            if msg.0 % 2 == 0 {
+                Ok(())
+            } else {
+                Err(OddInput)
+            }
+        }
+    }
+
+    #[actix::test]
+    async fn runs_in_order() {
+        let addr = Calc::default().start();
+
+        assert_eq!(addr.send(Double(2)).await.unwrap(), 4);
+        assert_eq!(
+            addr.send(Log).await.unwrap(),
+            [
+                "first before 2",
+                "second before 2",
+                "last before 2",
+                "handle",
+                "last after 4",
+                "second after 4",
+                "first after 4",
+            ]
+        );
+    }
+
+    #[actix::test]
+    async fn short_circuits() {
+        let addr = Calc::default().start();
+
+        assert_eq!(addr.send(Double(200)).await.unwrap(), 0);
+        assert_eq!(
+            addr.send(Log).await.unwrap(),
+            [
+                "first before 200",
+                "second before 200",
+                "second after 0",
+                "first after 0",
+            ]
+        );
+    }
+
+    #[actix::test]
+    async fn observes_async_result() {
+        let addr = Calc::default().start();
+
+        assert_eq!(addr.send(SlowDouble(3)).await.unwrap(), 6);
+        assert_eq!(
+            addr.send(Log).await.unwrap(),
+            ["handle", "last after 6", "first after 6"]
+        );
+    }
+
+    #[actix::test]
+    async fn rejects_with_typed_rejection() {
+        let addr = Calc {
+            guarded: true,
+            ..Default::default()
+        }
+        .start();
+
+        assert_eq!(addr.send(Double(2)).await.unwrap(), 4);
+        assert_eq!(addr.send(Double(3)).await.unwrap(), usize::MAX);
+        assert_eq!(
+            addr.send(Log).await.unwrap(),
+            [
+                "first before 2",
+                "second before 2",
+                "last before 2",
+                "handle",
+                "last after 4",
+                "second after 4",
+                "first after 4",
+                "first before 3",
+                &format!("first after {}", usize::MAX),
+            ]
+        );
+    }
+}
+
+impl DynHandler for SessionActor {}
+
+struct OpenSessions(Vec<usize>);
+
+impl Command<SessionActor> for OpenSessions {
+    fn execute(self: Box<Self>, act: &mut SessionActor, _: &mut Context<SessionActor>) {
+        for id in self.0 {
+            let _ = act.add_session(id);
+        }
+    }
+}
+
+struct CountSessions;
+
+impl Command<SessionActor, usize> for CountSessions {
+    fn execute(self: Box<Self>, act: &mut SessionActor, _: &mut Context<SessionActor>) -> usize {
+        act.session_count()
+    }
+}
+
+#[actix::test]
+async fn test_dynamic_commands() {
+    let addr = SessionActor::new().start();
+    let commands: Vec<Box<dyn Command<SessionActor>>> = vec![
+        Box::new(OpenSessions(vec![1, 2])),
+        Box::new(OpenSessions(vec![2, 3])),
+    ];
+    for cmd in commands {
+        addr.send(cmd).await.unwrap();
+    }
+
+    let count: Box<dyn Command<SessionActor, usize>> = Box::new(CountSessions);
+    assert_eq!(addr.send(count).await.unwrap(), 3);
+    // regular handlers keep working alongside commands
+    assert_eq!(addr.send(GetSessionCount).await.unwrap(), 3);
+}
+
+mod memo {
+    use std::time::Duration;
+
+    use actix::clock::sleep;
+
+    use super::*;
+
+    #[derive(Message, Clone, PartialEq, Eq, Hash)]
+    #[rtype(result = "u64")]
+    struct Balance(u32);
+
+    #[derive(Message, Clone, PartialEq, Eq, Hash)]
+    #[rtype(result = "u64")]
+    struct SlowBalance(u32);
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct Deposit(u32, u64);
+
+    #[derive(Message)]
+    #[rtype(result = "usize")]
+    struct Queries;
+
+    #[derive(Default)]
+    struct Ledger {
+        balances: std::collections::HashMap<u32, u64>,
+        queries: usize,
+    }
+
+    impl Actor for Ledger {
+        type Context = Context<Self>;
+
+        fn started(&mut self, ctx: &mut Self::Context) {
+            ctx.enable_memo::<Balance>(Duration::from_millis(50));
+            ctx.enable_memo::<SlowBalance>(Duration::from_secs(10));
+        }
+    }
+
+    impl Handler<Balance> for Ledger {
+        type Result = u64;
+
+        fn handle(&mut self, Balance(account): Balance, _: &mut Self::Context) -> u64 {
+            self.queries += 1;
+            self.balances.get(&account).copied().unwrap_or_default()
+        }
+    }
+
+    impl Handler<SlowBalance> for Ledger {
+        type Result = ResponseActFuture<Self, u64>;
+
+        fn handle(
+            &mut self,
+            SlowBalance(account): SlowBalance,
+            _: &mut Self::Context,
+        ) -> Self::Result {
+            self.queries += 1;
+            let balance = self.balances.get(&account).copied().unwrap_or_default();
+            Box::pin(
+                fut::wrap_future::<_, Self>(sleep(Duration::from_millis(20)))
+                    .map(move |_, _, _| balance),
+            )
+        }
+    }
+
+    impl Handler<Deposit> for Ledger {
+        type Result = ();
+
+        fn handle(&mut self, Deposit(account, amount): Deposit, ctx: &mut Self::Context) {
+            *self.balances.entry(account).or_default() += amount;
+            ctx.invalidate_memo(&Balance(account));
+            ctx.invalidate_memo(&SlowBalance(account));
+        }
+    }
+
+    impl Handler<Queries> for Ledger {
+        type Result = usize;
+
+        fn handle(&mut self, _: Queries, _: &mut Self::Context) -> usize {
+            self.queries
+        }
+    }
+
+    #[actix::test]
+    async fn answers_equal_messages_from_cache() {
+        let ledger = Ledger::default().start();
+        ledger.send(Deposit(1, 10)).await.unwrap();
+
+        assert_eq!(ledger.send(Balance(1)).await.unwrap(), 10);
+        assert_eq!(ledger.send(Balance(1)).await.unwrap(), 10);
+        assert_eq!(ledger.send(Queries).await.unwrap(), 1);
+
+        assert_eq!(ledger.send(Balance(2)).await.unwrap(), 0);
+        assert_eq!(ledger.send(Queries).await.unwrap(), 2);
+    }
+
+    #[actix::test]
+    async fn expires_after_ttl() {
+        let ledger = Ledger::default().start();
+
+        ledger.send(Balance(1)).await.unwrap();
+        sleep(Duration::from_millis(60)).await;
+        ledger.send(Balance(1)).await.unwrap();
+        assert_eq!(ledger.send(Queries).await.unwrap(), 2);
+    }
+
+    #[actix::test]
+    async fn invalidates_single_message() {
+        let ledger = Ledger::default().start();
+
+        assert_eq!(ledger.send(Balance(1)).await.unwrap(), 0);
+        assert_eq!(ledger.send(Balance(2)).await.unwrap(), 0);
+        ledger.send(Deposit(1, 5)).await.unwrap();
+
+        assert_eq!(ledger.send(Balance(1)).await.unwrap(), 5);
+        assert_eq!(ledger.send(Balance(2)).await.unwrap(), 0);
+        assert_eq!(ledger.send(Queries).await.unwrap(), 3);
+    }
+
+    #[actix::test]
+    async fn caches_async_results_once_resolved() {
+        let ledger = Ledger::default().start();
+
+        assert_eq!(ledger.send(SlowBalance(1)).await.unwrap(), 0);
+        assert_eq!(ledger.send(SlowBalance(1)).await.unwrap(), 0);
+        assert_eq!(ledger.send(Queries).await.unwrap(), 1);
+    }
+
+    #[actix::test]
+    async fn skips_results_pending_while_invalidated() {
+        let ledger = Ledger::default().start();
+
+        let pending = ledger.send(SlowBalance(1));
+        let deposit = ledger.send(Deposit(1, 5));
+        let (balance, _) = futures_util::future::join(pending, deposit).await;
+        // computed before the deposit
+        assert_eq!(balance.unwrap(), 0);
+
+        assert_eq!(ledger.send(SlowBalance(1)).await.unwrap(), 5);
+        assert_eq!(ledger.send(Queries).await.unwrap(), 2);
+    }
+}
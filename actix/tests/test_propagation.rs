@@ -0,0 +1,161 @@
+#![cfg(feature = "macros")]
+
+use std::{
+    cell::Cell,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::{prelude::*, set_context_propagator, ContextPropagator};
+use actix_rt::time::sleep;
+
+thread_local! {
+    static TRACE: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+fn current() -> Option<u64> {
+    TRACE.with(Cell::get)
+}
+
+struct Traces;
+
+struct Restore(Option<u64>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        TRACE.with(|trace| trace.set(self.0));
+    }
+}
+
+impl ContextPropagator for Traces {
+    type Context = u64;
+    type Guard = Restore;
+
+    fn capture(&self) -> Option<u64> {
+        current()
+    }
+
+    fn enter(&self, trace: &u64) -> Restore {
+        Restore(TRACE.with(|current| current.replace(Some(*trace))))
+    }
+}
+
+fn install() {
+    let _ = set_context_propagator(Traces);
+}
+
+type Seen = Arc<Mutex<Vec<(&'static str, Option<u64>)>>>;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Work;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Continue;
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Forwarded;
+
+struct Worker {
+    seen: Seen,
+    peer: Option<Addr<Worker>>,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+impl Handler<Work> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Work, ctx: &mut Context<Self>) {
+        self.seen.lock().unwrap().push(("handle", current()));
+        ctx.notify(Continue);
+        ctx.spawn(
+            fut::wrap_future(sleep(Duration::from_millis(5)))
+                .map(|_, act: &mut Self, _| act.seen.lock().unwrap().push(("spawn", current()))),
+        );
+        if let Some(ref peer) = self.peer {
+            peer.do_send(Forwarded);
+        }
+    }
+}
+
+impl Handler<Continue> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Continue, _: &mut Context<Self>) {
+        self.seen.lock().unwrap().push(("notify", current()));
+    }
+}
+
+impl Handler<Forwarded> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Forwarded, _: &mut Context<Self>) {
+        self.seen.lock().unwrap().push(("peer", current()));
+    }
+}
+
+#[actix::test]
+async fn propagates_context_of_sender() {
+    install();
+    let seen = Seen::default();
+    let peer = Worker {
+        seen: Arc::clone(&seen),
+        peer: None,
+    }
+    .start();
+    let addr = Worker {
+        seen: Arc::clone(&seen),
+        peer: Some(peer),
+    }
+    .start();
+
+    {
+        let _trace = Traces.enter(&7);
+        addr.do_send(Work);
+    }
+    assert_eq!(current(), None);
+    sleep(Duration::from_millis(20)).await;
+
+    let mut seen = seen.lock().unwrap().clone();
+    seen.sort();
+    assert_eq!(
+        seen,
+        [
+            ("handle", Some(7)),
+            ("notify", Some(7)),
+            ("peer", Some(7)),
+            ("spawn", Some(7)),
+        ]
+    );
+}
+
+#[actix::test]
+async fn without_context_nothing_is_entered() {
+    install();
+    let seen = Seen::default();
+    let addr = Worker {
+        seen: Arc::clone(&seen),
+        peer: None,
+    }
+    .start();
+
+    addr.send(Work).await.unwrap();
+    sleep(Duration::from_millis(20)).await;
+
+    assert!(seen
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|(_, trace)| trace.is_none()));
+}
+
+#[test]
+fn installs_once() {
+    install();
+    assert!(set_context_propagator(Traces).is_err());
+}
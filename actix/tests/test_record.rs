@@ -0,0 +1,122 @@
+#![cfg(feature = "macros")]
+
+use actix::{
+    prelude::*,
+    record::{Recorder, Recording, ReplayError},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Message, Serialize, Deserialize)]
+#[rtype(result = "()")]
+struct Push(u32);
+
+#[derive(Message, Serialize, Deserialize)]
+#[rtype(result = "()")]
+struct Double;
+
+#[derive(Message)]
+#[rtype(result = "Vec<u32>")]
+struct Values;
+
+#[derive(Default)]
+struct Stack {
+    values: Vec<u32>,
+    recorder: Option<Recorder>,
+}
+
+impl Actor for Stack {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if let Some(recorder) = &self.recorder {
+            ctx.record::<Push>(recorder);
+            ctx.record::<Double>(recorder);
+        }
+    }
+}
+
+impl Handler<Push> for Stack {
+    type Result = ();
+
+    fn handle(&mut self, Push(n): Push, _: &mut Self::Context) {
+        self.values.push(n);
+    }
+}
+
+impl Handler<Double> for Stack {
+    type Result = ();
+
+    fn handle(&mut self, _: Double, _: &mut Self::Context) {
+        self.values.iter_mut().for_each(|n| *n *= 2);
+    }
+}
+
+impl Handler<Values> for Stack {
+    type Result = MessageResult<Values>;
+
+    fn handle(&mut self, _: Values, _: &mut Self::Context) -> Self::Result {
+        MessageResult(self.values.clone())
+    }
+}
+
+#[actix::test]
+async fn test_record_and_replay_in_order() {
+    let recorder = Recorder::new();
+    let recorded = |values| {
+        Stack {
+            values,
+            recorder: Some(recorder.clone()),
+        }
+        .start()
+    };
+    let a = recorded(Vec::new());
+    let b = recorded(vec![7]);
+
+    a.send(Push(1)).await.unwrap();
+    b.send(Double).await.unwrap();
+    a.send(Double).await.unwrap();
+    a.send(Push(3)).await.unwrap();
+    // not recorded
+    a.send(Values).await.unwrap();
+
+    let json = serde_json::to_string(&recorder.take()).unwrap();
+    let recording: Recording = serde_json::from_str(&json).unwrap();
+    assert_eq!(recording.messages.len(), 4);
+    assert!(recorder.recording().messages.is_empty());
+
+    let actors = recording.actors();
+    assert_eq!(actors.len(), 2);
+
+    let replayed = Stack::default().start();
+    let sent = recording
+        .replay(actors[0], &replayed)
+        .message::<Push>()
+        .message::<Double>()
+        .run()
+        .unwrap();
+    assert_eq!(sent, 3);
+    assert_eq!(replayed.send(Values).await.unwrap(), [2, 3]);
+}
+
+#[actix::test]
+async fn test_replay_requires_registered_messages() {
+    let recorder = Recorder::new();
+    let addr = Stack {
+        values: Vec::new(),
+        recorder: Some(recorder.clone()),
+    }
+    .start();
+    addr.send(Push(1)).await.unwrap();
+    addr.send(Double).await.unwrap();
+
+    let recording = recorder.recording();
+    let replayed = Stack::default().start();
+    let res = recording
+        .replay(recording.actors()[0], &replayed)
+        .message::<Push>()
+        .run();
+    assert!(matches!(res, Err(ReplayError::Unregistered(name)) if name.ends_with("Double")));
+
+    // nothing was replayed
+    assert!(replayed.send(Values).await.unwrap().is_empty());
+}
@@ -0,0 +1,75 @@
+//! Sending a message moves its payload into the handler.
+//!
+//! Lives in its own test binary because it installs a global allocator.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System as SystemAlloc},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use actix::prelude::*;
+
+const PAYLOAD: usize = 4 * 1024 * 1024;
+
+/// Counts allocations that are at least as large as the payload.
+struct CountingAlloc;
+
+static LARGE_ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= PAYLOAD {
+            LARGE_ALLOCS.fetch_add(1, Ordering::SeqCst);
+        }
+        SystemAlloc.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        SystemAlloc.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+struct Payload(Vec<u8>);
+
+impl Message for Payload {
+    type Result = usize;
+}
+
+struct Sink;
+
+impl Actor for Sink {
+    type Context = Context<Self>;
+}
+
+impl Handler<Payload> for Sink {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Payload, _: &mut Context<Self>) -> usize {
+        msg.0.as_ptr() as usize
+    }
+}
+
+#[actix::test]
+async fn test_send_moves_payload() {
+    let addr = Sink.start();
+    let recipient = addr.clone().recipient::<Payload>();
+
+    for _ in 0..3 {
+        let data = vec![1u8; PAYLOAD];
+        let ptr = data.as_ptr() as usize;
+        let before = LARGE_ALLOCS.load(Ordering::SeqCst);
+
+        assert_eq!(addr.send(Payload(data)).await.unwrap(), ptr);
+        assert_eq!(LARGE_ALLOCS.load(Ordering::SeqCst), before);
+    }
+
+    let data = vec![1u8; PAYLOAD];
+    let ptr = data.as_ptr() as usize;
+    let before = LARGE_ALLOCS.load(Ordering::SeqCst);
+
+    assert_eq!(recipient.send(Payload(data)).await.unwrap(), ptr);
+    assert_eq!(LARGE_ALLOCS.load(Ordering::SeqCst), before);
+}
@@ -0,0 +1,56 @@
+#![cfg(unix)]
+
+use std::{
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix::prelude::*;
+
+struct Server {
+    stopped: Arc<AtomicBool>,
+}
+
+impl Actor for Server {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.shutdown_phase(0);
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn shuts_down_on_sigterm() {
+    let stopped = Arc::new(AtomicBool::new(false));
+    let sys = SystemBuilder::new()
+        .workers(1)
+        .shutdown_grace(Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let server = Server {
+        stopped: Arc::clone(&stopped),
+    };
+    sys.block_on(async move {
+        server.start();
+    });
+
+    // the system listens for signals once built, the signal is handled when it runs
+    let status = Command::new("kill")
+        .args(["-TERM", &std::process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    sys.run().unwrap();
+
+    assert!(stopped.load(Ordering::SeqCst));
+}
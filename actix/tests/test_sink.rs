@@ -304,3 +304,93 @@ async fn test_send_single_bytes() {
 
     assert_eq!(b"hi!", &res[..]);
 }
+
+mod drain_on_stop {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use actix_rt::time::sleep;
+    use tokio_util::sync::{PollSendError, PollSender};
+
+    use super::*;
+
+    #[derive(Message)]
+    #[rtype(result = "()")]
+    struct WriteAndStop(Vec<u32>);
+
+    struct Writer {
+        sink: SinkWrite<u32, PollSender<u32>>,
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Actor for Writer {
+        type Context = actix::Context<Self>;
+
+        fn stopped(&mut self, _: &mut Self::Context) {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl actix::io::WriteHandler<PollSendError<u32>> for Writer {}
+
+    impl Handler<WriteAndStop> for Writer {
+        type Result = ();
+
+        fn handle(&mut self, WriteAndStop(items): WriteAndStop, ctx: &mut actix::Context<Self>) {
+            for item in items {
+                self.sink.write(item).unwrap();
+            }
+            ctx.stop();
+        }
+    }
+
+    fn writer(
+        capacity: usize,
+        timeout: Duration,
+    ) -> (Addr<Writer>, mpsc::Receiver<u32>, Arc<AtomicBool>) {
+        let (tx, rx) = mpsc::channel(capacity);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stopped2 = Arc::clone(&stopped);
+        let addr = Writer::create(move |ctx| {
+            let mut sink = SinkWrite::new(PollSender::new(tx), ctx);
+            sink.set_drain_timeout(timeout);
+            Writer {
+                sink,
+                stopped: stopped2,
+            }
+        });
+        (addr, rx, stopped)
+    }
+
+    #[actix::test]
+    async fn test_queued_items_flushed_on_stop() {
+        let (addr, mut rx, stopped) = writer(1, Duration::from_secs(5));
+        addr.do_send(WriteAndStop(vec![1, 2, 3, 4, 5]));
+        drop(addr);
+
+        let mut received = Vec::new();
+        while let Some(item) = rx.recv().await {
+            received.push(item);
+        }
+        assert_eq!(received, [1, 2, 3, 4, 5]);
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    #[actix::test]
+    async fn test_drain_gives_up_after_timeout() {
+        let (addr, mut rx, stopped) = writer(1, Duration::from_millis(10));
+        addr.do_send(WriteAndStop(vec![1, 2, 3]));
+
+        sleep(Duration::from_millis(5)).await;
+        assert!(!stopped.load(Ordering::SeqCst));
+
+        sleep(Duration::from_millis(20)).await;
+        assert!(stopped.load(Ordering::SeqCst));
+        assert_eq!(rx.recv().await, Some(1));
+    }
+}
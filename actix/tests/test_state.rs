@@ -0,0 +1,57 @@
+#![cfg(feature = "macros")]
+
+use actix::{
+    prelude::*,
+    state::{GetState, StateSnapshot},
+};
+use serde_json::json;
+
+struct Queue {
+    items: Vec<&'static str>,
+}
+
+impl Actor for Queue {
+    type Context = Context<Self>;
+}
+
+impl StateSnapshot for Queue {
+    fn snapshot(&self) -> serde_json::Value {
+        json!({ "items": self.items })
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Push(&'static str);
+
+impl Handler<Push> for Queue {
+    type Result = ();
+
+    fn handle(&mut self, Push(item): Push, _: &mut Context<Self>) {
+        self.items.push(item);
+    }
+}
+
+#[actix::test]
+async fn test_get_state() {
+    let addr = Queue { items: vec![] }.start();
+    addr.send(Push("a")).await.unwrap();
+    addr.send(Push("b")).await.unwrap();
+
+    let snapshot = addr.send(GetState).await.unwrap();
+    assert!(snapshot.actor.ends_with("Queue"));
+    assert_eq!(snapshot.state, ActorState::Running);
+    assert_eq!(snapshot.data, json!({ "items": ["a", "b"] }));
+}
+
+#[actix::test]
+async fn test_get_state_through_recipient() {
+    let recipient: Recipient<GetState> = Queue { items: vec!["x"] }.start().recipient();
+
+    let snapshot = recipient.send(GetState).await.unwrap();
+    assert_eq!(
+        serde_json::to_value(&snapshot).unwrap()["data"],
+        json!({ "items": ["x"] })
+    );
+    assert_eq!(serde_json::to_value(&snapshot).unwrap()["state"], "Running");
+}
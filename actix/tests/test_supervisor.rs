@@ -70,3 +70,42 @@ fn test_supervisor_restart() {
     assert_eq!(restarts.load(Ordering::Relaxed), 2);
     assert_eq!(messages.load(Ordering::Relaxed), 2);
 }
+
+struct Echo(usize);
+
+impl Message for Echo {
+    type Result = usize;
+}
+
+impl actix::Handler<Echo> for MyActor {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Echo, _: &mut actix::Context<MyActor>) -> usize {
+        msg.0
+    }
+}
+
+#[actix::test]
+async fn test_supervisor_keeps_mailbox_on_restart() {
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let addr = {
+        let restarts = Arc::clone(&restarts);
+        actix::Supervisor::start(move |_| {
+            MyActor(
+                Arc::new(AtomicUsize::new(0)),
+                restarts,
+                Arc::new(AtomicUsize::new(0)),
+            )
+        })
+    };
+
+    // requests queued behind a failure are answered by the restarted actor
+    addr.do_send(Die);
+    let requests = (0..3).map(|n| addr.send(Echo(n))).collect::<Vec<_>>();
+    for (n, req) in requests.into_iter().enumerate() {
+        assert_eq!(req.await.unwrap(), n);
+    }
+
+    assert_eq!(restarts.load(Ordering::Relaxed), 1);
+    assert!(addr.connected());
+}
@@ -1,9 +1,10 @@
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
-    Arc, Condvar, Mutex,
+    mpsc, Arc, Condvar, Mutex,
 };
 
 use actix::prelude::*;
+use futures_util::future::join_all;
 
 struct Fibonacci(pub u32);
 
@@ -100,3 +101,227 @@ fn test_sync() {
         "Wrong number of messages"
     );
 }
+
+/// Work blocking its worker until released through the channel.
+struct Blocking(mpsc::Receiver<()>);
+
+impl Message for Blocking {
+    type Result = ();
+}
+
+struct Quick;
+
+impl Message for Quick {
+    type Result = ();
+}
+
+struct Worker(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for Worker {
+    type Context = SyncContext<Self>;
+}
+
+impl Handler<Blocking> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, Blocking(release): Blocking, _: &mut Self::Context) {
+        let _ = release.recv();
+        self.0.lock().unwrap().push("blocking");
+    }
+}
+
+impl Handler<Quick> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Quick, _: &mut Self::Context) {
+        self.0.lock().unwrap().push("quick");
+    }
+}
+
+#[actix::test]
+async fn test_idle_workers_take_queued_messages() {
+    let done = Arc::new(Mutex::new(Vec::new()));
+    let done_c = Arc::clone(&done);
+    let addr = SyncArbiter::start(2, move || Worker(Arc::clone(&done_c)));
+
+    // whichever worker takes the blocking message, the other one handles all quick ones
+    let (release, blocked) = mpsc::channel();
+    let blocking = addr.send(Blocking(blocked));
+    let quick = join_all((0..5).map(|_| addr.send(Quick)));
+
+    // a worker queue would leave some quick messages behind the blocked worker forever
+    let answered = actix_rt::time::timeout(std::time::Duration::from_secs(5), quick)
+        .await
+        .expect("quick messages are stuck behind the blocked worker");
+    assert!(answered.into_iter().all(|res| res.is_ok()));
+    assert_eq!(*done.lock().unwrap(), ["quick"; 5]);
+
+    release.send(()).unwrap();
+    blocking.await.unwrap();
+    assert_eq!(
+        *done.lock().unwrap(),
+        ["quick", "quick", "quick", "quick", "quick", "blocking"]
+    );
+}
+
+#[cfg(target_os = "linux")]
+mod pinned {
+    use super::*;
+
+    fn current_cores() -> Vec<usize> {
+        // SAFETY: the set is a plain bit mask and pid 0 is the calling thread
+        unsafe {
+            let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+            let res = libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set);
+            assert_eq!(res, 0);
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|&core| libc::CPU_ISSET(core, &set))
+                .collect()
+        }
+    }
+
+    struct Cores;
+
+    impl Message for Cores {
+        type Result = Vec<usize>;
+    }
+
+    struct Pinned;
+
+    impl Actor for Pinned {
+        type Context = SyncContext<Self>;
+    }
+
+    impl Handler<Cores> for Pinned {
+        type Result = MessageResult<Cores>;
+
+        fn handle(&mut self, _: Cores, _: &mut Self::Context) -> Self::Result {
+            MessageResult(current_cores())
+        }
+    }
+
+    #[actix::test]
+    async fn test_workers_are_pinned() {
+        let core = *current_cores().last().unwrap();
+        let addr = SyncArbiter::start_pinned(&[core, core], || Pinned);
+
+        assert_eq!(addr.send(Cores).await.unwrap(), [core]);
+        assert_eq!(addr.send(Cores).await.unwrap(), [core]);
+    }
+
+    #[actix::test]
+    async fn test_rejected_pinning_runs_unpinned() {
+        let cores = current_cores();
+        let addr = SyncArbiter::start_pinned(&[usize::MAX], || Pinned);
+
+        assert_eq!(addr.send(Cores).await.unwrap(), cores);
+    }
+}
+
+mod resize {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    struct Busy;
+
+    impl Message for Busy {
+        type Result = ();
+    }
+
+    struct Elastic {
+        live: Arc<AtomicUsize>,
+        handled: Arc<Mutex<Vec<thread::ThreadId>>>,
+    }
+
+    impl Actor for Elastic {
+        type Context = SyncContext<Self>;
+
+        fn started(&mut self, _: &mut Self::Context) {
+            self.live.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn stopped(&mut self, _: &mut Self::Context) {
+            self.live.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Handler<Busy> for Elastic {
+        type Result = ();
+
+        fn handle(&mut self, _: Busy, _: &mut Self::Context) {
+            thread::sleep(Duration::from_millis(50));
+            self.handled.lock().unwrap().push(thread::current().id());
+        }
+    }
+
+    async fn wait_for_live(live: &AtomicUsize, n: usize) {
+        for _ in 0..100 {
+            if live.load(Ordering::SeqCst) == n {
+                return;
+            }
+            actix_rt::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!(
+            "expected {n} live workers, got {}",
+            live.load(Ordering::SeqCst)
+        );
+    }
+
+    #[actix::test]
+    async fn test_grow_and_shrink() {
+        let live = Arc::new(AtomicUsize::new(0));
+        let handled = Arc::new(Mutex::new(Vec::new()));
+
+        let (live_c, handled_c) = (Arc::clone(&live), Arc::clone(&handled));
+        let (addr, pool) = SyncArbiter::start_resizable(1, move || Elastic {
+            live: Arc::clone(&live_c),
+            handled: Arc::clone(&handled_c),
+        });
+        wait_for_live(&live, 1).await;
+
+        pool.send(Resize(4)).await.unwrap();
+        wait_for_live(&live, 4).await;
+
+        // sends are balanced across the grown set
+        let sends = (0..4).map(|_| addr.send(Busy)).collect::<Vec<_>>();
+        for send in sends {
+            send.await.unwrap();
+        }
+        let mut threads = handled.lock().unwrap().clone();
+        threads.sort_unstable_by_key(|id| format!("{id:?}"));
+        threads.dedup();
+        assert_eq!(threads.len(), 4);
+
+        pool.send(Resize(2)).await.unwrap();
+        wait_for_live(&live, 2).await;
+    }
+
+    #[actix::test]
+    async fn test_shrinking_handles_messages_in_flight() {
+        let live = Arc::new(AtomicUsize::new(0));
+        let handled = Arc::new(Mutex::new(Vec::new()));
+
+        let (live_c, handled_c) = (Arc::clone(&live), Arc::clone(&handled));
+        let (addr, pool) = SyncArbiter::start_resizable(2, move || Elastic {
+            live: Arc::clone(&live_c),
+            handled: Arc::clone(&handled_c),
+        });
+        wait_for_live(&live, 2).await;
+
+        let sends = (0..4).map(|_| addr.send(Busy)).collect::<Vec<_>>();
+        pool.send(Resize(0)).await.unwrap();
+
+        for send in sends {
+            send.await.unwrap();
+        }
+        assert_eq!(handled.lock().unwrap().len(), 4);
+        wait_for_live(&live, 0).await;
+
+        // messages sent without workers wait for the pool to grow again
+        let send = addr.send(Busy);
+        pool.send(Resize(1)).await.unwrap();
+        send.await.unwrap();
+        assert_eq!(handled.lock().unwrap().len(), 5);
+    }
+}
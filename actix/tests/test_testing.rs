@@ -0,0 +1,332 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use actix::{clock::Instant, prelude::*, testing::TestSystem};
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Hop(usize);
+
+/// Forwards hops to its peer until they reach zero.
+struct Relay {
+    peer: Option<Addr<Relay>>,
+    hops: Arc<AtomicUsize>,
+}
+
+impl Actor for Relay {
+    type Context = Context<Self>;
+}
+
+impl Handler<Hop> for Relay {
+    type Result = ();
+
+    fn handle(&mut self, Hop(n): Hop, _: &mut Self::Context) {
+        self.hops.fetch_add(1, Ordering::SeqCst);
+        if n > 0 {
+            self.peer.as_ref().unwrap().do_send(Hop(n - 1));
+        }
+    }
+}
+
+#[test]
+fn step_polls_ready_actors_once() {
+    let sys = TestSystem::new();
+    let hops = Arc::new(AtomicUsize::new(0));
+
+    let a = sys.block_on(async {
+        Relay::create(|ctx| {
+            let b = Relay {
+                peer: Some(ctx.address()),
+                hops: Arc::clone(&hops),
+            };
+            Relay {
+                peer: Some(b.start()),
+                hops: Arc::clone(&hops),
+            }
+        })
+    });
+
+    // actors have started and are idle
+    sys.run_until_idle();
+    assert!(!sys.step());
+
+    // each actor is polled at most once per step
+    a.do_send(Hop(5));
+    assert!(sys.step());
+    assert_eq!(hops.load(Ordering::SeqCst), 2);
+    assert!(sys.step());
+    assert_eq!(hops.load(Ordering::SeqCst), 4);
+
+    sys.run_until_idle();
+    assert_eq!(hops.load(Ordering::SeqCst), 6);
+    assert!(!sys.step());
+}
+
+struct Ticker(Arc<AtomicUsize>);
+
+impl Actor for Ticker {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(Duration::from_secs(1), |act, _| {
+            act.0.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+}
+
+#[test]
+fn timers_fire_only_when_time_advances() {
+    let sys = TestSystem::new();
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let _addr = sys.block_on(async { Ticker(Arc::clone(&ticks)).start() });
+
+    sys.run_until_idle();
+    assert_eq!(ticks.load(Ordering::SeqCst), 0);
+
+    sys.advance_time(Duration::from_millis(999));
+    assert_eq!(ticks.load(Ordering::SeqCst), 0);
+
+    sys.advance_time(Duration::from_millis(1));
+    assert_eq!(ticks.load(Ordering::SeqCst), 1);
+
+    // missed ticks are coalesced
+    sys.advance_time(Duration::from_secs(3));
+    assert_eq!(ticks.load(Ordering::SeqCst), 2);
+
+    sys.advance_time(Duration::from_secs(1));
+    assert_eq!(ticks.load(Ordering::SeqCst), 3);
+}
+
+struct Lagging {
+    missed: MissedTicks,
+    skipped: Arc<Mutex<Vec<u32>>>,
+}
+
+impl Actor for Lagging {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval_with(Duration::from_secs(1), self.missed, |act, _, skipped| {
+            act.skipped.lock().unwrap().push(skipped);
+        });
+    }
+}
+
+#[test]
+fn interval_reports_coalesced_ticks() {
+    let sys = TestSystem::new();
+    let skipped = Arc::new(Mutex::new(Vec::new()));
+    let _addr = sys.block_on(async {
+        Lagging {
+            missed: MissedTicks::Coalesce,
+            skipped: Arc::clone(&skipped),
+        }
+        .start()
+    });
+
+    sys.advance_time(Duration::from_millis(3500));
+    assert_eq!(*skipped.lock().unwrap(), [2]);
+
+    // the original schedule is kept
+    sys.advance_time(Duration::from_millis(500));
+    assert_eq!(*skipped.lock().unwrap(), [2, 0]);
+}
+
+#[test]
+fn interval_can_catch_up() {
+    let sys = TestSystem::new();
+    let skipped = Arc::new(Mutex::new(Vec::new()));
+    let _addr = sys.block_on(async {
+        Lagging {
+            missed: MissedTicks::CatchUp,
+            skipped: Arc::clone(&skipped),
+        }
+        .start()
+    });
+
+    sys.advance_time(Duration::from_millis(3500));
+    assert_eq!(*skipped.lock().unwrap(), [0, 0, 0]);
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Call;
+
+struct Limited(Arc<AtomicUsize>);
+
+impl Actor for Limited {
+    type Context = Context<Self>;
+}
+
+impl Handler<Call> for Limited {
+    type Result = ();
+
+    fn handle(&mut self, _: Call, ctx: &mut Self::Context) {
+        let limiter = ctx.rate_limiter("calls", Rate::per_second(2));
+        limiter
+            .acquire()
+            .map(|_, act: &mut Self, _| {
+                act.0.fetch_add(1, Ordering::SeqCst);
+            })
+            .spawn(ctx);
+    }
+}
+
+#[test]
+fn rate_limiter_waits_for_tokens() {
+    let sys = TestSystem::new();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let addr = sys.block_on(async { Limited(Arc::clone(&calls)).start() });
+
+    for _ in 0..4 {
+        addr.do_send(Call);
+    }
+
+    // the initial burst is served right away
+    sys.run_until_idle();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    sys.advance_time(Duration::from_millis(499));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    sys.advance_time(Duration::from_millis(1));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    sys.advance_time(Duration::from_millis(500));
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Fetch;
+
+type Attempts = Arc<Mutex<Vec<usize>>>;
+type Outcome = Arc<Mutex<Option<Result<usize, usize>>>>;
+
+/// Fails its first `failures` calls.
+struct Downstream {
+    failures: usize,
+    attempts: Attempts,
+    result: Outcome,
+}
+
+impl Actor for Downstream {
+    type Context = Context<Self>;
+}
+
+impl Handler<Fetch> for Downstream {
+    type Result = ();
+
+    fn handle(&mut self, _: Fetch, ctx: &mut Self::Context) {
+        fut::retry(3, Duration::from_secs(1), |act: &mut Self, _, attempt| {
+            act.attempts.lock().unwrap().push(attempt);
+            let res = if act.failures >= attempt {
+                Err(attempt)
+            } else {
+                Ok(attempt)
+            };
+            fut::ready(res)
+        })
+        .map(|res, act, _| *act.result.lock().unwrap() = Some(res))
+        .spawn(ctx);
+    }
+}
+
+fn fetch(failures: usize) -> (TestSystem, Attempts, Outcome) {
+    let sys = TestSystem::new();
+    let attempts = Arc::new(Mutex::new(Vec::new()));
+    let result = Arc::new(Mutex::new(None));
+    let addr = sys.block_on(async {
+        Downstream {
+            failures,
+            attempts: Arc::clone(&attempts),
+            result: Arc::clone(&result),
+        }
+        .start()
+    });
+    addr.do_send(Fetch);
+    sys.run_until_idle();
+    (sys, attempts, result)
+}
+
+#[test]
+fn retry_backs_off_between_attempts() {
+    let (sys, attempts, result) = fetch(1);
+    assert_eq!(*attempts.lock().unwrap(), [1]);
+    assert_eq!(*result.lock().unwrap(), None);
+
+    sys.advance_time(Duration::from_millis(999));
+    assert_eq!(*attempts.lock().unwrap(), [1]);
+
+    sys.advance_time(Duration::from_millis(1));
+    assert_eq!(*attempts.lock().unwrap(), [1, 2]);
+    assert_eq!(*result.lock().unwrap(), Some(Ok(2)));
+}
+
+#[test]
+fn retry_gives_up_with_last_error() {
+    let (sys, attempts, result) = fetch(5);
+
+    sys.advance_time(Duration::from_secs(1));
+    sys.advance_time(Duration::from_secs(1));
+    assert_eq!(*attempts.lock().unwrap(), [1, 2, 3]);
+    assert_eq!(*result.lock().unwrap(), Some(Err(3)));
+
+    sys.advance_time(Duration::from_secs(10));
+    assert_eq!(*attempts.lock().unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn clock_fires_timers_at_their_deadline() {
+    let sys = TestSystem::new();
+    let clock = sys.clock();
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let _addr = sys.block_on(async { Ticker(Arc::clone(&ticks)).start() });
+
+    clock.advance(Duration::from_millis(999));
+    assert_eq!(ticks.load(Ordering::SeqCst), 0);
+
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(ticks.load(Ordering::SeqCst), 1);
+
+    // unlike `advance_time`, every period ticks
+    clock.advance(Duration::from_secs(3));
+    assert_eq!(ticks.load(Ordering::SeqCst), 4);
+    assert_eq!(clock.elapsed(), Duration::from_secs(4));
+}
+
+struct Reminder(Arc<Mutex<Vec<Duration>>>, Instant);
+
+impl Actor for Reminder {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // every reminder sets up the next one
+        ctx.run_later(Duration::from_secs(60), |act, ctx| {
+            act.0.lock().unwrap().push(act.1.elapsed());
+            act.started(ctx);
+        });
+    }
+}
+
+#[test]
+fn clock_fires_timers_set_while_advancing() {
+    let sys = TestSystem::new();
+    let clock = sys.clock();
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let start = clock.now();
+    let _addr = sys.block_on(async { Reminder(Arc::clone(&fired), start).start() });
+
+    clock.advance(Duration::from_secs(60 * 5));
+    assert_eq!(
+        *fired.lock().unwrap(),
+        (1..=5)
+            .map(|min| Duration::from_secs(60 * min))
+            .collect::<Vec<_>>()
+    );
+}
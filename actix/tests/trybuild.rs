@@ -0,0 +1,12 @@
+/// Compile tests of the bounds on cross-thread messaging.
+///
+/// The expected errors follow the diagnostics of the current stable toolchain. Regenerate them
+/// with `TRYBUILD=overwrite` when a new release changes their layout.
+#[test]
+fn compile_send_bounds() {
+    let t = trybuild::TestCases::new();
+
+    t.pass("tests/trybuild/sync-send.rs");
+    t.compile_fail("tests/trybuild/sync-send-fail-message.rs");
+    t.compile_fail("tests/trybuild/sync-send-fail-result.rs");
+}
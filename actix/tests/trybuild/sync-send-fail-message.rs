@@ -0,0 +1,28 @@
+use std::rc::Rc;
+
+use actix::prelude::*;
+
+struct Sum(Rc<usize>, usize);
+
+impl Message for Sum {
+    type Result = usize;
+}
+
+struct Adder;
+
+impl Actor for Adder {
+    type Context = SyncContext<Self>;
+}
+
+impl Handler<Sum> for Adder {
+    type Result = usize;
+
+    fn handle(&mut self, Sum(a, b): Sum, _: &mut SyncContext<Self>) -> usize {
+        *a + b
+    }
+}
+
+fn main() {
+    let addr = SyncArbiter::start(1, || Adder);
+    addr.do_send(Sum(Rc::new(1), 2));
+}
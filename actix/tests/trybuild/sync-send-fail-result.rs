@@ -0,0 +1,28 @@
+use std::rc::Rc;
+
+use actix::prelude::*;
+
+struct Sum(usize, usize);
+
+impl Message for Sum {
+    type Result = Rc<usize>;
+}
+
+struct Adder;
+
+impl Actor for Adder {
+    type Context = SyncContext<Self>;
+}
+
+impl Handler<Sum> for Adder {
+    type Result = MessageResult<Sum>;
+
+    fn handle(&mut self, Sum(a, b): Sum, _: &mut SyncContext<Self>) -> Self::Result {
+        MessageResult(Rc::new(a + b))
+    }
+}
+
+fn main() {
+    let addr = SyncArbiter::start(1, || Adder);
+    let _ = addr.send(Sum(1, 2));
+}
@@ -0,0 +1,28 @@
+use actix::prelude::*;
+
+struct Sum(usize, usize);
+
+impl Message for Sum {
+    type Result = usize;
+}
+
+struct Adder;
+
+impl Actor for Adder {
+    type Context = SyncContext<Self>;
+}
+
+impl Handler<Sum> for Adder {
+    type Result = usize;
+
+    fn handle(&mut self, Sum(a, b): Sum, _: &mut SyncContext<Self>) -> usize {
+        a + b
+    }
+}
+
+#[actix::main]
+async fn main() {
+    let addr = SyncArbiter::start(1, || Adder);
+    addr.do_send(Sum(1, 2));
+    assert_eq!(addr.send(Sum(1, 2)).await.unwrap(), 3);
+}